@@ -12,10 +12,16 @@ import { createInterface } from 'readline';
 import { EventEmitter } from 'events';
 import { LargeModelsInterface } from 'large-models-interface';
 
+// Version of the line-delimited frame protocol spoken with the Rust client.
+// Bump together with `LMI_BRIDGE_PROTOCOL_VERSION` in lmi_bridge_client.rs.
+const PROTOCOL_VERSION = 1;
+
 class ModelBridgeService extends EventEmitter {
   constructor() {
     super();
     this.lmi = new LargeModelsInterface();
+    // AbortControllers for in-flight streaming requests, keyed by request id.
+    this.inFlight = new Map();
     this.setupStdioHandling();
   }
 
@@ -30,6 +36,27 @@ class ModelBridgeService extends EventEmitter {
     rl.on('line', async (line) => {
       try {
         const request = JSON.parse(line);
+        if (request.type === 'hello') {
+          this.sendFrame({
+            id: request.id,
+            type: 'hello',
+            protocol_version: PROTOCOL_VERSION,
+            bridge_version: process.env.npm_package_version,
+          });
+          return;
+        }
+        if (request.type === 'ping') {
+          this.sendFrame({ id: request.id, type: 'pong' });
+          return;
+        }
+        if (request.type === 'cancel') {
+          this.inFlight.get(request.id)?.abort();
+          return;
+        }
+        if (request.type === 'chat_completion' && request.options?.stream) {
+          await this.handleStreamingChatCompletion(request);
+          return;
+        }
         const response = await this.handleRequest(request);
         this.sendResponse(response);
       } catch (error) {
@@ -85,6 +112,94 @@ class ModelBridgeService extends EventEmitter {
     }
   }
 
+  /**
+   * Streams a chat completion back to the Rust client as line-delimited
+   * frames. Every frame is a JSON object carrying the request `id` and a
+   * `type` of `created`, `delta`, `reasoning_delta`, `tool_call_delta`,
+   * `completed` or `error`; exactly one `completed` or `error` frame
+   * terminates the response. Several requests may be in flight at once and
+   * a `cancel` request with the same `id` aborts generation.
+   */
+  async handleStreamingChatCompletion(request) {
+    const { id, provider, model, messages, tools, base_url } = request;
+    const controller = new AbortController();
+    this.inFlight.set(id, controller);
+    const send = (frame) => this.sendFrame({ id, ...frame });
+    try {
+      send({ type: 'created' });
+
+      if (typeof this.lmi.streamChatCompletion !== 'function') {
+        // Provider SDK cannot stream: emit the whole answer as one delta.
+        const response = await this.lmi.chatCompletion({
+          provider, model, messages, tools, baseUrl: base_url, signal: controller.signal,
+        });
+        this.emitChoice(send, response?.choices?.[0]?.message ?? {});
+        send({ type: 'completed', response_id: response?.id, usage: this.mapUsage(response?.usage) });
+        return;
+      }
+
+      let responseId;
+      let usage;
+      for await (const chunk of this.lmi.streamChatCompletion({
+        provider, model, messages, tools, baseUrl: base_url, signal: controller.signal,
+      })) {
+        if (controller.signal.aborted) {
+          break;
+        }
+        responseId = responseId ?? chunk?.id;
+        usage = chunk?.usage ?? usage;
+        this.emitChoice(send, chunk?.choices?.[0]?.delta ?? {});
+      }
+      if (controller.signal.aborted) {
+        send({ type: 'error', message: 'cancelled' });
+      } else {
+        send({ type: 'completed', response_id: responseId, usage: this.mapUsage(usage) });
+      }
+    } catch (error) {
+      send({ type: 'error', message: error.message });
+    } finally {
+      this.inFlight.delete(id);
+    }
+  }
+
+  emitChoice(send, delta) {
+    const reasoning = typeof delta.reasoning === 'string'
+      ? delta.reasoning
+      : delta.reasoning?.text ?? delta.reasoning?.content;
+    if (reasoning) {
+      send({ type: 'reasoning_delta', text: reasoning });
+    }
+    if (typeof delta.content === 'string' && delta.content.length > 0) {
+      send({ type: 'delta', text: delta.content });
+    }
+    (delta.tool_calls ?? []).forEach((call, position) => {
+      send({
+        type: 'tool_call_delta',
+        index: call.index ?? position,
+        id: call.id,
+        name: call.function?.name,
+        arguments: call.function?.arguments,
+      });
+    });
+  }
+
+  mapUsage(usage) {
+    if (!usage) {
+      return undefined;
+    }
+    return {
+      input_tokens: usage.prompt_tokens ?? usage.input_tokens ?? 0,
+      cached_input_tokens: usage.prompt_tokens_details?.cached_tokens ?? 0,
+      output_tokens: usage.completion_tokens ?? usage.output_tokens ?? 0,
+      reasoning_output_tokens: usage.completion_tokens_details?.reasoning_tokens ?? 0,
+      total_tokens: usage.total_tokens,
+    };
+  }
+
+  sendFrame(frame) {
+    process.stdout.write(`${JSON.stringify(frame)}\n`);
+  }
+
   async handleListModels(provider) {
     try {
       const models = await this.lmi.listModels(provider);
@@ -3,12 +3,15 @@ use std::path::PathBuf;
 use icodex_common::CliConfigOverrides;
 use icodex_core::config::Config;
 use icodex_core::config::ConfigOverrides;
+use icodex_core::container::spawn_command_under_container;
 use icodex_core::exec_env::create_env;
+use icodex_core::exec_limits::CommandLimits;
 use icodex_core::landlock::spawn_command_under_linux_sandbox;
 use icodex_core::seatbelt::spawn_command_under_seatbelt;
 use icodex_core::spawn::StdioPolicy;
 use icodex_protocol::config_types::SandboxMode;
 
+use crate::ContainerCommand;
 use crate::LandlockCommand;
 use crate::SeatbeltCommand;
 use crate::exit_status::handle_exit_status;
@@ -51,9 +54,33 @@ pub async fn run_command_under_landlock(
     .await
 }
 
+pub async fn run_command_under_container(
+    command: ContainerCommand,
+    icodex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let ContainerCommand {
+        full_auto,
+        mut config_overrides,
+        command,
+    } = command;
+    // Use the container even when the config leaves the native backend on.
+    config_overrides
+        .raw_overrides
+        .push("sandbox_backend=\"container\"".to_string());
+    run_command_under_sandbox(
+        full_auto,
+        command,
+        config_overrides,
+        icodex_linux_sandbox_exe,
+        SandboxType::Container,
+    )
+    .await
+}
+
 enum SandboxType {
     Seatbelt,
     Landlock,
+    Container,
 }
 
 async fn run_command_under_sandbox(
@@ -76,12 +103,20 @@ async fn run_command_under_sandbox(
         },
     )?;
     let stdio_policy = StdioPolicy::Inherit;
-    let env = create_env(&config.shell_environment_policy);
+    let env = create_env(&config.shell_environment_policy, None);
+    let limits = CommandLimits::new(&config.exec_limits);
 
     let mut child = match sandbox_type {
         SandboxType::Seatbelt => {
-            spawn_command_under_seatbelt(command, &config.sandbox_policy, cwd, stdio_policy, env)
-                .await?
+            spawn_command_under_seatbelt(
+                command,
+                &config.sandbox_policy,
+                cwd,
+                stdio_policy,
+                env,
+                Some(&limits),
+            )
+            .await?
         }
         SandboxType::Landlock => {
             #[expect(clippy::expect_used)]
@@ -95,11 +130,32 @@ async fn run_command_under_sandbox(
                 cwd,
                 stdio_policy,
                 env,
+                Some(&limits),
             )
             .await?
         }
+        SandboxType::Container => {
+            let container_sandbox = config
+                .container_sandbox
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("the container sandbox is not configured"))?;
+            let (child, _container) = spawn_command_under_container(
+                container_sandbox,
+                command,
+                &config.sandbox_policy,
+                cwd,
+                stdio_policy,
+                env,
+                Some(&limits),
+            )
+            .await?;
+            child
+        }
     };
     let status = child.wait().await?;
+    // `handle_exit_status` exits the process, which would skip removing the
+    // command's cgroup.
+    drop(limits);
 
     handle_exit_status(status);
 }
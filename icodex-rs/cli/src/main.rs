@@ -5,6 +5,7 @@ use clap_complete::generate;
 use icodex_arg0::arg0_dispatch_or_else;
 use icodex_chatgpt::apply_command::ApplyCommand;
 use icodex_chatgpt::apply_command::run_apply_command;
+use icodex_cli::ContainerCommand;
 use icodex_cli::LandlockCommand;
 use icodex_cli::SeatbeltCommand;
 use icodex_cli::login::run_login_status;
@@ -18,9 +19,11 @@ use icodex_tui::Cli as TuiCli;
 use std::path::PathBuf;
 
 mod mcp_cmd;
+mod sessions_cmd;
 
 use crate::mcp_cmd::McpCli;
 use crate::proto::ProtoCli;
+use crate::sessions_cmd::SessionsCli;
 
 /// Codex CLI
 ///
@@ -79,6 +82,9 @@ enum Subcommand {
     /// Resume a previous interactive session (picker by default; use --last to continue the most recent).
     Resume(ResumeCommand),
 
+    /// Search recorded sessions.
+    Sessions(SessionsCli),
+
     /// Internal: generate TypeScript protocol bindings.
     #[clap(hide = true)]
     GenerateTs(GenerateTsCommand),
@@ -119,6 +125,9 @@ enum DebugCommand {
 
     /// Run a command under Landlock+seccomp (Linux only).
     Landlock(LandlockCommand),
+
+    /// Run a command in the container sandbox configured by `[sandbox_container]`.
+    Container(ContainerCommand),
 }
 
 #[derive(Debug, Parser)]
@@ -207,6 +216,13 @@ async fn cli_main(icodex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<(
             );
             icodex_tui::run_main(interactive, icodex_linux_sandbox_exe).await?;
         }
+        Some(Subcommand::Sessions(mut sessions_cli)) => {
+            prepend_config_flags(
+                &mut sessions_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            sessions_cli.run().await?;
+        }
         Some(Subcommand::Login(mut login_cli)) => {
             prepend_config_flags(
                 &mut login_cli.config_overrides,
@@ -265,6 +281,17 @@ async fn cli_main(icodex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<(
                 )
                 .await?;
             }
+            DebugCommand::Container(mut container_cli) => {
+                prepend_config_flags(
+                    &mut container_cli.config_overrides,
+                    root_config_overrides.clone(),
+                );
+                icodex_cli::debug_sandbox::run_command_under_container(
+                    container_cli,
+                    icodex_linux_sandbox_exe,
+                )
+                .await?;
+            }
         },
         Some(Subcommand::Apply(mut apply_cli)) => {
             prepend_config_flags(
@@ -13,6 +13,7 @@ use icodex_core::config::find_icodex_home;
 use icodex_core::config::load_global_mcp_servers;
 use icodex_core::config::write_global_mcp_servers;
 use icodex_core::config_types::McpServerConfig;
+use icodex_core::config_types::McpServerTransportConfig;
 
 /// [experimental] Launch Codex as an MCP server or manage configured MCP servers.
 ///
@@ -75,8 +76,18 @@ pub struct AddArgs {
     #[arg(long, value_parser = parse_env_pair, value_name = "KEY=VALUE")]
     pub env: Vec<(String, String)>,
 
+    /// URL of a remote MCP server (streamable HTTP, or HTTP+SSE for older
+    /// servers) to use instead of launching a command.
+    #[arg(long, conflicts_with_all = ["env", "command"])]
+    pub url: Option<String>,
+
+    /// Environment variable holding a bearer token to send to the `--url`
+    /// server.
+    #[arg(long, requires = "url", value_name = "ENV_VAR")]
+    pub bearer_token_env_var: Option<String>,
+
     /// Command to launch the MCP server.
-    #[arg(trailing_var_arg = true, num_args = 1..)]
+    #[arg(trailing_var_arg = true, num_args = 1.., required_unless_present = "url")]
     pub command: Vec<String>,
 }
 
@@ -120,24 +131,46 @@ fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<(
     // Validate any provided overrides even though they are not currently applied.
     config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
 
-    let AddArgs { name, env, command } = add_args;
+    let AddArgs {
+        name,
+        env,
+        url,
+        bearer_token_env_var,
+        command,
+    } = add_args;
 
     validate_server_name(&name)?;
 
-    let mut command_parts = command.into_iter();
-    let command_bin = command_parts
-        .next()
-        .ok_or_else(|| anyhow!("command is required"))?;
-    let command_args: Vec<String> = command_parts.collect();
-
-    let env_map = if env.is_empty() {
-        None
-    } else {
-        let mut map = HashMap::new();
-        for (key, value) in env {
-            map.insert(key, value);
+    let transport = match url {
+        Some(url) => McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token_env_var,
+            http_headers: None,
+            env_http_headers: None,
+        },
+        None => {
+            let mut command_parts = command.into_iter();
+            let command_bin = command_parts
+                .next()
+                .ok_or_else(|| anyhow!("command is required"))?;
+            let command_args: Vec<String> = command_parts.collect();
+
+            let env_map = if env.is_empty() {
+                None
+            } else {
+                let mut map = HashMap::new();
+                for (key, value) in env {
+                    map.insert(key, value);
+                }
+                Some(map)
+            };
+
+            McpServerTransportConfig::Stdio {
+                command: command_bin,
+                args: command_args,
+                env: env_map,
+            }
         }
-        Some(map)
     };
 
     let icodex_home = find_icodex_home().context("failed to resolve CODEX_HOME")?;
@@ -145,10 +178,12 @@ fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<(
         .with_context(|| format!("failed to load MCP servers from {}", icodex_home.display()))?;
 
     let new_entry = McpServerConfig {
-        command: command_bin,
-        args: command_args,
-        env: env_map,
+        transport,
         startup_timeout_ms: None,
+        allow_sampling: false,
+        enabled_tools: None,
+        disabled_tools: Vec::new(),
+        require_approval: Vec::new(),
     };
 
     servers.insert(name.clone(), new_entry);
@@ -199,20 +234,7 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
     if list_args.json {
         let json_entries: Vec<_> = entries
             .into_iter()
-            .map(|(name, cfg)| {
-                let env = cfg.env.as_ref().map(|env| {
-                    env.iter()
-                        .map(|(k, v)| (k.clone(), v.clone()))
-                        .collect::<BTreeMap<_, _>>()
-                });
-                serde_json::json!({
-                    "name": name,
-                    "command": cfg.command,
-                    "args": cfg.args,
-                    "env": env,
-                    "startup_timeout_ms": cfg.startup_timeout_ms,
-                })
-            })
+            .map(|(name, cfg)| server_json(name, cfg))
             .collect();
         let output = serde_json::to_string_pretty(&json_entries)?;
         println!("{output}");
@@ -226,30 +248,27 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
 
     let mut rows: Vec<[String; 4]> = Vec::new();
     for (name, cfg) in entries {
-        let args = if cfg.args.is_empty() {
-            "-".to_string()
-        } else {
-            cfg.args.join(" ")
-        };
-
-        let env = match cfg.env.as_ref() {
-            None => "-".to_string(),
-            Some(map) if map.is_empty() => "-".to_string(),
-            Some(map) => {
-                let mut pairs: Vec<_> = map.iter().collect();
-                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
-                pairs
-                    .into_iter()
-                    .map(|(k, v)| format!("{k}={v}"))
-                    .collect::<Vec<_>>()
-                    .join(", ")
+        match &cfg.transport {
+            McpServerTransportConfig::Stdio { command, args, env } => {
+                let args = if args.is_empty() {
+                    "-".to_string()
+                } else {
+                    args.join(" ")
+                };
+                rows.push([
+                    name.clone(),
+                    command.clone(),
+                    args,
+                    format_pairs(env.as_ref()),
+                ]);
             }
-        };
-
-        rows.push([name.clone(), cfg.command.clone(), args, env]);
+            McpServerTransportConfig::StreamableHttp { url, .. } => {
+                rows.push([name.clone(), url.clone(), "-".to_string(), "-".to_string()]);
+            }
+        }
     }
 
-    let mut widths = ["Name".len(), "Command".len(), "Args".len(), "Env".len()];
+    let mut widths = ["Name".len(), "Command/URL".len(), "Args".len(), "Env".len()];
     for row in &rows {
         for (i, cell) in row.iter().enumerate() {
             widths[i] = widths[i].max(cell.len());
@@ -259,7 +278,7 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
     println!(
         "{:<name_w$}  {:<cmd_w$}  {:<args_w$}  {:<env_w$}",
         "Name",
-        "Command",
+        "Command/URL",
         "Args",
         "Env",
         name_w = widths[0],
@@ -295,52 +314,110 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
     };
 
     if get_args.json {
-        let env = server.env.as_ref().map(|env| {
-            env.iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect::<BTreeMap<_, _>>()
-        });
-        let output = serde_json::to_string_pretty(&serde_json::json!({
-            "name": get_args.name,
-            "command": server.command,
-            "args": server.args,
-            "env": env,
-            "startup_timeout_ms": server.startup_timeout_ms,
-        }))?;
+        let output = serde_json::to_string_pretty(&server_json(&get_args.name, server))?;
         println!("{output}");
         return Ok(());
     }
 
     println!("{}", get_args.name);
-    println!("  command: {}", server.command);
-    let args = if server.args.is_empty() {
-        "-".to_string()
-    } else {
-        server.args.join(" ")
-    };
-    println!("  args: {args}");
-    let env_display = match server.env.as_ref() {
-        None => "-".to_string(),
-        Some(map) if map.is_empty() => "-".to_string(),
-        Some(map) => {
-            let mut pairs: Vec<_> = map.iter().collect();
-            pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
-            pairs
-                .into_iter()
-                .map(|(k, v)| format!("{k}={v}"))
-                .collect::<Vec<_>>()
-                .join(", ")
+    match &server.transport {
+        McpServerTransportConfig::Stdio { command, args, env } => {
+            println!("  command: {command}");
+            let args = if args.is_empty() {
+                "-".to_string()
+            } else {
+                args.join(" ")
+            };
+            println!("  args: {args}");
+            println!("  env: {}", format_pairs(env.as_ref()));
         }
-    };
-    println!("  env: {env_display}");
+        McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token_env_var,
+            http_headers,
+            env_http_headers,
+        } => {
+            println!("  url: {url}");
+            if let Some(var) = bearer_token_env_var {
+                println!("  bearer_token_env_var: {var}");
+            }
+            println!("  http_headers: {}", format_pairs(http_headers.as_ref()));
+            println!(
+                "  env_http_headers: {}",
+                format_pairs(env_http_headers.as_ref())
+            );
+        }
+    }
     if let Some(timeout) = server.startup_timeout_ms {
         println!("  startup_timeout_ms: {timeout}");
     }
+    println!("  allow_sampling: {}", server.allow_sampling);
+    if let Some(enabled_tools) = &server.enabled_tools {
+        println!("  enabled_tools: {}", enabled_tools.join(", "));
+    }
+    if !server.disabled_tools.is_empty() {
+        println!("  disabled_tools: {}", server.disabled_tools.join(", "));
+    }
+    if !server.require_approval.is_empty() {
+        println!("  require_approval: {}", server.require_approval.join(", "));
+    }
     println!("  remove: icodex mcp remove {}", get_args.name);
 
     Ok(())
 }
 
+fn server_json(name: &str, server: &McpServerConfig) -> serde_json::Value {
+    match &server.transport {
+        McpServerTransportConfig::Stdio { command, args, env } => serde_json::json!({
+            "name": name,
+            "command": command,
+            "args": args,
+            "env": sorted_pairs(env.as_ref()),
+            "startup_timeout_ms": server.startup_timeout_ms,
+            "allow_sampling": server.allow_sampling,
+            "enabled_tools": server.enabled_tools,
+            "disabled_tools": server.disabled_tools,
+            "require_approval": server.require_approval,
+        }),
+        McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token_env_var,
+            http_headers,
+            env_http_headers,
+        } => serde_json::json!({
+            "name": name,
+            "url": url,
+            "bearer_token_env_var": bearer_token_env_var,
+            "http_headers": sorted_pairs(http_headers.as_ref()),
+            "env_http_headers": sorted_pairs(env_http_headers.as_ref()),
+            "startup_timeout_ms": server.startup_timeout_ms,
+            "allow_sampling": server.allow_sampling,
+            "enabled_tools": server.enabled_tools,
+            "disabled_tools": server.disabled_tools,
+            "require_approval": server.require_approval,
+        }),
+    }
+}
+
+fn sorted_pairs(map: Option<&HashMap<String, String>>) -> Option<BTreeMap<String, String>> {
+    map.map(|map| {
+        map.iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<BTreeMap<_, _>>()
+    })
+}
+
+fn format_pairs(map: Option<&HashMap<String, String>>) -> String {
+    match sorted_pairs(map) {
+        Some(pairs) if !pairs.is_empty() => pairs
+            .into_iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => "-".to_string(),
+    }
+}
+
 fn parse_env_pair(raw: &str) -> Result<(String, String), String> {
     let mut parts = raw.splitn(2, '=');
     let key = parts
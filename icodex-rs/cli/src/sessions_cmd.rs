@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use icodex_common::CliConfigOverrides;
+use icodex_core::SearchField;
+use icodex_core::SessionSearchHit;
+use icodex_core::SessionSearchQuery;
+use icodex_core::config::find_icodex_home;
+use icodex_core::is_valid_search_date;
+use icodex_core::search_sessions;
+
+/// Width to which matching texts are shortened in the listing.
+const SNIPPET_CHARS: usize = 100;
+
+/// Work with recorded sessions.
+///
+/// Subcommands:
+/// - `search` — find sessions by what was said, run or edited in them
+#[derive(Debug, clap::Parser)]
+pub struct SessionsCli {
+    #[clap(flatten)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: SessionsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SessionsSubcommand {
+    /// Search the messages, commands and edited file paths of all recorded
+    /// sessions.
+    Search(SearchArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct SearchArgs {
+    /// Words that must all occur in a session (case-insensitive). Without
+    /// any, lists the sessions that match the filters.
+    #[arg(value_name = "QUERY")]
+    pub query: Vec<String>,
+
+    /// Only sessions in a git repository whose remote URL contains this text,
+    /// e.g. `myorg/myrepo`.
+    #[arg(long, value_name = "TEXT")]
+    pub repo: Option<String>,
+
+    /// Only sessions started in this directory or below it.
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Only sessions started on or after this date (UTC): YYYY, YYYY-MM or
+    /// YYYY-MM-DD.
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// Only sessions started on or before this date (UTC): YYYY, YYYY-MM or
+    /// YYYY-MM-DD.
+    #[arg(long, value_name = "DATE")]
+    pub until: Option<String>,
+
+    /// Maximum number of sessions to show, newest first.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Output the matching sessions as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl SessionsCli {
+    pub async fn run(self) -> Result<()> {
+        let SessionsCli {
+            config_overrides,
+            cmd,
+        } = self;
+        // Validate any provided overrides even though they are not currently applied.
+        config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+
+        match cmd {
+            SessionsSubcommand::Search(args) => run_search(args).await,
+        }
+    }
+}
+
+async fn run_search(args: SearchArgs) -> Result<()> {
+    let SearchArgs {
+        query,
+        repo,
+        cwd,
+        since,
+        until,
+        limit,
+        json,
+    } = args;
+
+    for date in since.iter().chain(until.iter()) {
+        if !is_valid_search_date(date) {
+            bail!("invalid date '{date}': expected YYYY, YYYY-MM or YYYY-MM-DD");
+        }
+    }
+    let cwd = cwd
+        .map(std::path::absolute)
+        .transpose()
+        .context("failed to resolve --cwd")?;
+
+    let icodex_home = find_icodex_home().context("failed to resolve CODEX_HOME")?;
+    let query = SessionSearchQuery {
+        text: query.join(" "),
+        repo,
+        cwd,
+        since,
+        until,
+    };
+    let hits = search_sessions(&icodex_home, &query, limit)
+        .await
+        .with_context(|| format!("failed to search sessions in {}", icodex_home.display()))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("No matching sessions.");
+        return Ok(());
+    }
+    for (i, hit) in hits.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print_hit(hit);
+    }
+    println!();
+    println!("Resume a session with `icodex resume <SESSION_ID>`.");
+
+    Ok(())
+}
+
+fn print_hit(hit: &SessionSearchHit) {
+    // RFC 3339 down to the minute.
+    let started = hit.timestamp.get(..16).unwrap_or(&hit.timestamp);
+    println!("{}  {}", started.replacen('T', " ", 1), hit.id);
+
+    let mut location = hit.cwd.display().to_string();
+    if let Some(git) = &hit.git {
+        let details: Vec<&str> = [git.branch.as_deref(), git.repository_url.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !details.is_empty() {
+            location.push_str(&format!(" ({})", details.join(", ")));
+        }
+    }
+    println!("  {location}");
+
+    for search_match in &hit.matches {
+        let label = match search_match.field {
+            SearchField::UserMessage => "user",
+            SearchField::AgentMessage => "agent",
+            SearchField::Command => "command",
+            SearchField::FilePath => "file",
+        };
+        println!("  {label}: {}", snippet(&search_match.text));
+    }
+}
+
+/// The text on one line, shortened to [`SNIPPET_CHARS`].
+fn snippet(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= SNIPPET_CHARS {
+        return line;
+    }
+    let mut shortened: String = line.chars().take(SNIPPET_CHARS - 1).collect();
+    shortened.push('…');
+    shortened
+}
@@ -2,6 +2,7 @@ use std::path::Path;
 
 use anyhow::Result;
 use icodex_core::config::load_global_mcp_servers;
+use icodex_core::config_types::McpServerTransportConfig;
 use predicates::str::contains;
 use pretty_assertions::assert_eq;
 use tempfile::TempDir;
@@ -26,9 +27,12 @@ fn add_and_remove_server_updates_global_config() -> Result<()> {
     let servers = load_global_mcp_servers(icodex_home.path())?;
     assert_eq!(servers.len(), 1);
     let docs = servers.get("docs").expect("server should exist");
-    assert_eq!(docs.command, "echo");
-    assert_eq!(docs.args, vec!["hello".to_string()]);
-    assert!(docs.env.is_none());
+    let McpServerTransportConfig::Stdio { command, args, env } = &docs.transport else {
+        panic!("expected a stdio server: {docs:?}");
+    };
+    assert_eq!(command, "echo");
+    assert_eq!(args, &vec!["hello".to_string()]);
+    assert!(env.is_none());
 
     let mut remove_cmd = icodex_command(icodex_home.path())?;
     remove_cmd
@@ -76,7 +80,10 @@ fn add_with_env_preserves_key_order_and_values() -> Result<()> {
 
     let servers = load_global_mcp_servers(icodex_home.path())?;
     let envy = servers.get("envy").expect("server should exist");
-    let env = envy.env.as_ref().expect("env should be present");
+    let McpServerTransportConfig::Stdio { env, .. } = &envy.transport else {
+        panic!("expected a stdio server: {envy:?}");
+    };
+    let env = env.as_ref().expect("env should be present");
 
     assert_eq!(env.len(), 2);
     assert_eq!(env.get("FOO"), Some(&"bar".to_string()));
@@ -84,3 +91,59 @@ fn add_with_env_preserves_key_order_and_values() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn add_url_server_with_bearer_token() -> Result<()> {
+    let icodex_home = TempDir::new()?;
+
+    let mut add_cmd = icodex_command(icodex_home.path())?;
+    add_cmd
+        .args([
+            "mcp",
+            "add",
+            "remote",
+            "--url",
+            "https://mcp.example.com/mcp",
+            "--bearer-token-env-var",
+            "REMOTE_TOKEN",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Added global MCP server 'remote'."));
+
+    let servers = load_global_mcp_servers(icodex_home.path())?;
+    let remote = servers.get("remote").expect("server should exist");
+    assert_eq!(
+        remote.transport,
+        McpServerTransportConfig::StreamableHttp {
+            url: "https://mcp.example.com/mcp".to_string(),
+            bearer_token_env_var: Some("REMOTE_TOKEN".to_string()),
+            http_headers: None,
+            env_http_headers: None,
+        }
+    );
+
+    let mut get_cmd = icodex_command(icodex_home.path())?;
+    get_cmd
+        .args(["mcp", "get", "remote"])
+        .assert()
+        .success()
+        .stdout(contains("url: https://mcp.example.com/mcp"))
+        .stdout(contains("bearer_token_env_var: REMOTE_TOKEN"));
+
+    let mut conflicting_cmd = icodex_command(icodex_home.path())?;
+    conflicting_cmd
+        .args([
+            "mcp",
+            "add",
+            "both",
+            "--url",
+            "https://mcp.example.com/mcp",
+            "--",
+            "echo",
+        ])
+        .assert()
+        .failure();
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use anyhow::Result;
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+use pretty_assertions::assert_eq;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+use tempfile::TempDir;
+
+fn icodex_command(icodex_home: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("icodex")?;
+    cmd.env("CODEX_HOME", icodex_home);
+    Ok(cmd)
+}
+
+/// Write a rollout as the recorder would, started on `day` (`YYYY-MM-DD`).
+fn write_rollout(icodex_home: &Path, day: &str, id: &str, repo: &str, message: &str) -> Result<()> {
+    let dir = icodex_home
+        .join("sessions")
+        .join(&day[0..4])
+        .join(&day[5..7])
+        .join(&day[8..10]);
+    std::fs::create_dir_all(&dir)?;
+    let lines = [
+        json!({
+            "timestamp": format!("{day}T09:00:00.000Z"),
+            "type": "session_meta",
+            "payload": {
+                "id": id,
+                "timestamp": format!("{day}T09:00:00.000Z"),
+                "instructions": null,
+                "cwd": "/work",
+                "originator": "icodex_cli_rs",
+                "cli_version": "0.0.0",
+                "git": {"branch": "main", "repository_url": repo},
+            },
+        }),
+        json!({
+            "timestamp": format!("{day}T09:00:01.000Z"),
+            "type": "event_msg",
+            "payload": {"type": "user_message", "message": message, "kind": "plain"},
+        }),
+    ];
+    let contents: String = lines.iter().map(|line| format!("{line}\n")).collect();
+    std::fs::write(
+        dir.join(format!("rollout-{day}T09-00-00-{id}.jsonl")),
+        contents,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn search_filters_by_text_repo_and_date() -> Result<()> {
+    let icodex_home = TempDir::new()?;
+    let app = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let docs = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+    write_rollout(
+        icodex_home.path(),
+        "2025-03-01",
+        app,
+        "https://github.com/example/app.git",
+        "fix the flaky migration test",
+    )?;
+    write_rollout(
+        icodex_home.path(),
+        "2025-04-01",
+        docs,
+        "https://github.com/example/docs.git",
+        "document the migration steps",
+    )?;
+
+    let mut cmd = icodex_command(icodex_home.path())?;
+    let output = cmd
+        .args(["sessions", "search", "migration", "--json"])
+        .output()?;
+    assert!(output.status.success());
+    let hits: JsonValue = serde_json::from_slice(&output.stdout)?;
+    let ids: Vec<&str> = hits
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|hit| hit["id"].as_str())
+        .collect();
+    assert_eq!(ids, vec![docs, app]);
+
+    let mut cmd = icodex_command(icodex_home.path())?;
+    cmd.args(["sessions", "search", "migration", "--repo", "example/app"])
+        .assert()
+        .success()
+        .stdout(contains(app))
+        .stdout(contains("user: fix the flaky migration test"))
+        .stdout(contains(docs).not());
+
+    let mut cmd = icodex_command(icodex_home.path())?;
+    cmd.args(["sessions", "search", "migration", "--since", "2025-04"])
+        .assert()
+        .success()
+        .stdout(contains(docs))
+        .stdout(contains(app).not());
+
+    let mut cmd = icodex_command(icodex_home.path())?;
+    cmd.args(["sessions", "search", "release", "notes"])
+        .assert()
+        .success()
+        .stdout(contains("No matching sessions."));
+
+    Ok(())
+}
+
+#[test]
+fn search_rejects_malformed_dates() -> Result<()> {
+    let icodex_home = TempDir::new()?;
+
+    let mut cmd = icodex_command(icodex_home.path())?;
+    cmd.args(["sessions", "search", "--since", "last week"])
+        .assert()
+        .failure()
+        .stderr(contains("invalid date 'last week'"));
+
+    Ok(())
+}
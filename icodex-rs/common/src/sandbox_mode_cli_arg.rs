@@ -13,6 +13,7 @@ use icodex_protocol::config_types::SandboxMode;
 #[value(rename_all = "kebab-case")]
 pub enum SandboxModeCliArg {
     ReadOnly,
+    WorkspaceRead,
     WorkspaceWrite,
     DangerFullAccess,
 }
@@ -21,6 +22,7 @@ impl From<SandboxModeCliArg> for SandboxMode {
     fn from(value: SandboxModeCliArg) -> Self {
         match value {
             SandboxModeCliArg::ReadOnly => SandboxMode::ReadOnly,
+            SandboxModeCliArg::WorkspaceRead => SandboxMode::WorkspaceRead,
             SandboxModeCliArg::WorkspaceWrite => SandboxMode::WorkspaceWrite,
             SandboxModeCliArg::DangerFullAccess => SandboxMode::DangerFullAccess,
         }
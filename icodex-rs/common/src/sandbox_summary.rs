@@ -4,6 +4,22 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
     match sandbox_policy {
         SandboxPolicy::DangerFullAccess => "danger-full-access".to_string(),
         SandboxPolicy::ReadOnly => "read-only".to_string(),
+        SandboxPolicy::WorkspaceRead {
+            readable_roots,
+            exclude_default_readable_roots,
+        } => {
+            let mut readable_entries = Vec::<String>::new();
+            readable_entries.push("workdir".to_string());
+            if !*exclude_default_readable_roots {
+                readable_entries.push("system".to_string());
+            }
+            readable_entries.extend(
+                readable_roots
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string()),
+            );
+            format!("workspace-read [{}]", readable_entries.join(", "))
+        }
         SandboxPolicy::WorkspaceWrite {
             writable_roots,
             network_access,
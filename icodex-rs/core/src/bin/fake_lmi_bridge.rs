@@ -0,0 +1,100 @@
+//! Minimal stand-in for `model-bridge.js` used by the LMI bridge integration
+//! tests. It speaks the line-delimited frame protocol on stdin/stdout and
+//! misbehaves in controlled ways selected by the first argument:
+//!
+//! * `echo` (default) – answers every request by echoing the last user
+//!   message back as a single delta.
+//! * `bad-version` – reports an unsupported protocol version.
+//! * `silent` – completes the handshake, then never answers again.
+//! * `crash-after-hello` – exits right after the handshake unless the marker
+//!   file passed as the second argument already exists, in which case it
+//!   behaves like `echo`. The marker is created before exiting, so the first
+//!   process crashes and its replacement serves requests.
+//! * `garbage` – completes the handshake, then writes unparseable output.
+
+use std::io::BufRead;
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde_json::Value;
+use serde_json::json;
+
+const PROTOCOL_VERSION: u64 = 1;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mode = args.next().unwrap_or_else(|| "echo".to_string());
+    let marker = args.next().map(PathBuf::from);
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(request) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let kind = request.get("type").and_then(Value::as_str).unwrap_or("");
+
+        let frames = match (mode.as_str(), kind) {
+            ("bad-version", "hello") => vec![json!({"type": "hello", "protocol_version": 999})],
+            (_, "hello") => vec![json!({
+                "type": "hello",
+                "protocol_version": PROTOCOL_VERSION,
+                "bridge_version": "fake",
+            })],
+            ("silent", _) => continue,
+            ("crash-after-hello", _) if marker.as_ref().is_some_and(|m| !m.exists()) => {
+                if let Some(marker) = &marker {
+                    let _ = std::fs::write(marker, "");
+                }
+                std::process::exit(1);
+            }
+            ("garbage", _) => {
+                for _ in 0..64 {
+                    let _ = writeln!(stdout, "this is not a frame");
+                }
+                let _ = stdout.flush();
+                continue;
+            }
+            (_, "ping") => vec![json!({"type": "pong"})],
+            (_, "chat_completion") => vec![
+                json!({"type": "created", "response_id": "fake-response"}),
+                json!({"type": "delta", "text": last_user_text(&request)}),
+                json!({
+                    "type": "completed",
+                    "usage": {"input_tokens": 1, "output_tokens": 1},
+                }),
+            ],
+            _ => continue,
+        };
+
+        for mut frame in frames {
+            if let Some(obj) = frame.as_object_mut() {
+                obj.insert("id".to_string(), id.clone());
+            }
+            if writeln!(stdout, "{frame}").is_err() {
+                return;
+            }
+        }
+        let _ = stdout.flush();
+    }
+}
+
+fn last_user_text(request: &Value) -> String {
+    request
+        .get("messages")
+        .and_then(Value::as_array)
+        .and_then(|messages| {
+            messages
+                .iter()
+                .rev()
+                .find(|m| m.get("role").and_then(Value::as_str) == Some("user"))
+        })
+        .and_then(|m| m.get("content"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
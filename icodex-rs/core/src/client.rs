@@ -1,6 +1,9 @@
+use std::borrow::Cow;
 use std::io::BufRead;
 use std::path::Path;
 use std::sync::OnceLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use crate::AuthManager;
@@ -35,7 +38,11 @@ use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::gemini::stream_gemini;
+use crate::lmi_bridge_client::LmiBridgeClient;
+use crate::messages_api::stream_messages;
 use crate::model_family::ModelFamily;
+use crate::model_provider_info::FallbackProvider;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::WireApi;
 use crate::openai_model_info::get_model_info;
@@ -74,6 +81,11 @@ pub struct ModelClient {
     conversation_id: ConversationId,
     effort: Option<ReasoningEffortConfig>,
     summary: ReasoningSummaryConfig,
+    lmi_bridge: LmiBridgeClient,
+    /// Index of the provider tried first: 0 is `provider`, `n` is
+    /// `config.fallback_providers[n - 1]`. Shared between clones so a
+    /// failover sticks for the rest of the session.
+    active_route: Arc<AtomicUsize>,
 }
 
 impl ModelClient {
@@ -86,6 +98,7 @@ impl ModelClient {
         conversation_id: ConversationId,
     ) -> Self {
         let client = create_client();
+        let lmi_bridge = LmiBridgeClient::new(config.lmi_bridge_command.clone());
 
         Self {
             config,
@@ -95,6 +108,8 @@ impl ModelClient {
             conversation_id,
             effort,
             summary,
+            lmi_bridge,
+            active_route: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -110,13 +125,82 @@ impl ModelClient {
         })
     }
 
+    /// Streams a response from the active provider, failing over to the next
+    /// entry of `fallback_providers` when the provider is unavailable. The
+    /// provider that served the request stays active for later requests.
+    pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        let routes = self.config.fallback_providers.len() + 1;
+        let first = self.active_route.load(Ordering::Relaxed) % routes;
+        let mut attempt = 0;
+        loop {
+            let route = (first + attempt) % routes;
+            match self.for_route(route).stream_from_provider(prompt).await {
+                Ok(stream) => {
+                    self.active_route.store(route, Ordering::Relaxed);
+                    return Ok(stream);
+                }
+                Err(e) if attempt + 1 < routes && e.is_provider_unavailable() => {
+                    warn!("model provider unavailable, trying the next fallback: {e}");
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns a client that targets the given route; see `active_route`.
+    fn for_route(&self, route: usize) -> Cow<'_, Self> {
+        let Some(fallback) = route
+            .checked_sub(1)
+            .and_then(|index| self.config.fallback_providers.get(index))
+        else {
+            return Cow::Borrowed(self);
+        };
+        let config = match &fallback.model {
+            Some(model) if *model != self.config.model => Arc::new(self.config.with_model(model)),
+            _ => Arc::clone(&self.config),
+        };
+        Cow::Owned(Self {
+            config,
+            provider: fallback.provider.clone(),
+            ..self.clone()
+        })
+    }
+
+    fn active_fallback(&self) -> Option<&FallbackProvider> {
+        self.active_route
+            .load(Ordering::Relaxed)
+            .checked_sub(1)
+            .and_then(|index| self.config.fallback_providers.get(index))
+    }
+
     /// Dispatches to either the Responses or Chat implementation depending on
     /// the provider config.  Public callers always invoke `stream()` – the
     /// specialised helpers are private to avoid accidental misuse.
-    pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+    async fn stream_from_provider(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
             WireApi::LmiBridge => self.stream_lmi_bridge(prompt).await,
+            WireApi::Messages => {
+                stream_messages(
+                    prompt,
+                    &self.config.model_family,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                )
+                .await
+            }
+            WireApi::Gemini => {
+                stream_gemini(
+                    prompt,
+                    &self.config.model_family,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                )
+                .await
+            }
             WireApi::Chat => {
                 // Create the raw streaming connection first.
                 let response_stream = stream_chat_completions(
@@ -209,7 +293,7 @@ impl ModelClient {
             input: &input_with_instructions,
             tools: &tools_json,
             tool_choice: "auto",
-            parallel_tool_calls: false,
+            parallel_tool_calls: self.config.model_family.supports_parallel_tool_calls,
             reasoning,
             store: azure_workaround,
             stream: true,
@@ -365,15 +449,9 @@ impl ModelClient {
 
     /// Implementation for the Large Models Interface bridge.
     async fn stream_lmi_bridge(&self, prompt: &Prompt) -> Result<ResponseStream> {
-        use crate::lmi_bridge_client::LmiBridgeClient;
-        
-        let mut bridge_client = LmiBridgeClient::new(
-            self.config.clone(),
-            self.provider.clone(),
-            self.conversation_id,
-        );
-        
-        bridge_client.stream(prompt).await
+        self.lmi_bridge
+            .stream(prompt, &self.config.model_family, &self.provider)
+            .await
     }
 
     pub fn get_provider(&self) -> ModelProviderInfo {
@@ -385,6 +463,38 @@ impl ModelClient {
         self.config.model.clone()
     }
 
+    /// Returns the key of the provider that serves requests, which differs
+    /// from the configured one after a failover.
+    pub fn get_active_provider_id(&self) -> String {
+        self.active_fallback()
+            .map(|fallback| fallback.provider_id.clone())
+            .unwrap_or_else(|| self.config.model_provider_id.clone())
+    }
+
+    /// Returns the model slug requested from the active provider.
+    pub fn get_active_model(&self) -> String {
+        self.active_fallback()
+            .and_then(|fallback| fallback.model.clone())
+            .unwrap_or_else(|| self.config.model.clone())
+    }
+
+    /// Makes `provider_id` the provider tried first, e.g. when resuming a
+    /// session that had failed over. Unknown keys are ignored.
+    pub fn set_active_provider(&self, provider_id: &str) {
+        let route = if provider_id == self.config.model_provider_id {
+            Some(0)
+        } else {
+            self.config
+                .fallback_providers
+                .iter()
+                .position(|fallback| fallback.provider_id == provider_id)
+                .map(|index| index + 1)
+        };
+        if let Some(route) = route {
+            self.active_route.store(route, Ordering::Relaxed);
+        }
+    }
+
     /// Returns the currently configured model family.
     pub fn get_model_family(&self) -> ModelFamily {
         self.config.model_family.clone()
@@ -403,6 +513,11 @@ impl ModelClient {
     pub fn get_auth_manager(&self) -> Option<Arc<AuthManager>> {
         self.auth_manager.clone()
     }
+
+    /// Returns the configuration the client was built with.
+    pub fn get_config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -1,10 +1,12 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::MutexGuard;
+use std::sync::Weak;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
 
@@ -26,17 +28,25 @@ use icodex_protocol::protocol::TurnAbortReason;
 use icodex_protocol::protocol::TurnAbortedEvent;
 use icodex_protocol::protocol::TurnContextItem;
 use futures::prelude::*;
+use futures::stream::FuturesOrdered;
 use mcp_types::CallToolResult;
+use mcp_types::CreateMessageRequestParams;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json;
+use tokio::sync::AcquireError;
+use tokio::sync::Semaphore;
+use tokio::sync::SemaphorePermit;
+use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::sync::watch;
 use tokio::task::AbortHandle;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing::trace;
 use tracing::warn;
+use uuid::Uuid;
 
 use crate::ModelProviderInfo;
 use crate::apply_patch;
@@ -48,7 +58,9 @@ use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::ExecLimits;
 use crate::config_types::ShellEnvironmentPolicy;
+use crate::container::ContainerSandbox;
 use crate::conversation_history::ConversationHistory;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
@@ -67,10 +79,18 @@ use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::exec_policy::ExecPolicy;
+use crate::is_safe_command::is_known_safe_command;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::mcp_connection_manager::McpServerRequest;
+use crate::mcp_resources;
+use crate::mcp_resources::LIST_MCP_RESOURCES_TOOL_NAME;
+use crate::mcp_resources::READ_MCP_RESOURCE_TOOL_NAME;
+use crate::mcp_resources::handle_list_mcp_resources;
+use crate::mcp_resources::handle_read_mcp_resource;
 use crate::mcp_tool_call::handle_mcp_tool_call;
-use crate::model_family::find_family_for_model;
-use crate::openai_model_info::get_model_info;
+use crate::network_proxy::NetworkAllowlist;
+use crate::network_proxy::NetworkProxy;
 use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
@@ -94,6 +114,12 @@ use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::McpInvocation;
+use crate::protocol::McpListPromptsResponseEvent;
+use crate::protocol::McpListResourcesResponseEvent;
+use crate::protocol::McpListToolsResponseEvent;
+use crate::protocol::McpSamplingApprovalRequestEvent;
+use crate::protocol::McpToolCallApprovalRequestEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
@@ -107,14 +133,24 @@ use crate::protocol::TaskCompleteEvent;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
+use crate::protocol::TurnRolledBackEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::review_target::collect_review_context;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::safety::get_platform_sandbox;
 use crate::shell;
+use crate::sub_agent::AgentSandbox;
+use crate::sub_agent::SPAWN_AGENT_TOOL_NAME;
+use crate::sub_agent::handle_spawn_agent;
+use crate::sub_agent::parse_spawn_agent_arguments;
 use crate::turn_diff_tracker::TurnDiffTracker;
+use crate::turn_snapshot::MAX_TURN_SNAPSHOTS;
+use crate::turn_snapshot::TurnSnapshot;
+use crate::turn_snapshot::discard_snapshots;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
 use crate::user_notification::UserNotification;
@@ -131,8 +167,10 @@ use icodex_protocol::models::ShellToolCallParams;
 use icodex_protocol::protocol::InitialHistory;
 
 mod compact;
+mod mcp_server_requests;
 use self::compact::build_compacted_history;
 use self::compact::collect_user_messages;
+use self::mcp_server_requests::serve_mcp_server_requests;
 
 // A convenience extension trait for acquiring mutex locks where poisoning is
 // unrecoverable and should abort the program. This avoids scattered `.unwrap()`
@@ -167,6 +205,11 @@ pub struct CodexSpawnOk {
 
 pub(crate) const INITIAL_SUBMIT_ID: &str = "";
 pub(crate) const SUBMISSION_CHANNEL_CAPACITY: usize = 64;
+/// Requests from MCP servers waiting for the session to answer them.
+const MCP_SERVER_REQUEST_CHANNEL_CAPACITY: usize = 16;
+
+/// Recorded in the history after `Op::RollbackTurn` restored the workspace.
+const ROLLBACK_NOTE: &str = "The user rolled the workspace back to how it was before one of the earlier turns. Changes to files made since then, including yours, have been undone; read files again before relying on their contents.";
 
 // Model-formatting limits: clients get full streams; oonly content sent to the model is truncated.
 pub(crate) const MODEL_FORMAT_MAX_BYTES: usize = 10 * 1024; // 10 KiB
@@ -184,6 +227,7 @@ impl Codex {
     ) -> CodexResult<CodexSpawnOk> {
         let (tx_sub, rx_sub) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
         let (tx_event, rx_event) = async_channel::unbounded();
+        let (tx_mcp_requests, rx_mcp_requests) = mpsc::channel(MCP_SERVER_REQUEST_CHANNEL_CAPACITY);
 
         let user_instructions = get_user_instructions(&config).await;
 
@@ -208,6 +252,7 @@ impl Codex {
             config.clone(),
             auth_manager.clone(),
             tx_event.clone(),
+            tx_mcp_requests,
             conversation_history,
         )
         .await
@@ -218,7 +263,13 @@ impl Codex {
         let conversation_id = session.conversation_id;
 
         // This task will run until Op::Shutdown is received.
-        tokio::spawn(submission_loop(session, turn_context, config, rx_sub));
+        tokio::spawn(submission_loop(
+            session,
+            turn_context,
+            config,
+            rx_sub,
+            rx_mcp_requests,
+        ));
         let icodex = Codex {
             next_id: AtomicU64::new(0),
             tx_sub,
@@ -268,8 +319,15 @@ struct State {
     approved_commands: HashSet<Vec<String>>,
     current_task: Option<AgentTask>,
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    /// MCP servers the user allowed to sample the model for the rest of the
+    /// session.
+    approved_sampling_servers: HashSet<String>,
+    /// (server, tool) pairs the user approved for the rest of the session.
+    approved_mcp_tools: HashSet<(String, String)>,
     pending_input: Vec<ResponseInputItem>,
     history: ConversationHistory,
+    /// Snapshots of the workspace taken before each turn, oldest first.
+    turn_snapshots: Vec<TurnSnapshot>,
     token_info: Option<TokenUsageInfo>,
     next_internal_sub_id: u64,
 }
@@ -297,6 +355,25 @@ pub(crate) struct Session {
     icodex_linux_sandbox_exe: Option<PathBuf>,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
+
+    /// User and project `exec.policy` rules consulted before running shell
+    /// commands.
+    exec_policy: ExecPolicy,
+
+    /// Proxy to the `network_allowlist` hosts for sandboxed shell commands.
+    network_proxy: Option<NetworkProxy>,
+
+    /// Resource limits applied to every shell command.
+    exec_limits: ExecLimits,
+
+    /// Container that replaces the platform sandbox, when configured.
+    container_sandbox: Option<ContainerSandbox>,
+
+    /// Whether to snapshot the workspace before each turn.
+    turn_snapshots: bool,
+
+    /// One permit per sub-agent that may run at once.
+    agent_slots: Semaphore,
 }
 
 /// The context needed for a single turn of the conversation.
@@ -322,6 +399,19 @@ impl TurnContext {
             .map(PathBuf::from)
             .map_or_else(|| self.cwd.clone(), |p| self.cwd.join(p))
     }
+
+    /// Snapshot of the settings used for a turn, as recorded in the rollout.
+    pub(crate) fn to_turn_context_item(&self) -> TurnContextItem {
+        TurnContextItem {
+            cwd: self.cwd.clone(),
+            approval_policy: self.approval_policy,
+            sandbox_policy: self.sandbox_policy.clone(),
+            model: self.client.get_active_model(),
+            model_provider: Some(self.client.get_active_provider_id()),
+            effort: self.client.get_reasoning_effort(),
+            summary: self.client.get_reasoning_summary(),
+        }
+    }
 }
 
 /// Configure the model session.
@@ -367,6 +457,7 @@ impl Session {
         config: Arc<Config>,
         auth_manager: Arc<AuthManager>,
         tx_event: Sender<Event>,
+        mcp_server_requests: mpsc::Sender<McpServerRequest>,
         initial_history: InitialHistory,
     ) -> anyhow::Result<(Arc<Self>, TurnContext)> {
         let ConfigureSession {
@@ -411,7 +502,7 @@ impl Session {
         // - load history metadata
         let rollout_fut = RolloutRecorder::new(&config, rollout_params);
 
-        let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone());
+        let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone(), mcp_server_requests);
         let default_shell_fut = shell::default_user_shell();
         let history_meta_fut = crate::message_history::history_metadata(&config);
 
@@ -456,6 +547,35 @@ impl Session {
             }
         }
 
+        let (exec_policy, exec_policy_errors) =
+            ExecPolicy::load(&config.icodex_home, &cwd, config.project_trusted);
+        for message in exec_policy_errors {
+            error!("{message}");
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            });
+        }
+
+        // Only the Linux sandbox can send commands through the proxy.
+        let (network_proxy, denied_hosts) =
+            if cfg!(target_os = "linux") && !config.network_allowlist.is_empty() {
+                match NetworkProxy::start(NetworkAllowlist::new(&config.network_allowlist)) {
+                    Ok((proxy, denied_hosts)) => (Some(proxy), Some(denied_hosts)),
+                    Err(e) => {
+                        let message = format!("Failed to start the network proxy: {e}");
+                        error!("{message}");
+                        post_session_configured_error_events.push(Event {
+                            id: INITIAL_SUBMIT_ID.to_owned(),
+                            msg: EventMsg::Error(ErrorEvent { message }),
+                        });
+                        (None, None)
+                    }
+                }
+            } else {
+                (None, None)
+            };
+
         // Now that the conversation id is final (may have been updated by resume),
         // construct the model client.
         let client = ModelClient::new(
@@ -466,6 +586,20 @@ impl Session {
             model_reasoning_summary,
             conversation_id,
         );
+        // Keep serving a resumed session from the provider it had failed over to.
+        if let InitialHistory::Resumed(resumed_history) = &initial_history {
+            let last_provider = resumed_history
+                .history
+                .iter()
+                .rev()
+                .find_map(|item| match item {
+                    RolloutItem::TurnContext(ctx) => ctx.model_provider.as_deref(),
+                    _ => None,
+                });
+            if let Some(provider_id) = last_provider {
+                client.set_active_provider(provider_id);
+            }
+        }
         let turn_context = TurnContext {
             client,
             tools_config: ToolsConfig::new(&ToolsConfigParams {
@@ -478,6 +612,7 @@ impl Session {
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
                 experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                include_spawn_agent_tool: config.can_spawn_agents(),
             }),
             user_instructions,
             base_instructions,
@@ -499,7 +634,16 @@ impl Session {
             icodex_linux_sandbox_exe: config.icodex_linux_sandbox_exe.clone(),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            exec_policy,
+            network_proxy,
+            exec_limits: config.exec_limits,
+            container_sandbox: config.container_sandbox.clone(),
+            turn_snapshots: config.turn_snapshots,
+            agent_slots: Semaphore::new(config.agents.max_concurrency),
         });
+        if let Some(denied_hosts) = denied_hosts {
+            tokio::spawn(report_denied_hosts(Arc::downgrade(&sess), denied_hosts));
+        }
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
         // If resuming, include converted initial messages in the payload so UIs can render them immediately.
@@ -655,6 +799,115 @@ impl Session {
         rx_approve
     }
 
+    /// Ask the user whether the model may make the MCP tool call `invocation`.
+    pub(crate) async fn request_mcp_tool_call_approval(
+        &self,
+        sub_id: String,
+        call_id: String,
+        invocation: McpInvocation,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let event_id = sub_id.clone();
+        let prev_entry = {
+            let mut state = self.state.lock_unchecked();
+            state.pending_approvals.insert(sub_id, tx_approve)
+        };
+        if prev_entry.is_some() {
+            warn!("Overwriting existing pending approval for sub_id: {event_id}");
+        }
+
+        let event = Event {
+            id: event_id,
+            msg: EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+                call_id,
+                invocation,
+            }),
+        };
+        self.send_event(event).await;
+        rx_approve
+    }
+
+    pub(crate) fn mcp_tool_requires_approval(&self, server: &str, tool: &str) -> bool {
+        self.mcp_connection_manager
+            .tool_requires_approval(server, tool)
+    }
+
+    pub(crate) fn is_mcp_tool_approved_for_session(&self, server: &str, tool: &str) -> bool {
+        let state = self.state.lock_unchecked();
+        state
+            .approved_mcp_tools
+            .contains(&(server.to_string(), tool.to_string()))
+    }
+
+    pub(crate) fn add_approved_mcp_tool(&self, server: String, tool: String) {
+        let mut state = self.state.lock_unchecked();
+        state.approved_mcp_tools.insert((server, tool));
+    }
+
+    /// Send the tools and the status of every MCP server in reply to `sub_id`.
+    async fn send_mcp_tools_list(&self, sub_id: String) {
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::McpListToolsResponse(McpListToolsResponseEvent {
+                tools: self.mcp_connection_manager.list_all_tools(),
+                servers: self.mcp_connection_manager.server_statuses(),
+            }),
+        };
+        self.send_event(event).await;
+    }
+
+    /// Report the outcome of restarting, enabling or disabling an MCP server,
+    /// followed by the updated tool list.
+    async fn finish_mcp_server_update(&self, sub_id: String, result: anyhow::Result<()>) {
+        if let Err(e) = result {
+            let event = Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message: format!("{e:#}"),
+                }),
+            };
+            self.send_event(event).await;
+        }
+        self.send_mcp_tools_list(sub_id).await;
+    }
+
+    /// Ask the user whether the MCP server `server` may sample the model with
+    /// `request`. Answered by `Op::McpSamplingApproval`.
+    pub async fn request_mcp_sampling_approval(
+        &self,
+        server: String,
+        request: CreateMessageRequestParams,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        let (tx_approve, rx_approve) = oneshot::channel();
+        // Sampling requests arrive outside of any submission, so they get an
+        // id of their own.
+        let event_id = format!("mcp-sampling-{}", Uuid::new_v4());
+        {
+            let mut state = self.state.lock_unchecked();
+            state.pending_approvals.insert(event_id.clone(), tx_approve);
+        }
+
+        let event = Event {
+            id: event_id,
+            msg: EventMsg::McpSamplingApprovalRequest(McpSamplingApprovalRequestEvent {
+                server,
+                request,
+            }),
+        };
+        self.send_event(event).await;
+        rx_approve
+    }
+
+    fn is_sampling_approved_for_session(&self, server: &str) -> bool {
+        let state = self.state.lock_unchecked();
+        state.approved_sampling_servers.contains(server)
+    }
+
+    fn approve_sampling_for_session(&self, server: String) {
+        let mut state = self.state.lock_unchecked();
+        state.approved_sampling_servers.insert(server);
+    }
+
     pub fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut state = self.state.lock_unchecked();
@@ -847,6 +1100,7 @@ impl Session {
             duration,
             exit_code,
             timed_out: _,
+            limit_exceeded,
         } = output;
         // Send full stdout/stderr to clients; do not truncate.
         let stdout = stdout.text.clone();
@@ -870,6 +1124,7 @@ impl Session {
                 exit_code: *exit_code,
                 duration: *duration,
                 formatted_output,
+                limit_exceeded: *limit_exceeded,
             })
         };
 
@@ -882,8 +1137,8 @@ impl Session {
         // If this is an apply_patch, after we emit the end patch, emit a second event
         // with the full turn diff if there is one.
         if is_apply_patch {
-            let unified_diff = turn_diff_tracker.get_unified_diff();
-            if let Ok(Some(unified_diff)) = unified_diff {
+            let unified_diff = self.turn_unified_diff(sub_id, turn_diff_tracker).await;
+            if let Some(unified_diff) = unified_diff {
                 let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
                 let event = Event {
                     id: sub_id.into(),
@@ -915,6 +1170,8 @@ impl Session {
             exec_args.sandbox_type,
             exec_args.sandbox_policy,
             exec_args.icodex_linux_sandbox_exe,
+            exec_args.container_sandbox,
+            &exec_args.exec_limits,
             exec_args.stdout_stream,
         )
         .await;
@@ -931,6 +1188,7 @@ impl Session {
                     aggregated_output: StreamOutput::new(get_error_message_ui(e)),
                     duration: Duration::default(),
                     timed_out: false,
+                    limit_exceeded: None,
                 };
                 &output_stderr
             }
@@ -947,6 +1205,29 @@ impl Session {
         result
     }
 
+    /// The network proxy socket for shell commands run with `turn_context`:
+    /// only workspace-write sandboxes without network access use the proxy.
+    fn network_proxy_socket(&self, turn_context: &TurnContext) -> Option<&Path> {
+        match turn_context.sandbox_policy {
+            SandboxPolicy::WorkspaceWrite {
+                network_access: false,
+                ..
+            } => self.network_proxy.as_ref().map(NetworkProxy::socket_path),
+            _ => None,
+        }
+    }
+
+    /// The id of the running task, for events that do not belong to a
+    /// submission of their own.
+    fn current_sub_id(&self) -> String {
+        let state = self.state.lock_unchecked();
+        state
+            .current_task
+            .as_ref()
+            .map(|task| task.sub_id.clone())
+            .unwrap_or_else(|| INITIAL_SUBMIT_ID.to_owned())
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
@@ -970,6 +1251,127 @@ impl Session {
         self.send_event(event).await;
     }
 
+    /// Snapshot the workspace before the turn `sub_id` runs, so that it can be
+    /// rolled back.
+    async fn snapshot_turn(&self, turn_context: &TurnContext, sub_id: &str) {
+        match TurnSnapshot::take(&turn_context.cwd, self.conversation_id, sub_id).await {
+            Ok(Some(snapshot)) => {
+                let discarded: Vec<TurnSnapshot> = {
+                    let mut state = self.state.lock_unchecked();
+                    state.turn_snapshots.push(snapshot);
+                    let excess = state
+                        .turn_snapshots
+                        .len()
+                        .saturating_sub(MAX_TURN_SNAPSHOTS);
+                    state.turn_snapshots.drain(..excess).collect()
+                };
+                discard_snapshots(&discarded).await;
+            }
+            // Not in a git repository; the turn cannot be rolled back.
+            Ok(None) => {}
+            Err(e) => {
+                warn!("failed to snapshot the workspace before turn {sub_id}: {e}");
+                self.notify_background_event(
+                    sub_id,
+                    format!("This turn cannot be rolled back: {e}."),
+                )
+                .await;
+            }
+        }
+    }
+
+    /// The diff of everything the turn `sub_id` changed so far. Taken against
+    /// the turn's snapshot when there is one, so that changes made by shell
+    /// commands are included, and otherwise built from the `apply_patch` calls
+    /// seen by `turn_diff_tracker`.
+    async fn turn_unified_diff(
+        &self,
+        sub_id: &str,
+        turn_diff_tracker: &mut TurnDiffTracker,
+    ) -> Option<String> {
+        let snapshot = self
+            .state
+            .lock_unchecked()
+            .turn_snapshots
+            .iter()
+            .rfind(|snapshot| snapshot.turn_id() == sub_id)
+            .cloned();
+        if let Some(snapshot) = snapshot {
+            match snapshot.diff().await {
+                Ok(unified_diff) => return unified_diff,
+                Err(e) => warn!("failed to diff the workspace against its snapshot: {e}"),
+            }
+        }
+        turn_diff_tracker.get_unified_diff().ok().flatten()
+    }
+
+    /// Handle `Op::RollbackTurn`.
+    async fn rollback_turn(&self, sub_id: String, turn_id: Option<String>) {
+        let msg = match self.try_rollback_turn(turn_id).await {
+            Ok(event) => EventMsg::TurnRolledBack(event),
+            Err(message) => EventMsg::Error(ErrorEvent { message }),
+        };
+        self.send_event(Event { id: sub_id, msg }).await;
+    }
+
+    async fn try_rollback_turn(
+        &self,
+        turn_id: Option<String>,
+    ) -> Result<TurnRolledBackEvent, String> {
+        let snapshot = {
+            let state = self.state.lock_unchecked();
+            if state.current_task.is_some() {
+                return Err("cannot roll back while a turn is running".to_string());
+            }
+            match &turn_id {
+                Some(turn_id) => state
+                    .turn_snapshots
+                    .iter()
+                    .rfind(|snapshot| snapshot.turn_id() == turn_id)
+                    .cloned()
+                    .ok_or_else(|| format!("no snapshot of turn {turn_id} to roll back to"))?,
+                None => state.turn_snapshots.last().cloned().ok_or_else(|| {
+                    "no turn to roll back; snapshots are only taken inside git repositories"
+                        .to_string()
+                })?,
+            }
+        };
+
+        let restored_paths = snapshot
+            .restore()
+            .await
+            .map_err(|e| format!("failed to roll back turn {}: {e}", snapshot.turn_id()))?;
+        let discarded = {
+            // The snapshots of this turn and later ones describe states that
+            // no longer exist.
+            let mut state = self.state.lock_unchecked();
+            match state
+                .turn_snapshots
+                .iter()
+                .rposition(|candidate| candidate == &snapshot)
+            {
+                Some(index) => state.turn_snapshots.split_off(index),
+                None => Vec::new(),
+            }
+        };
+        discard_snapshots(&discarded).await;
+
+        // Otherwise the model would go on assuming its changes are in place.
+        let note = ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: ROLLBACK_NOTE.to_string(),
+            }],
+        };
+        self.record_conversation_items(&[note]).await;
+
+        Ok(TurnRolledBackEvent {
+            turn_id: snapshot.turn_id().to_string(),
+            restored_paths,
+        })
+    }
+
     /// Build the full turn input by concatenating the current conversation
     /// history with additional items for this turn.
     pub fn turn_input_with_history(&self, extra: Vec<ResponseItem>) -> Vec<ResponseItem> {
@@ -1010,6 +1412,12 @@ impl Session {
             .await
     }
 
+    /// Wait until another sub-agent may start. The sub-agent holds the slot
+    /// until the permit is dropped.
+    pub(crate) async fn acquire_agent_slot(&self) -> Result<SemaphorePermit<'_>, AcquireError> {
+        self.agent_slots.acquire().await
+    }
+
     fn interrupt_task(&self) {
         info!("interrupt received: abort current task, if any");
         let mut state = self.state.lock_unchecked();
@@ -1174,14 +1582,42 @@ impl AgentTask {
     }
 }
 
+/// Tell the user, once per host, which hosts the network proxy refused.
+async fn report_denied_hosts(
+    sess: Weak<Session>,
+    mut denied_hosts: mpsc::UnboundedReceiver<String>,
+) {
+    let mut reported: HashSet<String> = HashSet::new();
+    while let Some(host) = denied_hosts.recv().await {
+        let Some(sess) = sess.upgrade() else {
+            break;
+        };
+        if reported.insert(host.clone()) {
+            let message = format!(
+                "Blocked network access to `{host}`: add it to `sandbox_workspace_write.network_allowlist` to allow it"
+            );
+            sess.notify_background_event(&sess.current_sub_id(), message)
+                .await;
+        }
+    }
+}
+
 async fn submission_loop(
     sess: Arc<Session>,
     turn_context: TurnContext,
     config: Arc<Config>,
     rx_sub: Receiver<Submission>,
+    rx_mcp_requests: mpsc::Receiver<McpServerRequest>,
 ) {
     // Wrap once to avoid cloning TurnContext for each task.
     let mut turn_context = Arc::new(turn_context);
+    // MCP server requests are answered against the most recent turn context.
+    let (turn_context_tx, turn_context_rx) = watch::channel(Arc::clone(&turn_context));
+    tokio::spawn(serve_mcp_server_requests(
+        Arc::downgrade(&sess),
+        rx_mcp_requests,
+        turn_context_rx,
+    ));
     // To break out of this loop, send Op::Shutdown.
     while let Ok(sub) = rx_sub.recv().await {
         debug!(?sub, "Submission");
@@ -1202,13 +1638,7 @@ async fn submission_loop(
                 let provider = prev.client.get_provider();
 
                 // Effective model + family
-                let (effective_model, effective_family) = if let Some(ref m) = model {
-                    let fam =
-                        find_family_for_model(m).unwrap_or_else(|| config.model_family.clone());
-                    (m.clone(), fam)
-                } else {
-                    (prev.client.get_model(), prev.client.get_model_family())
-                };
+                let effective_model = model.clone().unwrap_or_else(|| prev.client.get_model());
 
                 // Effective reasoning settings
                 let effective_effort = effort.unwrap_or(prev.client.get_reasoning_effort());
@@ -1217,12 +1647,8 @@ async fn submission_loop(
                 let auth_manager = prev.client.get_auth_manager();
 
                 // Build updated config for the client
-                let mut updated_config = (*config).clone();
-                updated_config.model = effective_model.clone();
-                updated_config.model_family = effective_family.clone();
-                if let Some(model_info) = get_model_info(&effective_family) {
-                    updated_config.model_context_window = Some(model_info.context_window);
-                }
+                let updated_config = config.with_model(&effective_model);
+                let effective_family = updated_config.model_family.clone();
 
                 let client = ModelClient::new(
                     Arc::new(updated_config),
@@ -1249,6 +1675,7 @@ async fn submission_loop(
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
                     experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                    include_spawn_agent_tool: config.can_spawn_agents(),
                 });
 
                 let new_turn_context = TurnContext {
@@ -1265,6 +1692,7 @@ async fn submission_loop(
 
                 // Install the new persistent context for subsequent tasks/turns.
                 turn_context = Arc::new(new_turn_context);
+                turn_context_tx.send_replace(Arc::clone(&turn_context));
 
                 // Optionally persist changes to model / effort
                 if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
@@ -1302,17 +1730,9 @@ async fn submission_loop(
                     let provider = turn_context.client.get_provider();
                     let auth_manager = turn_context.client.get_auth_manager();
 
-                    // Derive a model family for the requested model; fall back to the session's.
-                    let model_family = find_family_for_model(&model)
-                        .unwrap_or_else(|| config.model_family.clone());
-
                     // Create a per‑turn Config clone with the requested model/family.
-                    let mut per_turn_config = (*config).clone();
-                    per_turn_config.model = model.clone();
-                    per_turn_config.model_family = model_family.clone();
-                    if let Some(model_info) = get_model_info(&model_family) {
-                        per_turn_config.model_context_window = Some(model_info.context_window);
-                    }
+                    let per_turn_config = config.with_model(&model);
+                    let model_family = per_turn_config.model_family.clone();
 
                     // Build a new client with per‑turn reasoning settings.
                     // Reuse the same provider and session id; auth defaults to env/API key.
@@ -1339,6 +1759,7 @@ async fn submission_loop(
                             include_view_image_tool: config.include_view_image_tool,
                             experimental_unified_exec_tool: config
                                 .use_experimental_unified_exec_tool,
+                            include_spawn_agent_tool: config.can_spawn_agents(),
                         }),
                         user_instructions: turn_context.user_instructions.clone(),
                         base_instructions: turn_context.base_instructions.clone(),
@@ -1348,10 +1769,11 @@ async fn submission_loop(
                         cwd,
                         is_review_mode: false,
                     };
+                    let fresh_turn_context = Arc::new(fresh_turn_context);
+                    turn_context_tx.send_replace(Arc::clone(&fresh_turn_context));
                     // TODO: record the new environment context in the conversation history
                     // no current task, spawn a new one with the per‑turn context
-                    let task =
-                        AgentTask::spawn(sess.clone(), Arc::new(fresh_turn_context), sub.id, items);
+                    let task = AgentTask::spawn(sess.clone(), fresh_turn_context, sub.id, items);
                     sess.set_task(task);
                 }
             }
@@ -1367,6 +1789,17 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other),
             },
+            Op::McpToolCallApproval { id, decision } => match decision {
+                ReviewDecision::Abort => {
+                    sess.interrupt_task();
+                }
+                other => sess.notify_approval(&id, other),
+            },
+            Op::McpSamplingApproval { id, decision } => {
+                // Sampling runs outside of any task, so aborting only
+                // declines the request.
+                sess.notify_approval(&id, decision);
+            }
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1412,17 +1845,37 @@ async fn submission_loop(
                 });
             }
             Op::ListMcpTools => {
-                let sub_id = sub.id.clone();
-
                 // This is a cheap lookup from the connection manager's cache.
-                let tools = sess.mcp_connection_manager.list_all_tools();
-                let event = Event {
-                    id: sub_id,
-                    msg: EventMsg::McpListToolsResponse(
-                        crate::protocol::McpListToolsResponseEvent { tools },
-                    ),
-                };
-                sess.send_event(event).await;
+                sess.send_mcp_tools_list(sub.id).await;
+            }
+            Op::RestartMcpServer { server } => {
+                // Starting a server can take up to its startup timeout, so do
+                // it off the submission loop.
+                let sess = sess.clone();
+                tokio::spawn(async move {
+                    let result = sess.mcp_connection_manager.restart_server(&server).await;
+                    sess.finish_mcp_server_update(sub.id, result).await;
+                });
+            }
+            Op::SetMcpServerEnabled { server, enabled } => {
+                let sess = sess.clone();
+                tokio::spawn(async move {
+                    let result = sess
+                        .mcp_connection_manager
+                        .set_server_enabled(&server, enabled)
+                        .await;
+                    sess.finish_mcp_server_update(sub.id, result).await;
+                });
+            }
+            Op::RefreshMcpTools { server } => {
+                let sess = sess.clone();
+                tokio::spawn(async move {
+                    let result = sess
+                        .mcp_connection_manager
+                        .refresh_tools(server.as_deref())
+                        .await;
+                    sess.finish_mcp_server_update(sub.id, result).await;
+                });
             }
             Op::ListCustomPrompts => {
                 let sub_id = sub.id.clone();
@@ -1442,6 +1895,59 @@ async fn submission_loop(
                 };
                 sess.send_event(event).await;
             }
+            Op::ListMcpResources => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::McpListResourcesResponse(McpListResourcesResponseEvent {
+                        resources: sess.mcp_connection_manager.list_all_resources(),
+                    }),
+                };
+                sess.send_event(event).await;
+            }
+            Op::ListMcpPrompts => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::McpListPromptsResponse(McpListPromptsResponseEvent {
+                        prompts: sess.mcp_connection_manager.list_all_prompts(),
+                    }),
+                };
+                sess.send_event(event).await;
+            }
+            Op::RunMcpPrompt {
+                server,
+                name,
+                arguments,
+            } => {
+                // Fetching the prompt is a round trip to the server, so do it
+                // off the submission loop.
+                let sess = sess.clone();
+                let turn_context = Arc::clone(&turn_context);
+                tokio::spawn(async move {
+                    let result = sess
+                        .mcp_connection_manager
+                        .get_prompt(&server, &name, arguments)
+                        .await;
+                    match result {
+                        Ok(prompt) => {
+                            let items = mcp_resources::prompt_to_input_items(prompt);
+                            if let Err(items) = sess.inject_input(items) {
+                                let task =
+                                    AgentTask::spawn(sess.clone(), turn_context, sub.id, items);
+                                sess.set_task(task);
+                            }
+                        }
+                        Err(e) => {
+                            let event = Event {
+                                id: sub.id,
+                                msg: EventMsg::Error(ErrorEvent {
+                                    message: format!("{e:#}"),
+                                }),
+                            };
+                            sess.send_event(event).await;
+                        }
+                    }
+                });
+            }
             Op::Compact => {
                 // Attempt to inject input into current task
                 if let Err(items) = sess.inject_input(vec![InputItem::Text {
@@ -1474,6 +1980,11 @@ async fn submission_loop(
                     sess.send_event(event).await;
                 }
 
+                // Snapshots are only kept for the session; drop their refs
+                // so the repository does not keep their objects.
+                let snapshots = std::mem::take(&mut sess.state.lock_unchecked().turn_snapshots);
+                discard_snapshots(&snapshots).await;
+
                 let event = Event {
                     id: sub.id.clone(),
                     msg: EventMsg::ShutdownComplete,
@@ -1509,14 +2020,22 @@ async fn submission_loop(
                 sess.send_event(event).await;
             }
             Op::Review { review_request } => {
-                spawn_review_thread(
+                // Collecting the changes to review runs git, so do it off the
+                // submission loop.
+                tokio::spawn(spawn_review_thread(
                     sess.clone(),
                     config.clone(),
                     turn_context.clone(),
                     sub.id,
                     review_request,
-                )
-                .await;
+                ));
+            }
+            Op::RollbackTurn { turn_id } => {
+                // Restoring files runs git, so do it off the submission loop.
+                let sess = sess.clone();
+                tokio::spawn(async move {
+                    sess.rollback_turn(sub.id, turn_id).await;
+                });
             }
             _ => {
                 // Ignore unknown ops; enum is non_exhaustive to allow extensions.
@@ -1534,9 +2053,38 @@ async fn spawn_review_thread(
     sub_id: String,
     review_request: ReviewRequest,
 ) {
+    let mut prompt = review_request.prompt.clone();
+    if let Some(target) = &review_request.target {
+        match collect_review_context(&parent_turn_context.cwd, target).await {
+            Ok(context) => {
+                prompt.push_str("\n\n");
+                prompt.push_str(&context);
+            }
+            Err(e) => {
+                sess.send_event(Event {
+                    id: sub_id.clone(),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message: format!("Failed to collect the changes to review: {e}"),
+                    }),
+                })
+                .await;
+                // Nothing ran, but clients wait for the task to end.
+                sess.send_event(Event {
+                    id: sub_id,
+                    msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                        last_agent_message: None,
+                    }),
+                })
+                .await;
+                return;
+            }
+        }
+    }
+
     let model = config.review_model.clone();
-    let review_model_family = find_family_for_model(&model)
-        .unwrap_or_else(|| parent_turn_context.client.get_model_family());
+    // Build per‑turn client with the requested model/family.
+    let per_turn_config = config.with_model(&model);
+    let review_model_family = per_turn_config.model_family.clone();
     let tools_config = ToolsConfig::new(&ToolsConfigParams {
         model_family: &review_model_family,
         approval_policy: parent_turn_context.approval_policy,
@@ -1547,20 +2095,12 @@ async fn spawn_review_thread(
         use_streamable_shell_tool: false,
         include_view_image_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        include_spawn_agent_tool: false,
     });
 
     let base_instructions = Some(REVIEW_PROMPT.to_string());
     let provider = parent_turn_context.client.get_provider();
     let auth_manager = parent_turn_context.client.get_auth_manager();
-    let model_family = review_model_family.clone();
-
-    // Build per‑turn client with the requested model/family.
-    let mut per_turn_config = (*config).clone();
-    per_turn_config.model = model.clone();
-    per_turn_config.model_family = model_family.clone();
-    if let Some(model_info) = get_model_info(&model_family) {
-        per_turn_config.model_context_window = Some(model_info.context_window);
-    }
 
     let client = ModelClient::new(
         Arc::new(per_turn_config),
@@ -1584,9 +2124,7 @@ async fn spawn_review_thread(
     };
 
     // Seed the child task with the review prompt as the initial user message.
-    let input: Vec<InputItem> = vec![InputItem::Text {
-        text: review_request.prompt.clone(),
-    }];
+    let input: Vec<InputItem> = vec![InputItem::Text { text: prompt }];
     let tc = Arc::new(review_turn_context);
 
     // Clone sub_id for the upcoming announcement before moving it into the task.
@@ -1636,6 +2174,12 @@ async fn run_task(
     };
     sess.send_event(event).await;
 
+    let (mentioned_resources, mention_errors) =
+        mcp_resources::resolve_resource_mentions(&sess.mcp_connection_manager, &input).await;
+    for error in mention_errors {
+        sess.notify_background_event(&sub_id, error).await;
+    }
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
     // For review threads, keep an isolated in-memory history so the
     // model sees a fresh conversation without the parent session's history.
@@ -1644,9 +2188,17 @@ async fn run_task(
     let mut review_thread_history: Vec<ResponseItem> = Vec::new();
     if is_review_mode {
         review_thread_history.push(initial_input_for_turn.into());
+        review_thread_history.extend(mentioned_resources);
     } else {
         sess.record_input_and_rollout_usermsg(&initial_input_for_turn)
             .await;
+        if let Some(resources) = mentioned_resources {
+            sess.record_conversation_items(&[resources]).await;
+        }
+    }
+
+    if !is_review_mode && sess.turn_snapshots {
+        sess.snapshot_turn(&turn_context, &sub_id).await;
     }
 
     let mut last_agent_message: Option<String> = None;
@@ -1934,10 +2486,13 @@ async fn run_turn(
     sub_id: String,
     input: Vec<ResponseItem>,
 ) -> CodexResult<TurnRunResult> {
-    let tools = get_openai_tools(
+    let mut tools = get_openai_tools(
         &turn_context.tools_config,
         Some(sess.mcp_connection_manager.list_all_tools()),
     );
+    if sess.mcp_connection_manager.has_resources() {
+        tools.extend(mcp_resources::create_mcp_resource_tools());
+    }
 
     let prompt = Prompt {
         input,
@@ -1954,6 +2509,9 @@ async fn run_turn(
             Err(e @ (CodexErr::UsageLimitReached(_) | CodexErr::UsageNotIncluded)) => {
                 return Err(e);
             }
+            Err(CodexErr::LmiBridge(e)) if !e.is_retryable() => {
+                return Err(CodexErr::LmiBridge(e));
+            }
             Err(e) => {
                 // Use the configured provider-specific stream retry budget.
                 let max_retries = turn_context.client.get_provider().stream_max_retries();
@@ -2065,18 +2623,30 @@ async fn try_run_turn(
         })
     };
 
-    let rollout_item = RolloutItem::TurnContext(TurnContextItem {
-        cwd: turn_context.cwd.clone(),
-        approval_policy: turn_context.approval_policy,
-        sandbox_policy: turn_context.sandbox_policy.clone(),
-        model: turn_context.client.get_model(),
-        effort: turn_context.client.get_reasoning_effort(),
-        summary: turn_context.client.get_reasoning_summary(),
-    });
+    let requested_provider = turn_context.client.get_active_provider_id();
+    let rollout_item = RolloutItem::TurnContext(turn_context.to_turn_context_item());
     sess.persist_rollout_items(&[rollout_item]).await;
     let mut stream = turn_context.client.clone().stream(&prompt).await?;
 
+    let serving_provider = turn_context.client.get_active_provider_id();
+    if serving_provider != requested_provider {
+        sess.notify_background_event(
+            sub_id,
+            format!(
+                "Model provider `{requested_provider}` is unavailable; this turn is served by `{serving_provider}` ({}).",
+                turn_context.client.get_active_model()
+            ),
+        )
+        .await;
+        let rollout_item = RolloutItem::TurnContext(turn_context.to_turn_context_item());
+        sess.persist_rollout_items(&[rollout_item]).await;
+    }
+
     let mut output = Vec::new();
+    // Read-only tool calls that run concurrently with each other. They are
+    // collected until a call that has to run on its own arrives (or the
+    // response completes), so results keep the order of the calls.
+    let mut parallel_calls = FuturesOrdered::new();
 
     loop {
         // Poll the next item from the model stream. We must inspect *both* Ok and Err
@@ -2104,15 +2674,25 @@ async fn try_run_turn(
         match event {
             ResponseEvent::Created => {}
             ResponseEvent::OutputItemDone(item) => {
-                let response = handle_response_item(
-                    sess,
-                    turn_context,
-                    turn_diff_tracker,
-                    sub_id,
-                    item.clone(),
-                )
-                .await?;
-                output.push(ProcessedResponseItem { item, response });
+                if runs_in_parallel(sess, turn_context, &item) {
+                    parallel_calls.push_back(handle_parallel_response_item(
+                        sess,
+                        turn_context,
+                        sub_id,
+                        item,
+                    ));
+                } else {
+                    finish_parallel_calls(&mut parallel_calls, &mut output).await?;
+                    let response = handle_response_item(
+                        sess,
+                        turn_context,
+                        turn_diff_tracker,
+                        sub_id,
+                        item.clone(),
+                    )
+                    .await?;
+                    output.push(ProcessedResponseItem { item, response });
+                }
             }
             ResponseEvent::WebSearchCallBegin { call_id } => {
                 let _ = sess
@@ -2127,6 +2707,7 @@ async fn try_run_turn(
                 response_id: _,
                 token_usage,
             } => {
+                finish_parallel_calls(&mut parallel_calls, &mut output).await?;
                 let info = sess.update_token_usage_info(turn_context, &token_usage);
                 let _ = sess
                     .send_event(Event {
@@ -2135,8 +2716,8 @@ async fn try_run_turn(
                     })
                     .await;
 
-                let unified_diff = turn_diff_tracker.get_unified_diff();
-                if let Ok(Some(unified_diff)) = unified_diff {
+                let unified_diff = sess.turn_unified_diff(sub_id, turn_diff_tracker).await;
+                if let Some(unified_diff) = unified_diff {
                     let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
                     let event = Event {
                         id: sub_id.to_string(),
@@ -2194,6 +2775,73 @@ async fn try_run_turn(
     }
 }
 
+/// Whether `item` is a tool call that only reads and never asks for approval,
+/// so that it can run concurrently with other such calls: known-safe shell
+/// commands, `view_image`, reading MCP resources, and MCP tools marked
+/// read-only that do not need approval.
+fn runs_in_parallel(sess: &Session, turn_context: &TurnContext, item: &ResponseItem) -> bool {
+    match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => match name.as_str() {
+            "view_image" | LIST_MCP_RESOURCES_TOOL_NAME | READ_MCP_RESOURCE_TOOL_NAME => true,
+            SPAWN_AGENT_TOOL_NAME => parse_spawn_agent_arguments(arguments)
+                .is_ok_and(|args| args.sandbox == AgentSandbox::ReadOnly),
+            "container.exec" | "shell" => serde_json::from_str::<ShellToolCallParams>(arguments)
+                .is_ok_and(|params| is_known_safe_command(&params.command)),
+            _ => {
+                sess.mcp_connection_manager.is_read_only_tool(name)
+                    && turn_context.approval_policy != AskForApproval::UnlessTrusted
+                    && sess
+                        .mcp_connection_manager
+                        .parse_tool_name(name)
+                        .is_some_and(|(server, tool)| {
+                            !sess.mcp_tool_requires_approval(&server, &tool)
+                        })
+            }
+        },
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => is_known_safe_command(&action.command),
+        _ => false,
+    }
+}
+
+/// [`handle_response_item`] for a call accepted by [`runs_in_parallel`]. Such
+/// calls never apply patches, so they do not need the turn's diff tracker.
+async fn handle_parallel_response_item(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    item: ResponseItem,
+) -> CodexResult<ProcessedResponseItem> {
+    let mut turn_diff_tracker = TurnDiffTracker::new();
+    let response = handle_response_item(
+        sess,
+        turn_context,
+        &mut turn_diff_tracker,
+        sub_id,
+        item.clone(),
+    )
+    .await?;
+    Ok(ProcessedResponseItem { item, response })
+}
+
+/// Wait for the queued parallel calls and append their results in call order.
+async fn finish_parallel_calls<F>(
+    parallel_calls: &mut FuturesOrdered<F>,
+    output: &mut Vec<ProcessedResponseItem>,
+) -> CodexResult<()>
+where
+    F: Future<Output = CodexResult<ProcessedResponseItem>>,
+{
+    while let Some(processed) = parallel_calls.next().await {
+        output.push(processed?);
+    }
+    Ok(())
+}
+
 async fn handle_response_item(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2253,7 +2901,7 @@ async fn handle_response_item(
                 }
             };
 
-            let exec_params = to_exec_params(params, turn_context);
+            let exec_params = to_exec_params(params, sess, turn_context);
             Some(
                 handle_container_exec_with_params(
                     exec_params,
@@ -2395,12 +3043,13 @@ async fn handle_function_call(
 ) -> ResponseInputItem {
     match name.as_str() {
         "container.exec" | "shell" => {
-            let params = match parse_container_exec_arguments(arguments, turn_context, &call_id) {
-                Ok(params) => params,
-                Err(output) => {
-                    return *output;
-                }
-            };
+            let params =
+                match parse_container_exec_arguments(arguments, sess, turn_context, &call_id) {
+                    Ok(params) => params,
+                    Err(output) => {
+                        return *output;
+                    }
+                };
             handle_container_exec_with_params(
                 params,
                 sess,
@@ -2505,6 +3154,15 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        SPAWN_AGENT_TOOL_NAME => {
+            handle_spawn_agent(sess, turn_context, sub_id, arguments, call_id).await
+        }
+        LIST_MCP_RESOURCES_TOOL_NAME => {
+            handle_list_mcp_resources(&sess.mcp_connection_manager, arguments, call_id)
+        }
+        READ_MCP_RESOURCE_TOOL_NAME => {
+            handle_read_mcp_resource(&sess.mcp_connection_manager, arguments, call_id).await
+        }
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params = match serde_json::from_str::<ExecCommandParams>(&arguments) {
@@ -2559,7 +3217,14 @@ async fn handle_function_call(
                     // TODO(mbolin): Determine appropriate timeout for tool call.
                     let timeout = None;
                     handle_mcp_tool_call(
-                        sess, &sub_id, call_id, server, tool_name, arguments, timeout,
+                        sess,
+                        &sub_id,
+                        call_id,
+                        server,
+                        tool_name,
+                        arguments,
+                        timeout,
+                        turn_context.approval_policy,
                     )
                     .await
                 }
@@ -2630,12 +3295,19 @@ async fn handle_custom_tool_call(
     }
 }
 
-fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> ExecParams {
+fn to_exec_params(
+    params: ShellToolCallParams,
+    sess: &Session,
+    turn_context: &TurnContext,
+) -> ExecParams {
     ExecParams {
         command: params.command,
         cwd: turn_context.resolve_path(params.workdir.clone()),
         timeout_ms: params.timeout_ms,
-        env: create_env(&turn_context.shell_environment_policy),
+        env: create_env(
+            &turn_context.shell_environment_policy,
+            sess.network_proxy_socket(turn_context),
+        ),
         with_escalated_permissions: params.with_escalated_permissions,
         justification: params.justification,
     }
@@ -2643,12 +3315,15 @@ fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> Ex
 
 fn parse_container_exec_arguments(
     arguments: String,
+    sess: &Session,
     turn_context: &TurnContext,
     call_id: &str,
 ) -> Result<ExecParams, Box<ResponseInputItem>> {
     // parse command
     match serde_json::from_str::<ShellToolCallParams>(&arguments) {
-        Ok(shell_tool_call_params) => Ok(to_exec_params(shell_tool_call_params, turn_context)),
+        Ok(shell_tool_call_params) => {
+            Ok(to_exec_params(shell_tool_call_params, sess, turn_context))
+        }
         Err(e) => {
             // allow model to re-sample
             let output = ResponseInputItem::FunctionCallOutput {
@@ -2668,6 +3343,8 @@ pub struct ExecInvokeArgs<'a> {
     pub sandbox_type: SandboxType,
     pub sandbox_policy: &'a SandboxPolicy,
     pub icodex_linux_sandbox_exe: &'a Option<PathBuf>,
+    pub container_sandbox: Option<&'a ContainerSandbox>,
+    pub exec_limits: ExecLimits,
     pub stdout_stream: Option<StdoutStream>,
 }
 
@@ -2726,7 +3403,7 @@ async fn handle_container_exec_with_params(
         MaybeApplyPatchVerified::NotApplyPatch => None,
     };
 
-    let (params, safety, command_for_display) = match &apply_patch_exec {
+    let (params, safety, command_for_display, exec_policy_note) = match &apply_patch_exec {
         Some(ApplyPatchExec {
             action: ApplyPatchAction { patch, cwd, .. },
             user_explicitly_approved_this_action,
@@ -2761,19 +3438,32 @@ async fn handle_container_exec_with_params(
                     sandbox_type: SandboxType::None,
                 }
             } else {
+                // Patches are applied on the host, by this executable, which
+                // a container image need not contain.
                 assess_safety_for_untrusted_command(
                     turn_context.approval_policy,
                     &turn_context.sandbox_policy,
                     params.with_escalated_permissions.unwrap_or(false),
+                    get_platform_sandbox(),
                 )
             };
             (
                 params,
                 safety,
                 vec!["apply_patch".to_string(), patch.clone()],
+                None,
             )
         }
         None => {
+            let verdict = sess.exec_policy.check(&params.command);
+            // A configured container takes the place of the platform sandbox
+            // for shell commands, including where there is no platform
+            // sandbox.
+            let sandbox = if sess.container_sandbox.is_some() {
+                Some(SandboxType::Container)
+            } else {
+                get_platform_sandbox()
+            };
             let safety = {
                 let state = sess.state.lock_unchecked();
                 assess_command_safety(
@@ -2782,10 +3472,14 @@ async fn handle_container_exec_with_params(
                     &turn_context.sandbox_policy,
                     &state.approved_commands,
                     params.with_escalated_permissions.unwrap_or(false),
+                    &verdict,
+                    &params.cwd,
+                    sandbox,
                 )
             };
+            let exec_policy_note = verdict.approval_note(&turn_context.sandbox_policy, &params.cwd);
             let command_for_display = params.command.clone();
-            (params, safety, command_for_display)
+            (params, safety, command_for_display, exec_policy_note)
         }
     };
 
@@ -2798,7 +3492,7 @@ async fn handle_container_exec_with_params(
                     call_id.clone(),
                     params.command.clone(),
                     params.cwd.clone(),
-                    params.justification.clone(),
+                    approval_reason(params.justification.clone(), exec_policy_note),
                 )
                 .await;
             match rx_approve.await.unwrap_or_default() {
@@ -2859,6 +3553,8 @@ async fn handle_container_exec_with_params(
                 sandbox_type,
                 sandbox_policy: &turn_context.sandbox_policy,
                 icodex_linux_sandbox_exe: &sess.icodex_linux_sandbox_exe,
+                container_sandbox: sess.container_sandbox.as_ref(),
+                exec_limits: sess.exec_limits,
                 stdout_stream: if exec_command_context.apply_patch.is_some() {
                     None
                 } else {
@@ -2908,6 +3604,16 @@ async fn handle_container_exec_with_params(
     }
 }
 
+/// Combines the model's justification with the `exec.policy` verdict so both
+/// show up in the approval prompt.
+fn approval_reason(justification: Option<String>, policy_note: Option<String>) -> Option<String> {
+    match (justification, policy_note) {
+        (Some(justification), Some(note)) => Some(format!("{justification} (exec policy: {note})")),
+        (None, Some(note)) => Some(format!("exec policy: {note}")),
+        (justification, None) => justification,
+    }
+}
+
 async fn handle_sandbox_error(
     turn_diff_tracker: &mut TurnDiffTracker,
     params: ExecParams,
@@ -2993,6 +3699,8 @@ async fn handle_sandbox_error(
                         sandbox_type: SandboxType::None,
                         sandbox_policy: &turn_context.sandbox_policy,
                         icodex_linux_sandbox_exe: &sess.icodex_linux_sandbox_exe,
+                        container_sandbox: sess.container_sandbox.as_ref(),
+                        exec_limits: sess.exec_limits,
                         stdout_stream: if exec_command_context.apply_patch.is_some() {
                             None
                         } else {
@@ -3060,6 +3768,9 @@ fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
             exec_output.duration.as_millis()
         ) + s;
         s = &prefixed_str;
+    } else if let Some(limit) = exec_output.limit_exceeded {
+        prefixed_str = format!("command {limit} (see `exec_limits` in config.toml)\n") + s;
+        s = &prefixed_str;
     }
 
     let total_lines = s.lines().count();
@@ -3265,6 +3976,7 @@ mod tests {
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
     use crate::protocol::CompactedItem;
+    use crate::protocol::ExecLimitExceeded;
     use crate::protocol::InitialHistory;
     use crate::protocol::ResumedHistory;
     use icodex_protocol::models::ContentItem;
@@ -3355,6 +4067,7 @@ mod tests {
             aggregated_output: StreamOutput::new(full),
             duration: StdDuration::from_secs(1),
             timed_out: false,
+            limit_exceeded: None,
         };
 
         let out = format_exec_output_str(&exec);
@@ -3398,6 +4111,7 @@ mod tests {
             aggregated_output: StreamOutput::new(full.clone()),
             duration: StdDuration::from_secs(1),
             timed_out: false,
+            limit_exceeded: None,
         };
 
         let out = format_exec_output_str(&exec);
@@ -3429,6 +4143,7 @@ mod tests {
             aggregated_output: StreamOutput::new("Command output".to_string()),
             duration: StdDuration::from_secs(1),
             timed_out: true,
+            limit_exceeded: None,
         };
 
         let out = format_exec_output_str(&exec);
@@ -3439,6 +4154,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn includes_limit_exceeded_message() {
+        let exec = ExecToolCallOutput {
+            exit_code: 137,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new("Command output".to_string()),
+            duration: StdDuration::from_secs(1),
+            timed_out: false,
+            limit_exceeded: Some(ExecLimitExceeded::OomKilled),
+        };
+
+        let out = format_exec_output_str(&exec);
+
+        assert_eq!(
+            out,
+            "command killed for exceeding the memory limit (see `exec_limits` in config.toml)\nCommand output"
+        );
+    }
+
     #[test]
     fn falls_back_to_content_when_structured_is_null() {
         let ctr = CallToolResult {
@@ -3491,6 +4226,33 @@ mod tests {
         assert_eq!(expected, got);
     }
 
+    #[test]
+    fn only_read_only_calls_run_in_parallel() {
+        let (session, turn_context) = make_session_and_context();
+        let call = |name: &str, arguments: serde_json::Value| ResponseItem::FunctionCall {
+            id: None,
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+            call_id: "call-1".to_string(),
+        };
+        let parallel = |item: ResponseItem| runs_in_parallel(&session, &turn_context, &item);
+
+        assert!(parallel(call("shell", json!({"command": ["ls", "-l"]}))));
+        assert!(parallel(call("view_image", json!({"path": "a.png"}))));
+        assert!(!parallel(call(
+            "shell",
+            json!({"command": ["rm", "-rf", "tmp"]})
+        )));
+        assert!(!parallel(call("apply_patch", json!({"input": ""}))));
+        assert!(!parallel(call("update_plan", json!({"plan": []}))));
+        assert!(!parallel(call("unknown__tool", json!({}))));
+        assert!(parallel(call("spawn_agent", json!({"prompt": "look"}))));
+        assert!(!parallel(call(
+            "spawn_agent",
+            json!({"prompt": "fix", "sandbox": "workspace-write"})
+        )));
+    }
+
     fn text_block(s: &str) -> ContentBlock {
         ContentBlock::TextContent(TextContent {
             annotations: None,
@@ -3528,6 +4290,7 @@ mod tests {
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+            include_spawn_agent_tool: config.can_spawn_agents(),
         });
         let turn_context = TurnContext {
             client,
@@ -3555,6 +4318,12 @@ mod tests {
             icodex_linux_sandbox_exe: None,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            exec_policy: ExecPolicy::default(),
+            network_proxy: None,
+            exec_limits: ExecLimits::default(),
+            container_sandbox: None,
+            turn_snapshots: false,
+            agent_slots: Semaphore::new(1),
         };
         (session, turn_context)
     }
@@ -0,0 +1,338 @@
+//! Answers the requests MCP servers send back to us: `roots/list` from the
+//! session's working directory and writable roots, and
+//! `sampling/createMessage` through the session's model client once the user
+//! has approved it.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Weak;
+
+use futures::prelude::*;
+use icodex_protocol::models::ContentItem;
+use icodex_protocol::models::ResponseItem;
+use mcp_types::CreateMessageRequest;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::CreateMessageResult;
+use mcp_types::CreateMessageResultContent;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCRequest;
+use mcp_types::ListRootsRequest;
+use mcp_types::ListRootsResult;
+use mcp_types::ModelContextProtocolRequest;
+use mcp_types::PingRequest;
+use mcp_types::Role;
+use mcp_types::Root;
+use mcp_types::SamplingMessage;
+use mcp_types::SamplingMessageContent;
+use mcp_types::TextContent;
+use reqwest::Url;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+
+use super::Session;
+use super::TurnContext;
+use super::get_last_assistant_message_from_turn;
+use crate::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::mcp_connection_manager::MCP_REQUEST_FAILED_ERROR_CODE;
+use crate::mcp_connection_manager::MCP_REQUEST_REJECTED_ERROR_CODE;
+use crate::mcp_connection_manager::McpServerRequest;
+use crate::mcp_connection_manager::mcp_request_error;
+use crate::protocol::AskForApproval;
+use crate::protocol::ReviewDecision;
+use crate::protocol::SandboxPolicy;
+
+const METHOD_NOT_FOUND_ERROR_CODE: i64 = -32601;
+const INVALID_PARAMS_ERROR_CODE: i64 = -32602;
+
+/// Answer the requests arriving on `requests` until every MCP client is gone.
+/// Holds only a weak reference so the clients (which own the senders) do not
+/// keep the session alive.
+pub(super) async fn serve_mcp_server_requests(
+    sess: Weak<Session>,
+    mut requests: mpsc::Receiver<McpServerRequest>,
+    turn_context: watch::Receiver<Arc<TurnContext>>,
+) {
+    while let Some(McpServerRequest {
+        server_name,
+        request,
+        reply,
+    }) = requests.recv().await
+    {
+        let Some(sess) = sess.upgrade() else {
+            break;
+        };
+        let turn_context = Arc::clone(&turn_context.borrow());
+        // Sampling waits for the user, so each request is answered on its own
+        // task.
+        tokio::spawn(async move {
+            let result = handle_request(&sess, &turn_context, server_name, request).await;
+            let _ = reply.send(result);
+        });
+    }
+}
+
+async fn handle_request(
+    sess: &Session,
+    turn_context: &TurnContext,
+    server_name: String,
+    request: JSONRPCRequest,
+) -> Result<serde_json::Value, JSONRPCErrorError> {
+    match request.method.as_str() {
+        PingRequest::METHOD => Ok(json!({})),
+        ListRootsRequest::METHOD => {
+            let roots = list_roots(&turn_context.cwd, &turn_context.sandbox_policy);
+            Ok(json!(roots))
+        }
+        CreateMessageRequest::METHOD => {
+            let params = request
+                .params
+                .and_then(|params| {
+                    serde_json::from_value::<CreateMessageRequestParams>(params).ok()
+                })
+                .ok_or_else(|| {
+                    mcp_request_error(
+                        INVALID_PARAMS_ERROR_CODE,
+                        "invalid sampling/createMessage params",
+                    )
+                })?;
+            let result = create_message(sess, turn_context, server_name, params).await?;
+            Ok(json!(result))
+        }
+        method => Err(mcp_request_error(
+            METHOD_NOT_FOUND_ERROR_CODE,
+            format!("client does not handle `{method}`"),
+        )),
+    }
+}
+
+/// The session's working directory followed by the other roots the sandbox
+/// lets commands write to.
+fn list_roots(cwd: &Path, sandbox_policy: &SandboxPolicy) -> ListRootsResult {
+    let mut paths: Vec<PathBuf> = vec![cwd.to_path_buf()];
+    for writable_root in sandbox_policy.get_writable_roots_with_cwd(cwd) {
+        if !paths.contains(&writable_root.root) {
+            paths.push(writable_root.root);
+        }
+    }
+    let roots = paths
+        .into_iter()
+        .filter_map(|path| {
+            let uri = Url::from_directory_path(&path).ok()?;
+            Some(Root {
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned()),
+                uri: uri.to_string(),
+            })
+        })
+        .collect();
+    ListRootsResult { roots }
+}
+
+async fn create_message(
+    sess: &Session,
+    turn_context: &TurnContext,
+    server_name: String,
+    params: CreateMessageRequestParams,
+) -> Result<CreateMessageResult, JSONRPCErrorError> {
+    if !sess.is_sampling_approved_for_session(&server_name) {
+        if turn_context.approval_policy == AskForApproval::Never {
+            return Err(mcp_request_error(
+                MCP_REQUEST_REJECTED_ERROR_CODE,
+                "sampling requires approval, but the approval policy is `never`",
+            ));
+        }
+        let rx_approve = sess
+            .request_mcp_sampling_approval(server_name.clone(), params.clone())
+            .await;
+        match rx_approve.await.unwrap_or_default() {
+            ReviewDecision::Approved => {}
+            ReviewDecision::ApprovedForSession => {
+                sess.approve_sampling_for_session(server_name);
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Err(mcp_request_error(
+                    MCP_REQUEST_REJECTED_ERROR_CODE,
+                    "the user declined the sampling request",
+                ));
+            }
+        }
+    }
+
+    let input = params
+        .messages
+        .into_iter()
+        .map(sampling_message_to_response_item)
+        .collect::<Result<Vec<_>, _>>()?;
+    let prompt = Prompt {
+        input,
+        tools: Vec::new(),
+        // Without an override the model would get the agent's own
+        // instructions, which are meaningless to the server.
+        base_instructions_override: Some(params.system_prompt.unwrap_or_default()),
+    };
+    let text = sample(turn_context, &prompt).await.map_err(|e| {
+        mcp_request_error(
+            MCP_REQUEST_FAILED_ERROR_CODE,
+            format!("sampling failed: {e}"),
+        )
+    })?;
+
+    Ok(CreateMessageResult {
+        content: CreateMessageResultContent::TextContent(TextContent {
+            annotations: None,
+            text,
+            r#type: "text".to_string(),
+        }),
+        model: turn_context.client.get_active_model(),
+        role: Role::Assistant,
+        stop_reason: Some("endTurn".to_string()),
+    })
+}
+
+fn sampling_message_to_response_item(
+    message: SamplingMessage,
+) -> Result<ResponseItem, JSONRPCErrorError> {
+    let content = match (message.content, &message.role) {
+        (SamplingMessageContent::TextContent(text), Role::User) => {
+            ContentItem::InputText { text: text.text }
+        }
+        (SamplingMessageContent::TextContent(text), Role::Assistant) => {
+            ContentItem::OutputText { text: text.text }
+        }
+        (SamplingMessageContent::ImageContent(image), Role::User) => ContentItem::InputImage {
+            image_url: format!("data:{};base64,{}", image.mime_type, image.data),
+        },
+        _ => {
+            return Err(mcp_request_error(
+                INVALID_PARAMS_ERROR_CODE,
+                "only text, and images from the user, can be sampled",
+            ));
+        }
+    };
+    let role = match message.role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    };
+    Ok(ResponseItem::Message {
+        id: None,
+        role: role.to_string(),
+        content: vec![content],
+    })
+}
+
+/// Run `prompt` to completion and return the assistant's reply.
+async fn sample(turn_context: &TurnContext, prompt: &Prompt) -> crate::error::Result<String> {
+    let mut stream = turn_context.client.clone().stream(prompt).await?;
+    let mut output = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event? {
+            ResponseEvent::OutputItemDone(item) => output.push(item),
+            ResponseEvent::Completed { .. } => break,
+            _ => {}
+        }
+    }
+    Ok(get_last_assistant_message_from_turn(&output).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::ImageContent;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn roots_start_with_cwd_and_skip_duplicates() {
+        let cwd = std::env::temp_dir().join("project");
+        let extra = std::env::temp_dir().join("shared");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![cwd.clone(), extra.clone()],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        let roots = list_roots(&cwd, &policy).roots;
+
+        let uris: Vec<String> = roots.iter().map(|root| root.uri.clone()).collect();
+        let expected: Vec<String> = [&cwd, &extra]
+            .into_iter()
+            .filter_map(|path| Url::from_directory_path(path).ok())
+            .map(|uri| uri.to_string())
+            .collect();
+        assert_eq!(uris, expected);
+        assert_eq!(roots[0].name.as_deref(), Some("project"));
+    }
+
+    #[test]
+    fn read_only_sandbox_only_exposes_cwd() {
+        let cwd = std::env::temp_dir().join("project");
+        let roots = list_roots(&cwd, &SandboxPolicy::ReadOnly).roots;
+        assert_eq!(roots.len(), 1);
+    }
+
+    #[test]
+    fn sampling_messages_map_to_response_items() {
+        let user = SamplingMessage {
+            content: SamplingMessageContent::TextContent(TextContent {
+                annotations: None,
+                text: "summarize".to_string(),
+                r#type: "text".to_string(),
+            }),
+            role: Role::User,
+        };
+        let image = SamplingMessage {
+            content: SamplingMessageContent::ImageContent(ImageContent {
+                annotations: None,
+                data: "AAAA".to_string(),
+                mime_type: "image/png".to_string(),
+                r#type: "image".to_string(),
+            }),
+            role: Role::User,
+        };
+
+        let items = [user, image]
+            .into_iter()
+            .map(sampling_message_to_response_item)
+            .collect::<Result<Vec<_>, _>>()
+            .expect("convert messages");
+
+        assert_eq!(
+            items,
+            vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: "summarize".to_string()
+                    }],
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputImage {
+                        image_url: "data:image/png;base64,AAAA".to_string()
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn images_from_the_assistant_are_rejected() {
+        let message = SamplingMessage {
+            content: SamplingMessageContent::ImageContent(ImageContent {
+                annotations: None,
+                data: "AAAA".to_string(),
+                mime_type: "image/png".to_string(),
+                r#type: "image".to_string(),
+            }),
+            role: Role::Assistant,
+        };
+        let err = sampling_message_to_response_item(message).expect_err("should be rejected");
+        assert_eq!(err.code, INVALID_PARAMS_ERROR_CODE);
+    }
+}
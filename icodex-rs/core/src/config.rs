@@ -1,20 +1,29 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::AgentLimits;
+use crate::config_types::AgentsToml;
+use crate::config_types::ExecLimits;
+use crate::config_types::FallbackProviderToml;
 use crate::config_types::History;
 use crate::config_types::McpServerConfig;
+use crate::config_types::McpServerTransportConfig;
+use crate::config_types::ModelConfigToml;
 use crate::config_types::Notifications;
 use crate::config_types::ReasoningSummaryFormat;
+use crate::config_types::SandboxBackend;
+use crate::config_types::SandboxContainerToml;
+use crate::config_types::SandboxWorkspaceRead;
 use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::config_types::Tui;
 use crate::config_types::UriBasedFileOpener;
+use crate::container::ContainerSandbox;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
-use crate::model_family::derive_default_model_family;
-use crate::model_family::find_family_for_model;
+use crate::model_provider_info::FallbackProvider;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
-use crate::openai_model_info::get_model_info;
+use crate::model_registry::ModelRegistry;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
 use anyhow::Context;
@@ -68,17 +77,51 @@ pub struct Config {
     /// Token usage threshold triggering auto-compaction of conversation history.
     pub model_auto_compact_token_limit: Option<i64>,
 
+    /// Model capabilities from `[models."<slug>"]` tables and `models.d/`.
+    pub models: ModelRegistry,
+
     /// Key into the model_providers map that specifies which provider to use.
     pub model_provider_id: String,
 
     /// Info needed to make an API request to the model.
     pub model_provider: ModelProviderInfo,
 
+    /// Providers to fail over to, in order, when `model_provider` keeps
+    /// returning rate-limit or server errors.
+    pub fallback_providers: Vec<FallbackProvider>,
+
     /// Approval policy for executing commands.
     pub approval_policy: AskForApproval,
 
     pub sandbox_policy: SandboxPolicy,
 
+    /// Hosts sandboxed commands may reach through the network proxy while the
+    /// sandbox policy otherwise blocks the network.
+    pub network_allowlist: Vec<String>,
+
+    /// Resource limits applied to every command the agent runs.
+    pub exec_limits: ExecLimits,
+
+    /// Whether `cwd` belongs to a project marked as trusted in `[projects]`.
+    /// Project files that relax safety checks, such as
+    /// `.icodex/exec.policy`, are only loaded for trusted projects.
+    pub project_trusted: bool,
+
+    /// When set, sandboxed commands run in this container instead of under
+    /// the platform sandbox.
+    pub container_sandbox: Option<ContainerSandbox>,
+
+    /// Snapshot the workspace before each turn so the turn can be rolled back
+    /// with `Op::RollbackTurn` (`/undo`).
+    pub turn_snapshots: bool,
+
+    /// Limits on the sub-agents started with the `spawn_agent` tool.
+    pub agents: AgentLimits,
+
+    /// How many `spawn_agent` calls deep this conversation is: `0` unless it
+    /// is a sub-agent.
+    pub agent_depth: u32,
+
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
     /// When `true`, `AgentReasoning` events emitted by the backend will be
@@ -194,6 +237,10 @@ pub struct Config {
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: bool,
+
+    /// Program and arguments used to start the LMI bridge for providers with
+    /// `wire_api = "lmi_bridge"`. When unset, `node` runs the bundled script.
+    pub lmi_bridge_command: Option<Vec<String>>,
 }
 
 impl Config {
@@ -310,27 +357,40 @@ pub fn write_global_mcp_servers(
         for (name, config) in servers {
             let mut entry = TomlTable::new();
             entry.set_implicit(false);
-            entry["command"] = toml_edit::value(config.command.clone());
+            match &config.transport {
+                McpServerTransportConfig::Stdio { command, args, env } => {
+                    entry["command"] = toml_edit::value(command.clone());
+
+                    if !args.is_empty() {
+                        let mut args_array = TomlArray::new();
+                        for arg in args {
+                            args_array.push(arg.clone());
+                        }
+                        entry["args"] = TomlItem::Value(args_array.into());
+                    }
 
-            if !config.args.is_empty() {
-                let mut args = TomlArray::new();
-                for arg in &config.args {
-                    args.push(arg.clone());
+                    if let Some(env) = env {
+                        insert_string_table(&mut entry, "env", env);
+                    }
                 }
-                entry["args"] = TomlItem::Value(args.into());
-            }
-
-            if let Some(env) = &config.env
-                && !env.is_empty()
-            {
-                let mut env_table = TomlTable::new();
-                env_table.set_implicit(false);
-                let mut pairs: Vec<_> = env.iter().collect();
-                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
-                for (key, value) in pairs {
-                    env_table.insert(key, toml_edit::value(value.clone()));
+                McpServerTransportConfig::StreamableHttp {
+                    url,
+                    bearer_token_env_var,
+                    http_headers,
+                    env_http_headers,
+                } => {
+                    entry["url"] = toml_edit::value(url.clone());
+
+                    if let Some(var) = bearer_token_env_var {
+                        entry["bearer_token_env_var"] = toml_edit::value(var.clone());
+                    }
+                    if let Some(headers) = http_headers {
+                        insert_string_table(&mut entry, "http_headers", headers);
+                    }
+                    if let Some(headers) = env_http_headers {
+                        insert_string_table(&mut entry, "env_http_headers", headers);
+                    }
                 }
-                entry["env"] = TomlItem::Table(env_table);
             }
 
             if let Some(timeout) = config.startup_timeout_ms {
@@ -343,6 +403,20 @@ pub fn write_global_mcp_servers(
                 entry["startup_timeout_ms"] = toml_edit::value(timeout);
             }
 
+            if config.allow_sampling {
+                entry["allow_sampling"] = toml_edit::value(true);
+            }
+
+            if let Some(enabled_tools) = &config.enabled_tools {
+                insert_string_array(&mut entry, "enabled_tools", enabled_tools);
+            }
+            if !config.disabled_tools.is_empty() {
+                insert_string_array(&mut entry, "disabled_tools", &config.disabled_tools);
+            }
+            if !config.require_approval.is_empty() {
+                insert_string_array(&mut entry, "require_approval", &config.require_approval);
+            }
+
             doc["mcp_servers"][name.as_str()] = TomlItem::Table(entry);
         }
     }
@@ -355,6 +429,31 @@ pub fn write_global_mcp_servers(
     Ok(())
 }
 
+/// Write `values` as an array under `key` in `entry`.
+fn insert_string_array(entry: &mut TomlTable, key: &str, values: &[String]) {
+    let mut array = TomlArray::new();
+    for value in values {
+        array.push(value.clone());
+    }
+    entry[key] = TomlItem::Value(array.into());
+}
+
+/// Write `values` as a sub-table of `entry`, sorted by key. Empty maps are
+/// omitted.
+fn insert_string_table(entry: &mut TomlTable, key: &str, values: &HashMap<String, String>) {
+    if values.is_empty() {
+        return;
+    }
+    let mut table = TomlTable::new();
+    table.set_implicit(false);
+    let mut pairs: Vec<_> = values.iter().collect();
+    pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, value) in pairs {
+        table.insert(name, toml_edit::value(value.clone()));
+    }
+    entry[key] = TomlItem::Table(table);
+}
+
 fn set_project_trusted_inner(doc: &mut DocumentMut, project_path: &Path) -> anyhow::Result<()> {
     // Ensure we render a human-friendly structure:
     //
@@ -611,6 +710,13 @@ pub struct ConfigToml {
     /// Provider to use from the model_providers map.
     pub model_provider: Option<String>,
 
+    /// Providers from the model_providers map to fail over to, in order.
+    pub fallback_providers: Option<Vec<FallbackProviderToml>>,
+
+    /// Capabilities of individual models, keyed by slug.
+    #[serde(default)]
+    pub models: HashMap<String, ModelConfigToml>,
+
     /// Size of the context window for the model, in tokens.
     pub model_context_window: Option<u64>,
 
@@ -626,12 +732,35 @@ pub struct ConfigToml {
     #[serde(default)]
     pub shell_environment_policy: ShellEnvironmentPolicyToml,
 
+    /// CPU, memory, process and output limits for the commands the agent
+    /// runs.
+    #[serde(default)]
+    pub exec_limits: ExecLimits,
+
+    /// Snapshot the workspace before each turn so it can be rolled back.
+    /// Defaults to `true`.
+    pub turn_snapshots: Option<bool>,
+
+    /// Depth and concurrency limits for sub-agents.
+    #[serde(default)]
+    pub agents: AgentsToml,
+
     /// Sandbox mode to use.
     pub sandbox_mode: Option<SandboxMode>,
 
     /// Sandbox configuration to apply if `sandbox` is `WorkspaceWrite`.
     pub sandbox_workspace_write: Option<SandboxWorkspaceWrite>,
 
+    /// Sandbox configuration to apply if `sandbox` is `WorkspaceRead`.
+    pub sandbox_workspace_read: Option<SandboxWorkspaceRead>,
+
+    /// What enforces the sandbox: the platform sandbox (default) or a
+    /// container.
+    pub sandbox_backend: Option<SandboxBackend>,
+
+    /// Container to run commands in when `sandbox_backend = "container"`.
+    pub sandbox_container: Option<SandboxContainerToml>,
+
     /// Optional external command to spawn for end-user notifications.
     #[serde(default)]
     pub notify: Option<Vec<String>>,
@@ -705,6 +834,9 @@ pub struct ConfigToml {
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: Option<bool>,
+
+    /// Command used to start the LMI bridge, e.g. `["node", "bridge.js"]`.
+    pub lmi_bridge_command: Option<Vec<String>>,
 }
 
 impl From<ConfigToml> for UserSavedConfig {
@@ -762,10 +894,21 @@ impl ConfigToml {
             .unwrap_or_default();
         match resolved_sandbox_mode {
             SandboxMode::ReadOnly => SandboxPolicy::new_read_only_policy(),
+            SandboxMode::WorkspaceRead => {
+                let SandboxWorkspaceRead {
+                    readable_roots,
+                    exclude_default_readable_roots,
+                } = self.sandbox_workspace_read.clone().unwrap_or_default();
+                SandboxPolicy::WorkspaceRead {
+                    readable_roots,
+                    exclude_default_readable_roots,
+                }
+            }
             SandboxMode::WorkspaceWrite => match self.sandbox_workspace_write.as_ref() {
                 Some(SandboxWorkspaceWrite {
                     writable_roots,
                     network_access,
+                    network_allowlist: _,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
                 }) => SandboxPolicy::WorkspaceWrite {
@@ -875,6 +1018,27 @@ impl Config {
             tools_web_search_request: override_tools_web_search_request,
         } = overrides;
 
+        let resolved_cwd = {
+            use std::env;
+
+            match cwd {
+                None => {
+                    tracing::info!("cwd not set, using current dir");
+                    env::current_dir()?
+                }
+                Some(p) if p.is_absolute() => p,
+                Some(p) => {
+                    // Resolve relative path against the current working directory.
+                    tracing::info!("cwd is relative, resolving against current dir");
+                    let mut current = env::current_dir()?;
+                    current.push(p);
+                    current
+                }
+            }
+        };
+
+        let project_trusted = cfg.is_cwd_trusted(&resolved_cwd);
+
         let active_profile_name = config_profile_key
             .as_ref()
             .or(cfg.profile.as_ref())
@@ -914,25 +1078,23 @@ impl Config {
                 )
             })?
             .clone();
+        let fallback_providers = config_profile
+            .fallback_providers
+            .or(cfg.fallback_providers)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| resolve_fallback_provider(entry, &model_providers))
+            .collect::<std::io::Result<Vec<_>>>()?;
 
         let shell_environment_policy = cfg.shell_environment_policy.into();
 
-        let resolved_cwd = {
-            use std::env;
-
-            match cwd {
-                None => {
-                    tracing::info!("cwd not set, using current dir");
-                    env::current_dir()?
-                }
-                Some(p) if p.is_absolute() => p,
-                Some(p) => {
-                    // Resolve relative path against the current working directory.
-                    tracing::info!("cwd is relative, resolving against current dir");
-                    let mut current = env::current_dir()?;
-                    current.push(p);
-                    current
-                }
+        let container_sandbox = match cfg.sandbox_backend.unwrap_or_default() {
+            SandboxBackend::Native => None,
+            SandboxBackend::Container => {
+                let toml = cfg.sandbox_container.as_ref();
+                Some(ContainerSandbox::from_toml(
+                    toml.unwrap_or(&SandboxContainerToml::default()),
+                )?)
             }
         };
 
@@ -951,30 +1113,25 @@ impl Config {
             .or(cfg.model)
             .unwrap_or_else(default_model);
 
-        let mut model_family =
-            find_family_for_model(&model).unwrap_or_else(|| derive_default_model_family(&model));
-
-        if let Some(supports_reasoning_summaries) = cfg.model_supports_reasoning_summaries {
-            model_family.supports_reasoning_summaries = supports_reasoning_summaries;
-        }
-        if let Some(model_reasoning_summary_format) = cfg.model_reasoning_summary_format {
-            model_family.reasoning_summary_format = model_reasoning_summary_format;
-        }
-
-        let openai_model_info = get_model_info(&model_family);
-        let model_context_window = cfg
-            .model_context_window
-            .or_else(|| openai_model_info.as_ref().map(|info| info.context_window));
-        let model_max_output_tokens = cfg.model_max_output_tokens.or_else(|| {
-            openai_model_info
-                .as_ref()
-                .map(|info| info.max_output_tokens)
-        });
-        let model_auto_compact_token_limit = cfg.model_auto_compact_token_limit.or_else(|| {
-            openai_model_info
-                .as_ref()
-                .and_then(|info| info.auto_compact_token_limit)
-        });
+        let config_toml_path = icodex_home.join(CONFIG_TOML_FILE);
+        let models = ModelRegistry::load(&icodex_home, &config_toml_path, cfg.models)?
+            .with_overrides(
+                config_toml_path,
+                &model,
+                ModelConfigToml {
+                    context_window: cfg.model_context_window,
+                    max_output_tokens: cfg.model_max_output_tokens,
+                    auto_compact_token_limit: cfg.model_auto_compact_token_limit,
+                    supports_reasoning_summaries: cfg.model_supports_reasoning_summaries,
+                    reasoning_summary_format: cfg.model_reasoning_summary_format,
+                    ..Default::default()
+                },
+            );
+        let resolved_model = models.resolve(&model);
+        let model_family = resolved_model.family;
+        let model_context_window = resolved_model.context_window;
+        let model_max_output_tokens = resolved_model.max_output_tokens;
+        let model_auto_compact_token_limit = resolved_model.auto_compact_token_limit;
 
         // Load base instructions override from a file if specified. If the
         // path is relative, resolve it against the effective cwd so the
@@ -999,14 +1156,27 @@ impl Config {
             model_context_window,
             model_max_output_tokens,
             model_auto_compact_token_limit,
+            models,
             model_provider_id,
             model_provider,
+            fallback_providers,
             cwd: resolved_cwd,
             approval_policy: approval_policy
                 .or(config_profile.approval_policy)
                 .or(cfg.approval_policy)
                 .unwrap_or_else(AskForApproval::default),
             sandbox_policy,
+            network_allowlist: cfg
+                .sandbox_workspace_write
+                .as_ref()
+                .map(|sandbox| sandbox.network_allowlist.clone())
+                .unwrap_or_default(),
+            exec_limits: cfg.exec_limits,
+            project_trusted,
+            container_sandbox,
+            turn_snapshots: cfg.turn_snapshots.unwrap_or(true),
+            agents: cfg.agents.into(),
+            agent_depth: 0,
             shell_environment_policy,
             notify: cfg.notify,
             user_instructions,
@@ -1048,6 +1218,7 @@ impl Config {
             include_view_image_tool,
             active_profile: active_profile_name,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
+            lmi_bridge_command: cfg.lmi_bridge_command,
             tui_notifications: cfg
                 .tui
                 .as_ref()
@@ -1057,6 +1228,25 @@ impl Config {
         Ok(config)
     }
 
+    /// Returns a copy of this config that targets `model`, with its
+    /// capabilities resolved through [`ModelRegistry`].
+    pub fn with_model(&self, model: &str) -> Config {
+        let resolved = self.models.resolve(model);
+        Config {
+            model: model.to_string(),
+            model_family: resolved.family,
+            model_context_window: resolved.context_window,
+            model_max_output_tokens: resolved.max_output_tokens,
+            model_auto_compact_token_limit: resolved.auto_compact_token_limit,
+            ..self.clone()
+        }
+    }
+
+    /// Whether this conversation may start sub-agents with `spawn_agent`.
+    pub fn can_spawn_agents(&self) -> bool {
+        self.agent_depth < self.agents.max_depth
+    }
+
     fn load_instructions(icodex_dir: Option<&Path>) -> Option<String> {
         let mut p = match icodex_dir {
             Some(p) => p.to_path_buf(),
@@ -1117,6 +1307,30 @@ impl Config {
     }
 }
 
+fn resolve_fallback_provider(
+    entry: FallbackProviderToml,
+    model_providers: &HashMap<String, ModelProviderInfo>,
+) -> std::io::Result<FallbackProvider> {
+    let (provider_id, model) = match entry {
+        FallbackProviderToml::Provider(provider) => (provider, None),
+        FallbackProviderToml::ProviderWithModel { provider, model } => (provider, Some(model)),
+    };
+    let provider = model_providers
+        .get(&provider_id)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Fallback model provider `{provider_id}` not found"),
+            )
+        })?
+        .clone();
+    Ok(FallbackProvider {
+        provider_id,
+        provider,
+        model,
+    })
+}
+
 fn default_model() -> String {
     OPENAI_DEFAULT_MODEL.to_string()
 }
@@ -1163,6 +1377,7 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
 #[cfg(test)]
 mod tests {
     use crate::config_types::HistoryPersistence;
+    use crate::model_family::find_family_for_model;
 
     use super::*;
     use pretty_assertions::assert_eq;
@@ -1255,6 +1470,51 @@ exclude_slash_tmp = true
             },
             sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override)
         );
+
+        let sandbox_workspace_read = r#"
+sandbox_mode = "workspace-read"
+
+[sandbox_workspace_read]
+readable_roots = [
+    "/opt/toolchains",
+]
+"#;
+
+        let sandbox_workspace_read_cfg = toml::from_str::<ConfigToml>(sandbox_workspace_read)
+            .expect("TOML deserialization should succeed");
+        let sandbox_mode_override = None;
+        assert_eq!(
+            SandboxPolicy::WorkspaceRead {
+                readable_roots: vec![PathBuf::from("/opt/toolchains")],
+                exclude_default_readable_roots: false,
+            },
+            sandbox_workspace_read_cfg.derive_sandbox_policy(sandbox_mode_override)
+        );
+    }
+
+    #[test]
+    fn sandbox_container_table_is_parsed() {
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+sandbox_backend = "container"
+
+[sandbox_container]
+runtime = "docker"
+image = "rust:1"
+extra_args = ["--pull=never"]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+
+        assert_eq!(cfg.sandbox_backend, Some(SandboxBackend::Container));
+        assert_eq!(
+            cfg.sandbox_container,
+            Some(SandboxContainerToml {
+                runtime: Some(crate::config_types::ContainerRuntime::Docker),
+                image: Some("rust:1".to_string()),
+                extra_args: vec!["--pull=never".to_string()],
+            })
+        );
     }
 
     #[test]
@@ -1275,20 +1535,46 @@ exclude_slash_tmp = true
         servers.insert(
             "docs".to_string(),
             McpServerConfig {
-                command: "echo".to_string(),
-                args: vec!["hello".to_string()],
-                env: None,
+                transport: McpServerTransportConfig::Stdio {
+                    command: "echo".to_string(),
+                    args: vec!["hello".to_string()],
+                    env: None,
+                },
                 startup_timeout_ms: None,
+                allow_sampling: false,
+                enabled_tools: None,
+                disabled_tools: Vec::new(),
+                require_approval: Vec::new(),
             },
         );
+        let remote = McpServerConfig {
+            transport: McpServerTransportConfig::StreamableHttp {
+                url: "https://mcp.example.com/mcp".to_string(),
+                bearer_token_env_var: Some("EXAMPLE_TOKEN".to_string()),
+                http_headers: Some(HashMap::from([("X-Team".to_string(), "docs".to_string())])),
+                env_http_headers: None,
+            },
+            startup_timeout_ms: Some(20_000),
+            allow_sampling: true,
+            enabled_tools: Some(vec!["search_*".to_string(), "fetch".to_string()]),
+            disabled_tools: vec!["search_private".to_string()],
+            require_approval: vec!["fetch".to_string()],
+        };
+        servers.insert("remote".to_string(), remote.clone());
 
         write_global_mcp_servers(icodex_home.path(), &servers)?;
 
         let loaded = load_global_mcp_servers(icodex_home.path())?;
-        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.len(), 2);
         let docs = loaded.get("docs").expect("docs entry");
-        assert_eq!(docs.command, "echo");
-        assert_eq!(docs.args, vec!["hello".to_string()]);
+        match &docs.transport {
+            McpServerTransportConfig::Stdio { command, args, .. } => {
+                assert_eq!(command, "echo");
+                assert_eq!(args, &vec!["hello".to_string()]);
+            }
+            other => panic!("unexpected transport: {other:?}"),
+        }
+        assert_eq!(loaded.get("remote"), Some(&remote));
 
         let empty = BTreeMap::new();
         write_global_mcp_servers(icodex_home.path(), &empty)?;
@@ -1586,10 +1872,19 @@ model_verbosity = "high"
                 model_context_window: Some(200_000),
                 model_max_output_tokens: Some(100_000),
                 model_auto_compact_token_limit: None,
+                models: ModelRegistry::default(),
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
+                fallback_providers: Vec::new(),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
+                network_allowlist: Vec::new(),
+                exec_limits: ExecLimits::default(),
+                project_trusted: false,
+                container_sandbox: None,
+                turn_snapshots: true,
+                agents: AgentLimits::default(),
+                agent_depth: 0,
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
                 user_instructions: None,
                 notify: None,
@@ -1616,6 +1911,7 @@ model_verbosity = "high"
                 include_view_image_tool: true,
                 active_profile: Some("o3".to_string()),
                 disable_paste_burst: false,
+                lmi_bridge_command: None,
                 tui_notifications: Default::default(),
             },
             o3_profile_config
@@ -1644,10 +1940,19 @@ model_verbosity = "high"
             model_context_window: Some(16_385),
             model_max_output_tokens: Some(4_096),
             model_auto_compact_token_limit: None,
+            models: ModelRegistry::default(),
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
+            fallback_providers: Vec::new(),
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            network_allowlist: Vec::new(),
+            exec_limits: ExecLimits::default(),
+            project_trusted: false,
+            container_sandbox: None,
+            turn_snapshots: true,
+            agents: AgentLimits::default(),
+            agent_depth: 0,
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
@@ -1674,6 +1979,7 @@ model_verbosity = "high"
             include_view_image_tool: true,
             active_profile: Some("gpt3".to_string()),
             disable_paste_burst: false,
+            lmi_bridge_command: None,
             tui_notifications: Default::default(),
         };
 
@@ -1717,10 +2023,19 @@ model_verbosity = "high"
             model_context_window: Some(200_000),
             model_max_output_tokens: Some(100_000),
             model_auto_compact_token_limit: None,
+            models: ModelRegistry::default(),
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            fallback_providers: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            network_allowlist: Vec::new(),
+            exec_limits: ExecLimits::default(),
+            project_trusted: false,
+            container_sandbox: None,
+            turn_snapshots: true,
+            agents: AgentLimits::default(),
+            agent_depth: 0,
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
@@ -1747,6 +2062,7 @@ model_verbosity = "high"
             include_view_image_tool: true,
             active_profile: Some("zdr".to_string()),
             disable_paste_burst: false,
+            lmi_bridge_command: None,
             tui_notifications: Default::default(),
         };
 
@@ -1776,10 +2092,19 @@ model_verbosity = "high"
             model_context_window: Some(272_000),
             model_max_output_tokens: Some(128_000),
             model_auto_compact_token_limit: None,
+            models: ModelRegistry::default(),
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            fallback_providers: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            network_allowlist: Vec::new(),
+            exec_limits: ExecLimits::default(),
+            project_trusted: false,
+            container_sandbox: None,
+            turn_snapshots: true,
+            agents: AgentLimits::default(),
+            agent_depth: 0,
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
@@ -1806,6 +2131,7 @@ model_verbosity = "high"
             include_view_image_tool: true,
             active_profile: Some("gpt5".to_string()),
             disable_paste_burst: false,
+            lmi_bridge_command: None,
             tui_notifications: Default::default(),
         };
 
@@ -1814,6 +2140,99 @@ model_verbosity = "high"
         Ok(())
     }
 
+    #[test]
+    fn fallback_providers_resolve_with_profile_override() -> std::io::Result<()> {
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+fallback_providers = ["oss"]
+
+[profiles.resilient]
+fallback_providers = ["oss", { provider = "openai", model = "gpt-5-mini" }]
+
+[profiles.broken]
+fallback_providers = ["missing"]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let cwd = TempDir::new()?;
+        let icodex_home = TempDir::new()?;
+        let load = |profile: Option<&str>| {
+            Config::load_from_base_config_with_overrides(
+                cfg.clone(),
+                ConfigOverrides {
+                    config_profile: profile.map(str::to_string),
+                    cwd: Some(cwd.path().to_path_buf()),
+                    ..Default::default()
+                },
+                icodex_home.path().to_path_buf(),
+            )
+        };
+        let providers = built_in_model_providers();
+
+        let config = load(None)?;
+        assert_eq!(
+            config.fallback_providers,
+            vec![FallbackProvider {
+                provider_id: "oss".to_string(),
+                provider: providers["oss"].clone(),
+                model: None,
+            }]
+        );
+
+        let config = load(Some("resilient"))?;
+        assert_eq!(
+            config.fallback_providers,
+            vec![
+                FallbackProvider {
+                    provider_id: "oss".to_string(),
+                    provider: providers["oss"].clone(),
+                    model: None,
+                },
+                FallbackProvider {
+                    provider_id: "openai".to_string(),
+                    provider: providers["openai"].clone(),
+                    model: Some("gpt-5-mini".to_string()),
+                },
+            ]
+        );
+
+        let err = load(Some("broken")).expect_err("unknown provider should fail");
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        Ok(())
+    }
+
+    #[test]
+    fn models_table_configures_session_model() -> std::io::Result<()> {
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+model = "local-coder"
+model_max_output_tokens = 4096
+
+[models."local-coder"]
+context_window = 32768
+max_output_tokens = 8192
+uses_local_shell_tool = true
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let cwd = TempDir::new()?;
+        let icodex_home = TempDir::new()?;
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(cwd.path().to_path_buf()),
+                ..Default::default()
+            },
+            icodex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(config.model_context_window, Some(32_768));
+        // The top-level key still wins over the table.
+        assert_eq!(config.model_max_output_tokens, Some(4_096));
+        assert!(config.model_family.uses_local_shell_tool);
+        Ok(())
+    }
+
     #[test]
     fn test_set_project_trusted_writes_explicit_tables() -> anyhow::Result<()> {
         let project_dir = Path::new("/some/path");
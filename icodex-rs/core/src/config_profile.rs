@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use std::path::PathBuf;
 
+use crate::config_types::FallbackProviderToml;
 use crate::protocol::AskForApproval;
 use icodex_protocol::config_types::ReasoningEffort;
 use icodex_protocol::config_types::ReasoningSummary;
@@ -14,6 +15,9 @@ pub struct ConfigProfile {
     /// The key in the `model_providers` map identifying the
     /// [`ModelProviderInfo`] to use.
     pub model_provider: Option<String>,
+    /// Keys of providers to fail over to, in order, when `model_provider` is
+    /// unavailable. Overrides the top-level `fallback_providers`.
+    pub fallback_providers: Option<Vec<FallbackProviderToml>>,
     pub approval_policy: Option<AskForApproval>,
     pub model_reasoning_effort: Option<ReasoningEffort>,
     pub model_reasoning_summary: Option<ReasoningSummary>,
@@ -9,19 +9,72 @@ use wildmatch::WildMatchPattern;
 
 use serde::Deserialize;
 
+use crate::tool_apply_patch::ApplyPatchToolType;
+
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
-    pub command: String,
+    #[serde(flatten)]
+    pub transport: McpServerTransportConfig,
 
+    /// Startup timeout in milliseconds for initializing MCP server & initially listing tools.
     #[serde(default)]
-    pub args: Vec<String>,
+    pub startup_timeout_ms: Option<u64>,
 
+    /// Whether the server may ask the model for completions through
+    /// `sampling/createMessage`. Each request still needs user approval.
     #[serde(default)]
-    pub env: Option<HashMap<String, String>>,
+    pub allow_sampling: bool,
 
-    /// Startup timeout in milliseconds for initializing MCP server & initially listing tools.
+    /// If set, only the tools matching one of these patterns are exposed to
+    /// the model. Patterns may use `*` and `?` wildcards.
     #[serde(default)]
-    pub startup_timeout_ms: Option<u64>,
+    pub enabled_tools: Option<Vec<String>>,
+
+    /// Tools matching one of these patterns are never exposed to the model,
+    /// even if they also match `enabled_tools`.
+    #[serde(default)]
+    pub disabled_tools: Vec<String>,
+
+    /// Calls to tools matching one of these patterns must be approved by the
+    /// user before they run.
+    #[serde(default)]
+    pub require_approval: Vec<String>,
+}
+
+/// How to reach an MCP server: a command that speaks MCP over stdio, or the
+/// URL of a remote server.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum McpServerTransportConfig {
+    Stdio {
+        command: String,
+
+        #[serde(default)]
+        args: Vec<String>,
+
+        #[serde(default)]
+        env: Option<HashMap<String, String>>,
+    },
+
+    /// Streamable HTTP, falling back to HTTP+SSE for older servers.
+    StreamableHttp {
+        url: String,
+
+        /// Environment variable holding a token sent as
+        /// `Authorization: Bearer <token>`.
+        #[serde(default)]
+        bearer_token_env_var: Option<String>,
+
+        /// Additional HTTP headers to include in requests to this server.
+        #[serde(default)]
+        http_headers: Option<HashMap<String, String>>,
+
+        /// Optional HTTP headers to include in requests to this server. The
+        /// values are environment variable names whose values will be used
+        /// as the header values.
+        #[serde(default)]
+        env_http_headers: Option<HashMap<String, String>>,
+    },
 }
 
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
@@ -89,6 +142,37 @@ impl Default for Notifications {
     }
 }
 
+/// Entry in `fallback_providers`: either a key into `model_providers`, which
+/// keeps the session's model, or a table that also names the model to request.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FallbackProviderToml {
+    Provider(String),
+    ProviderWithModel { provider: String, model: String },
+}
+
+/// Capabilities of one model, from a `[models."<slug>"]` table in
+/// `config.toml` or a file in `models.d/`. Unset fields keep the built-in
+/// value for the slug (or for `family`, when set).
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ModelConfigToml {
+    /// Built-in model family to inherit unset values from, e.g. `"gpt-5"`.
+    pub family: Option<String>,
+    pub context_window: Option<u64>,
+    pub max_output_tokens: Option<u64>,
+    pub auto_compact_token_limit: Option<i64>,
+    pub supports_reasoning_summaries: Option<bool>,
+    pub reasoning_summary_format: Option<ReasoningSummaryFormat>,
+    pub needs_special_apply_patch_instructions: Option<bool>,
+    pub uses_local_shell_tool: Option<bool>,
+    pub apply_patch_tool_type: Option<ApplyPatchToolType>,
+    pub supports_parallel_tool_calls: Option<bool>,
+    /// File with the base instructions for the model. Relative paths are
+    /// resolved against the directory of the file that defines the table.
+    pub base_instructions_file: Option<PathBuf>,
+}
+
 /// Collection of settings that are specific to the TUI.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Tui {
@@ -103,12 +187,120 @@ pub struct SandboxWorkspaceWrite {
     pub writable_roots: Vec<PathBuf>,
     #[serde(default)]
     pub network_access: bool,
+    /// Hosts (`*` and `?` wildcards allowed) that sandboxed commands may
+    /// reach through the network proxy when `network_access` is `false`.
+    /// Only honored by the Linux sandbox.
+    #[serde(default)]
+    pub network_allowlist: Vec<String>,
     #[serde(default)]
     pub exclude_tmpdir_env_var: bool,
     #[serde(default)]
     pub exclude_slash_tmp: bool,
 }
 
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SandboxWorkspaceRead {
+    #[serde(default)]
+    pub readable_roots: Vec<PathBuf>,
+    #[serde(default)]
+    pub exclude_default_readable_roots: bool,
+}
+
+/// What enforces the sandbox policy for commands the agent runs.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SandboxBackend {
+    /// Seatbelt on macOS, Landlock and seccomp on Linux.
+    #[default]
+    Native,
+    /// A local container, configured by `[sandbox_container]`.
+    Container,
+}
+
+/// Container runtimes the `container` sandbox backend can drive.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContainerRuntime {
+    Podman,
+    Docker,
+    /// `bwrap`, which runs commands against the host filesystem rather than
+    /// an image.
+    Bubblewrap,
+}
+
+/// Settings for the `container` sandbox backend.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SandboxContainerToml {
+    /// Defaults to the first of `podman`, `docker` and `bwrap` on the PATH.
+    pub runtime: Option<ContainerRuntime>,
+    /// Image commands run in. Required with podman and docker.
+    pub image: Option<String>,
+    /// Extra arguments for `podman run`/`docker run` (placed before the
+    /// image) or `bwrap` (placed before the command).
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+/// Resource limits applied to every command the agent runs, from the
+/// `[exec_limits]` table. Unset fields are not limited.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ExecLimits {
+    /// Memory the command and its children may use, in MiB.
+    pub memory_mb: Option<u64>,
+    /// Processes (and threads) the command may have running at once. Only
+    /// enforced with per-command cgroups on Linux.
+    pub max_processes: Option<u64>,
+    /// CPU time each process of the command may consume, in seconds.
+    pub cpu_seconds: Option<u64>,
+    /// Share of one CPU the command may use, in percent. Only enforced with
+    /// cgroups on Linux.
+    pub cpu_percent: Option<u64>,
+    /// Bytes of combined stdout and stderr after which the command is killed.
+    pub max_output_bytes: Option<u64>,
+}
+
+/// Limits on the sub-agents started with the `spawn_agent` tool, from the
+/// `[agents]` table.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AgentsToml {
+    /// How deeply sub-agents may nest. `0` disables `spawn_agent`.
+    pub max_depth: Option<u32>,
+    /// Sub-agents a conversation may run at once.
+    pub max_concurrency: Option<usize>,
+}
+
+/// Resolved `[agents]` settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AgentLimits {
+    pub max_depth: u32,
+    pub max_concurrency: usize,
+}
+
+impl Default for AgentLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 1,
+            max_concurrency: 4,
+        }
+    }
+}
+
+impl From<AgentsToml> for AgentLimits {
+    fn from(agents: AgentsToml) -> Self {
+        let default = Self::default();
+        Self {
+            max_depth: agents.max_depth.unwrap_or(default.max_depth),
+            max_concurrency: agents
+                .max_concurrency
+                .unwrap_or(default.max_concurrency)
+                .max(1),
+        }
+    }
+}
+
 impl From<SandboxWorkspaceWrite> for icodex_protocol::mcp_protocol::SandboxSettings {
     fn from(sandbox_workspace_write: SandboxWorkspaceWrite) -> Self {
         Self {
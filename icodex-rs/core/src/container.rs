@@ -0,0 +1,545 @@
+//! Runs sandboxed commands in a local container instead of under the platform
+//! sandbox, for environments such as CI where Seatbelt or Landlock are not
+//! available or commands should run against a known toolchain image.
+//!
+//! As with the Linux sandbox helper, the [`SandboxPolicy`] is translated into
+//! the runtime's command line: the working directory and writable roots are
+//! bind-mounted at the same paths (read-only unless the policy lets commands
+//! write there) and the network is off unless the policy allows it.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::process::Child;
+use tokio::process::Command;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config_types::ContainerRuntime;
+use crate::config_types::ExecLimits;
+use crate::config_types::SandboxContainerToml;
+use crate::exec_limits::CommandLimits;
+use crate::protocol::SandboxPolicy;
+use crate::spawn::CODEX_SANDBOX_ENV_VAR;
+use crate::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use crate::spawn::StdioPolicy;
+use crate::spawn::spawn_child_async;
+
+/// Variables describing the host that would be wrong inside an image.
+const HOST_ONLY_ENV_VARS: &[&str] = &["HOME", "PATH", "TMPDIR"];
+
+/// Runtimes tried, in order, when `sandbox_container.runtime` is not set.
+const RUNTIME_PREFERENCE: &[ContainerRuntime] = &[
+    ContainerRuntime::Podman,
+    ContainerRuntime::Docker,
+    ContainerRuntime::Bubblewrap,
+];
+
+/// The `container` sandbox backend, with its runtime resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerSandbox {
+    pub runtime: ContainerRuntime,
+    /// The runtime's executable.
+    pub program: PathBuf,
+    /// Image commands run in; `None` for bubblewrap.
+    pub image: Option<String>,
+    pub extra_args: Vec<String>,
+}
+
+impl ContainerSandbox {
+    /// Resolve `[sandbox_container]`, looking the runtime up on the PATH.
+    pub fn from_toml(toml: &SandboxContainerToml) -> io::Result<Self> {
+        let (runtime, program) = match toml.runtime {
+            Some(runtime) => {
+                let name = program_name(runtime);
+                let program = which::which(name).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("container runtime `{name}` not found: {e}"),
+                    )
+                })?;
+                (runtime, program)
+            }
+            None => RUNTIME_PREFERENCE
+                .iter()
+                .find_map(|&runtime| {
+                    let program = which::which(program_name(runtime)).ok()?;
+                    Some((runtime, program))
+                })
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "no container runtime found; install podman, docker or bwrap, or set `sandbox_container.runtime`",
+                    )
+                })?,
+        };
+
+        let image = match runtime {
+            ContainerRuntime::Bubblewrap => None,
+            ContainerRuntime::Podman | ContainerRuntime::Docker => {
+                let image = toml.image.clone().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "`sandbox_container.image` is required with {}",
+                            program_name(runtime)
+                        ),
+                    )
+                })?;
+                Some(image)
+            }
+        };
+
+        Ok(Self {
+            runtime,
+            program,
+            image,
+            extra_args: toml.extra_args.clone(),
+        })
+    }
+}
+
+fn program_name(runtime: ContainerRuntime) -> &'static str {
+    match runtime {
+        ContainerRuntime::Podman => "podman",
+        ContainerRuntime::Docker => "docker",
+        ContainerRuntime::Bubblewrap => "bwrap",
+    }
+}
+
+/// A podman or docker container started for a command.
+#[derive(Debug)]
+pub struct RunningContainer {
+    program: PathBuf,
+    name: String,
+}
+
+impl RunningContainer {
+    /// Stop and remove the container. Killing the `run` client does not stop
+    /// it, and `--rm` only cleans up once it has exited on its own.
+    pub async fn remove(&self) {
+        let status = Command::new(&self.program)
+            .args(["rm", "--force", &self.name])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => warn!("failed to remove container {}: {status}", self.name),
+            Err(e) => warn!("failed to remove container {}: {e}", self.name),
+        }
+    }
+}
+
+/// Spawn a shell tool command in the container described by `container`.
+/// Along with the runtime's process, returns the container to remove if the
+/// command has to be stopped early; bubblewrap needs none since its sandbox
+/// dies with it.
+pub async fn spawn_command_under_container(
+    container: &ContainerSandbox,
+    command: Vec<String>,
+    sandbox_policy: &SandboxPolicy,
+    cwd: PathBuf,
+    stdio_policy: StdioPolicy,
+    mut env: HashMap<String, String>,
+    limits: Option<&CommandLimits>,
+) -> io::Result<(Child, Option<RunningContainer>)> {
+    env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "container".to_string());
+    let (args, running, spawn_limits) = match &container.image {
+        Some(image) => {
+            let name = format!("icodex-{}", Uuid::new_v4().simple());
+            let args = create_container_run_args(
+                container,
+                image,
+                &name,
+                command,
+                sandbox_policy,
+                &cwd,
+                &env,
+                matches!(stdio_policy, StdioPolicy::Inherit),
+                limits.map(CommandLimits::limits),
+            );
+            let running = RunningContainer {
+                program: container.program.clone(),
+                name,
+            };
+            // The runtime enforces the limits on the container; applying
+            // them to its client as well would only constrain the client.
+            (args, Some(running), None)
+        }
+        None => (
+            create_bwrap_args(container, command, sandbox_policy, &cwd),
+            None,
+            limits,
+        ),
+    };
+    let arg0 = None;
+    let child = spawn_child_async(
+        container.program.clone(),
+        args,
+        arg0,
+        cwd,
+        sandbox_policy,
+        stdio_policy,
+        env,
+        spawn_limits,
+    )
+    .await?;
+    Ok((child, running))
+}
+
+/// A host path made visible to the command at the same path.
+#[derive(Debug, PartialEq)]
+struct Mount {
+    path: PathBuf,
+    writable: bool,
+}
+
+/// The writable roots, each followed by the subpaths that stay read-only
+/// within it (such as `.git`), or the working directory read-only when the
+/// policy allows no writes.
+fn writable_mounts(sandbox_policy: &SandboxPolicy, cwd: &Path) -> Vec<Mount> {
+    if sandbox_policy.has_full_disk_write_access() {
+        return vec![Mount {
+            path: cwd.to_path_buf(),
+            writable: true,
+        }];
+    }
+    let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
+    if writable_roots.is_empty() {
+        return vec![Mount {
+            path: cwd.to_path_buf(),
+            writable: false,
+        }];
+    }
+    let mut mounts = Vec::new();
+    for writable_root in writable_roots {
+        mounts.push(Mount {
+            path: writable_root.root,
+            writable: true,
+        });
+        mounts.extend(
+            writable_root
+                .read_only_subpaths
+                .into_iter()
+                .map(|path| Mount {
+                    path,
+                    writable: false,
+                }),
+        );
+    }
+    mounts
+}
+
+/// Arguments for `podman run`/`docker run`. The image provides the system;
+/// only the paths the policy names come from the host.
+#[allow(clippy::too_many_arguments)]
+fn create_container_run_args(
+    container: &ContainerSandbox,
+    image: &str,
+    name: &str,
+    command: Vec<String>,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    env: &HashMap<String, String>,
+    interactive: bool,
+    limits: Option<&ExecLimits>,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "run".into(),
+        "--rm".into(),
+        "--init".into(),
+        "--name".into(),
+        name.into(),
+    ];
+    if interactive {
+        args.push("--interactive".into());
+    }
+
+    // Writes outside the mounted roots fail, as they would under the
+    // platform sandbox.
+    args.push("--read-only".into());
+    if !sandbox_policy.has_full_network_access() {
+        args.extend(["--network".into(), "none".into()]);
+    }
+
+    // Run as the calling user so files written to the workspace are theirs.
+    #[cfg(unix)]
+    {
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        if container.runtime == ContainerRuntime::Podman {
+            args.push("--userns=keep-id".into());
+        }
+        args.extend(["--user".into(), format!("{uid}:{gid}")]);
+    }
+
+    let mut mounts = writable_mounts(sandbox_policy, cwd);
+    if let SandboxPolicy::WorkspaceRead { readable_roots, .. } = sandbox_policy {
+        mounts.extend(readable_roots.iter().map(|path| Mount {
+            path: path.clone(),
+            writable: false,
+        }));
+    }
+    let mounts_tmp = mounts.iter().any(|mount| mount.path == Path::new("/tmp"));
+    for Mount { path, writable } in mounts {
+        args.extend([
+            "--mount".into(),
+            bind_mount_arg(&path.to_string_lossy(), writable),
+        ]);
+    }
+    if !mounts_tmp {
+        args.extend(["--tmpfs".into(), "/tmp".into()]);
+    }
+    args.extend(["--workdir".into(), cwd.to_string_lossy().into_owned()]);
+
+    // Name the variables only: the runtime copies their values from its own
+    // environment, which keeps them off its command line.
+    let mut env_keys: Vec<&String> = env
+        .keys()
+        .filter(|key| !HOST_ONLY_ENV_VARS.contains(&key.as_str()))
+        .collect();
+    env_keys.sort();
+    for key in env_keys {
+        args.extend(["--env".into(), key.clone()]);
+    }
+    if !sandbox_policy.has_full_network_access() {
+        args.extend([
+            "--env".into(),
+            format!("{CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR}=1"),
+        ]);
+    }
+
+    // The command runs under the container runtime rather than as our
+    // child, so the limits have to be handed to the runtime.
+    if let Some(limits) = limits {
+        if let Some(memory_mb) = limits.memory_mb {
+            args.extend(["--memory".into(), format!("{memory_mb}m")]);
+        }
+        if let Some(max_processes) = limits.max_processes {
+            args.extend(["--pids-limit".into(), max_processes.to_string()]);
+        }
+        if let Some(cpu_percent) = limits.cpu_percent {
+            let cpus = cpu_percent as f64 / 100.0;
+            args.extend(["--cpus".into(), format!("{cpus:.2}")]);
+        }
+        if let Some(cpu_seconds) = limits.cpu_seconds {
+            let hard = cpu_seconds.saturating_add(1);
+            args.extend(["--ulimit".into(), format!("cpu={cpu_seconds}:{hard}")]);
+        }
+    }
+
+    args.extend(container.extra_args.iter().cloned());
+    args.push(image.to_string());
+    args.extend(command);
+    args
+}
+
+/// `--mount` value binding `path` to the same path in the container. Unlike
+/// `--volume`, it does not split on `:`. The value is CSV, so fields holding
+/// a comma or quote are quoted.
+fn bind_mount_arg(path: &str, writable: bool) -> String {
+    let csv_field = |field: String| {
+        if field.contains([',', '"']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field
+        }
+    };
+    let mut fields = vec![
+        "type=bind".to_string(),
+        csv_field(format!("src={path}")),
+        csv_field(format!("dst={path}")),
+    ];
+    if !writable {
+        fields.push("readonly".to_string());
+    }
+    fields.join(",")
+}
+
+/// Arguments for `bwrap`, which shares the host filesystem: all of it
+/// read-only, or only the readable roots under `workspace-read`.
+fn create_bwrap_args(
+    container: &ContainerSandbox,
+    command: Vec<String>,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "--new-session".into(),
+        "--die-with-parent".into(),
+        "--unshare-all".into(),
+    ];
+    if sandbox_policy.has_full_network_access() {
+        args.push("--share-net".into());
+    }
+
+    if sandbox_policy.has_full_disk_read_access() {
+        args.extend(["--ro-bind".into(), "/".into(), "/".into()]);
+    } else {
+        for root in sandbox_policy.get_readable_roots_with_cwd(cwd) {
+            let root = root.to_string_lossy().into_owned();
+            args.extend(["--ro-bind".into(), root.clone(), root]);
+        }
+    }
+    args.extend([
+        "--dev".into(),
+        "/dev".into(),
+        "--proc".into(),
+        "/proc".into(),
+        "--tmpfs".into(),
+        "/tmp".into(),
+    ]);
+
+    // Later mounts win, so writable roots override the read-only view and
+    // their read-only subpaths override them in turn.
+    for Mount { path, writable } in writable_mounts(sandbox_policy, cwd) {
+        let path = path.to_string_lossy().into_owned();
+        let flag = if writable { "--bind" } else { "--ro-bind" };
+        args.extend([flag.into(), path.clone(), path]);
+    }
+
+    args.extend(["--chdir".into(), cwd.to_string_lossy().into_owned()]);
+    args.extend(container.extra_args.iter().cloned());
+    args.push("--".into());
+    args.extend(command);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn podman() -> ContainerSandbox {
+        ContainerSandbox {
+            runtime: ContainerRuntime::Podman,
+            program: PathBuf::from("/usr/bin/podman"),
+            image: Some("rust:1".to_string()),
+            extra_args: vec!["--pull=never".to_string()],
+        }
+    }
+
+    fn workspace_write(network_access: bool) -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    /// Whether `needle` appears in `args` as consecutive arguments.
+    fn contains_args(args: &[String], needle: &[&str]) -> bool {
+        args.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn container_run_mounts_cwd_and_disables_network() {
+        let cwd = std::env::temp_dir().join("project");
+        let cwd_str = cwd.to_string_lossy().into_owned();
+        let env = HashMap::from([
+            ("PATH".to_string(), "/host/bin".to_string()),
+            ("CARGO_TERM_COLOR".to_string(), "never".to_string()),
+        ]);
+
+        let args = create_container_run_args(
+            &podman(),
+            "rust:1",
+            "icodex-test",
+            vec!["cargo".to_string(), "test".to_string()],
+            &workspace_write(false),
+            &cwd,
+            &env,
+            false,
+            None,
+        );
+
+        assert!(contains_args(&args, &["--name", "icodex-test"]));
+        assert!(contains_args(&args, &["--network", "none"]));
+        assert!(contains_args(
+            &args,
+            &["--mount", &format!("type=bind,src={cwd_str},dst={cwd_str}")]
+        ));
+        assert!(contains_args(&args, &["--workdir", &cwd_str]));
+        assert!(contains_args(&args, &["--env", "CARGO_TERM_COLOR"]));
+        assert!(!contains_args(&args, &["--env", "PATH"]));
+        assert!(contains_args(&args, &["--tmpfs", "/tmp"]));
+        assert_eq!(
+            args[args.len() - 4..],
+            ["--pull=never", "rust:1", "cargo", "test"]
+        );
+    }
+
+    #[test]
+    fn container_run_respects_read_only_policy_and_limits() {
+        let cwd = std::env::temp_dir().join("project");
+        let cwd_str = cwd.to_string_lossy().into_owned();
+        let limits = ExecLimits {
+            memory_mb: Some(512),
+            cpu_percent: Some(150),
+            ..Default::default()
+        };
+
+        let args = create_container_run_args(
+            &podman(),
+            "rust:1",
+            "icodex-test",
+            vec!["ls".to_string()],
+            &SandboxPolicy::ReadOnly,
+            &cwd,
+            &HashMap::new(),
+            false,
+            Some(&limits),
+        );
+
+        assert!(contains_args(
+            &args,
+            &[
+                "--mount",
+                &format!("type=bind,src={cwd_str},dst={cwd_str},readonly")
+            ]
+        ));
+        assert!(contains_args(&args, &["--memory", "512m"]));
+        assert!(contains_args(&args, &["--cpus", "1.50"]));
+    }
+
+    #[test]
+    fn bind_mounts_keep_colons_and_quote_commas() {
+        assert_eq!(
+            bind_mount_arg("/work/a:b", true),
+            "type=bind,src=/work/a:b,dst=/work/a:b"
+        );
+        assert_eq!(
+            bind_mount_arg("/work/a,b", false),
+            r#"type=bind,"src=/work/a,b","dst=/work/a,b",readonly"#
+        );
+    }
+
+    #[test]
+    fn bwrap_binds_writable_roots_over_read_only_root() {
+        let cwd = std::env::temp_dir().join("project");
+        let cwd_str = cwd.to_string_lossy().into_owned();
+        let bwrap = ContainerSandbox {
+            runtime: ContainerRuntime::Bubblewrap,
+            program: PathBuf::from("/usr/bin/bwrap"),
+            image: None,
+            extra_args: Vec::new(),
+        };
+
+        let args = create_bwrap_args(
+            &bwrap,
+            vec!["make".to_string()],
+            &workspace_write(true),
+            &cwd,
+        );
+
+        assert!(contains_args(&args, &["--ro-bind", "/", "/"]));
+        assert!(contains_args(&args, &["--bind", &cwd_str, &cwd_str]));
+        assert!(contains_args(&args, &["--share-net"]));
+        assert_eq!(args[args.len() - 2..], ["--", "make"]);
+    }
+}
@@ -27,6 +27,7 @@ pub(crate) struct EnvironmentContext {
     pub sandbox_mode: Option<SandboxMode>,
     pub network_access: Option<NetworkAccess>,
     pub writable_roots: Option<Vec<PathBuf>>,
+    pub readable_roots: Option<Vec<PathBuf>>,
     pub shell: Option<Shell>,
 }
 
@@ -43,12 +44,14 @@ impl EnvironmentContext {
             sandbox_mode: match sandbox_policy {
                 Some(SandboxPolicy::DangerFullAccess) => Some(SandboxMode::DangerFullAccess),
                 Some(SandboxPolicy::ReadOnly) => Some(SandboxMode::ReadOnly),
+                Some(SandboxPolicy::WorkspaceRead { .. }) => Some(SandboxMode::WorkspaceRead),
                 Some(SandboxPolicy::WorkspaceWrite { .. }) => Some(SandboxMode::WorkspaceWrite),
                 None => None,
             },
             network_access: match sandbox_policy {
                 Some(SandboxPolicy::DangerFullAccess) => Some(NetworkAccess::Enabled),
                 Some(SandboxPolicy::ReadOnly) => Some(NetworkAccess::Restricted),
+                Some(SandboxPolicy::WorkspaceRead { .. }) => Some(NetworkAccess::Restricted),
                 Some(SandboxPolicy::WorkspaceWrite { network_access, .. }) => {
                     if network_access {
                         Some(NetworkAccess::Enabled)
@@ -58,12 +61,22 @@ impl EnvironmentContext {
                 }
                 None => None,
             },
-            writable_roots: match sandbox_policy {
+            writable_roots: match &sandbox_policy {
                 Some(SandboxPolicy::WorkspaceWrite { writable_roots, .. }) => {
                     if writable_roots.is_empty() {
                         None
                     } else {
-                        Some(writable_roots)
+                        Some(writable_roots.clone())
+                    }
+                }
+                _ => None,
+            },
+            readable_roots: match sandbox_policy {
+                Some(SandboxPolicy::WorkspaceRead { readable_roots, .. }) => {
+                    if readable_roots.is_empty() {
+                        None
+                    } else {
+                        Some(readable_roots)
                     }
                 }
                 _ => None,
@@ -84,6 +97,7 @@ impl EnvironmentContext {
     ///   <approval_policy>...</approval_policy>
     ///   <sandbox_mode>...</sandbox_mode>
     ///   <writable_roots>...</writable_roots>
+    ///   <readable_roots>...</readable_roots>
     ///   <network_access>...</network_access>
     ///   <shell>...</shell>
     /// </environment_context>
@@ -116,6 +130,16 @@ impl EnvironmentContext {
             }
             lines.push("  </writable_roots>".to_string());
         }
+        if let Some(readable_roots) = self.readable_roots {
+            lines.push("  <readable_roots>".to_string());
+            for readable_root in readable_roots {
+                lines.push(format!(
+                    "    <root>{}</root>",
+                    readable_root.to_string_lossy()
+                ));
+            }
+            lines.push("  </readable_roots>".to_string());
+        }
         if let Some(shell) = self.shell
             && let Some(shell_name) = shell.name()
         {
@@ -193,6 +217,31 @@ mod tests {
         assert_eq!(context.serialize_to_xml(), expected);
     }
 
+    #[test]
+    fn serialize_workspace_read_environment_context() {
+        let context = EnvironmentContext::new(
+            Some(PathBuf::from("/repo")),
+            Some(AskForApproval::OnRequest),
+            Some(SandboxPolicy::WorkspaceRead {
+                readable_roots: vec![PathBuf::from("/opt/sdk")],
+                exclude_default_readable_roots: false,
+            }),
+            None,
+        );
+
+        let expected = r#"<environment_context>
+  <cwd>/repo</cwd>
+  <approval_policy>on-request</approval_policy>
+  <sandbox_mode>workspace-read</sandbox_mode>
+  <network_access>restricted</network_access>
+  <readable_roots>
+    <root>/opt/sdk</root>
+  </readable_roots>
+</environment_context>"#;
+
+        assert_eq!(context.serialize_to_xml(), expected);
+    }
+
     #[test]
     fn serialize_full_access_environment_context() {
         let context = EnvironmentContext::new(
@@ -43,6 +43,45 @@ pub enum SandboxErr {
     LandlockRestrict,
 }
 
+/// Failures of the Large Models Interface bridge process itself, as opposed to
+/// errors reported by the upstream model provider through the bridge.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LmiBridgeError {
+    #[error(
+        "failed to start the LMI bridge `{command}`: {message}. Check that Node.js is installed or set `lmi_bridge_command` in config.toml."
+    )]
+    Spawn { command: String, message: String },
+
+    #[error(
+        "the LMI bridge speaks protocol version {found}, but this build requires version {expected}. Update the icodex-cli package."
+    )]
+    IncompatibleProtocol { expected: u32, found: u32 },
+
+    #[error("LMI bridge handshake failed: {0}")]
+    HandshakeFailed(String),
+
+    #[error("the LMI bridge did not answer a health check within {0:?} and was stopped")]
+    Unresponsive(Duration),
+
+    #[error("the LMI bridge wrote invalid output: {0}")]
+    InvalidOutput(String),
+
+    #[error("the LMI bridge exited unexpectedly")]
+    Exited,
+}
+
+impl LmiBridgeError {
+    /// Whether a fresh bridge process may succeed where this one failed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            LmiBridgeError::Unresponsive(_)
+                | LmiBridgeError::InvalidOutput(_)
+                | LmiBridgeError::Exited
+        )
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum CodexErr {
     /// Returned by ResponsesClient when the SSE stream disconnects or errors out **after** the HTTP
@@ -104,6 +143,13 @@ pub enum CodexErr {
     #[error("icodex-linux-sandbox was required but not provided")]
     LandlockSandboxExecutableNotProvided,
 
+    #[error("the container sandbox was required but is not configured")]
+    ContainerSandboxNotConfigured,
+
+    /// The LMI bridge process failed; see [`LmiBridgeError`].
+    #[error(transparent)]
+    LmiBridge(#[from] LmiBridgeError),
+
     // -----------------------------------------------------------------
     // Automatic conversions for common external error types
     // -----------------------------------------------------------------
@@ -131,6 +177,22 @@ pub enum CodexErr {
     EnvVar(EnvVarError),
 }
 
+impl CodexErr {
+    /// Whether the provider itself could not serve the request (rate limits,
+    /// server errors or an unreachable endpoint), as opposed to a problem with
+    /// the request. These are the errors that trigger `fallback_providers`.
+    pub fn is_provider_unavailable(&self) -> bool {
+        match self {
+            CodexErr::RetryLimit(_)
+            | CodexErr::InternalServerError
+            | CodexErr::UsageLimitReached(_)
+            | CodexErr::LmiBridge(_) => true,
+            CodexErr::Reqwest(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct UsageLimitReachedError {
     pub(crate) plan_type: Option<PlanType>,
@@ -275,6 +337,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn provider_unavailable_covers_rate_limits_and_server_errors() {
+        assert!(CodexErr::RetryLimit(StatusCode::TOO_MANY_REQUESTS).is_provider_unavailable());
+        assert!(CodexErr::RetryLimit(StatusCode::BAD_GATEWAY).is_provider_unavailable());
+        assert!(CodexErr::InternalServerError.is_provider_unavailable());
+        assert!(
+            !CodexErr::UnexpectedStatus(StatusCode::BAD_REQUEST, String::new())
+                .is_provider_unavailable()
+        );
+        assert!(!CodexErr::UsageNotIncluded.is_provider_unavailable());
+    }
+
     #[test]
     fn usage_limit_reached_error_formats_free_plan() {
         let err = UsageLimitReachedError {
@@ -371,6 +445,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lmi_bridge_errors_distinguish_retryable_failures() {
+        assert!(LmiBridgeError::Exited.is_retryable());
+        assert!(LmiBridgeError::Unresponsive(Duration::from_secs(1)).is_retryable());
+        assert!(
+            !LmiBridgeError::IncompatibleProtocol {
+                expected: 1,
+                found: 2
+            }
+            .is_retryable()
+        );
+        assert_eq!(
+            CodexErr::from(LmiBridgeError::Exited).to_string(),
+            "the LMI bridge exited unexpectedly"
+        );
+    }
+
     #[test]
     fn usage_limit_reached_less_than_minute() {
         let err = UsageLimitReachedError {
@@ -39,6 +39,8 @@ pub(crate) fn map_response_item_to_event_messages(
                                 Some(InputMessageKind::EnvironmentContext)
                             } else if trimmed.starts_with("<user_instructions>") {
                                 Some(InputMessageKind::UserInstructions)
+                            } else if trimmed.starts_with("<mcp_resources>") {
+                                Some(InputMessageKind::McpResources)
                             } else {
                                 Some(InputMessageKind::Plain)
                             };
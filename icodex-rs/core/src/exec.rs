@@ -5,6 +5,10 @@ use std::collections::HashMap;
 use std::io;
 use std::path::PathBuf;
 use std::process::ExitStatus;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -13,14 +17,21 @@ use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
+use tokio::sync::Notify;
 
+use crate::config_types::ExecLimits;
+use crate::container::ContainerSandbox;
+use crate::container::spawn_command_under_container;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
+use crate::exec_limits::CommandLimits;
 use crate::landlock::spawn_command_under_linux_sandbox;
+use crate::network_proxy::take_proxy_env;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecCommandOutputDeltaEvent;
+use crate::protocol::ExecLimitExceeded;
 use crate::protocol::ExecOutputStream;
 use crate::protocol::SandboxPolicy;
 use crate::seatbelt::spawn_command_under_seatbelt;
@@ -69,6 +80,9 @@ pub enum SandboxType {
 
     /// Only available on Linux.
     LinuxSeccomp,
+
+    /// A local container; see [`crate::container`].
+    Container,
 }
 
 #[derive(Clone)]
@@ -79,19 +93,36 @@ pub struct StdoutStream {
 }
 
 pub async fn process_exec_tool_call(
-    params: ExecParams,
+    mut params: ExecParams,
     sandbox_type: SandboxType,
     sandbox_policy: &SandboxPolicy,
     icodex_linux_sandbox_exe: &Option<PathBuf>,
+    container_sandbox: Option<&ContainerSandbox>,
+    limits: &ExecLimits,
     stdout_stream: Option<StdoutStream>,
 ) -> Result<ExecToolCallOutput> {
     let start = Instant::now();
 
     let timeout_duration = params.timeout_duration();
+    let command_limits = CommandLimits::new(limits);
+    let max_output_bytes = command_limits.max_output_bytes();
+
+    // Only the Linux sandbox provides the port the proxy variables point at.
+    if sandbox_type != SandboxType::LinuxSeccomp {
+        take_proxy_env(&mut params.env);
+    }
 
     let raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr> = match sandbox_type
     {
-        SandboxType::None => exec(params, sandbox_policy, stdout_stream.clone()).await,
+        SandboxType::None => {
+            exec(
+                params,
+                sandbox_policy,
+                &command_limits,
+                stdout_stream.clone(),
+            )
+            .await
+        }
         SandboxType::MacosSeatbelt => {
             let ExecParams {
                 command, cwd, env, ..
@@ -102,9 +133,16 @@ pub async fn process_exec_tool_call(
                 cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
+                Some(&command_limits),
             )
             .await?;
-            consume_truncated_output(child, timeout_duration, stdout_stream.clone()).await
+            consume_truncated_output(
+                child,
+                timeout_duration,
+                max_output_bytes,
+                stdout_stream.clone(),
+            )
+            .await
         }
         SandboxType::LinuxSeccomp => {
             let ExecParams {
@@ -121,30 +159,68 @@ pub async fn process_exec_tool_call(
                 cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
+                Some(&command_limits),
             )
             .await?;
 
-            consume_truncated_output(child, timeout_duration, stdout_stream).await
+            consume_truncated_output(child, timeout_duration, max_output_bytes, stdout_stream).await
+        }
+        SandboxType::Container => {
+            let ExecParams {
+                command, cwd, env, ..
+            } = params;
+
+            let container_sandbox =
+                container_sandbox.ok_or(CodexErr::ContainerSandboxNotConfigured)?;
+            let (child, running) = spawn_command_under_container(
+                container_sandbox,
+                command,
+                sandbox_policy,
+                cwd,
+                StdioPolicy::RedirectForShellTool,
+                env,
+                Some(&command_limits),
+            )
+            .await?;
+
+            let output =
+                consume_truncated_output(child, timeout_duration, max_output_bytes, stdout_stream)
+                    .await;
+            let stopped_early = match &output {
+                Ok(output) => output.timed_out || output.output_limit_exceeded,
+                Err(_) => true,
+            };
+            if stopped_early && let Some(running) = running {
+                running.remove().await;
+            }
+            output
         }
     };
     let duration = start.elapsed();
     match raw_output_result {
         Ok(raw_output) => {
+            let limit_exceeded = if raw_output.output_limit_exceeded {
+                Some(ExecLimitExceeded::OutputLimit)
+            } else {
+                command_limits.exceeded(raw_output.exit_status)
+            };
             #[allow(unused_mut)]
             let mut timed_out = raw_output.timed_out;
+            let mut exit_code = raw_output.exit_status.code().unwrap_or(-1);
 
             #[cfg(target_family = "unix")]
             {
                 if let Some(signal) = raw_output.exit_status.signal() {
                     if signal == TIMEOUT_CODE {
                         timed_out = true;
+                    } else if limit_exceeded.is_some() {
+                        exit_code = EXIT_CODE_SIGNAL_BASE + signal;
                     } else {
                         return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
                     }
                 }
             }
 
-            let mut exit_code = raw_output.exit_status.code().unwrap_or(-1);
             if timed_out {
                 exit_code = EXEC_TIMEOUT_EXIT_CODE;
             }
@@ -159,6 +235,7 @@ pub async fn process_exec_tool_call(
                 aggregated_output,
                 duration,
                 timed_out,
+                limit_exceeded,
             };
 
             if timed_out {
@@ -167,7 +244,12 @@ pub async fn process_exec_tool_call(
                 }));
             }
 
-            if exit_code != 0 && is_likely_sandbox_denied(sandbox_type, exit_code) {
+            // Running into a limit is not the sandbox's doing, and retrying
+            // without it would not help.
+            if exit_code != 0
+                && limit_exceeded.is_none()
+                && is_likely_sandbox_denied(sandbox_type, exit_code)
+            {
                 return Err(CodexErr::Sandbox(SandboxErr::Denied {
                     output: Box::new(exec_output),
                 }));
@@ -214,6 +296,7 @@ struct RawExecToolCallOutput {
     pub stderr: StreamOutput<Vec<u8>>,
     pub aggregated_output: StreamOutput<Vec<u8>>,
     pub timed_out: bool,
+    pub output_limit_exceeded: bool,
 }
 
 impl StreamOutput<String> {
@@ -247,11 +330,14 @@ pub struct ExecToolCallOutput {
     pub aggregated_output: StreamOutput<String>,
     pub duration: Duration,
     pub timed_out: bool,
+    /// Set when the command was stopped by one of the `exec_limits`.
+    pub limit_exceeded: Option<ExecLimitExceeded>,
 }
 
 async fn exec(
     params: ExecParams,
     sandbox_policy: &SandboxPolicy,
+    limits: &CommandLimits,
     stdout_stream: Option<StdoutStream>,
 ) -> Result<RawExecToolCallOutput> {
     let timeout = params.timeout_duration();
@@ -274,16 +360,66 @@ async fn exec(
         sandbox_policy,
         StdioPolicy::RedirectForShellTool,
         env,
+        Some(limits),
     )
     .await?;
-    consume_truncated_output(child, timeout, stdout_stream).await
+    consume_truncated_output(child, timeout, limits.max_output_bytes(), stdout_stream).await
+}
+
+/// Bytes a command may still write to stdout and stderr combined before it
+/// is killed.
+struct OutputBudget {
+    remaining: AtomicU64,
+    exceeded: AtomicBool,
+    exhausted: Notify,
+}
+
+impl OutputBudget {
+    fn new(max_output_bytes: u64) -> Self {
+        Self {
+            remaining: AtomicU64::new(max_output_bytes),
+            exceeded: AtomicBool::new(false),
+            exhausted: Notify::new(),
+        }
+    }
+
+    /// Spend up to `n` bytes of the budget and return how many of them fit.
+    fn spend(&self, n: usize) -> usize {
+        let wanted = n as u64;
+        let remaining = self
+            .remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |remaining| {
+                Some(remaining.saturating_sub(wanted))
+            })
+            .unwrap_or_default();
+        if wanted > remaining {
+            self.exceeded.store(true, Ordering::SeqCst);
+            self.exhausted.notify_one();
+        }
+        wanted.min(remaining) as usize
+    }
+
+    fn is_exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::SeqCst)
+    }
+}
+
+/// Resolves once the command has written more than its budget, or never
+/// when there is none.
+async fn output_budget_exhausted(budget: Option<&OutputBudget>) {
+    match budget {
+        Some(budget) => budget.exhausted.notified().await,
+        None => std::future::pending().await,
+    }
 }
 
 /// Consumes the output of a child process, truncating it so it is suitable for
-/// use as the output of a `shell` tool call. Also enforces specified timeout.
+/// use as the output of a `shell` tool call. Also enforces specified timeout,
+/// and kills the child once it writes more than `max_output_bytes`.
 async fn consume_truncated_output(
     mut child: Child,
     timeout: Duration,
+    max_output_bytes: Option<u64>,
     stdout_stream: Option<StdoutStream>,
 ) -> Result<RawExecToolCallOutput> {
     // Both stdout and stderr were configured with `Stdio::piped()`
@@ -302,18 +438,21 @@ async fn consume_truncated_output(
     })?;
 
     let (agg_tx, agg_rx) = async_channel::unbounded::<Vec<u8>>();
+    let output_budget = max_output_bytes.map(|max| Arc::new(OutputBudget::new(max)));
 
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
         stdout_stream.clone(),
         false,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
     let stderr_handle = tokio::spawn(read_capped(
         BufReader::new(stderr_reader),
         stdout_stream.clone(),
         true,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
 
     let (exit_status, timed_out) = tokio::select! {
@@ -335,6 +474,10 @@ async fn consume_truncated_output(
             child.start_kill()?;
             (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
         }
+        _ = output_budget_exhausted(output_budget.as_deref()) => {
+            child.start_kill()?;
+            (synthetic_exit_status(SIGKILL_CODE), false)
+        }
     };
 
     let stdout = stdout_handle.await??;
@@ -357,6 +500,7 @@ async fn consume_truncated_output(
         stderr,
         aggregated_output,
         timed_out,
+        output_limit_exceeded: output_budget.is_some_and(|budget| budget.is_exceeded()),
     })
 }
 
@@ -365,6 +509,7 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     stream: Option<StdoutStream>,
     is_stderr: bool,
     aggregate_tx: Option<Sender<Vec<u8>>>,
+    output_budget: Option<Arc<OutputBudget>>,
 ) -> io::Result<StreamOutput<Vec<u8>>> {
     let mut buf = Vec::with_capacity(AGGREGATE_BUFFER_INITIAL_CAPACITY);
     let mut tmp = [0u8; READ_CHUNK_SIZE];
@@ -377,6 +522,15 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         if n == 0 {
             break;
         }
+        // Past the output limit, keep draining the pipe until the kill lands
+        // but drop what is read.
+        let n = match &output_budget {
+            Some(budget) => budget.spend(n),
+            None => n,
+        };
+        if n == 0 {
+            continue;
+        }
 
         if let Some(stream) = &stream
             && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL
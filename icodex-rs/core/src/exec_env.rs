@@ -1,8 +1,10 @@
 use crate::config_types::EnvironmentVariablePattern;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyInherit;
+use crate::network_proxy::apply_proxy_env;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 
 /// Construct an environment map based on the rules in the specified policy. The
 /// resulting map can be passed directly to `Command::envs()` after calling
@@ -10,9 +12,18 @@ use std::collections::HashSet;
 /// process.
 ///
 /// The derivation follows the algorithm documented in the struct-level comment
-/// for [`ShellEnvironmentPolicy`].
-pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
-    populate_env(std::env::vars(), policy)
+/// for [`ShellEnvironmentPolicy`]. When `network_proxy_socket` is set,
+/// `HTTP(S)_PROXY` are then pointed at the sandbox's network proxy, which
+/// forwards to that socket.
+pub fn create_env(
+    policy: &ShellEnvironmentPolicy,
+    network_proxy_socket: Option<&Path>,
+) -> HashMap<String, String> {
+    let mut env_map = populate_env(std::env::vars(), policy);
+    if let Some(socket_path) = network_proxy_socket {
+        apply_proxy_env(&mut env_map, socket_path);
+    }
+    env_map
 }
 
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
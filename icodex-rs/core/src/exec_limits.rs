@@ -0,0 +1,412 @@
+//! CPU, memory, process and output limits for the commands the agent runs.
+//!
+//! Limits come from the `[exec_limits]` table and are applied to each command
+//! as it is spawned. On Linux, when this process runs in a cgroup v2
+//! hierarchy delegated to the user, each command also gets a cgroup of its
+//! own enforcing `memory.max`, `pids.max` and `cpu.max`; its event counters
+//! are what let us tell an out-of-memory kill from any other `SIGKILL`.
+//! Without cgroups, `RLIMIT_DATA` stands in for the memory limit and the
+//! process limit is not enforced: `RLIMIT_NPROC` counts every process the
+//! user owns, so any useful value would break `fork` in the command.
+//! `RLIMIT_CPU` always enforces the CPU time limit, and the output limit is
+//! enforced while reading the command's output.
+
+#[cfg(target_os = "linux")]
+use std::ffi::CStr;
+#[cfg(all(unix, not(target_os = "linux")))]
+use std::ffi::CString;
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::sync::Once;
+
+use tracing::warn;
+
+use crate::config_types::ExecLimits;
+use crate::protocol::ExecLimitExceeded;
+
+#[cfg(unix)]
+const MIB: u64 = 1024 * 1024;
+
+/// Limits for one command, along with the cgroup enforcing them when there
+/// is one. Dropping it stops whatever the command left running in that
+/// cgroup.
+#[derive(Debug)]
+pub struct CommandLimits {
+    limits: ExecLimits,
+    #[cfg(target_os = "linux")]
+    cgroup: Option<cgroup::CommandCgroup>,
+}
+
+impl CommandLimits {
+    pub fn new(limits: &ExecLimits) -> Self {
+        #[cfg(target_os = "linux")]
+        let cgroup = cgroup::CommandCgroup::for_limits(limits);
+        #[cfg(target_os = "linux")]
+        let has_cgroup = cgroup.is_some();
+        #[cfg(not(target_os = "linux"))]
+        let has_cgroup = false;
+        if limits.max_processes.is_some() && !has_cgroup {
+            static WARNED: Once = Once::new();
+            WARNED.call_once(|| {
+                warn!(
+                    "`exec_limits.max_processes` is not enforced: it requires per-command cgroups"
+                );
+            });
+        }
+
+        Self {
+            limits: *limits,
+            #[cfg(target_os = "linux")]
+            cgroup,
+        }
+    }
+
+    pub fn limits(&self) -> &ExecLimits {
+        &self.limits
+    }
+
+    pub fn max_output_bytes(&self) -> Option<u64> {
+        self.limits.max_output_bytes
+    }
+
+    /// Closure for [`std::os::unix::process::CommandExt::pre_exec`] that
+    /// moves the child into its cgroup and sets its rlimits. Everything it
+    /// needs is prepared here because only async-signal-safe calls may be
+    /// made between `fork` and `exec`.
+    #[cfg(unix)]
+    pub(crate) fn pre_exec_hook(&self) -> impl FnMut() -> io::Result<()> + Send + Sync + 'static {
+        let limits = self.limits;
+        #[cfg(target_os = "linux")]
+        let cgroup_procs = self
+            .cgroup
+            .as_ref()
+            .map(|cgroup| cgroup.procs_path().to_owned());
+        #[cfg(not(target_os = "linux"))]
+        let cgroup_procs: Option<CString> = None;
+
+        move || {
+            #[cfg(target_os = "linux")]
+            if let Some(procs) = &cgroup_procs {
+                join_cgroup(procs)?;
+            }
+
+            let set_rlimit = |resource, soft: u64, hard: u64| -> io::Result<()> {
+                let limit = libc::rlimit {
+                    rlim_cur: soft as libc::rlim_t,
+                    rlim_max: hard as libc::rlim_t,
+                };
+                if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            };
+
+            if let Some(seconds) = limits.cpu_seconds {
+                // The soft limit delivers SIGXCPU; the hard limit a second
+                // later kills processes that ignore it.
+                set_rlimit(libc::RLIMIT_CPU, seconds, seconds.saturating_add(1))?;
+            }
+            if cgroup_procs.is_none()
+                && let Some(memory_mb) = limits.memory_mb
+            {
+                let bytes = memory_mb.saturating_mul(MIB);
+                set_rlimit(libc::RLIMIT_DATA, bytes, bytes)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// The limit that stopped a command which exited with `exit_status`, if
+    /// any. Commands that succeed are never reported, even if one of their
+    /// processes ran into a limit along the way.
+    pub fn exceeded(&self, exit_status: ExitStatus) -> Option<ExecLimitExceeded> {
+        if exit_status.success() {
+            return None;
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup {
+            if cgroup.oom_killed() {
+                return Some(ExecLimitExceeded::OomKilled);
+            }
+            if cgroup.hit_process_limit() {
+                return Some(ExecLimitExceeded::ProcessLimit);
+            }
+        }
+
+        #[cfg(unix)]
+        if self.limits.cpu_seconds.is_some() {
+            // A shell reports a child killed by SIGXCPU as 128 + SIGXCPU.
+            let killed_by_sigxcpu = exit_status.signal() == Some(libc::SIGXCPU)
+                || exit_status.code() == Some(128 + libc::SIGXCPU);
+            if killed_by_sigxcpu {
+                return Some(ExecLimitExceeded::CpuTimeLimit);
+            }
+        }
+
+        None
+    }
+}
+
+/// Move the calling process into the cgroup whose `cgroup.procs` is `procs`.
+#[cfg(target_os = "linux")]
+fn join_cgroup(procs: &CStr) -> io::Result<()> {
+    let fd = unsafe { libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Writing 0 moves the writer itself.
+    let written = unsafe { libc::write(fd, b"0".as_ptr().cast(), 1) };
+    let err = io::Error::last_os_error();
+    unsafe { libc::close(fd) };
+    if written != 1 {
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod cgroup {
+    use std::ffi::CStr;
+    use std::ffi::CString;
+    use std::fs;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    use tracing::debug;
+    use tracing::warn;
+    use uuid::Uuid;
+
+    use crate::config_types::ExecLimits;
+
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+    /// Period `cpu.max` quotas are expressed against, in microseconds.
+    const CPU_PERIOD_US: u64 = 100_000;
+
+    /// How long dropping a cgroup waits for its killed processes to exit.
+    const REMOVE_ATTEMPTS: u32 = 20;
+    const REMOVE_RETRY_DELAY: Duration = Duration::from_millis(5);
+
+    static EXEC_CGROUP_PARENT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+    #[derive(Debug)]
+    pub(super) struct CommandCgroup {
+        path: PathBuf,
+        procs: CString,
+    }
+
+    impl CommandCgroup {
+        /// A cgroup enforcing `limits`, or `None` when no limit needs one or
+        /// cgroups are unavailable.
+        pub(super) fn for_limits(limits: &ExecLimits) -> Option<Self> {
+            if limits.memory_mb.is_none()
+                && limits.max_processes.is_none()
+                && limits.cpu_percent.is_none()
+            {
+                return None;
+            }
+            let parent = exec_cgroup_parent()?;
+            match Self::create(parent, limits) {
+                Ok(cgroup) => Some(cgroup),
+                Err(e) => {
+                    warn!(
+                        "failed to create a cgroup for the command, falling back to rlimits: {e}"
+                    );
+                    None
+                }
+            }
+        }
+
+        fn create(parent: &Path, limits: &ExecLimits) -> io::Result<Self> {
+            let path = parent.join(format!("exec-{}", Uuid::new_v4().simple()));
+            let procs = CString::new(path.join("cgroup.procs").as_os_str().as_bytes())
+                .map_err(io::Error::other)?;
+            fs::create_dir(&path)?;
+            // From here on, dropping `cgroup` removes the directory again.
+            let cgroup = Self { path, procs };
+
+            if let Some(memory_mb) = limits.memory_mb {
+                let bytes = memory_mb.saturating_mul(super::MIB);
+                cgroup.write("memory.max", &bytes.to_string())?;
+                // Swap would let the command go past the limit; not every
+                // kernel has swap accounting.
+                let _ = cgroup.write("memory.swap.max", "0");
+                // Kill the whole command rather than one of its processes.
+                let _ = cgroup.write("memory.oom.group", "1");
+            }
+            if let Some(max_processes) = limits.max_processes {
+                cgroup.write("pids.max", &max_processes.to_string())?;
+            }
+            if let Some(cpu_percent) = limits.cpu_percent {
+                let quota = (CPU_PERIOD_US * cpu_percent / 100).max(1000);
+                cgroup.write("cpu.max", &format!("{quota} {CPU_PERIOD_US}"))?;
+            }
+            Ok(cgroup)
+        }
+
+        pub(super) fn procs_path(&self) -> &CStr {
+            &self.procs
+        }
+
+        pub(super) fn oom_killed(&self) -> bool {
+            self.event_count("memory.events", "oom_kill") > 0
+        }
+
+        pub(super) fn hit_process_limit(&self) -> bool {
+            self.event_count("pids.events", "max") > 0
+        }
+
+        fn write(&self, file: &str, value: &str) -> io::Result<()> {
+            fs::write(self.path.join(file), value)
+        }
+
+        /// Read the `key` counter from an events file such as
+        /// `memory.events`, whose lines are `<key> <count>`.
+        fn event_count(&self, file: &str, key: &str) -> u64 {
+            let Ok(events) = fs::read_to_string(self.path.join(file)) else {
+                return 0;
+            };
+            events
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .find(|(name, _)| *name == key)
+                .and_then(|(_, count)| count.trim().parse().ok())
+                .unwrap_or(0)
+        }
+    }
+
+    impl Drop for CommandCgroup {
+        fn drop(&mut self) {
+            // Background processes the command left behind would otherwise
+            // keep running outside of any limit.
+            let _ = self.write("cgroup.kill", "1");
+            // Removal waits for the killed processes, which must not block an
+            // async worker thread.
+            let path = std::mem::take(&mut self.path);
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle) => {
+                    handle.spawn_blocking(move || remove_cgroup(&path));
+                }
+                Err(_) => remove_cgroup(&path),
+            }
+        }
+    }
+
+    fn remove_cgroup(path: &Path) {
+        for _ in 0..REMOVE_ATTEMPTS {
+            match fs::remove_dir(path) {
+                Ok(()) => return,
+                // Killed processes take a moment to leave the cgroup.
+                Err(e) if e.raw_os_error() == Some(libc::EBUSY) => {
+                    std::thread::sleep(REMOVE_RETRY_DELAY);
+                }
+                Err(e) => {
+                    debug!("failed to remove cgroup {}: {e}", path.display());
+                    return;
+                }
+            }
+        }
+        debug!("gave up removing busy cgroup {}", path.display());
+    }
+
+    /// The cgroup per-command cgroups are created in: the one this process
+    /// started in, once the process itself has moved into a leaf below it so
+    /// controllers can be enabled for its children (cgroup v2 does not allow
+    /// both processes and controlled children in one cgroup). `None` when
+    /// that is not possible, e.g. the hierarchy is not delegated to the user.
+    fn exec_cgroup_parent() -> Option<&'static Path> {
+        EXEC_CGROUP_PARENT
+            .get_or_init(|| match prepare_exec_cgroup_parent() {
+                Ok(parent) => Some(parent),
+                Err(e) => {
+                    warn!("per-command cgroups are unavailable, falling back to rlimits: {e}");
+                    None
+                }
+            })
+            .as_deref()
+    }
+
+    fn prepare_exec_cgroup_parent() -> io::Result<PathBuf> {
+        let own = current_cgroup()?;
+
+        // Moving out of a cgroup we share with other processes would not
+        // free it up for controllers.
+        let pid = std::process::id().to_string();
+        let procs = fs::read_to_string(own.join("cgroup.procs"))?;
+        if procs.lines().any(|line| line.trim() != pid) {
+            return Err(io::Error::other(format!(
+                "{} is shared with other processes",
+                own.display()
+            )));
+        }
+
+        let leaf = own.join("icodex");
+        match fs::create_dir(&leaf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        fs::write(leaf.join("cgroup.procs"), &pid)?;
+        fs::write(own.join("cgroup.subtree_control"), "+memory +pids +cpu")?;
+        Ok(own)
+    }
+
+    /// This process's cgroup, from the `0::<path>` line of
+    /// `/proc/self/cgroup` that cgroup v2 writes.
+    fn current_cgroup() -> io::Result<PathBuf> {
+        let cgroups = fs::read_to_string("/proc/self/cgroup")?;
+        let relative = cgroups
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .ok_or_else(|| io::Error::other("no cgroup v2 hierarchy"))?;
+        Ok(Path::new(CGROUP_ROOT).join(relative.trim_start_matches('/')))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn cpu_time_kills_are_reported() {
+        let limits = CommandLimits::new(&ExecLimits {
+            cpu_seconds: Some(1),
+            ..Default::default()
+        });
+
+        let by_signal = ExitStatus::from_raw(libc::SIGXCPU);
+        let by_shell = ExitStatus::from_raw((128 + libc::SIGXCPU) << 8);
+        assert_eq!(
+            limits.exceeded(by_signal),
+            Some(ExecLimitExceeded::CpuTimeLimit)
+        );
+        assert_eq!(
+            limits.exceeded(by_shell),
+            Some(ExecLimitExceeded::CpuTimeLimit)
+        );
+    }
+
+    #[test]
+    fn nothing_is_reported_without_limits_or_on_success() {
+        let unlimited = CommandLimits::new(&ExecLimits::default());
+        assert_eq!(
+            unlimited.exceeded(ExitStatus::from_raw(libc::SIGXCPU)),
+            None
+        );
+
+        let limited = CommandLimits::new(&ExecLimits {
+            cpu_seconds: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(limited.exceeded(ExitStatus::from_raw(0)), None);
+    }
+}
@@ -0,0 +1,467 @@
+//! User- and project-supplied `execpolicy` rules for shell commands.
+//!
+//! Two optional Starlark policy files are consulted, in this order:
+//!
+//!   1. `$CODEX_HOME/exec.policy` (applies to every session)
+//!   2. `<repo root>/.icodex/exec.policy` (falls back to the session `cwd`
+//!      when it is not inside a Git repository)
+//!
+//! The files use the same format as the built-in `default.policy` that ships
+//! with the `icodex-execpolicy` crate.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use icodex_execpolicy::ArgType;
+use icodex_execpolicy::ExecCall;
+use icodex_execpolicy::MatchedExec;
+use icodex_execpolicy::Policy;
+use icodex_execpolicy::PolicyParser;
+
+use crate::bash::try_parse_bash;
+use crate::bash::try_parse_word_only_commands_sequence;
+use crate::git_info::get_git_repo_root;
+use crate::protocol::SandboxPolicy;
+use crate::safety::is_path_writable_under_policy;
+
+pub(crate) const EXEC_POLICY_FILENAME: &str = "exec.policy";
+
+/// Directory inside a project that holds project-local icodex settings.
+const PROJECT_CONFIG_DIR: &str = ".icodex";
+
+/// Outcome of checking a command against the loaded policies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ExecPolicyVerdict {
+    /// No policy is loaded, or at least one command did not match any rule.
+    Unmatched,
+    /// Every command matched a rule and none of them writes files.
+    Safe { policy: PathBuf },
+    /// Every command matched a rule and the only files they write are
+    /// `paths`, as given on the command line (relative paths are relative to
+    /// the command's `cwd`).
+    Writes {
+        policy: PathBuf,
+        paths: Vec<PathBuf>,
+    },
+    /// Every command matched a rule, but at least one of them has arguments
+    /// whose effect cannot be determined, so it may write anywhere.
+    MightWriteFiles { policy: PathBuf },
+    /// A command was explicitly forbidden by a policy.
+    Forbidden { policy: PathBuf, reason: String },
+}
+
+impl ExecPolicyVerdict {
+    /// The first file the command writes that `sandbox_policy` does not
+    /// allow writing, resolved against `cwd`.
+    pub(crate) fn write_outside_writable_roots(
+        &self,
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> Option<PathBuf> {
+        let ExecPolicyVerdict::Writes { paths, .. } = self else {
+            return None;
+        };
+        paths
+            .iter()
+            .map(|path| cwd.join(path))
+            .find(|path| !is_path_writable_under_policy(path, sandbox_policy, cwd))
+    }
+
+    /// Short note describing the verdict for the approval prompt, if the
+    /// command matched any policy at all.
+    pub(crate) fn approval_note(
+        &self,
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> Option<String> {
+        if let Some(path) = self.write_outside_writable_roots(sandbox_policy, cwd) {
+            return Some(format!(
+                "writes to {}, outside the writable roots",
+                path.display()
+            ));
+        }
+        match self {
+            ExecPolicyVerdict::Unmatched | ExecPolicyVerdict::Forbidden { .. } => None,
+            ExecPolicyVerdict::Safe { policy } => Some(format!("allowed by {}", policy.display())),
+            ExecPolicyVerdict::Writes { policy, .. } => Some(format!(
+                "matched {}, writes only inside the writable roots",
+                policy.display()
+            )),
+            ExecPolicyVerdict::MightWriteFiles { policy } => {
+                Some(format!("matched {}, but may write files", policy.display()))
+            }
+        }
+    }
+}
+
+struct LoadedPolicy {
+    path: PathBuf,
+    policy: Policy,
+}
+
+/// The set of policies that apply to a session.
+#[derive(Default)]
+pub(crate) struct ExecPolicy {
+    policies: Vec<LoadedPolicy>,
+}
+
+impl std::fmt::Debug for ExecPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.policies.iter().map(|p| &p.path))
+            .finish()
+    }
+}
+
+impl ExecPolicy {
+    /// Loads the global policy file and, for a trusted project, the project
+    /// one; a cloned repository must not be able to approve its own commands.
+    /// Files that do not exist are skipped; files that fail to parse are
+    /// skipped and reported in the returned list of error messages so the
+    /// session can still start.
+    pub(crate) fn load(
+        icodex_home: &Path,
+        cwd: &Path,
+        project_trusted: bool,
+    ) -> (Self, Vec<String>) {
+        let mut candidates = vec![icodex_home.join(EXEC_POLICY_FILENAME)];
+        if project_trusted {
+            let project_root = get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf());
+            candidates.push(
+                project_root
+                    .join(PROJECT_CONFIG_DIR)
+                    .join(EXEC_POLICY_FILENAME),
+            );
+        }
+
+        let mut policies = Vec::new();
+        let mut errors = Vec::new();
+        for path in candidates {
+            if policies.iter().any(|p: &LoadedPolicy| p.path == path) {
+                continue;
+            }
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    errors.push(format!(
+                        "failed to read exec policy {}: {e}",
+                        path.display()
+                    ));
+                    continue;
+                }
+            };
+            match PolicyParser::new(&path.to_string_lossy(), &contents).parse() {
+                Ok(policy) => policies.push(LoadedPolicy { path, policy }),
+                Err(e) => errors.push(format!(
+                    "failed to parse exec policy {}: {}",
+                    path.display(),
+                    e.into_anyhow()
+                )),
+            }
+        }
+
+        (Self { policies }, errors)
+    }
+
+    /// Checks `command` against every loaded policy. `bash -lc` scripts made
+    /// up of plain commands are split and each command is checked on its
+    /// own: the script is safe only if every command is, and forbidden if any
+    /// command is.
+    pub(crate) fn check(&self, command: &[String]) -> ExecPolicyVerdict {
+        if self.policies.is_empty() {
+            return ExecPolicyVerdict::Unmatched;
+        }
+
+        if let [bash, flag, script] = command
+            && bash == "bash"
+            && flag == "-lc"
+            && let Some(tree) = try_parse_bash(script)
+            && let Some(all_commands) = try_parse_word_only_commands_sequence(&tree, script)
+            && !all_commands.is_empty()
+        {
+            let verdicts: Vec<ExecPolicyVerdict> = all_commands
+                .iter()
+                .map(|cmd| self.check_single(cmd))
+                .collect();
+            let changes_dir = all_commands
+                .iter()
+                .any(|cmd| cmd.first().map(String::as_str) == Some("cd"));
+            return combine_verdicts(verdicts, changes_dir);
+        }
+
+        self.check_single(command)
+    }
+
+    fn check_single(&self, command: &[String]) -> ExecPolicyVerdict {
+        let Some((program, args)) = command.split_first() else {
+            return ExecPolicyVerdict::Unmatched;
+        };
+        let exec_call = ExecCall {
+            program: program.clone(),
+            args: args.to_vec(),
+        };
+
+        // A prohibition in any policy wins over a match in another one.
+        let mut verdict = ExecPolicyVerdict::Unmatched;
+        for LoadedPolicy { path, policy } in &self.policies {
+            match policy.check(&exec_call) {
+                Ok(MatchedExec::Forbidden { reason, .. }) => {
+                    return ExecPolicyVerdict::Forbidden {
+                        policy: path.clone(),
+                        reason,
+                    };
+                }
+                Ok(MatchedExec::Match { exec }) => {
+                    if verdict != ExecPolicyVerdict::Unmatched {
+                        continue;
+                    }
+                    let arg_types = exec
+                        .args
+                        .iter()
+                        .map(|arg| (&arg.r#type, &arg.value))
+                        .chain(exec.opts.iter().map(|opt| (&opt.r#type, &opt.value)));
+                    let mut paths = Vec::new();
+                    let mut unknown = false;
+                    for (arg_type, value) in arg_types {
+                        match arg_type {
+                            ArgType::WriteableFile => paths.push(PathBuf::from(value)),
+                            ArgType::Unknown => unknown = true,
+                            _ => {}
+                        }
+                    }
+                    let policy = path.clone();
+                    verdict = if unknown {
+                        ExecPolicyVerdict::MightWriteFiles { policy }
+                    } else if paths.is_empty() {
+                        ExecPolicyVerdict::Safe { policy }
+                    } else {
+                        ExecPolicyVerdict::Writes { policy, paths }
+                    };
+                }
+                Err(_) => {}
+            }
+        }
+        verdict
+    }
+}
+
+/// Merges the verdicts for the commands of a `bash -lc` script. When the
+/// script contains a `cd`, relative write targets cannot be resolved against
+/// the original `cwd`, so writes are treated as unknown.
+fn combine_verdicts(verdicts: Vec<ExecPolicyVerdict>, changes_dir: bool) -> ExecPolicyVerdict {
+    if let Some(forbidden) = verdicts
+        .iter()
+        .find(|v| matches!(v, ExecPolicyVerdict::Forbidden { .. }))
+    {
+        return forbidden.clone();
+    }
+    if verdicts.contains(&ExecPolicyVerdict::Unmatched) {
+        return ExecPolicyVerdict::Unmatched;
+    }
+    if let Some(writes) = verdicts
+        .iter()
+        .find(|v| matches!(v, ExecPolicyVerdict::MightWriteFiles { .. }))
+    {
+        return writes.clone();
+    }
+
+    let mut combined: Option<ExecPolicyVerdict> = None;
+    for verdict in verdicts {
+        combined = Some(match (combined, verdict) {
+            (None, verdict) => verdict,
+            (
+                Some(ExecPolicyVerdict::Writes { policy, mut paths }),
+                ExecPolicyVerdict::Writes { paths: more, .. },
+            ) => {
+                paths.extend(more);
+                ExecPolicyVerdict::Writes { policy, paths }
+            }
+            (Some(ExecPolicyVerdict::Writes { policy, paths }), _) => {
+                ExecPolicyVerdict::Writes { policy, paths }
+            }
+            (Some(_), verdict) => verdict,
+        });
+    }
+    match combined {
+        Some(ExecPolicyVerdict::Writes { policy, .. }) if changes_dir => {
+            ExecPolicyVerdict::MightWriteFiles { policy }
+        }
+        Some(verdict) => verdict,
+        None => ExecPolicyVerdict::Unmatched,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    const POLICY: &str = r#"
+define_program(
+    program="cargo",
+    args=["check"],
+)
+
+define_program(
+    program="touch",
+    args=[ARG_WFILE],
+)
+
+define_program(
+    program="cd",
+    args=[ARG_RFILE],
+)
+
+forbid_program_regex(
+    regex="^(rm|shred)$",
+    reason="deleting files is not allowed in this repo",
+)
+"#;
+
+    fn vec_str(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn load_project_policy(contents: &str) -> (TempDir, ExecPolicy, Vec<String>) {
+        load_project_policy_with_trust(contents, true)
+    }
+
+    fn load_project_policy_with_trust(
+        contents: &str,
+        project_trusted: bool,
+    ) -> (TempDir, ExecPolicy, Vec<String>) {
+        let home = TempDir::new().unwrap();
+        let project = home.path().join("project");
+        std::fs::create_dir_all(project.join(".git")).unwrap();
+        std::fs::create_dir_all(project.join(PROJECT_CONFIG_DIR)).unwrap();
+        std::fs::write(
+            project.join(PROJECT_CONFIG_DIR).join(EXEC_POLICY_FILENAME),
+            contents,
+        )
+        .unwrap();
+        let cwd = project.join("src");
+        std::fs::create_dir_all(&cwd).unwrap();
+        let (policy, errors) = ExecPolicy::load(home.path(), &cwd, project_trusted);
+        (home, policy, errors)
+    }
+
+    #[test]
+    fn classifies_commands_from_project_policy() {
+        let (home, policy, errors) = load_project_policy(POLICY);
+        assert!(errors.is_empty(), "{errors:?}");
+        let policy_path = home
+            .path()
+            .join("project")
+            .join(PROJECT_CONFIG_DIR)
+            .join(EXEC_POLICY_FILENAME);
+
+        assert_eq!(
+            policy.check(&vec_str(&["cargo", "check"])),
+            ExecPolicyVerdict::Safe {
+                policy: policy_path.clone()
+            }
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["touch", "out.txt"])),
+            ExecPolicyVerdict::Writes {
+                policy: policy_path.clone(),
+                paths: vec![PathBuf::from("out.txt")],
+            }
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["rm", "-rf", "target"])),
+            ExecPolicyVerdict::Forbidden {
+                policy: policy_path,
+                reason: "deleting files is not allowed in this repo".to_string(),
+            }
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["cargo", "publish"])),
+            ExecPolicyVerdict::Unmatched
+        );
+    }
+
+    #[test]
+    fn untrusted_project_policy_is_ignored() {
+        let (_home, policy, errors) = load_project_policy_with_trust(POLICY, false);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            policy.check(&vec_str(&["cargo", "check"])),
+            ExecPolicyVerdict::Unmatched
+        );
+    }
+
+    #[test]
+    fn bash_scripts_are_checked_command_by_command() {
+        let (_home, policy, _) = load_project_policy(POLICY);
+
+        assert!(matches!(
+            policy.check(&vec_str(&["bash", "-lc", "cargo check && cargo check"])),
+            ExecPolicyVerdict::Safe { .. }
+        ));
+        assert!(matches!(
+            policy.check(&vec_str(&["bash", "-lc", "cargo check && rm foo"])),
+            ExecPolicyVerdict::Forbidden { .. }
+        ));
+        assert_eq!(
+            policy.check(&vec_str(&["bash", "-lc", "cargo check && ls"])),
+            ExecPolicyVerdict::Unmatched
+        );
+        assert!(matches!(
+            policy.check(&vec_str(&["bash", "-lc", "touch a && touch /b"])),
+            ExecPolicyVerdict::Writes { paths, .. }
+                if paths == vec![PathBuf::from("a"), PathBuf::from("/b")]
+        ));
+        // Relative paths cannot be resolved once the script changes directory.
+        assert!(matches!(
+            policy.check(&vec_str(&["bash", "-lc", "cd .. && touch a"])),
+            ExecPolicyVerdict::MightWriteFiles { .. }
+        ));
+    }
+
+    #[test]
+    fn writes_are_checked_against_writable_roots() {
+        let (home, policy, _) = load_project_policy(POLICY);
+        let cwd = home.path().join("project");
+        let workspace_write = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        let inside = policy.check(&vec_str(&["touch", "out.txt"]));
+        assert_eq!(
+            inside.write_outside_writable_roots(&workspace_write, &cwd),
+            None
+        );
+        assert_eq!(
+            inside.write_outside_writable_roots(&SandboxPolicy::ReadOnly, &cwd),
+            Some(cwd.join("out.txt"))
+        );
+
+        let outside = policy.check(&vec_str(&["touch", "../escape.txt"]));
+        assert_eq!(
+            outside.write_outside_writable_roots(&workspace_write, &cwd),
+            Some(cwd.join("../escape.txt"))
+        );
+        assert_eq!(
+            outside.write_outside_writable_roots(&SandboxPolicy::DangerFullAccess, &cwd),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_errors_are_reported_and_skipped() {
+        let (_home, policy, errors) = load_project_policy("define_program(");
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("failed to parse exec policy"));
+        assert_eq!(
+            policy.check(&vec_str(&["cargo", "check"])),
+            ExecPolicyVerdict::Unmatched
+        );
+    }
+}
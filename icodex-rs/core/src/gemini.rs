@@ -0,0 +1,712 @@
+//! Native client for the Gemini API's `streamGenerateContent` endpoint.
+//!
+//! The conversation history is translated into `user` / `model` contents made
+//! of parts: function calls become `functionCall` parts and their outputs
+//! `functionResponse` parts. Each server-sent event carries a partial
+//! `GenerateContentResponse`; text is forwarded as deltas and the finished
+//! message, reasoning and function calls are emitted when the stream ends, in
+//! the same order the Chat Completions client uses.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_responses_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use icodex_protocol::models::ContentItem;
+use icodex_protocol::models::ReasoningItemContent;
+use icodex_protocol::models::ResponseItem;
+
+/// Implementation for the Gemini `streamGenerateContent` API.
+pub(crate) async fn stream_gemini(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+) -> Result<ResponseStream> {
+    let payload = build_gemini_request(prompt, model_family, max_output_tokens)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_gemini_stream_url(&model_family.slug),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let req_builder = provider
+            .create_gemini_request_builder(client, &model_family.slug)
+            .await?;
+
+        let res = req_builder
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload)
+            .send()
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_gemini_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Builds the JSON body of a `streamGenerateContent` request.
+pub(crate) fn build_gemini_request(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+) -> Result<Value> {
+    let instructions = prompt.get_full_instructions(model_family);
+    let contents = build_contents(&prompt.get_formatted_input());
+    let declarations = function_declarations(create_tools_json_for_responses_api(&prompt.tools)?);
+
+    let mut payload = json!({
+        "systemInstruction": {"parts": [{"text": instructions}]},
+        "contents": contents,
+    });
+    if let Some(obj) = payload.as_object_mut() {
+        if !declarations.is_empty() {
+            obj.insert(
+                "tools".to_string(),
+                json!([{"functionDeclarations": declarations}]),
+            );
+        }
+        if let Some(max_output_tokens) = max_output_tokens {
+            obj.insert(
+                "generationConfig".to_string(),
+                json!({"maxOutputTokens": max_output_tokens}),
+            );
+        }
+    }
+    Ok(payload)
+}
+
+/// Rewrites the Responses API function tools into Gemini function
+/// declarations. Other tool types have no Gemini equivalent and are dropped.
+fn function_declarations(responses_api_tools: Vec<Value>) -> Vec<Value> {
+    responses_api_tools
+        .into_iter()
+        .filter(|tool| tool.get("type").and_then(Value::as_str) == Some("function"))
+        .map(|tool| {
+            let mut parameters = tool.get("parameters").cloned().unwrap_or(Value::Null);
+            strip_unsupported_schema_keys(&mut parameters);
+            json!({
+                "name": tool.get("name"),
+                "description": tool.get("description"),
+                "parameters": parameters,
+            })
+        })
+        .collect()
+}
+
+/// Gemini accepts an OpenAPI subset of JSON Schema that rejects
+/// `additionalProperties`.
+fn strip_unsupported_schema_keys(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            map.remove("additionalProperties");
+            map.values_mut().for_each(strip_unsupported_schema_keys);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_unsupported_schema_keys),
+        _ => {}
+    }
+}
+
+/// Translates the conversation history into Gemini contents. Adjacent items
+/// with the same role are merged so that every `functionResponse` directly
+/// follows the `model` turn holding the matching `functionCall`.
+fn build_contents(input: &[ResponseItem]) -> Vec<Value> {
+    let mut contents: Vec<Value> = Vec::new();
+    // Function responses are matched by name, which `FunctionCallOutput`
+    // does not carry.
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+    let mut push_part = |role: &str, part: Value| {
+        if let Some(last) = contents.last_mut()
+            && last.get("role").and_then(Value::as_str) == Some(role)
+            && let Some(parts) = last.get_mut("parts").and_then(Value::as_array_mut)
+        {
+            parts.push(part);
+            return;
+        }
+        contents.push(json!({"role": role, "parts": [part]}));
+    };
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" { "model" } else { "user" };
+                for content_item in content {
+                    match content_item {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push_part(role, json!({"text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push_part(role, image_part(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                call_names.insert(call_id, name);
+                let args = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                push_part(
+                    "model",
+                    json!({"functionCall": {"name": name, "args": args}}),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                if let Some(call_id) = call_id.as_deref().or(id.as_deref()) {
+                    call_names.insert(call_id, "local_shell");
+                }
+                push_part(
+                    "model",
+                    json!({"functionCall": {"name": "local_shell", "args": action}}),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                call_names.insert(call_id, name);
+                push_part(
+                    "model",
+                    json!({"functionCall": {"name": name, "args": {"input": input}}}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                push_part(
+                    "user",
+                    function_response_part(&call_names, call_id, &output.content),
+                );
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                push_part("user", function_response_part(&call_names, call_id, output));
+            }
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {
+                // Omit these items from the conversation history.
+                continue;
+            }
+        }
+    }
+
+    contents
+}
+
+fn function_response_part(call_names: &HashMap<&str, &str>, call_id: &str, output: &str) -> Value {
+    let name = call_names.get(call_id).copied().unwrap_or(call_id);
+    json!({"functionResponse": {"name": name, "response": {"content": output}}})
+}
+
+/// Images are attached as data URLs by the TUI; anything else is passed by
+/// reference.
+fn image_part(image_url: &str) -> Value {
+    if let Some(rest) = image_url.strip_prefix("data:")
+        && let Some((mime_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({"inlineData": {"mimeType": mime_type, "data": data}});
+    }
+    json!({"fileData": {"fileUri": image_url}})
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentChunk {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    #[serde(default)]
+    usage_metadata: Option<UsageMetadata>,
+    #[serde(default)]
+    response_id: Option<String>,
+    #[serde(default)]
+    error: Option<GeminiError>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    #[serde(default)]
+    content: Option<CandidateContent>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CandidateContent {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    thought: bool,
+    #[serde(default)]
+    function_call: Option<FunctionCallPart>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FunctionCallPart {
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    args: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    cached_content_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+    #[serde(default)]
+    thoughts_token_count: u64,
+    #[serde(default)]
+    total_token_count: Option<u64>,
+}
+
+impl From<UsageMetadata> for TokenUsage {
+    fn from(val: UsageMetadata) -> Self {
+        // Thinking tokens are billed as output but reported separately.
+        let output_tokens = val.candidates_token_count + val.thoughts_token_count;
+        TokenUsage {
+            input_tokens: val.prompt_token_count,
+            cached_input_tokens: val.cached_content_token_count,
+            output_tokens,
+            reasoning_output_tokens: val.thoughts_token_count,
+            total_tokens: val
+                .total_token_count
+                .unwrap_or(val.prompt_token_count + output_tokens),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiError {
+    #[serde(default)]
+    status: Option<String>,
+    message: String,
+}
+
+/// SSE processor for the `streamGenerateContent?alt=sse` format. The stream
+/// has no terminal event: it is complete once a candidate reported a
+/// `finishReason` and the server closed the connection.
+async fn process_gemini_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut created = false;
+    let mut finished = false;
+    let mut response_id = String::new();
+    let mut usage: Option<UsageMetadata> = None;
+    let mut assistant_text = String::new();
+    let mut reasoning_text = String::new();
+    let mut function_calls: Vec<ResponseItem> = Vec::new();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) if !finished => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before finishReason".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        trace!("gemini received SSE chunk: {}", sse.data);
+        let chunk: GenerateContentChunk = match serde_json::from_str(&sse.data) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                debug!("Failed to parse Gemini SSE chunk: {e}, data: {}", &sse.data);
+                continue;
+            }
+        };
+
+        if let Some(error) = chunk.error {
+            let message = match error.status {
+                Some(status) => format!("{status}: {}", error.message),
+                None => error.message,
+            };
+            let _ = tx_event.send(Err(CodexErr::Stream(message, None))).await;
+            return;
+        }
+
+        if !created {
+            created = true;
+            if tx_event.send(Ok(ResponseEvent::Created)).await.is_err() {
+                return;
+            }
+        }
+        if let Some(id) = chunk.response_id {
+            response_id = id;
+        }
+        if chunk.usage_metadata.is_some() {
+            usage = chunk.usage_metadata;
+        }
+
+        // Only the first candidate is requested.
+        let Some(candidate) = chunk.candidates.into_iter().next() else {
+            continue;
+        };
+        if candidate.finish_reason.is_some() {
+            finished = true;
+        }
+        let parts = candidate.content.map(|c| c.parts).unwrap_or_default();
+        for part in parts {
+            if let Some(call) = part.function_call {
+                let arguments = call.args.unwrap_or_else(|| json!({})).to_string();
+                function_calls.push(ResponseItem::FunctionCall {
+                    id: None,
+                    name: call.name,
+                    arguments,
+                    call_id: call
+                        .id
+                        .unwrap_or_else(|| format!("call_{}", Uuid::new_v4().simple())),
+                });
+                continue;
+            }
+
+            let Some(text) = part.text.filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            let event = if part.thought {
+                reasoning_text.push_str(&text);
+                ResponseEvent::ReasoningContentDelta(text)
+            } else {
+                assistant_text.push_str(&text);
+                ResponseEvent::OutputTextDelta(text)
+            };
+            if tx_event.send(Ok(event)).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    let mut items = Vec::new();
+    if !reasoning_text.is_empty() {
+        items.push(ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: Some(vec![ReasoningItemContent::ReasoningText {
+                text: reasoning_text,
+            }]),
+            encrypted_content: None,
+        });
+    }
+    if !assistant_text.is_empty() {
+        items.push(ResponseItem::Message {
+            id: None,
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText {
+                text: assistant_text,
+            }],
+        });
+    }
+    items.extend(function_calls);
+    for item in items {
+        if tx_event
+            .send(Ok(ResponseEvent::OutputItemDone(item)))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+
+    let _ = tx_event
+        .send(Ok(ResponseEvent::Completed {
+            response_id,
+            token_usage: usage.map(Into::into),
+        }))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icodex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    async fn run_sse(chunks: Vec<Value>) -> Vec<Result<ResponseEvent>> {
+        let body: String = chunks
+            .iter()
+            .map(|chunk| format!("data: {chunk}\n\n"))
+            .collect();
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(32);
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        process_gemini_sse(stream, tx, Duration::from_secs(1)).await;
+
+        let mut out = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            out.push(ev);
+        }
+        out
+    }
+
+    #[test]
+    fn translates_function_calls_and_responses() {
+        let input = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "list files".to_string(),
+                }],
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "call_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "README.md".to_string(),
+                    success: Some(true),
+                },
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Found a README.".to_string(),
+                }],
+            },
+        ];
+
+        assert_eq!(
+            build_contents(&input),
+            vec![
+                json!({"role": "user", "parts": [{"text": "list files"}]}),
+                json!({"role": "model", "parts": [
+                    {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}},
+                ]}),
+                json!({"role": "user", "parts": [
+                    {"functionResponse": {"name": "shell", "response": {"content": "README.md"}}},
+                ]}),
+                json!({"role": "model", "parts": [{"text": "Found a README."}]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_additional_properties_from_nested_schemas() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "opts": {"type": "object", "properties": {}, "additionalProperties": false},
+            },
+            "additionalProperties": false,
+        });
+        strip_unsupported_schema_keys(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {"opts": {"type": "object", "properties": {}}},
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_stream_onto_response_events() {
+        let events = run_sse(vec![
+            json!({"candidates": [{"content": {"role": "model", "parts": [
+                {"text": "planning", "thought": true},
+            ]}}], "responseId": "resp_1"}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"text": "Let me look."}]}}]}),
+            json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [
+                        {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}},
+                    ]},
+                    "finishReason": "STOP",
+                }],
+                "usageMetadata": {
+                    "promptTokenCount": 100,
+                    "cachedContentTokenCount": 40,
+                    "candidatesTokenCount": 10,
+                    "thoughtsTokenCount": 5,
+                    "totalTokenCount": 115,
+                },
+            }),
+        ])
+        .await;
+
+        assert_eq!(events.len(), 7);
+        assert!(matches!(events[0], Ok(ResponseEvent::Created)));
+        assert!(
+            matches!(&events[1], Ok(ResponseEvent::ReasoningContentDelta(d)) if d == "planning")
+        );
+        assert!(matches!(&events[2], Ok(ResponseEvent::OutputTextDelta(d)) if d == "Let me look."));
+        assert!(matches!(
+            &events[3],
+            Ok(ResponseEvent::OutputItemDone(
+                ResponseItem::Reasoning { .. }
+            ))
+        ));
+        assert!(matches!(
+            &events[4],
+            Ok(ResponseEvent::OutputItemDone(ResponseItem::Message { .. }))
+        ));
+        assert!(matches!(
+            &events[5],
+            Ok(ResponseEvent::OutputItemDone(ResponseItem::FunctionCall { name, arguments, .. }))
+                if name == "shell" && arguments == r#"{"command":["ls"]}"#
+        ));
+        match &events[6] {
+            Ok(ResponseEvent::Completed {
+                response_id,
+                token_usage: Some(usage),
+            }) => {
+                assert_eq!(response_id, "resp_1");
+                assert_eq!(usage.input_tokens, 100);
+                assert_eq!(usage.cached_input_tokens, 40);
+                assert_eq!(usage.output_tokens, 15);
+                assert_eq!(usage.reasoning_output_tokens, 5);
+                assert_eq!(usage.total_tokens, 115);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_without_finish_reason_is_an_error() {
+        let events = run_sse(vec![
+            json!({"candidates": [{"content": {"parts": [{"text": "Hi"}]}}]}),
+        ])
+        .await;
+
+        assert!(matches!(events.last(), Some(Err(CodexErr::Stream(..)))));
+    }
+
+    #[tokio::test]
+    async fn error_chunk_surfaces_stream_error() {
+        let events = run_sse(vec![json!({
+            "error": {"code": 429, "status": "RESOURCE_EXHAUSTED", "message": "Quota exceeded"},
+        })])
+        .await;
+
+        match events.last() {
+            Some(Err(CodexErr::Stream(msg, None))) => {
+                assert_eq!(msg, "RESOURCE_EXHAUSTED: Quota exceeded")
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+}
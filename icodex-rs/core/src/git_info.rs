@@ -1,6 +1,9 @@
 use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::io;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Stdio;
 
 use icodex_protocol::mcp_protocol::GitSha;
 use icodex_protocol::protocol::GitInfo;
@@ -137,6 +140,40 @@ async fn run_git_command_with_timeout(args: &[&str], cwd: &Path) -> Option<std::
     }
 }
 
+/// Run `git` in `cwd` with the extra environment variables `envs` and return
+/// its standard output. Fails if git exits unsuccessfully or takes longer than
+/// `timeout_after`.
+pub(crate) async fn run_git_for_stdout(
+    cwd: &Path,
+    args: &[&str],
+    envs: &[(&str, &OsStr)],
+    timeout_after: TokioDuration,
+) -> io::Result<String> {
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .envs(envs.iter().copied())
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = timeout(timeout_after, command.output())
+        .await
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("`git {}` timed out", args.join(" ")),
+            )
+        })??;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 async fn get_git_remotes(cwd: &Path) -> Option<Vec<String>> {
     let output = run_git_command_with_timeout(&["remote"], cwd).await?;
     if !output.status.success() {
@@ -160,7 +197,7 @@ async fn get_git_remotes(cwd: &Path) -> Option<Vec<String>> {
 /// 1) The symbolic ref at `refs/remotes/<remote>/HEAD` for the first remote (origin prioritized)
 /// 2) `git remote show <remote>` parsed for "HEAD branch: <name>"
 /// 3) Local fallback to existing `main` or `master` if present
-async fn get_default_branch(cwd: &Path) -> Option<String> {
+pub async fn get_default_branch(cwd: &Path) -> Option<String> {
     // Prefer the first remote (with origin prioritized)
     let remotes = get_git_remotes(cwd).await.unwrap_or_default();
     for remote in remotes {
@@ -1,3 +1,5 @@
+use crate::exec_limits::CommandLimits;
+use crate::network_proxy::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
@@ -19,12 +21,15 @@ pub async fn spawn_command_under_linux_sandbox<P>(
     sandbox_policy: &SandboxPolicy,
     cwd: PathBuf,
     stdio_policy: StdioPolicy,
-    env: HashMap<String, String>,
+    mut env: HashMap<String, String>,
+    limits: Option<&CommandLimits>,
 ) -> std::io::Result<Child>
 where
     P: AsRef<Path>,
 {
-    let args = create_linux_sandbox_command_args(command, sandbox_policy, &cwd);
+    let network_proxy_socket = env.remove(CODEX_NETWORK_PROXY_SOCKET_ENV_VAR);
+    let args =
+        create_linux_sandbox_command_args(command, sandbox_policy, &cwd, network_proxy_socket);
     let arg0 = Some("icodex-linux-sandbox");
     spawn_child_async(
         icodex_linux_sandbox_exe.as_ref().to_path_buf(),
@@ -34,6 +39,7 @@ where
         sandbox_policy,
         stdio_policy,
         env,
+        limits,
     )
     .await
 }
@@ -43,6 +49,7 @@ fn create_linux_sandbox_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+    network_proxy_socket: Option<String>,
 ) -> Vec<String> {
     #[expect(clippy::expect_used)]
     let sandbox_policy_cwd = cwd.to_str().expect("cwd must be valid UTF-8").to_string();
@@ -51,13 +58,18 @@ fn create_linux_sandbox_command_args(
     let sandbox_policy_json =
         serde_json::to_string(sandbox_policy).expect("Failed to serialize SandboxPolicy to JSON");
 
-    let mut linux_cmd: Vec<String> = vec![
+    let mut linux_cmd: Vec<String> = Vec::new();
+    if let Some(socket_path) = network_proxy_socket {
+        linux_cmd.push("--network-proxy-socket".to_string());
+        linux_cmd.push(socket_path);
+    }
+    linux_cmd.extend([
         sandbox_policy_cwd,
         sandbox_policy_json,
         // Separator so that command arguments starting with `-` are not parsed as
         // options of the helper itself.
         "--".to_string(),
-    ];
+    ]);
 
     // Append the original tool command.
     linux_cmd.extend(command);
@@ -19,6 +19,7 @@ pub mod config;
 pub mod config_edit;
 pub mod config_profile;
 pub mod config_types;
+pub mod container;
 mod conversation_history;
 pub mod custom_prompts;
 mod environment_context;
@@ -26,21 +27,28 @@ pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+pub mod exec_limits;
+mod exec_policy;
 mod flags;
+mod gemini;
 pub mod git_info;
 pub mod internal_storage;
 mod is_safe_command;
 pub mod landlock;
 mod lmi_bridge_client;
 mod mcp_connection_manager;
+mod mcp_resources;
 mod mcp_tool_call;
 mod message_history;
+mod messages_api;
 mod model_provider_info;
+pub mod network_proxy;
 pub mod parse_command;
 mod truncate;
 mod unified_exec;
 mod user_instructions;
 pub use model_provider_info::BUILT_IN_OSS_MODEL_PROVIDER_ID;
+pub use model_provider_info::FallbackProvider;
 pub use model_provider_info::ModelProviderInfo;
 pub use model_provider_info::WireApi;
 pub use model_provider_info::built_in_model_providers;
@@ -55,18 +63,22 @@ pub use auth::AuthManager;
 pub use auth::CodexAuth;
 pub mod default_client;
 pub mod model_family;
+pub mod model_registry;
 mod openai_model_info;
 mod openai_tools;
 pub mod plan_tool;
 pub mod project_doc;
+pub mod review_target;
 mod rollout;
 pub(crate) mod safety;
 pub mod seatbelt;
 pub mod shell;
 pub mod spawn;
+mod sub_agent;
 pub mod terminal;
 mod tool_apply_patch;
 pub mod turn_diff_tracker;
+mod turn_snapshot;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
 pub use rollout::SESSIONS_SUBDIR;
@@ -75,6 +87,12 @@ pub use rollout::find_conversation_path_by_id_str;
 pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
+pub use rollout::search::SearchField;
+pub use rollout::search::SearchMatch;
+pub use rollout::search::SessionSearchHit;
+pub use rollout::search::SessionSearchQuery;
+pub use rollout::search::is_valid_search_date;
+pub use rollout::search::search_sessions;
 mod user_notification;
 pub mod util;
 
@@ -2,406 +2,1123 @@
 //!
 //! This module provides a client for communicating with the Node.js bridge service
 //! that uses the large-models-interface package to support 51+ model providers.
+//!
+//! Requests are written to the bridge's stdin as a single JSON line. The bridge
+//! answers with a sequence of line-delimited JSON frames (see [`BridgeFrame`])
+//! which are mapped onto the same [`ResponseEvent`] sequence that the Responses
+//! API SSE parser produces, so the rest of the agent cannot tell the two apart.
+//!
+//! A single bridge process serves every request issued through one client.
+//! Each request carries a unique `id` that the bridge echoes on every frame; a
+//! background reader task routes frames to the matching request, and a
+//! `cancel` frame is sent when the consumer drops the stream (e.g. on
+//! `Op::Interrupt`) so the bridge can stop generating.
+//!
+//! The bridge is supervised: a `hello` handshake checks the protocol version
+//! when it starts, a periodic `ping` detects a hung process, and a bridge that
+//! crashes or stops answering is restarted with backoff on the next request.
+//! Failures surface as [`LmiBridgeError`] rather than opaque stream errors.
 
-use crate::client_common::ResponseItem;
-use crate::error::CodexErr;
-use crate::model_family::ModelFamily;
-use crate::openai_tools::OpenAiTool;
-use crate::protocol::AskForApproval;
-use crate::protocol::SandboxPolicy;
-use crate::tool_apply_patch::ApplyPatchToolType;
-use crate::tool_apply_patch::create_apply_patch_freeform_tool;
-use crate::tool_apply_patch::create_apply_patch_json_tool;
-use crate::tool_exec_command::create_exec_command_tool_for_responses_api;
-use crate::tool_exec_command::create_write_stdin_tool_for_responses_api;
-use crate::tool_plan::PLAN_TOOL;
-use crate::tool_unified_exec::create_unified_exec_tool;
-use crate::tool_web_search::create_web_search_tool;
-use crate::tool_view_image::create_view_image_tool;
-use crate::CodexAuth;
-use crate::Config;
-use crate::ModelProviderInfo;
-use crate::Prompt;
-use crate::ResponseStream;
-use crate::ToolsConfig;
-use crate::VerbosityConfig;
-use crate::WireApi;
-use icodex_protocol::mcp_protocol::AuthMode;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
+use std::process::Stdio;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
-use tokio::process::{ChildStdin, ChildStdout};
+use std::sync::Weak;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::io::Lines;
+use tokio::process::Child;
+use tokio::process::ChildStdin;
+use tokio::process::Command;
 use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use tracing::warn;
 use uuid::Uuid;
 
-/// LMI Bridge Client for communicating with the Node.js bridge service
-pub struct LmiBridgeClient {
-    config: Arc<Config>,
-    provider: ModelProviderInfo,
-    conversation_id: Uuid,
-    bridge_process: Option<Arc<Mutex<Child>>>,
-    bridge_stdin: Option<Arc<Mutex<ChildStdin>>>,
-    bridge_stdout: Option<Arc<Mutex<ChildStdout>>>,
+use crate::ModelProviderInfo;
+use crate::chat_completions::build_chat_messages;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::LmiBridgeError;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use icodex_protocol::models::ContentItem;
+use icodex_protocol::models::ReasoningItemContent;
+use icodex_protocol::models::ResponseItem;
+
+/// Version of the line-delimited frame protocol spoken with the bridge. The
+/// bridge reports its own version during the `hello` handshake.
+pub(crate) const LMI_BRIDGE_PROTOCOL_VERSION: u32 = 1;
+
+/// Script started with `node` when no bridge command is supplied.
+const DEFAULT_BRIDGE_SCRIPT: &str = "src/model-bridge.js";
+
+/// Environment variable that overrides the script passed to `node`.
+const BRIDGE_SCRIPT_ENV_VAR: &str = "CODEX_LMI_BRIDGE_SCRIPT";
+
+/// Upper bound on how long a freshly started bridge may take to answer the
+/// handshake. The provider's idle timeout applies if it is shorter.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the supervisor pings an otherwise idle bridge.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of consecutive unparseable lines tolerated before the bridge is
+/// considered broken.
+const MAX_CONSECUTIVE_INVALID_LINES: usize = 16;
+
+type FrameResult = std::result::Result<BridgeFrame, LmiBridgeError>;
+
+/// In-flight requests keyed by request id.
+type PendingRequests = Arc<std::sync::Mutex<HashMap<String, mpsc::UnboundedSender<FrameResult>>>>;
+
+/// Reason recorded for a bridge that is being torn down, reported to every
+/// request that was still waiting on it.
+type FailureReason = Arc<std::sync::Mutex<Option<LmiBridgeError>>>;
+
+/// Resolves the program and arguments used to start the bridge: the
+/// configured `lmi_bridge_command`, or `node` running the bundled script.
+fn resolve_bridge_command(configured: Option<&[String]>) -> (String, Vec<String>) {
+    if let Some((program, args)) = configured.and_then(<[String]>::split_first) {
+        return (program.clone(), args.to_vec());
+    }
+
+    let script = std::env::var(BRIDGE_SCRIPT_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_BRIDGE_SCRIPT.to_string());
+    ("node".to_string(), vec![script])
 }
 
-impl LmiBridgeClient {
-    pub fn new(
-        config: Arc<Config>,
-        provider: ModelProviderInfo,
-        conversation_id: Uuid,
-    ) -> Self {
-        Self {
-            config,
-            provider,
-            conversation_id,
-            bridge_process: None,
-            bridge_stdin: None,
-            bridge_stdout: None,
-        }
+/// A running bridge process shared by every request issued through one
+/// [`LmiBridgeClient`].
+struct BridgeConnection {
+    child: Mutex<Child>,
+    stdin: Mutex<ChildStdin>,
+    pending: PendingRequests,
+    failure: FailureReason,
+    reader: JoinHandle<()>,
+}
+
+impl BridgeConnection {
+    /// Starts the bridge process, performs the version handshake and spawns
+    /// the supervisor that keeps pinging it.
+    async fn start(
+        command: Option<&[String]>,
+        provider: &ModelProviderInfo,
+    ) -> std::result::Result<Arc<Self>, LmiBridgeError> {
+        let (program, args) = resolve_bridge_command(command);
+        let mut child = Command::new(&program)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| LmiBridgeError::Spawn {
+                command: program.clone(),
+                message: e.to_string(),
+            })?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(LmiBridgeError::Spawn {
+                command: program,
+                message: "stdin/stdout not captured".to_string(),
+            });
+        };
+
+        let pending = PendingRequests::default();
+        let failure = FailureReason::default();
+        let reader = tokio::spawn(route_bridge_output(
+            BufReader::new(stdout).lines(),
+            pending.clone(),
+            failure.clone(),
+        ));
+
+        let conn = Arc::new(Self {
+            child: Mutex::new(child),
+            stdin: Mutex::new(stdin),
+            pending,
+            failure,
+            reader,
+        });
+
+        let idle_timeout = provider.stream_idle_timeout();
+        conn.handshake(HANDSHAKE_TIMEOUT.min(idle_timeout)).await?;
+        tokio::spawn(supervise_bridge(
+            Arc::downgrade(&conn),
+            PING_INTERVAL,
+            idle_timeout,
+        ));
+        Ok(conn)
     }
 
-    /// Start the LMI bridge process
-    async fn start_bridge(&mut self) -> Result<(), CodexErr> {
-        if self.bridge_process.is_some() {
-            return Ok(());
+    async fn handshake(&self, timeout: Duration) -> std::result::Result<(), LmiBridgeError> {
+        let hello = LmiHelloRequest {
+            r#type: "hello",
+            id: &Uuid::new_v4().to_string(),
+            protocol_version: LMI_BRIDGE_PROTOCOL_VERSION,
+        };
+        match self.request_once(hello.id, &hello, timeout).await? {
+            Some(BridgeFrame::Hello {
+                protocol_version,
+                bridge_version,
+            }) => {
+                if protocol_version != LMI_BRIDGE_PROTOCOL_VERSION {
+                    return Err(LmiBridgeError::IncompatibleProtocol {
+                        expected: LMI_BRIDGE_PROTOCOL_VERSION,
+                        found: protocol_version,
+                    });
+                }
+                debug!(
+                    "LMI bridge {} ready (protocol v{protocol_version})",
+                    bridge_version.as_deref().unwrap_or("<unknown version>")
+                );
+                Ok(())
+            }
+            Some(BridgeFrame::Error { message, .. }) => {
+                Err(LmiBridgeError::HandshakeFailed(message))
+            }
+            Some(other) => Err(LmiBridgeError::HandshakeFailed(format!(
+                "unexpected reply {other:?}"
+            ))),
+            None => Err(LmiBridgeError::HandshakeFailed(format!(
+                "no reply within {timeout:?}"
+            ))),
         }
+    }
 
-        // Get the bridge PID from environment (set by Node.js CLI)
-        let bridge_pid = std::env::var("CODEX_LMI_BRIDGE_PID")
-            .ok()
-            .and_then(|pid_str| pid_str.parse::<u32>().ok());
+    /// Sends a control request and waits for its single reply frame. Returns
+    /// `Ok(None)` when no reply arrived within `timeout_after`.
+    async fn request_once<T: Serialize>(
+        &self,
+        id: &str,
+        request: &T,
+        timeout_after: Duration,
+    ) -> std::result::Result<Option<BridgeFrame>, LmiBridgeError> {
+        let mut line = serde_json::to_string(request)
+            .map_err(|e| LmiBridgeError::InvalidOutput(e.to_string()))?;
+        line.push('\n');
 
-        if let Some(pid) = bridge_pid {
-            // Bridge is already running, we'll communicate via stdin/stdout
-            // For now, we'll start our own bridge process
-            // TODO: Implement communication with existing bridge process
+        let mut rx = self.register(id);
+        if self.write_line(&line).await.is_err() {
+            self.unregister(id);
+            return Err(self.failure_reason());
         }
+        let reply = match timeout(timeout_after, rx.recv()).await {
+            Ok(Some(frame)) => frame.map(Some),
+            Ok(None) => Err(self.failure_reason()),
+            Err(_) => Ok(None),
+        };
+        self.unregister(id);
+        reply
+    }
 
-        // Start the bridge process
-        let mut cmd = Command::new("node");
-        cmd.arg("src/model-bridge.js")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::inherit());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            CodexErr::Internal(format!("Failed to start LMI bridge: {}", e))
-        })?;
+    /// A connection is dead once a failure has been recorded or the reader
+    /// task has exited because the bridge closed its stdout.
+    fn is_alive(&self) -> bool {
+        let failed = self.failure.lock().map_or(true, |reason| reason.is_some());
+        !failed && !self.reader.is_finished()
+    }
 
-        let stdin = child.stdin.take().unwrap();
-        let stdout = child.stdout.take().unwrap();
+    fn failure_reason(&self) -> LmiBridgeError {
+        self.failure
+            .lock()
+            .ok()
+            .and_then(|reason| reason.clone())
+            .unwrap_or(LmiBridgeError::Exited)
+    }
 
-        self.bridge_stdin = Some(Arc::new(Mutex::new(stdin)));
-        self.bridge_stdout = Some(Arc::new(Mutex::new(stdout)));
-        self.bridge_process = Some(Arc::new(Mutex::new(child)));
+    /// Records why the bridge is unhealthy and kills it. The reader task then
+    /// observes EOF and fails every pending request with `reason`.
+    async fn fail(&self, reason: LmiBridgeError) {
+        warn!("stopping LMI bridge: {reason}");
+        if let Ok(mut failure) = self.failure.lock() {
+            failure.get_or_insert(reason);
+        }
+        if let Err(e) = self.child.lock().await.start_kill() {
+            debug!("failed to kill LMI bridge: {e}");
+        }
+    }
 
+    async fn write_line(&self, line: &str) -> Result<()> {
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(line.as_bytes()).await?;
+        stdin.flush().await?;
         Ok(())
     }
 
-    /// Send a request to the LMI bridge
-    async fn send_request(&self, request: LmiBridgeRequest) -> Result<LmiBridgeResponse, CodexErr> {
-        let stdin = self.bridge_stdin.as_ref().ok_or_else(|| {
-            CodexErr::Internal("Bridge not started".to_string())
-        })?;
-
-        let stdout = self.bridge_stdout.as_ref().ok_or_else(|| {
-            CodexErr::Internal("Bridge not started".to_string())
-        })?;
-
-        // Serialize and send request
-        let request_json = serde_json::to_string(&request).map_err(|e| {
-            CodexErr::Internal(format!("Failed to serialize request: {}", e))
-        })?;
-
-        let mut stdin_guard = stdin.lock().await;
-        stdin_guard.write_all(request_json.as_bytes()).await.map_err(|e| {
-            CodexErr::Internal(format!("Failed to write to bridge stdin: {}", e))
-        })?;
-        stdin_guard.write_all(b"\n").await.map_err(|e| {
-            CodexErr::Internal(format!("Failed to write newline to bridge stdin: {}", e))
-        })?;
-        stdin_guard.flush().await.map_err(|e| {
-            CodexErr::Internal(format!("Failed to flush bridge stdin: {}", e))
-        })?;
-        drop(stdin_guard);
-
-        // Read response
-        let mut stdout_guard = stdout.lock().await;
-        let mut response_line = String::new();
-        stdout_guard.read_line(&mut response_line).await.map_err(|e| {
-            CodexErr::Internal(format!("Failed to read from bridge stdout: {}", e))
-        })?;
-        drop(stdout_guard);
-
-        let response: LmiBridgeResponse = serde_json::from_str(&response_line).map_err(|e| {
-            CodexErr::Internal(format!("Failed to parse bridge response: {}", e))
-        })?;
-
-        if !response.success {
-            return Err(CodexErr::Internal(format!(
-                "Bridge request failed: {}",
-                response.error.unwrap_or_else(|| "Unknown error".to_string())
-            )));
+    fn register(&self, id: &str) -> mpsc::UnboundedReceiver<FrameResult> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(id.to_string(), tx);
         }
+        rx
+    }
 
-        Ok(response)
-    }
-
-    /// Convert iEchor tools to LMI format
-    fn convert_tools_to_lmi(&self, tools: &[OpenAiTool]) -> Vec<LmiTool> {
-        tools.iter().map(|tool| match tool {
-            OpenAiTool::Function(func_tool) => LmiTool {
-                r#type: "function".to_string(),
-                function: Some(LmiFunction {
-                    name: func_tool.name.clone(),
-                    description: Some(func_tool.description.clone()),
-                    parameters: Some(func_tool.parameters.clone()),
-                }),
-                local_shell: None,
-                web_search: None,
-                custom: None,
-            },
-            OpenAiTool::LocalShell {} => LmiTool {
-                r#type: "local_shell".to_string(),
-                function: None,
-                local_shell: Some(LmiLocalShell {}),
-                web_search: None,
-                custom: None,
-            },
-            OpenAiTool::WebSearch {} => LmiTool {
-                r#type: "web_search".to_string(),
-                function: None,
-                local_shell: None,
-                web_search: Some(LmiWebSearch {}),
-                custom: None,
-            },
-            OpenAiTool::Freeform(freeform_tool) => LmiTool {
-                r#type: "custom".to_string(),
-                function: None,
-                local_shell: None,
-                web_search: None,
-                custom: Some(LmiCustom {
-                    name: freeform_tool.name.clone(),
-                    description: freeform_tool.description.clone(),
-                    format: LmiFormat {
-                        r#type: freeform_tool.format.r#type.clone(),
-                        syntax: freeform_tool.format.syntax.clone(),
-                        definition: freeform_tool.format.definition.clone(),
-                    },
-                }),
-            },
-        }).collect()
-    }
-
-    /// Convert response items to LMI messages
-    fn convert_response_items_to_messages(&self, items: &[ResponseItem]) -> Vec<LmiMessage> {
-        items.iter().map(|item| match item {
-            ResponseItem::User { content } => LmiMessage {
-                role: "user".to_string(),
-                content: content.clone(),
-            },
-            ResponseItem::Assistant { content } => LmiMessage {
-                role: "assistant".to_string(),
-                content: content.clone(),
-            },
-            ResponseItem::System { content } => LmiMessage {
-                role: "system".to_string(),
-                content: content.clone(),
-            },
-            ResponseItem::ToolCall { name, arguments, id } => LmiMessage {
-                role: "assistant".to_string(),
-                content: format!("Tool call: {} with args: {}", name, arguments),
-            },
-            ResponseItem::ToolResult { content, tool_call_id: _ } => LmiMessage {
-                role: "tool".to_string(),
-                content: content.clone(),
-            },
-            ResponseItem::Other { content } => LmiMessage {
-                role: "user".to_string(),
-                content: content.clone(),
-            },
-        }).collect()
+    fn unregister(&self, id: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(id);
+        }
     }
 
-    /// Stream responses from the LMI bridge
-    pub async fn stream(&mut self, prompt: &Prompt) -> Result<ResponseStream, CodexErr> {
-        self.start_bridge().await?;
+    /// Asks the bridge to stop generating for `id`. Failures are logged only:
+    /// the request is abandoned on our side either way.
+    async fn cancel(&self, id: &str) {
+        self.unregister(id);
+        let cancel = LmiCancelRequest {
+            r#type: "cancel",
+            id,
+        };
+        let result = match serde_json::to_string(&cancel) {
+            Ok(mut line) => {
+                line.push('\n');
+                self.write_line(&line).await
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            warn!("failed to cancel LMI bridge request {id}: {e}");
+        }
+    }
+}
 
-        // Extract provider name from the provider info
-        let provider_name = self.provider.name.to_lowercase().replace(" ", "_");
-        
-        // Convert tools to LMI format
-        let tools = self.get_tools_for_prompt(prompt);
-        let lmi_tools = self.convert_tools_to_lmi(&tools);
+impl Drop for BridgeConnection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
 
-        // Convert messages
-        let messages = self.convert_response_items_to_messages(&prompt.input);
+/// Periodically pings the bridge and kills it when a ping goes unanswered for
+/// longer than the provider's stream idle timeout. Exits once the connection
+/// has been dropped or the bridge has gone away.
+async fn supervise_bridge(
+    conn: Weak<BridgeConnection>,
+    interval: Duration,
+    ping_timeout: Duration,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(conn) = conn.upgrade() else {
+            return;
+        };
+        if !conn.is_alive() {
+            return;
+        }
 
-        // Create request
-        let request = LmiBridgeRequest {
-            r#type: "chat_completion".to_string(),
-            provider: provider_name,
-            model: self.config.model.clone(),
-            messages,
-            tools: Some(lmi_tools),
-            options: LmiOptions {
-                temperature: self.config.temperature,
-                max_tokens: self.config.max_tokens,
-                stream: true,
-            },
+        let ping = LmiPingRequest {
+            r#type: "ping",
+            id: &Uuid::new_v4().to_string(),
         };
+        match conn.request_once(ping.id, &ping, ping_timeout).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                conn.fail(LmiBridgeError::Unresponsive(ping_timeout)).await;
+                return;
+            }
+            Err(_) => return,
+        }
+    }
+}
 
-        // Send request and get response
-        let response = self.send_request(request).await?;
+/// LMI Bridge Client for communicating with the Node.js bridge service
+#[derive(Clone, Default)]
+pub(crate) struct LmiBridgeClient {
+    /// Program and arguments used to start the bridge (`lmi_bridge_command`).
+    command: Option<Vec<String>>,
+    connection: Arc<Mutex<Option<Arc<BridgeConnection>>>>,
+}
 
-        // Convert LMI response to Codex ResponseStream
-        // For now, we'll create a simple streaming response
-        // TODO: Implement proper streaming from LMI bridge
-        Ok(ResponseStream::from_lmi_response(response))
+impl std::fmt::Debug for LmiBridgeClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LmiBridgeClient")
+            .field("command", &self.command)
+            .finish_non_exhaustive()
     }
+}
 
-    /// Get tools for the prompt based on configuration
-    fn get_tools_for_prompt(&self, prompt: &Prompt) -> Vec<OpenAiTool> {
-        let mut tools = Vec::new();
-
-        // Add shell tool based on configuration
-        match &self.config.tools_config.shell_type {
-            crate::flags::ConfigShellToolType::DefaultShell => {
-                tools.push(create_shell_tool());
-            }
-            crate::flags::ConfigShellToolType::ShellWithRequest { sandbox_policy } => {
-                tools.push(create_shell_tool_for_sandbox(sandbox_policy));
-            }
-            crate::flags::ConfigShellToolType::LocalShell => {
-                tools.push(OpenAiTool::LocalShell {});
-            }
-            crate::flags::ConfigShellToolType::StreamableShell => {
-                tools.push(OpenAiTool::Function(
-                    create_exec_command_tool_for_responses_api(),
-                ));
-                tools.push(OpenAiTool::Function(
-                    create_write_stdin_tool_for_responses_api(),
-                ));
-            }
+impl LmiBridgeClient {
+    pub(crate) fn new(command: Option<Vec<String>>) -> Self {
+        Self {
+            command,
+            connection: Arc::default(),
         }
+    }
 
-        // Add other tools based on configuration
-        if self.config.tools_config.plan_tool {
-            tools.push(PLAN_TOOL.clone());
+    /// Returns the shared bridge connection. When none is running, or the
+    /// previous bridge has exited, a new one is started, retrying with
+    /// backoff up to the provider's `request_max_retries`.
+    async fn connection(&self, provider: &ModelProviderInfo) -> Result<Arc<BridgeConnection>> {
+        let mut guard = self.connection.lock().await;
+        if let Some(conn) = guard.as_ref().filter(|conn| conn.is_alive()) {
+            return Ok(conn.clone());
         }
+        *guard = None;
 
-        if let Some(apply_patch_tool_type) = &self.config.tools_config.apply_patch_tool_type {
-            match apply_patch_tool_type {
-                ApplyPatchToolType::Freeform => {
-                    tools.push(create_apply_patch_freeform_tool());
+        let max_retries = provider.request_max_retries();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match BridgeConnection::start(self.command.as_deref(), provider).await {
+                Ok(conn) => {
+                    *guard = Some(conn.clone());
+                    return Ok(conn);
                 }
-                ApplyPatchToolType::Function => {
-                    tools.push(create_apply_patch_json_tool());
+                Err(e) if !e.is_retryable() || attempt > max_retries => return Err(e.into()),
+                Err(e) => {
+                    let delay = backoff(attempt);
+                    warn!(
+                        "LMI bridge failed to start ({e}); retrying {attempt}/{max_retries} in {delay:?}"
+                    );
+                    tokio::time::sleep(delay).await;
                 }
             }
         }
+    }
+
+    /// Stream responses from the LMI bridge
+    pub(crate) async fn stream(
+        &self,
+        prompt: &Prompt,
+        model_family: &ModelFamily,
+        provider: &ModelProviderInfo,
+    ) -> Result<ResponseStream> {
+        let id = Uuid::new_v4().to_string();
+        let request = LmiBridgeRequest {
+            r#type: "chat_completion",
+            id: &id,
+            provider: provider.name.to_lowercase().replace(' ', "_"),
+            model: &model_family.slug,
+            base_url: provider.base_url.as_deref(),
+            messages: build_chat_messages(prompt, model_family),
+            tools: create_tools_json_for_chat_completions_api(&prompt.tools)?,
+            options: LmiOptions { stream: true },
+        };
+        let mut request_line = serde_json::to_string(&request)?;
+        request_line.push('\n');
+        trace!("LMI bridge request: {request_line}");
 
-        if self.config.tools_config.web_search_request {
-            tools.push(create_web_search_tool());
+        let conn = self.connection(provider).await?;
+        // Register before writing so no frame can arrive for an unknown id.
+        let mut rx_frames = conn.register(&id);
+        if conn.write_line(&request_line).await.is_err() {
+            conn.unregister(&id);
+            return Err(conn.failure_reason().into());
         }
 
-        if self.config.tools_config.view_image_request {
-            tools.push(create_view_image_tool());
+        let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+        let idle_timeout = provider.stream_idle_timeout();
+        tokio::spawn(async move {
+            match process_bridge_frames(&mut rx_frames, tx_event, idle_timeout).await {
+                StreamOutcome::Finished => {}
+                StreamOutcome::Abandoned => conn.cancel(&id).await,
+            }
+        });
+
+        Ok(ResponseStream { rx_event })
+    }
+}
+
+/// Background task that owns the bridge's stdout and routes every frame to
+/// the request it belongs to. When the bridge exits (or keeps writing
+/// garbage), every pending request is failed with the recorded reason so
+/// waiting streams observe the failure instead of hanging.
+async fn route_bridge_output<R>(
+    mut lines: Lines<R>,
+    pending: PendingRequests,
+    failure: FailureReason,
+) where
+    R: AsyncBufRead + Unpin,
+{
+    let mut consecutive_invalid = 0;
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                debug!("failed to read from LMI bridge: {e}");
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
         }
+        trace!("LMI bridge frame: {line}");
+
+        let envelope: BridgeEnvelope = match serde_json::from_str(&line) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                debug!("Failed to parse LMI bridge frame: {e}, data: {line}");
+                consecutive_invalid += 1;
+                if consecutive_invalid >= MAX_CONSECUTIVE_INVALID_LINES {
+                    if let Ok(mut failure) = failure.lock() {
+                        failure.get_or_insert(LmiBridgeError::InvalidOutput(line));
+                    }
+                    break;
+                }
+                continue;
+            }
+        };
+        consecutive_invalid = 0;
 
-        tools
+        let Ok(mut pending) = pending.lock() else {
+            break;
+        };
+        let is_terminal = envelope.frame.is_terminal();
+        match pending.get(&envelope.id) {
+            Some(tx) => {
+                // The receiver may already be gone if the request was cancelled.
+                let _ = tx.send(Ok(envelope.frame));
+            }
+            None => debug!(
+                "dropping LMI bridge frame for unknown request {}",
+                envelope.id
+            ),
+        }
+        if is_terminal {
+            pending.remove(&envelope.id);
+        }
+    }
+
+    // Record the failure before notifying anyone so a caller that reacts to
+    // the error never picks this connection up again.
+    let reason = match failure.lock() {
+        Ok(mut failure) => failure.get_or_insert(LmiBridgeError::Exited).clone(),
+        Err(_) => LmiBridgeError::Exited,
+    };
+    if let Ok(mut pending) = pending.lock() {
+        for (_, tx) in pending.drain() {
+            let _ = tx.send(Err(reason.clone()));
+        }
     }
 }
 
 // LMI Bridge Protocol Types
 
 #[derive(Debug, Serialize)]
-struct LmiBridgeRequest {
-    r#type: String,
+struct LmiBridgeRequest<'a> {
+    r#type: &'static str,
+    id: &'a str,
     provider: String,
-    model: String,
-    messages: Vec<LmiMessage>,
-    tools: Option<Vec<LmiTool>>,
+    model: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_url: Option<&'a str>,
+    messages: Vec<JsonValue>,
+    tools: Vec<JsonValue>,
     options: LmiOptions,
 }
 
-#[derive(Debug, Deserialize)]
-struct LmiBridgeResponse {
-    success: bool,
-    data: Option<JsonValue>,
-    error: Option<String>,
-}
-
 #[derive(Debug, Serialize)]
-struct LmiMessage {
-    role: String,
-    content: String,
+struct LmiOptions {
+    stream: bool,
 }
 
 #[derive(Debug, Serialize)]
-struct LmiTool {
-    r#type: String,
-    function: Option<LmiFunction>,
-    local_shell: Option<LmiLocalShell>,
-    web_search: Option<LmiWebSearch>,
-    custom: Option<LmiCustom>,
+struct LmiCancelRequest<'a> {
+    r#type: &'static str,
+    id: &'a str,
 }
 
 #[derive(Debug, Serialize)]
-struct LmiFunction {
-    name: String,
-    description: Option<String>,
-    parameters: Option<JsonValue>,
+struct LmiHelloRequest<'a> {
+    r#type: &'static str,
+    id: &'a str,
+    protocol_version: u32,
 }
 
 #[derive(Debug, Serialize)]
-struct LmiLocalShell {}
+struct LmiPingRequest<'a> {
+    r#type: &'static str,
+    id: &'a str,
+}
 
-#[derive(Debug, Serialize)]
-struct LmiWebSearch {}
+/// Every frame is tagged with the id of the request it answers.
+#[derive(Debug, Deserialize)]
+struct BridgeEnvelope {
+    id: String,
+    #[serde(flatten)]
+    frame: BridgeFrame,
+}
 
-#[derive(Debug, Serialize)]
-struct LmiCustom {
-    name: String,
-    description: String,
-    format: LmiFormat,
+/// A single line emitted by the bridge while answering a request.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum BridgeFrame {
+    /// Reply to the `hello` handshake.
+    Hello {
+        protocol_version: u32,
+        #[serde(default)]
+        bridge_version: Option<String>,
+    },
+    /// Reply to a health-check `ping`.
+    Pong,
+    /// The upstream provider accepted the request.
+    Created {
+        #[serde(default)]
+        response_id: Option<String>,
+    },
+    /// Assistant text token(s).
+    Delta { text: String },
+    /// Raw reasoning / thinking token(s).
+    ReasoningDelta { text: String },
+    /// Fragment of a tool call. Fragments sharing an `index` are concatenated
+    /// in arrival order; `id` and `name` are taken from the first fragment
+    /// that carries them.
+    ToolCallDelta {
+        #[serde(default)]
+        index: u32,
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        arguments: Option<String>,
+    },
+    /// Terminal frame for a successful response.
+    Completed {
+        #[serde(default)]
+        response_id: Option<String>,
+        #[serde(default)]
+        usage: Option<BridgeUsage>,
+    },
+    /// Terminal frame for a failed response.
+    Error {
+        message: String,
+        #[serde(default)]
+        retry_after_ms: Option<u64>,
+    },
 }
 
-#[derive(Debug, Serialize)]
-struct LmiFormat {
-    r#type: String,
-    syntax: String,
-    definition: String,
+impl BridgeFrame {
+    fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            BridgeFrame::Hello { .. }
+                | BridgeFrame::Pong
+                | BridgeFrame::Completed { .. }
+                | BridgeFrame::Error { .. }
+        )
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct LmiOptions {
-    temperature: Option<f64>,
-    max_tokens: Option<u32>,
-    stream: bool,
+#[derive(Debug, Deserialize, PartialEq)]
+pub(crate) struct BridgeUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    cached_input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    reasoning_output_tokens: u64,
+    #[serde(default)]
+    total_tokens: Option<u64>,
 }
 
-// Extension trait for ResponseStream to handle LMI responses
-impl ResponseStream {
-    fn from_lmi_response(response: LmiBridgeResponse) -> Self {
-        // TODO: Implement proper conversion from LMI response to ResponseStream
-        // For now, return an empty stream
-        ResponseStream::empty()
+impl From<BridgeUsage> for TokenUsage {
+    fn from(val: BridgeUsage) -> Self {
+        TokenUsage {
+            input_tokens: val.input_tokens,
+            cached_input_tokens: val.cached_input_tokens,
+            output_tokens: val.output_tokens,
+            reasoning_output_tokens: val.reasoning_output_tokens,
+            total_tokens: val
+                .total_tokens
+                .unwrap_or(val.input_tokens + val.output_tokens),
+        }
     }
 }
 
-// Helper functions (these would need to be imported from the appropriate modules)
-fn create_shell_tool() -> OpenAiTool {
-    // TODO: Implement or import this function
-    OpenAiTool::LocalShell {}
+#[derive(Default)]
+struct ToolCallState {
+    call_id: Option<String>,
+    name: Option<String>,
+    arguments: String,
 }
 
-fn create_shell_tool_for_sandbox(_policy: &SandboxPolicy) -> OpenAiTool {
-    // TODO: Implement or import this function
-    OpenAiTool::LocalShell {}
+/// How a stream ended, which tells the caller whether the bridge still has
+/// work in flight for the request.
+#[derive(Debug, PartialEq, Eq)]
+enum StreamOutcome {
+    /// The bridge sent a terminal frame, or has exited.
+    Finished,
+    /// The consumer went away or the bridge went silent mid-response; the
+    /// request should be cancelled on the bridge side.
+    Abandoned,
+}
+
+/// Receives the frames routed to one request until a terminal frame arrives
+/// and forwards the mapped [`ResponseEvent`]s. Assistant text, reasoning and
+/// tool calls are streamed as deltas and then finalised as `OutputItemDone`
+/// items right before `Completed`, mirroring the order produced by the
+/// Responses API.
+async fn process_bridge_frames(
+    rx_frames: &mut mpsc::UnboundedReceiver<FrameResult>,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) -> StreamOutcome {
+    let mut assistant_text = String::new();
+    let mut reasoning_text = String::new();
+    let mut tool_calls: BTreeMap<u32, ToolCallState> = BTreeMap::new();
+    let mut created_response_id: Option<String> = None;
+
+    loop {
+        let frame = tokio::select! {
+            // Dropping the `ResponseStream` (e.g. when the turn is
+            // interrupted) closes the channel; stop waiting on the bridge.
+            _ = tx_event.closed() => return StreamOutcome::Abandoned,
+            frame = timeout(idle_timeout, rx_frames.recv()) => frame,
+        };
+        let frame = match frame {
+            Ok(Some(Ok(frame))) => frame,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event.send(Err(e.into())).await;
+                return StreamOutcome::Finished;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "LMI bridge closed before completion".into(),
+                        None,
+                    )))
+                    .await;
+                return StreamOutcome::Finished;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for LMI bridge".into(),
+                        None,
+                    )))
+                    .await;
+                return StreamOutcome::Abandoned;
+            }
+        };
+
+        let event = match frame {
+            // Control replies are never routed to a streaming request.
+            BridgeFrame::Hello { .. } | BridgeFrame::Pong => continue,
+            BridgeFrame::Created { response_id } => {
+                created_response_id = response_id;
+                ResponseEvent::Created
+            }
+            BridgeFrame::Delta { text } => {
+                if text.is_empty() {
+                    continue;
+                }
+                assistant_text.push_str(&text);
+                ResponseEvent::OutputTextDelta(text)
+            }
+            BridgeFrame::ReasoningDelta { text } => {
+                if text.is_empty() {
+                    continue;
+                }
+                reasoning_text.push_str(&text);
+                ResponseEvent::ReasoningContentDelta(text)
+            }
+            BridgeFrame::ToolCallDelta {
+                index,
+                id,
+                name,
+                arguments,
+            } => {
+                let state = tool_calls.entry(index).or_default();
+                if let Some(id) = id {
+                    state.call_id.get_or_insert(id);
+                }
+                if let Some(name) = name {
+                    state.name.get_or_insert(name);
+                }
+                if let Some(arguments) = arguments {
+                    state.arguments.push_str(&arguments);
+                }
+                continue;
+            }
+            BridgeFrame::Completed { response_id, usage } => {
+                let mut items = Vec::new();
+                if !reasoning_text.is_empty() {
+                    items.push(ResponseItem::Reasoning {
+                        id: String::new(),
+                        summary: Vec::new(),
+                        content: Some(vec![ReasoningItemContent::ReasoningText {
+                            text: std::mem::take(&mut reasoning_text),
+                        }]),
+                        encrypted_content: None,
+                    });
+                }
+                if !assistant_text.is_empty() {
+                    items.push(ResponseItem::Message {
+                        id: None,
+                        role: "assistant".to_string(),
+                        content: vec![ContentItem::OutputText {
+                            text: std::mem::take(&mut assistant_text),
+                        }],
+                    });
+                }
+                for (_, call) in std::mem::take(&mut tool_calls) {
+                    items.push(ResponseItem::FunctionCall {
+                        id: None,
+                        name: call.name.unwrap_or_default(),
+                        arguments: call.arguments,
+                        call_id: call.call_id.unwrap_or_default(),
+                    });
+                }
+                for item in items {
+                    if tx_event
+                        .send(Ok(ResponseEvent::OutputItemDone(item)))
+                        .await
+                        .is_err()
+                    {
+                        return StreamOutcome::Abandoned;
+                    }
+                }
+
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id: response_id
+                            .or(created_response_id.take())
+                            .unwrap_or_default(),
+                        token_usage: usage.map(Into::into),
+                    }))
+                    .await;
+                return StreamOutcome::Finished;
+            }
+            BridgeFrame::Error {
+                message,
+                retry_after_ms,
+            } => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        message,
+                        retry_after_ms.map(Duration::from_millis),
+                    )))
+                    .await;
+                return StreamOutcome::Finished;
+            }
+        };
+
+        if tx_event.send(Ok(event)).await.is_err() {
+            return StreamOutcome::Abandoned;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    /// Serializes `frames` as bridge output for request `id`, one per line.
+    fn bridge_output(id: &str, frames: Vec<serde_json::Value>) -> String {
+        let mut body = String::new();
+        for mut frame in frames {
+            if let Some(obj) = frame.as_object_mut() {
+                obj.insert("id".to_string(), json!(id));
+            }
+            body.push_str(&frame.to_string());
+            body.push('\n');
+        }
+        body
+    }
+
+    /// Routes `frames` through the bridge reader and returns every event the
+    /// request's stream produced.
+    async fn run_frames(
+        frames: Vec<serde_json::Value>,
+    ) -> (Vec<Result<ResponseEvent>>, StreamOutcome) {
+        let body = bridge_output("req", frames);
+        let pending = PendingRequests::default();
+        let (tx_frames, mut rx_frames) = mpsc::unbounded_channel();
+        pending
+            .lock()
+            .expect("pending lock")
+            .insert("req".to_string(), tx_frames);
+        route_bridge_output(
+            BufReader::new(body.as_bytes()).lines(),
+            pending,
+            FailureReason::default(),
+        )
+        .await;
+
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        let outcome = process_bridge_frames(&mut rx_frames, tx, Duration::from_millis(1000)).await;
+
+        let mut events = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            events.push(ev);
+        }
+        (events, outcome)
+    }
+
+    #[tokio::test]
+    async fn streams_text_and_reasoning_deltas() {
+        let (events, outcome) = run_frames(vec![
+            json!({"type": "created", "response_id": "resp1"}),
+            json!({"type": "reasoning_delta", "text": "think"}),
+            json!({"type": "delta", "text": "Hel"}),
+            json!({"type": "delta", "text": "lo"}),
+            json!({
+                "type": "completed",
+                "usage": {"input_tokens": 10, "output_tokens": 5}
+            }),
+        ])
+        .await;
+
+        assert_eq!(outcome, StreamOutcome::Finished);
+        assert_eq!(events.len(), 7);
+        assert!(matches!(events[0], Ok(ResponseEvent::Created)));
+        assert!(matches!(&events[1], Ok(ResponseEvent::ReasoningContentDelta(d)) if d == "think"));
+        assert!(matches!(&events[2], Ok(ResponseEvent::OutputTextDelta(d)) if d == "Hel"));
+        assert!(matches!(&events[3], Ok(ResponseEvent::OutputTextDelta(d)) if d == "lo"));
+        assert!(matches!(
+            &events[4],
+            Ok(ResponseEvent::OutputItemDone(
+                ResponseItem::Reasoning { .. }
+            ))
+        ));
+        match &events[5] {
+            Ok(ResponseEvent::OutputItemDone(ResponseItem::Message { role, content, .. })) => {
+                assert_eq!(role, "assistant");
+                assert_eq!(
+                    content,
+                    &vec![ContentItem::OutputText {
+                        text: "Hello".to_string()
+                    }]
+                );
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        match &events[6] {
+            Ok(ResponseEvent::Completed {
+                response_id,
+                token_usage: Some(usage),
+            }) => {
+                assert_eq!(response_id, "resp1");
+                assert_eq!(usage.total_tokens, 15);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn assembles_tool_call_deltas_in_index_order() {
+        let (events, outcome) = run_frames(vec![
+            json!({"type": "tool_call_delta", "index": 1, "id": "call_b", "name": "view_image", "arguments": "{}"}),
+            json!({"type": "tool_call_delta", "index": 0, "id": "call_a", "name": "shell", "arguments": "{\"command\":"}),
+            json!({"type": "tool_call_delta", "index": 0, "arguments": "[\"ls\"]}"}),
+            json!({"type": "completed", "response_id": "r"}),
+        ])
+        .await;
+
+        assert_eq!(outcome, StreamOutcome::Finished);
+        let calls: Vec<(String, String, String)> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                Ok(ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+                    name,
+                    arguments,
+                    call_id,
+                    ..
+                })) => Some((name.clone(), arguments.clone(), call_id.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                (
+                    "shell".to_string(),
+                    "{\"command\":[\"ls\"]}".to_string(),
+                    "call_a".to_string()
+                ),
+                (
+                    "view_image".to_string(),
+                    "{}".to_string(),
+                    "call_b".to_string()
+                ),
+            ]
+        );
+        assert!(matches!(
+            events.last(),
+            Some(Ok(ResponseEvent::Completed { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn error_frame_surfaces_stream_error_with_delay() {
+        let (events, outcome) = run_frames(vec![
+            json!({"type": "delta", "text": "partial"}),
+            json!({"type": "error", "message": "rate limited", "retry_after_ms": 1500}),
+        ])
+        .await;
+
+        assert_eq!(outcome, StreamOutcome::Finished);
+        assert_eq!(events.len(), 2);
+        match &events[1] {
+            Err(CodexErr::Stream(msg, delay)) => {
+                assert_eq!(msg, "rate limited");
+                assert_eq!(*delay, Some(Duration::from_millis(1500)));
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn error_when_bridge_closes_before_completed() {
+        let (events, outcome) = run_frames(vec![json!({"type": "delta", "text": "hi"})]).await;
+
+        assert_eq!(outcome, StreamOutcome::Finished);
+        assert!(matches!(
+            events.last(),
+            Some(Err(CodexErr::LmiBridge(LmiBridgeError::Exited)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn skips_unparseable_frames() {
+        let (events, _) = run_frames(vec![
+            json!({"type": "unknown_frame"}),
+            json!({"type": "completed"}),
+        ])
+        .await;
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], Ok(ResponseEvent::Completed { .. })));
+    }
+
+    #[tokio::test]
+    async fn persistent_garbage_fails_pending_requests() {
+        let body = "not json\n".repeat(MAX_CONSECUTIVE_INVALID_LINES);
+        let pending = PendingRequests::default();
+        let (tx_frames, mut rx_frames) = mpsc::unbounded_channel();
+        pending
+            .lock()
+            .expect("pending lock")
+            .insert("req".to_string(), tx_frames);
+        route_bridge_output(
+            BufReader::new(body.as_bytes()).lines(),
+            pending,
+            FailureReason::default(),
+        )
+        .await;
+
+        assert_eq!(
+            rx_frames.recv().await,
+            Some(Err(LmiBridgeError::InvalidOutput("not json".to_string())))
+        );
+    }
+
+    #[test]
+    fn configured_command_takes_precedence() {
+        let command = vec![
+            "bun".to_string(),
+            "run".to_string(),
+            "bridge.ts".to_string(),
+        ];
+        assert_eq!(
+            resolve_bridge_command(Some(&command)),
+            (
+                "bun".to_string(),
+                vec!["run".to_string(), "bridge.ts".to_string()]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn routes_interleaved_frames_by_request_id() {
+        let mut body = String::new();
+        body.push_str(&bridge_output(
+            "a",
+            vec![json!({"type": "delta", "text": "A1"})],
+        ));
+        body.push_str(&bridge_output(
+            "b",
+            vec![json!({"type": "delta", "text": "B1"})],
+        ));
+        body.push_str(&bridge_output("a", vec![json!({"type": "completed"})]));
+        body.push_str(&bridge_output(
+            "b",
+            vec![json!({"type": "delta", "text": "B2"})],
+        ));
+
+        let pending = PendingRequests::default();
+        let (tx_a, mut rx_a) = mpsc::unbounded_channel();
+        let (tx_b, mut rx_b) = mpsc::unbounded_channel();
+        {
+            let mut map = pending.lock().expect("pending lock");
+            map.insert("a".to_string(), tx_a);
+            map.insert("b".to_string(), tx_b);
+        }
+        route_bridge_output(
+            BufReader::new(body.as_bytes()).lines(),
+            pending.clone(),
+            FailureReason::default(),
+        )
+        .await;
+
+        let mut frames_a = Vec::new();
+        while let Some(frame) = rx_a.recv().await {
+            frames_a.push(frame);
+        }
+        let mut frames_b = Vec::new();
+        while let Some(frame) = rx_b.recv().await {
+            frames_b.push(frame);
+        }
+        // `b` never completed, so it is failed when the bridge output ends.
+        assert_eq!(frames_b.pop(), Some(Err(LmiBridgeError::Exited)));
+        let frames_a: Vec<BridgeFrame> = frames_a.into_iter().flatten().collect();
+        let frames_b: Vec<BridgeFrame> = frames_b.into_iter().flatten().collect();
+
+        assert_eq!(
+            frames_a,
+            vec![
+                BridgeFrame::Delta {
+                    text: "A1".to_string()
+                },
+                BridgeFrame::Completed {
+                    response_id: None,
+                    usage: None
+                },
+            ]
+        );
+        assert_eq!(
+            frames_b,
+            vec![
+                BridgeFrame::Delta {
+                    text: "B1".to_string()
+                },
+                BridgeFrame::Delta {
+                    text: "B2".to_string()
+                },
+            ]
+        );
+        assert!(pending.lock().expect("pending lock").is_empty());
+    }
+
+    #[tokio::test]
+    async fn dropping_stream_abandons_request() {
+        let (_tx_frames, mut rx_frames) = mpsc::unbounded_channel::<FrameResult>();
+        let (tx, rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        drop(rx);
+
+        let outcome = process_bridge_frames(&mut rx_frames, tx, Duration::from_secs(60)).await;
+        assert_eq!(outcome, StreamOutcome::Abandoned);
+    }
 }
@@ -4,30 +4,69 @@
 //! configured server (keyed by the *server name*). It offers convenience
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
-//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key. The resources and
+//! prompts offered by servers that advertise them are cached the same way,
+//! keyed by server name.
+//!
+//! Each server's `enabled_tools` and `disabled_tools` decide which of its tools
+//! are exposed at all; `require_approval` marks the ones the session must ask
+//! the user about before calling.
+//!
+//! Requests the servers send back to us (`roots/list`,
+//! `sampling/createMessage`) are forwarded to the session as
+//! [`McpServerRequest`]s.
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsString;
 use std::sync::Arc;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+use std::sync::Weak;
 use std::time::Duration;
 
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
 use icodex_mcp_client::McpClient;
+use icodex_mcp_client::NotificationHandler;
+use icodex_mcp_client::ServerRequestFuture;
+use icodex_mcp_client::ServerRequestHandler;
 use mcp_types::ClientCapabilities;
+use mcp_types::ClientCapabilitiesRoots;
+use mcp_types::CreateMessageRequest;
+use mcp_types::GetPromptResult;
 use mcp_types::Implementation;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCNotification;
+use mcp_types::JSONRPCRequest;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::ModelContextProtocolRequest;
+use mcp_types::Prompt;
+use mcp_types::ReadResourceResult;
+use mcp_types::Resource;
+use mcp_types::ServerCapabilities;
 use mcp_types::Tool;
+use mcp_types::ToolListChangedNotification;
 
 use serde_json::json;
 use sha1::Digest;
 use sha1::Sha1;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 use tokio::task::JoinSet;
 use tracing::info;
 use tracing::warn;
+use wildmatch::WildMatchPattern;
 
 use crate::config_types::McpServerConfig;
+use crate::config_types::McpServerTransportConfig;
+use crate::protocol::McpServerState;
+use crate::protocol::McpServerStatus;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -40,10 +79,86 @@ const MAX_TOOL_NAME_LENGTH: usize = 64;
 /// Default timeout for initializing MCP server & initially listing tools.
 const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Timeout for reading a resource or fetching a prompt.
+const RESOURCE_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Upper bound on the number of pages requested from a single `*/list` call,
+/// so a server that keeps returning a cursor cannot stall startup forever.
+const MAX_LIST_PAGES: usize = 100;
+
+/// JSON-RPC error code sent back when a server request is refused, either by
+/// the configuration or by the user. Matches the code used in the examples of
+/// the MCP specification.
+pub(crate) const MCP_REQUEST_REJECTED_ERROR_CODE: i64 = -1;
+
+/// JSON-RPC error code sent back when a server request could not be answered.
+pub(crate) const MCP_REQUEST_FAILED_ERROR_CODE: i64 = -32603;
+
+/// Pattern matched against tool names in `enabled_tools`, `disabled_tools` and
+/// `require_approval`.
+type McpToolPattern = WildMatchPattern<'*', '?'>;
+
 /// Map that holds a startup error for every MCP server that could **not** be
 /// spawned successfully.
 pub type ClientStartErrors = HashMap<String, anyhow::Error>;
 
+/// A request an MCP server sent to us, waiting for the session to answer it.
+pub(crate) struct McpServerRequest {
+    /// Name of the server that sent the request.
+    pub(crate) server_name: String,
+    pub(crate) request: JSONRPCRequest,
+    /// Receives the `result` (or the error) to send back to the server.
+    pub(crate) reply: oneshot::Sender<Result<serde_json::Value, JSONRPCErrorError>>,
+}
+
+pub(crate) fn mcp_request_error(code: i64, message: impl Into<String>) -> JSONRPCErrorError {
+    JSONRPCErrorError {
+        code,
+        data: None,
+        message: message.into(),
+    }
+}
+
+/// Build the handler that forwards requests from `server_name` to
+/// `server_requests`. Sampling requests are refused outright unless the
+/// server's configuration allows them.
+fn forward_server_requests(
+    server_name: String,
+    allow_sampling: bool,
+    server_requests: mpsc::Sender<McpServerRequest>,
+) -> ServerRequestHandler {
+    Arc::new(move |request: JSONRPCRequest| -> ServerRequestFuture {
+        let server_name = server_name.clone();
+        let server_requests = server_requests.clone();
+        Box::pin(async move {
+            if request.method == CreateMessageRequest::METHOD && !allow_sampling {
+                return Err(mcp_request_error(
+                    MCP_REQUEST_REJECTED_ERROR_CODE,
+                    format!("sampling is not enabled for MCP server `{server_name}`"),
+                ));
+            }
+            let (reply, reply_rx) = oneshot::channel();
+            let request = McpServerRequest {
+                server_name,
+                request,
+                reply,
+            };
+            if server_requests.send(request).await.is_err() {
+                return Err(mcp_request_error(
+                    MCP_REQUEST_FAILED_ERROR_CODE,
+                    "the session has shut down",
+                ));
+            }
+            reply_rx.await.unwrap_or_else(|_| {
+                Err(mcp_request_error(
+                    MCP_REQUEST_FAILED_ERROR_CODE,
+                    "the session dropped the request",
+                ))
+            })
+        })
+    })
+}
+
 fn qualify_tools(tools: Vec<ToolInfo>) -> HashMap<String, ToolInfo> {
     let mut used_names = HashSet::new();
     let mut qualified_tools = HashMap::new();
@@ -76,6 +191,7 @@ fn qualify_tools(tools: Vec<ToolInfo>) -> HashMap<String, ToolInfo> {
     qualified_tools
 }
 
+#[derive(Clone)]
 struct ToolInfo {
     server_name: String,
     tool_name: String,
@@ -85,19 +201,149 @@ struct ToolInfo {
 struct ManagedClient {
     client: Arc<McpClient>,
     startup_timeout: Duration,
+    capabilities: ServerCapabilities,
+    tool_policy: McpToolPolicy,
 }
 
-/// A thin wrapper around a set of running [`McpClient`] instances.
+/// Which of a server's tools are exposed to the model, and which of those
+/// need the user's approval before each call.
+#[derive(Debug, Clone, Default)]
+struct McpToolPolicy {
+    /// `None` exposes every tool that is not disabled.
+    enabled: Option<Vec<McpToolPattern>>,
+    disabled: Vec<McpToolPattern>,
+    require_approval: Vec<McpToolPattern>,
+}
+
+impl McpToolPolicy {
+    fn from_config(cfg: &McpServerConfig) -> Self {
+        let compile = |patterns: &[String]| -> Vec<McpToolPattern> {
+            patterns.iter().map(|p| McpToolPattern::new(p)).collect()
+        };
+        Self {
+            enabled: cfg.enabled_tools.as_deref().map(compile),
+            disabled: compile(&cfg.disabled_tools),
+            require_approval: compile(&cfg.require_approval),
+        }
+    }
+
+    fn is_enabled(&self, tool: &str) -> bool {
+        let enabled = self
+            .enabled
+            .as_ref()
+            .is_none_or(|patterns| patterns.iter().any(|p| p.matches(tool)));
+        enabled && !self.disabled.iter().any(|p| p.matches(tool))
+    }
+
+    fn requires_approval(&self, tool: &str) -> bool {
+        self.require_approval.iter().any(|p| p.matches(tool))
+    }
+}
+
+/// The running servers and what they listed. Changes when a server is
+/// restarted, enabled or disabled, or tells us its tools changed.
 #[derive(Default)]
-pub(crate) struct McpConnectionManager {
+struct ConnectionState {
     /// Server-name -> client instance.
     ///
     /// The server name originates from the keys of the `mcp_servers` map in
     /// the user configuration.
     clients: HashMap<String, ManagedClient>,
 
-    /// Fully qualified tool name -> tool instance.
+    /// Server name -> tools listed by that server.
+    server_tools: HashMap<String, Vec<ToolInfo>>,
+
+    /// Fully qualified tool name -> tool instance, rebuilt from
+    /// `server_tools`.
     tools: HashMap<String, ToolInfo>,
+
+    /// Server name -> resources listed by that server.
+    resources: HashMap<String, Vec<Resource>>,
+
+    /// Server name -> prompts listed by that server.
+    prompts: HashMap<String, Vec<Prompt>>,
+
+    /// Server name -> why that server is not running.
+    errors: HashMap<String, String>,
+
+    /// Servers the user stopped for the rest of the session.
+    disabled: HashSet<String>,
+}
+
+impl ConnectionState {
+    fn set_server_tools(&mut self, server_name: &str, tools: Vec<ToolInfo>) {
+        self.server_tools.insert(server_name.to_string(), tools);
+        self.requalify_tools();
+    }
+
+    /// Forget everything about `server_name`. Dropping its client stops the
+    /// server.
+    fn remove_server(&mut self, server_name: &str) {
+        self.clients.remove(server_name);
+        self.server_tools.remove(server_name);
+        self.resources.remove(server_name);
+        self.prompts.remove(server_name);
+        self.errors.remove(server_name);
+        self.requalify_tools();
+    }
+
+    fn status(&self, server_name: &str) -> McpServerStatus {
+        let (state, error) = if self.disabled.contains(server_name) {
+            (McpServerState::Disabled, None)
+        } else if let Some(managed) = self.clients.get(server_name) {
+            if managed.client.is_closed() {
+                let error = "the server closed the connection".to_string();
+                (McpServerState::Failed, Some(error))
+            } else {
+                (McpServerState::Running, None)
+            }
+        } else {
+            let error = self
+                .errors
+                .get(server_name)
+                .cloned()
+                .unwrap_or_else(|| "the server is not running".to_string());
+            (McpServerState::Failed, Some(error))
+        };
+        McpServerStatus {
+            name: server_name.to_string(),
+            state,
+            error,
+        }
+    }
+
+    fn requalify_tools(&mut self) {
+        // Sort by server so that which of two clashing tools wins does not
+        // change every time a server is refreshed.
+        let mut server_names: Vec<&String> = self.server_tools.keys().collect();
+        server_names.sort();
+        let all_tools = server_names
+            .into_iter()
+            .flat_map(|server_name| self.server_tools[server_name].iter().cloned())
+            .collect();
+        self.tools = qualify_tools(all_tools);
+    }
+}
+
+fn read_state(state: &RwLock<ConnectionState>) -> RwLockReadGuard<'_, ConnectionState> {
+    state.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_state(state: &RwLock<ConnectionState>) -> RwLockWriteGuard<'_, ConnectionState> {
+    state.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A thin wrapper around a set of running [`McpClient`] instances.
+#[derive(Default)]
+pub(crate) struct McpConnectionManager {
+    /// Server name -> configuration, used to start servers again mid-session.
+    configs: HashMap<String, McpServerConfig>,
+
+    /// Where the clients of restarted servers forward the requests their
+    /// server sends us.
+    server_requests: Option<mpsc::Sender<McpServerRequest>>,
+
+    state: Arc<RwLock<ConnectionState>>,
 }
 
 impl McpConnectionManager {
@@ -107,84 +353,44 @@ impl McpConnectionManager {
     ///   are human-readable server identifiers and *values* are the spawn
     ///   instructions.
     ///
+    /// * `server_requests` – Receives the requests the servers send back to
+    ///   us (`roots/list`, `sampling/createMessage`).
+    ///
     /// Servers that fail to start are reported in `ClientStartErrors`: the
     /// user should be informed about these errors.
     pub async fn new(
         mcp_servers: HashMap<String, McpServerConfig>,
+        server_requests: mpsc::Sender<McpServerRequest>,
     ) -> Result<(Self, ClientStartErrors)> {
         // Early exit if no servers are configured.
         if mcp_servers.is_empty() {
             return Ok((Self::default(), ClientStartErrors::default()));
         }
 
+        let state: Arc<RwLock<ConnectionState>> = Arc::default();
+
         // Launch all configured servers concurrently.
         let mut join_set = JoinSet::new();
         let mut errors = ClientStartErrors::new();
 
-        for (server_name, cfg) in mcp_servers {
+        for (server_name, cfg) in &mcp_servers {
             // Validate server name before spawning
-            if !is_valid_mcp_server_name(&server_name) {
+            if !is_valid_mcp_server_name(server_name) {
                 let error = anyhow::anyhow!(
                     "invalid server name '{}': must match pattern ^[a-zA-Z0-9_-]+$",
                     server_name
                 );
-                errors.insert(server_name, error);
+                errors.insert(server_name.clone(), error);
                 continue;
             }
 
-            let startup_timeout = cfg
-                .startup_timeout_ms
-                .map(Duration::from_millis)
-                .unwrap_or(DEFAULT_STARTUP_TIMEOUT);
-
+            let server_name = server_name.clone();
+            let cfg = cfg.clone();
+            let server_requests = server_requests.clone();
+            let weak_state = Arc::downgrade(&state);
             join_set.spawn(async move {
-                let McpServerConfig {
-                    command, args, env, ..
-                } = cfg;
-                let client_res = McpClient::new_stdio_client(
-                    command.into(),
-                    args.into_iter().map(OsString::from).collect(),
-                    env,
-                )
-                .await;
-                match client_res {
-                    Ok(client) => {
-                        // Initialize the client.
-                        let params = mcp_types::InitializeRequestParams {
-                            capabilities: ClientCapabilities {
-                                experimental: None,
-                                roots: None,
-                                sampling: None,
-                                // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
-                                // indicates this should be an empty object.
-                                elicitation: Some(json!({})),
-                            },
-                            client_info: Implementation {
-                                name: "icodex-mcp-client".to_owned(),
-                                version: env!("CARGO_PKG_VERSION").to_owned(),
-                                title: Some("Codex".into()),
-                                // This field is used by Codex when it is an MCP
-                                // server: it should not be used when Codex is
-                                // an MCP client.
-                                user_agent: None,
-                            },
-                            protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
-                        };
-                        let initialize_notification_params = None;
-                        match client
-                            .initialize(
-                                params,
-                                initialize_notification_params,
-                                Some(startup_timeout),
-                            )
-                            .await
-                        {
-                            Ok(_response) => (server_name, Ok((client, startup_timeout))),
-                            Err(e) => (server_name, Err(e)),
-                        }
-                    }
-                    Err(e) => (server_name, Err(e.into())),
-                }
+                let client_res = start_client(&server_name, cfg, server_requests, weak_state).await;
+                (server_name, client_res)
             });
         }
 
@@ -200,14 +406,8 @@ impl McpConnectionManager {
             };
 
             match client_res {
-                Ok((client, startup_timeout)) => {
-                    clients.insert(
-                        server_name,
-                        ManagedClient {
-                            client: Arc::new(client),
-                            startup_timeout,
-                        },
-                    );
+                Ok(managed_client) => {
+                    clients.insert(server_name, managed_client);
                 }
                 Err(e) => {
                     errors.insert(server_name, e);
@@ -215,23 +415,37 @@ impl McpConnectionManager {
             }
         }
 
-        let all_tools = match list_all_tools(&clients).await {
-            Ok(tools) => tools,
-            Err(e) => {
-                warn!("Failed to list tools from some MCP servers: {e:#}");
-                Vec::new()
-            }
-        };
+        let server_tools = list_all_tools(&clients).await;
+        let (resources, prompts) = list_all_resources_and_prompts(&clients).await;
 
-        let tools = qualify_tools(all_tools);
+        {
+            let mut state = write_state(&state);
+            state.clients = clients;
+            state.server_tools = server_tools;
+            state.requalify_tools();
+            state.resources = resources;
+            state.prompts = prompts;
+            state.errors = errors
+                .iter()
+                .map(|(server_name, e)| (server_name.clone(), format!("{e:#}")))
+                .collect();
+        }
 
-        Ok((Self { clients, tools }, errors))
+        Ok((
+            Self {
+                configs: mcp_servers,
+                server_requests: Some(server_requests),
+                state,
+            },
+            errors,
+        ))
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
     /// fully-qualified name for the tool.
     pub fn list_all_tools(&self) -> HashMap<String, Tool> {
-        self.tools
+        read_state(&self.state)
+            .tools
             .iter()
             .map(|(name, tool)| (name.clone(), tool.tool.clone()))
             .collect()
@@ -245,12 +459,19 @@ impl McpConnectionManager {
         arguments: Option<serde_json::Value>,
         timeout: Option<Duration>,
     ) -> Result<mcp_types::CallToolResult> {
-        let client = self
-            .clients
-            .get(server)
-            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?
-            .client
-            .clone();
+        let client = {
+            let state = read_state(&self.state);
+            let managed = state
+                .clients
+                .get(server)
+                .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+            if !managed.tool_policy.is_enabled(tool) {
+                return Err(anyhow!(
+                    "tool `{tool}` is disabled for MCP server `{server}`"
+                ));
+            }
+            managed.client.clone()
+        };
 
         client
             .call_tool(tool.to_string(), arguments, timeout)
@@ -259,15 +480,352 @@ impl McpConnectionManager {
     }
 
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
-        self.tools
+        read_state(&self.state)
+            .tools
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// Whether the server marks the fully-qualified `tool_name` as read-only
+    /// (`readOnlyHint`).
+    pub fn is_read_only_tool(&self, tool_name: &str) -> bool {
+        read_state(&self.state)
+            .tools
+            .get(tool_name)
+            .is_some_and(|tool| {
+                tool.tool
+                    .annotations
+                    .as_ref()
+                    .and_then(|annotations| annotations.read_only_hint)
+                    .unwrap_or(false)
+            })
+    }
+
+    /// Whether `server`'s `require_approval` patterns match `tool`.
+    pub fn tool_requires_approval(&self, server: &str, tool: &str) -> bool {
+        read_state(&self.state)
+            .clients
+            .get(server)
+            .is_some_and(|managed| managed.tool_policy.requires_approval(tool))
+    }
+
+    /// Returns the resources listed by each server, keyed by server name.
+    pub fn list_all_resources(&self) -> HashMap<String, Vec<Resource>> {
+        read_state(&self.state).resources.clone()
+    }
+
+    /// Whether any server listed at least one resource.
+    pub fn has_resources(&self) -> bool {
+        read_state(&self.state)
+            .resources
+            .values()
+            .any(|resources| !resources.is_empty())
+    }
+
+    /// Returns the prompts listed by each server, keyed by server name.
+    pub fn list_all_prompts(&self) -> HashMap<String, Vec<Prompt>> {
+        read_state(&self.state).prompts.clone()
+    }
+
+    /// Whether `server` is a running MCP server.
+    pub fn has_server(&self, server: &str) -> bool {
+        read_state(&self.state).clients.contains_key(server)
+    }
+
+    /// Read the resource at `uri` from `server`. The URI does not need to be
+    /// one of the listed resources: servers may also serve templated URIs.
+    pub async fn read_resource(&self, server: &str, uri: &str) -> Result<ReadResourceResult> {
+        self.client(server)?
+            .read_resource(uri.to_string(), Some(RESOURCE_REQUEST_TIMEOUT))
+            .await
+            .with_context(|| format!("failed to read resource `{uri}` from `{server}`"))
+    }
+
+    /// Fetch the prompt `name` from `server`, filled in with `arguments`.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<GetPromptResult> {
+        let arguments = if arguments.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_value(arguments)?)
+        };
+        self.client(server)?
+            .get_prompt(name.to_string(), arguments, Some(RESOURCE_REQUEST_TIMEOUT))
+            .await
+            .with_context(|| format!("failed to get prompt `{name}` from `{server}`"))
+    }
+
+    /// The status of every configured server, sorted by name.
+    pub fn server_statuses(&self) -> Vec<McpServerStatus> {
+        let state = read_state(&self.state);
+        let mut server_names: Vec<&String> = self.configs.keys().collect();
+        server_names.sort();
+        server_names
+            .into_iter()
+            .map(|server_name| state.status(server_name))
+            .collect()
+    }
+
+    /// Stop `server` if it is running and start it again from its
+    /// configuration, listing its tools, resources and prompts anew. A
+    /// disabled server is enabled again.
+    pub async fn restart_server(&self, server: &str) -> Result<()> {
+        let cfg = self
+            .configs
+            .get(server)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        if !is_valid_mcp_server_name(server) {
+            return Err(anyhow!(
+                "invalid server name '{server}': must match pattern ^[a-zA-Z0-9_-]+$"
+            ));
+        }
+        let server_requests = self
+            .server_requests
+            .clone()
+            .ok_or_else(|| anyhow!("MCP servers cannot be started in this session"))?;
+
+        {
+            let mut state = write_state(&self.state);
+            state.disabled.remove(server);
+            state.remove_server(server);
+        }
+
+        let managed_client =
+            match start_client(server, cfg, server_requests, Arc::downgrade(&self.state)).await {
+                Ok(managed_client) => managed_client,
+                Err(e) => {
+                    write_state(&self.state)
+                        .errors
+                        .insert(server.to_string(), format!("{e:#}"));
+                    return Err(e.context(format!("MCP server `{server}` failed to start")));
+                }
+            };
+
+        let clients = HashMap::from([(server.to_string(), managed_client)]);
+        let mut server_tools = list_all_tools(&clients).await;
+        let (resources, prompts) = list_all_resources_and_prompts(&clients).await;
+
+        let mut state = write_state(&self.state);
+        if state.disabled.contains(server) {
+            // Disabled while it was starting: drop the new client.
+            return Ok(());
+        }
+        state.clients.extend(clients);
+        state.set_server_tools(server, server_tools.remove(server).unwrap_or_default());
+        state.resources.extend(resources);
+        state.prompts.extend(prompts);
+        Ok(())
+    }
+
+    /// Start (`enabled`) or stop `server` for the rest of the session.
+    pub async fn set_server_enabled(&self, server: &str, enabled: bool) -> Result<()> {
+        if !self.configs.contains_key(server) {
+            return Err(anyhow!("unknown MCP server '{server}'"));
+        }
+        if enabled {
+            if self.has_server(server) {
+                return Ok(());
+            }
+            return self.restart_server(server).await;
+        }
+        let mut state = write_state(&self.state);
+        state.remove_server(server);
+        state.disabled.insert(server.to_string());
+        Ok(())
+    }
+
+    /// Ask `server`, or every running server when `None`, for its tools again.
+    pub async fn refresh_tools(&self, server: Option<&str>) -> Result<()> {
+        let server_names: Vec<String> = match server {
+            Some(server) => vec![server.to_string()],
+            None => read_state(&self.state).clients.keys().cloned().collect(),
+        };
+        for server_name in server_names {
+            refresh_server_tools(&self.state, &server_name).await?;
+        }
+        Ok(())
+    }
+
+    fn client(&self, server: &str) -> Result<Arc<McpClient>> {
+        read_state(&self.state)
+            .clients
+            .get(server)
+            .map(|managed| managed.client.clone())
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))
+    }
+}
+
+/// Launch the server `server_name` described by `cfg` and complete the MCP
+/// handshake. When the server says its tools changed, they are listed again
+/// into `state`.
+async fn start_client(
+    server_name: &str,
+    cfg: McpServerConfig,
+    server_requests: mpsc::Sender<McpServerRequest>,
+    state: Weak<RwLock<ConnectionState>>,
+) -> Result<ManagedClient> {
+    let startup_timeout = cfg
+        .startup_timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_STARTUP_TIMEOUT);
+    let allow_sampling = cfg.allow_sampling;
+    let tool_policy = McpToolPolicy::from_config(&cfg);
+    let handler = forward_server_requests(server_name.to_string(), allow_sampling, server_requests);
+
+    let client = match cfg.transport {
+        McpServerTransportConfig::Stdio { command, args, env } => McpClient::new_stdio_client(
+            command.into(),
+            args.into_iter().map(OsString::from).collect(),
+            env,
+        )
+        .await
+        .map_err(anyhow::Error::from)?,
+        McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token_env_var,
+            http_headers,
+            env_http_headers,
+        } => {
+            let headers = resolve_http_headers(
+                bearer_token_env_var,
+                http_headers,
+                env_http_headers,
+                |var| std::env::var(var).ok(),
+            )?;
+            McpClient::new_streamable_http_client(&url, headers).await?
+        }
+    };
+
+    // Register the handlers first: servers may send requests as soon as the
+    // session is open.
+    client.set_server_request_handler(handler);
+    client.set_notification_handler(refresh_tools_on_list_changed(
+        server_name.to_string(),
+        state,
+    ));
+
+    // Initialize the client.
+    let params = mcp_types::InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            experimental: None,
+            roots: Some(ClientCapabilitiesRoots {
+                list_changed: Some(false),
+            }),
+            sampling: allow_sampling.then(|| json!({})),
+            // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
+            // indicates this should be an empty object.
+            elicitation: Some(json!({})),
+        },
+        client_info: Implementation {
+            name: "icodex-mcp-client".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            title: Some("Codex".into()),
+            // This field is used by Codex when it is an MCP
+            // server: it should not be used when Codex is
+            // an MCP client.
+            user_agent: None,
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+    };
+    let initialize_notification_params = None;
+    let response = client
+        .initialize(
+            params,
+            initialize_notification_params,
+            Some(startup_timeout),
+        )
+        .await?;
+
+    Ok(ManagedClient {
+        client: Arc::new(client),
+        startup_timeout,
+        capabilities: response.capabilities,
+        tool_policy,
+    })
+}
+
+/// Build the handler that lists the tools of `server_name` again when it
+/// sends `notifications/tools/list_changed`.
+fn refresh_tools_on_list_changed(
+    server_name: String,
+    state: Weak<RwLock<ConnectionState>>,
+) -> NotificationHandler {
+    Arc::new(move |notification: JSONRPCNotification| {
+        if notification.method != ToolListChangedNotification::METHOD {
+            return;
+        }
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let server_name = server_name.clone();
+        tokio::spawn(async move {
+            if let Err(e) = refresh_server_tools(&state, &server_name).await {
+                warn!("Failed to refresh tools for MCP server '{server_name}': {e:#}");
+            }
+        });
+    })
 }
 
-/// Query every server for its available tools and return a single map that
-/// contains **all** tools. Each key is the fully-qualified name for the tool.
-async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<ToolInfo>> {
+async fn refresh_server_tools(state: &RwLock<ConnectionState>, server_name: &str) -> Result<()> {
+    let (client, startup_timeout, tool_policy) = {
+        let state = read_state(state);
+        let managed = state
+            .clients
+            .get(server_name)
+            .ok_or_else(|| anyhow!("MCP server '{server_name}' is not running"))?;
+        (
+            managed.client.clone(),
+            managed.startup_timeout,
+            managed.tool_policy.clone(),
+        )
+    };
+    let tools = list_server_tools(server_name, &client, startup_timeout, &tool_policy).await?;
+    let mut state = write_state(state);
+    // The server may have been restarted or stopped while we were listing.
+    if state
+        .clients
+        .get(server_name)
+        .is_some_and(|managed| Arc::ptr_eq(&managed.client, &client))
+    {
+        info!(
+            "refreshed {} tools from MCP server '{server_name}'",
+            tools.len()
+        );
+        state.set_server_tools(server_name, tools);
+    }
+    Ok(())
+}
+
+/// List the tools of one server that its policy exposes.
+async fn list_server_tools(
+    server_name: &str,
+    client: &McpClient,
+    timeout: Duration,
+    tool_policy: &McpToolPolicy,
+) -> Result<Vec<ToolInfo>> {
+    let list_result = client.list_tools(None, Some(timeout)).await?;
+    Ok(list_result
+        .tools
+        .into_iter()
+        .filter(|tool| tool_policy.is_enabled(&tool.name))
+        .map(|tool| ToolInfo {
+            server_name: server_name.to_string(),
+            tool_name: tool.name.clone(),
+            tool,
+        })
+        .collect())
+}
+
+/// Query every server for its available tools, keyed by server name. A
+/// server whose listing fails is left out with a warning.
+async fn list_all_tools(
+    clients: &HashMap<String, ManagedClient>,
+) -> HashMap<String, Vec<ToolInfo>> {
     let mut join_set = JoinSet::new();
 
     // Spawn one task per server so we can query them concurrently. This
@@ -277,13 +835,20 @@ async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<
         let server_name_cloned = server_name.clone();
         let client_clone = managed_client.client.clone();
         let startup_timeout = managed_client.startup_timeout;
+        let tool_policy = managed_client.tool_policy.clone();
         join_set.spawn(async move {
-            let res = client_clone.list_tools(None, Some(startup_timeout)).await;
+            let res = list_server_tools(
+                &server_name_cloned,
+                &client_clone,
+                startup_timeout,
+                &tool_policy,
+            )
+            .await;
             (server_name_cloned, res)
         });
     }
 
-    let mut aggregated: Vec<ToolInfo> = Vec::with_capacity(join_set.len());
+    let mut aggregated: HashMap<String, Vec<ToolInfo>> = HashMap::with_capacity(join_set.len());
 
     while let Some(join_res) = join_set.join_next().await {
         let (server_name, list_result) = if let Ok(result) = join_res {
@@ -293,30 +858,147 @@ async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<
             continue;
         };
 
-        let list_result = if let Ok(result) = list_result {
-            result
+        let tools = if let Ok(tools) = list_result {
+            tools
         } else {
             warn!("Failed to list tools for MCP server '{server_name}': {list_result:#?}");
             continue;
         };
 
-        for tool in list_result.tools {
-            let tool_info = ToolInfo {
-                server_name: server_name.clone(),
-                tool_name: tool.name.clone(),
-                tool,
-            };
-            aggregated.push(tool_info);
-        }
+        aggregated.insert(server_name, tools);
     }
 
     info!(
         "aggregated {} tools from {} servers",
-        aggregated.len(),
+        aggregated.values().map(Vec::len).sum::<usize>(),
         clients.len()
     );
 
-    Ok(aggregated)
+    aggregated
+}
+
+/// Query the servers that advertise resources or prompts for them. A server
+/// whose listing fails is left out with a warning, like for tools.
+async fn list_all_resources_and_prompts(
+    clients: &HashMap<String, ManagedClient>,
+) -> (HashMap<String, Vec<Resource>>, HashMap<String, Vec<Prompt>>) {
+    let mut join_set = JoinSet::new();
+    for (server_name, managed_client) in clients {
+        let server_name = server_name.clone();
+        let client = managed_client.client.clone();
+        let timeout = managed_client.startup_timeout;
+        let wants_resources = managed_client.capabilities.resources.is_some();
+        let wants_prompts = managed_client.capabilities.prompts.is_some();
+        if !wants_resources && !wants_prompts {
+            continue;
+        }
+        join_set.spawn(async move {
+            let resources = if wants_resources {
+                list_resources_paginated(&client, timeout).await
+            } else {
+                Ok(Vec::new())
+            };
+            let prompts = if wants_prompts {
+                list_prompts_paginated(&client, timeout).await
+            } else {
+                Ok(Vec::new())
+            };
+            (server_name, resources, prompts)
+        });
+    }
+
+    let mut all_resources = HashMap::new();
+    let mut all_prompts = HashMap::new();
+    while let Some(join_res) = join_set.join_next().await {
+        let (server_name, resources, prompts) = match join_res {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Task panic when listing resources for MCP server: {e:#}");
+                continue;
+            }
+        };
+        match resources {
+            Ok(resources) if !resources.is_empty() => {
+                all_resources.insert(server_name.clone(), resources);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to list resources for MCP server '{server_name}': {e:#}"),
+        }
+        match prompts {
+            Ok(prompts) if !prompts.is_empty() => {
+                all_prompts.insert(server_name, prompts);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to list prompts for MCP server '{server_name}': {e:#}"),
+        }
+    }
+
+    (all_resources, all_prompts)
+}
+
+async fn list_resources_paginated(client: &McpClient, timeout: Duration) -> Result<Vec<Resource>> {
+    let mut resources = Vec::new();
+    let mut cursor = None;
+    for _ in 0..MAX_LIST_PAGES {
+        let params = cursor.map(|cursor| ListResourcesRequestParams {
+            cursor: Some(cursor),
+        });
+        let page = client.list_resources(params, Some(timeout)).await?;
+        resources.extend(page.resources);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    Ok(resources)
+}
+
+async fn list_prompts_paginated(client: &McpClient, timeout: Duration) -> Result<Vec<Prompt>> {
+    let mut prompts = Vec::new();
+    let mut cursor = None;
+    for _ in 0..MAX_LIST_PAGES {
+        let params = cursor.map(|cursor| ListPromptsRequestParams {
+            cursor: Some(cursor),
+        });
+        let page = client.list_prompts(params, Some(timeout)).await?;
+        prompts.extend(page.prompts);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    Ok(prompts)
+}
+
+/// Build the headers sent to a remote MCP server. `env_http_headers` entries
+/// whose variable is unset or empty are skipped, like for model providers, but
+/// a missing bearer token is an error: the server would reject every request.
+fn resolve_http_headers(
+    bearer_token_env_var: Option<String>,
+    http_headers: Option<HashMap<String, String>>,
+    env_http_headers: Option<HashMap<String, String>>,
+    get_env: impl Fn(&str) -> Option<String>,
+) -> Result<HashMap<String, String>> {
+    let mut headers = http_headers.unwrap_or_default();
+
+    for (header, env_var) in env_http_headers.unwrap_or_default() {
+        if let Some(value) = get_env(&env_var)
+            && !value.trim().is_empty()
+        {
+            headers.insert(header, value);
+        }
+    }
+
+    if let Some(env_var) = bearer_token_env_var {
+        let token = get_env(&env_var)
+            .filter(|token| !token.trim().is_empty())
+            .ok_or_else(|| {
+                anyhow!("environment variable `{env_var}` for the bearer token is not set")
+            })?;
+        headers.insert("Authorization".to_string(), format!("Bearer {token}"));
+    }
+
+    Ok(headers)
 }
 
 fn is_valid_mcp_server_name(server_name: &str) -> bool {
@@ -412,4 +1094,124 @@ mod tests {
             "my_server__yet_another_e1c3987bd9c50b826cbe1687966f79f0c602d19ca"
         );
     }
+
+    #[test]
+    fn resolve_http_headers_reads_environment() {
+        let env = HashMap::from([
+            ("DOCS_TOKEN".to_string(), "secret".to_string()),
+            ("DOCS_TEAM".to_string(), "core".to_string()),
+            ("EMPTY".to_string(), " ".to_string()),
+        ]);
+        let get_env = |var: &str| env.get(var).cloned();
+
+        let headers = resolve_http_headers(
+            Some("DOCS_TOKEN".to_string()),
+            Some(HashMap::from([(
+                "X-Client".to_string(),
+                "icodex".to_string(),
+            )])),
+            Some(HashMap::from([
+                ("X-Team".to_string(), "DOCS_TEAM".to_string()),
+                ("X-Empty".to_string(), "EMPTY".to_string()),
+                ("X-Missing".to_string(), "MISSING".to_string()),
+            ])),
+            get_env,
+        )
+        .expect("headers");
+        assert_eq!(
+            headers,
+            HashMap::from([
+                ("Authorization".to_string(), "Bearer secret".to_string()),
+                ("X-Client".to_string(), "icodex".to_string()),
+                ("X-Team".to_string(), "core".to_string()),
+            ])
+        );
+
+        let err = resolve_http_headers(Some("MISSING".to_string()), None, None, get_env)
+            .expect_err("missing bearer token");
+        assert!(err.to_string().contains("`MISSING`"));
+    }
+
+    fn server_config(
+        enabled_tools: Option<Vec<&str>>,
+        disabled_tools: Vec<&str>,
+        require_approval: Vec<&str>,
+    ) -> McpServerConfig {
+        let to_strings = |patterns: Vec<&str>| patterns.into_iter().map(String::from).collect();
+        McpServerConfig {
+            transport: McpServerTransportConfig::Stdio {
+                command: "server".to_string(),
+                args: Vec::new(),
+                env: None,
+            },
+            startup_timeout_ms: None,
+            allow_sampling: false,
+            enabled_tools: enabled_tools.map(to_strings),
+            disabled_tools: to_strings(disabled_tools),
+            require_approval: to_strings(require_approval),
+        }
+    }
+
+    #[test]
+    fn tool_policy_defaults_to_every_tool_without_approval() {
+        let policy = McpToolPolicy::from_config(&server_config(None, vec![], vec![]));
+        assert!(policy.is_enabled("search"));
+        assert!(!policy.requires_approval("search"));
+    }
+
+    #[test]
+    fn tool_policy_disabled_tools_win_over_enabled_tools() {
+        let policy = McpToolPolicy::from_config(&server_config(
+            Some(vec!["issue_*", "search"]),
+            vec!["issue_delete"],
+            vec![],
+        ));
+        assert!(policy.is_enabled("search"));
+        assert!(policy.is_enabled("issue_create"));
+        assert!(!policy.is_enabled("issue_delete"));
+        assert!(!policy.is_enabled("fetch"));
+    }
+
+    #[test]
+    fn tool_policy_require_approval_matches_patterns() {
+        let policy =
+            McpToolPolicy::from_config(&server_config(None, vec![], vec!["delete_*", "push"]));
+        assert!(policy.requires_approval("delete_branch"));
+        assert!(policy.requires_approval("push"));
+        assert!(!policy.requires_approval("pull"));
+    }
+
+    #[test]
+    fn removing_a_server_drops_its_tools() {
+        let mut state = ConnectionState::default();
+        state.set_server_tools("alpha", vec![create_test_tool("alpha", "search")]);
+        state.set_server_tools("beta", vec![create_test_tool("beta", "fetch")]);
+        assert!(state.tools.contains_key("alpha__search"));
+        assert!(state.tools.contains_key("beta__fetch"));
+
+        state.remove_server("alpha");
+
+        assert!(!state.tools.contains_key("alpha__search"));
+        assert!(state.tools.contains_key("beta__fetch"));
+    }
+
+    #[test]
+    fn status_reports_disabled_and_failed_servers() {
+        let mut state = ConnectionState::default();
+        state
+            .errors
+            .insert("broken".to_string(), "command not found".to_string());
+        state.disabled.insert("stopped".to_string());
+
+        assert_eq!(
+            state.status("broken"),
+            McpServerStatus {
+                name: "broken".to_string(),
+                state: McpServerState::Failed,
+                error: Some("command not found".to_string()),
+            }
+        );
+        assert_eq!(state.status("stopped").state, McpServerState::Disabled);
+        assert_eq!(state.status("stopped").error, None);
+    }
 }
@@ -0,0 +1,423 @@
+//! Exposes MCP resources and prompts to the model and to the user.
+//!
+//! Resources are surfaced in two ways: the `list_mcp_resources` and
+//! `read_mcp_resource` tools let the model browse them on its own, and
+//! `@server:uri` mentions in user input are read up front and attached to the
+//! turn. Prompts are fetched on demand and converted into user input.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use icodex_protocol::models::ContentItem;
+use icodex_protocol::models::FunctionCallOutputPayload;
+use icodex_protocol::models::ResponseInputItem;
+use icodex_protocol::models::ResponseItem;
+use icodex_protocol::protocol::MCP_RESOURCES_CLOSE_TAG;
+use icodex_protocol::protocol::MCP_RESOURCES_OPEN_TAG;
+use mcp_types::ContentBlock;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::GetPromptResult;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::mcp_connection_manager::McpConnectionManager;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::InputItem;
+
+pub(crate) const LIST_MCP_RESOURCES_TOOL_NAME: &str = "list_mcp_resources";
+pub(crate) const READ_MCP_RESOURCE_TOOL_NAME: &str = "read_mcp_resource";
+
+/// Characters stripped from the end of an `@server:uri` mention so that
+/// mentions at the end of a sentence still resolve.
+const MENTION_TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', '!', '?', ')'];
+
+/// Tools offered to the model when at least one MCP server lists resources.
+pub(crate) fn create_mcp_resource_tools() -> Vec<OpenAiTool> {
+    let mut list_properties = BTreeMap::new();
+    list_properties.insert(
+        "server".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Only list resources from this MCP server. Omit to list every server's resources."
+                    .to_string(),
+            ),
+        },
+    );
+
+    let mut read_properties = BTreeMap::new();
+    read_properties.insert(
+        "server".to_string(),
+        JsonSchema::String {
+            description: Some("Name of the MCP server that serves the resource.".to_string()),
+        },
+    );
+    read_properties.insert(
+        "uri".to_string(),
+        JsonSchema::String {
+            description: Some("URI of the resource to read.".to_string()),
+        },
+    );
+
+    vec![
+        OpenAiTool::Function(ResponsesApiTool {
+            name: LIST_MCP_RESOURCES_TOOL_NAME.to_string(),
+            description: "Lists the resources (files, documents, records, ...) exposed by the \
+                          configured MCP servers. Returns a JSON array of objects with the \
+                          server, uri, name and, when available, title, description and mimeType."
+                .to_string(),
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: list_properties,
+                required: None,
+                additional_properties: Some(false),
+            },
+        }),
+        OpenAiTool::Function(ResponsesApiTool {
+            name: READ_MCP_RESOURCE_TOOL_NAME.to_string(),
+            description: "Reads an MCP resource and returns its contents. Use \
+                          list_mcp_resources to discover the available resources."
+                .to_string(),
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: read_properties,
+                required: Some(vec!["server".to_string(), "uri".to_string()]),
+                additional_properties: Some(false),
+            },
+        }),
+    ]
+}
+
+#[derive(Deserialize)]
+struct ListMcpResourcesArgs {
+    #[serde(default)]
+    server: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReadMcpResourceArgs {
+    server: String,
+    uri: String,
+}
+
+/// One entry of the `list_mcp_resources` output.
+#[derive(Serialize)]
+struct ListedResource<'a> {
+    server: &'a str,
+    uri: &'a str,
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    mime_type: Option<&'a str>,
+}
+
+pub(crate) fn handle_list_mcp_resources(
+    manager: &McpConnectionManager,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let args = match serde_json::from_str::<ListMcpResourcesArgs>(&arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return function_output(
+                call_id,
+                format!("failed to parse function arguments: {e}"),
+                None,
+            );
+        }
+    };
+    if let Some(server) = &args.server
+        && !manager.has_server(server)
+    {
+        return function_output(
+            call_id,
+            format!("unknown MCP server '{server}'"),
+            Some(false),
+        );
+    }
+
+    let all_resources = manager.list_all_resources();
+    let mut servers: Vec<&String> = all_resources
+        .keys()
+        .filter(|server| args.server.as_ref().is_none_or(|only| only == *server))
+        .collect();
+    servers.sort();
+
+    let listed: Vec<ListedResource> = servers
+        .into_iter()
+        .flat_map(|server| {
+            all_resources[server]
+                .iter()
+                .map(move |resource| ListedResource {
+                    server,
+                    uri: &resource.uri,
+                    name: &resource.name,
+                    title: resource.title.as_deref(),
+                    description: resource.description.as_deref(),
+                    mime_type: resource.mime_type.as_deref(),
+                })
+        })
+        .collect();
+
+    match serde_json::to_string(&listed) {
+        Ok(content) => function_output(call_id, content, Some(true)),
+        Err(e) => function_output(call_id, format!("failed to list resources: {e}"), None),
+    }
+}
+
+pub(crate) async fn handle_read_mcp_resource(
+    manager: &McpConnectionManager,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let args = match serde_json::from_str::<ReadMcpResourceArgs>(&arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return function_output(
+                call_id,
+                format!("failed to parse function arguments: {e}"),
+                None,
+            );
+        }
+    };
+
+    match manager.read_resource(&args.server, &args.uri).await {
+        Ok(result) => function_output(call_id, render_resource_contents(&result), Some(true)),
+        Err(e) => function_output(call_id, format!("{e:#}"), Some(false)),
+    }
+}
+
+fn function_output(call_id: String, content: String, success: Option<bool>) -> ResponseInputItem {
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload { content, success },
+    }
+}
+
+/// Render the contents of a resource as text. Binary contents cannot be shown
+/// to the model as text, so they are replaced by a short note.
+fn render_resource_contents(result: &ReadResourceResult) -> String {
+    result
+        .contents
+        .iter()
+        .map(|contents| match contents {
+            ReadResourceResultContents::TextResourceContents(text) => text.text.clone(),
+            ReadResourceResultContents::BlobResourceContents(blob) => {
+                binary_note(&blob.uri, blob.mime_type.as_deref(), blob.blob.len())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn binary_note(uri: &str, mime_type: Option<&str>, base64_len: usize) -> String {
+    let mime_type = mime_type.unwrap_or("application/octet-stream");
+    format!("[binary resource {uri} ({mime_type}, {base64_len} bytes of base64) omitted]")
+}
+
+/// Find the `@server:uri` mentions in `text` whose server satisfies
+/// `is_server`, in order of appearance and without duplicates.
+pub(crate) fn parse_resource_mentions(
+    text: &str,
+    is_server: impl Fn(&str) -> bool,
+) -> Vec<(String, String)> {
+    let mut seen = HashSet::new();
+    let mut mentions = Vec::new();
+    for token in text.split_whitespace() {
+        let Some(mention) = token.strip_prefix('@') else {
+            continue;
+        };
+        let mention = mention.trim_end_matches(MENTION_TRAILING_PUNCTUATION);
+        let Some((server, uri)) = mention.split_once(':') else {
+            continue;
+        };
+        if server.is_empty() || uri.is_empty() || !is_server(server) {
+            continue;
+        }
+        let mention = (server.to_string(), uri.to_string());
+        if seen.insert(mention.clone()) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+/// Read every resource mentioned in the text of `input`.
+///
+/// Returns a user message carrying the contents of the resources that could
+/// be read, wrapped in `<mcp_resources>` so clients can tell it apart from
+/// what the user typed, plus one error message per resource that could not.
+pub(crate) async fn resolve_resource_mentions(
+    manager: &McpConnectionManager,
+    input: &[InputItem],
+) -> (Option<ResponseItem>, Vec<String>) {
+    let mentions: Vec<(String, String)> = input
+        .iter()
+        .filter_map(|item| match item {
+            InputItem::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .flat_map(|text| parse_resource_mentions(text, |server| manager.has_server(server)))
+        .collect::<Vec<_>>();
+    if mentions.is_empty() {
+        return (None, Vec::new());
+    }
+
+    let mut seen = HashSet::new();
+    let mut sections = Vec::new();
+    let mut errors = Vec::new();
+    for (server, uri) in mentions {
+        if !seen.insert((server.clone(), uri.clone())) {
+            continue;
+        }
+        match manager.read_resource(&server, &uri).await {
+            Ok(result) => sections.push(format!(
+                "<resource server=\"{server}\" uri=\"{uri}\">\n{}\n</resource>",
+                render_resource_contents(&result)
+            )),
+            Err(e) => errors.push(format!("{e:#}")),
+        }
+    }
+    if sections.is_empty() {
+        return (None, errors);
+    }
+
+    let text = format!(
+        "{MCP_RESOURCES_OPEN_TAG}\n{}\n{MCP_RESOURCES_CLOSE_TAG}",
+        sections.join("\n")
+    );
+    let message = ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText { text }],
+    };
+    (Some(message), errors)
+}
+
+/// Convert the messages of an MCP prompt into user input. Text and images are
+/// passed through, embedded text resources are inlined and content the model
+/// cannot consume (audio, binary resources) is replaced by a short note.
+pub(crate) fn prompt_to_input_items(result: GetPromptResult) -> Vec<InputItem> {
+    result
+        .messages
+        .into_iter()
+        .map(|message| match message.content {
+            ContentBlock::TextContent(text) => InputItem::Text { text: text.text },
+            ContentBlock::ImageContent(image) => InputItem::Image {
+                image_url: format!("data:{};base64,{}", image.mime_type, image.data),
+            },
+            ContentBlock::AudioContent(audio) => InputItem::Text {
+                text: format!("[audio ({}) omitted]", audio.mime_type),
+            },
+            ContentBlock::ResourceLink(link) => InputItem::Text { text: link.uri },
+            ContentBlock::EmbeddedResource(embedded) => match embedded.resource {
+                EmbeddedResourceResource::TextResourceContents(text) => {
+                    InputItem::Text { text: text.text }
+                }
+                EmbeddedResourceResource::BlobResourceContents(blob) => InputItem::Text {
+                    text: binary_note(&blob.uri, blob.mime_type.as_deref(), blob.blob.len()),
+                },
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::ImageContent;
+    use mcp_types::PromptMessage;
+    use mcp_types::Role;
+    use mcp_types::TextContent;
+    use mcp_types::TextResourceContents;
+    use pretty_assertions::assert_eq;
+
+    fn mention(server: &str, uri: &str) -> (String, String) {
+        (server.to_string(), uri.to_string())
+    }
+
+    #[test]
+    fn parses_mentions_of_known_servers() {
+        let text = "compare @docs:docs://guide with @docs:docs://faq. \
+                    Also see @src/main.rs and @other:thing (again @docs:docs://guide)";
+        let mentions = parse_resource_mentions(text, |server| server == "docs");
+        assert_eq!(
+            mentions,
+            vec![
+                mention("docs", "docs://guide"),
+                mention("docs", "docs://faq")
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_mentions_without_uri() {
+        let mentions = parse_resource_mentions("@docs: @docs @:uri", |_| true);
+        assert_eq!(mentions, Vec::<(String, String)>::new());
+    }
+
+    #[test]
+    fn renders_text_and_binary_contents() {
+        let result = ReadResourceResult {
+            contents: vec![
+                ReadResourceResultContents::TextResourceContents(TextResourceContents {
+                    mime_type: Some("text/plain".to_string()),
+                    text: "hello".to_string(),
+                    uri: "docs://hello".to_string(),
+                }),
+                ReadResourceResultContents::BlobResourceContents(mcp_types::BlobResourceContents {
+                    blob: "AAAA".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    uri: "docs://logo".to_string(),
+                }),
+            ],
+        };
+        assert_eq!(
+            render_resource_contents(&result),
+            "hello\n\n[binary resource docs://logo (image/png, 4 bytes of base64) omitted]"
+        );
+    }
+
+    #[test]
+    fn converts_prompt_messages_to_input_items() {
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![
+                PromptMessage {
+                    content: ContentBlock::TextContent(TextContent {
+                        annotations: None,
+                        text: "Review this code".to_string(),
+                        r#type: "text".to_string(),
+                    }),
+                    role: Role::User,
+                },
+                PromptMessage {
+                    content: ContentBlock::ImageContent(ImageContent {
+                        annotations: None,
+                        data: "AAAA".to_string(),
+                        mime_type: "image/png".to_string(),
+                        r#type: "image".to_string(),
+                    }),
+                    role: Role::User,
+                },
+            ],
+        };
+
+        let items = prompt_to_input_items(result);
+        assert_eq!(items.len(), 2);
+        assert!(matches!(
+            &items[0],
+            InputItem::Text { text } if text == "Review this code"
+        ));
+        assert!(matches!(
+            &items[1],
+            InputItem::Image { image_url } if image_url == "data:image/png;base64,AAAA"
+        ));
+    }
+}
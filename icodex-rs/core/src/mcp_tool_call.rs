@@ -4,16 +4,19 @@ use std::time::Instant;
 use tracing::error;
 
 use crate::icodex::Session;
+use crate::protocol::AskForApproval;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::ReviewDecision;
 use icodex_protocol::models::FunctionCallOutputPayload;
 use icodex_protocol::models::ResponseInputItem;
 
 /// Handles the specified tool call dispatches the appropriate
 /// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn handle_mcp_tool_call(
     sess: &Session,
     sub_id: &str,
@@ -22,6 +25,7 @@ pub(crate) async fn handle_mcp_tool_call(
     tool_name: String,
     arguments: String,
     timeout: Option<Duration>,
+    approval_policy: AskForApproval,
 ) -> ResponseInputItem {
     // Parse the `arguments` as JSON. An empty string is OK, but invalid JSON
     // is not.
@@ -49,6 +53,18 @@ pub(crate) async fn handle_mcp_tool_call(
         arguments: arguments_value.clone(),
     };
 
+    if let Some(rejection) =
+        request_approval_if_needed(sess, sub_id, &call_id, &invocation, approval_policy).await
+    {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: rejection,
+                success: None,
+            },
+        };
+    }
+
     let tool_call_begin_event = EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
         call_id: call_id.clone(),
         invocation: invocation.clone(),
@@ -73,6 +89,43 @@ pub(crate) async fn handle_mcp_tool_call(
     ResponseInputItem::McpToolCallOutput { call_id, result }
 }
 
+/// Ask the user about the call if the server's `require_approval` patterns
+/// match the tool, or if the policy asks about everything that is not known to
+/// be safe. Returns the message for the model when the call must not run.
+async fn request_approval_if_needed(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    invocation: &McpInvocation,
+    approval_policy: AskForApproval,
+) -> Option<String> {
+    let McpInvocation { server, tool, .. } = invocation;
+    let required = approval_policy == AskForApproval::UnlessTrusted
+        || sess.mcp_tool_requires_approval(server, tool);
+    if !required || sess.is_mcp_tool_approved_for_session(server, tool) {
+        return None;
+    }
+    if approval_policy == AskForApproval::Never {
+        return Some(format!(
+            "MCP tool `{server}/{tool}` requires approval, but the approval policy is `never`"
+        ));
+    }
+
+    let rx_approve = sess
+        .request_mcp_tool_call_approval(sub_id.to_string(), call_id.to_string(), invocation.clone())
+        .await;
+    match rx_approve.await.unwrap_or_default() {
+        ReviewDecision::Approved => None,
+        ReviewDecision::ApprovedForSession => {
+            sess.add_approved_mcp_tool(server.clone(), tool.clone());
+            None
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => {
+            Some("MCP tool call rejected by user".to_string())
+        }
+    }
+}
+
 async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMsg) {
     sess.send_event(Event {
         id: sub_id.to_string(),
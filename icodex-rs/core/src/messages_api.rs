@@ -0,0 +1,791 @@
+//! Native client for the Messages API (`/v1/messages`) used by Claude-style
+//! providers.
+//!
+//! The conversation history is translated into alternating `user` /
+//! `assistant` messages made of content blocks: function calls become
+//! `tool_use` blocks and their outputs `tool_result` blocks. The streamed
+//! reply is mapped onto the same [`ResponseEvent`] sequence that the Responses
+//! API produces, emitting one `OutputItemDone` per finished content block.
+//!
+//! Prompt-caching breakpoints are placed on the system prompt, the last tool
+//! definition and the last block of the conversation so that consecutive
+//! turns reuse the cached prefix.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_messages_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use icodex_protocol::models::ContentItem;
+use icodex_protocol::models::ReasoningItemContent;
+use icodex_protocol::models::ResponseItem;
+
+/// Value sent in the `anthropic-version` header unless the provider config
+/// sets its own.
+const MESSAGES_API_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is mandatory in the Messages API; used when neither the
+/// config nor the model metadata provide a limit.
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 8_192;
+
+/// Implementation for the Messages API.
+pub(crate) async fn stream_messages(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+) -> Result<ResponseStream> {
+    let payload = build_messages_request(prompt, model_family, max_output_tokens)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(&None),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let sets_version = provider.http_headers.as_ref().is_some_and(|headers| {
+        headers
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case("anthropic-version"))
+    });
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let mut req_builder = provider.create_request_builder(client, &None).await?;
+        if !sets_version {
+            req_builder = req_builder.header("anthropic-version", MESSAGES_API_VERSION);
+        }
+
+        let res = req_builder
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload)
+            .send()
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_messages_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Builds the JSON body of a streaming `/v1/messages` request.
+pub(crate) fn build_messages_request(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+) -> Result<Value> {
+    let instructions = prompt.get_full_instructions(model_family);
+    let mut messages = build_messages(&prompt.get_formatted_input());
+    let mut tools = create_tools_json_for_messages_api(&prompt.tools)?;
+
+    if let Some(tool) = tools.last_mut() {
+        mark_cache_breakpoint(tool);
+    }
+    if let Some(block) = messages
+        .last_mut()
+        .and_then(|message| message.get_mut("content"))
+        .and_then(Value::as_array_mut)
+        .and_then(|blocks| blocks.last_mut())
+    {
+        mark_cache_breakpoint(block);
+    }
+
+    let mut payload = json!({
+        "model": model_family.slug,
+        "max_tokens": max_output_tokens.unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS),
+        "system": [{
+            "type": "text",
+            "text": instructions,
+            "cache_control": {"type": "ephemeral"},
+        }],
+        "messages": messages,
+        "stream": true,
+    });
+    if !tools.is_empty()
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert("tools".to_string(), Value::Array(tools));
+    }
+    Ok(payload)
+}
+
+fn mark_cache_breakpoint(value: &mut Value) {
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("cache_control".to_string(), json!({"type": "ephemeral"}));
+    }
+}
+
+/// Translates the conversation history into Messages API messages. Adjacent
+/// items that map to the same role are merged into one message because the
+/// API expects `tool_result` blocks in the `user` turn that directly follows
+/// the matching `tool_use`.
+fn build_messages(input: &[ResponseItem]) -> Vec<Value> {
+    let mut messages: Vec<Value> = Vec::new();
+    let mut push_block = |role: &str, block: Value| {
+        if let Some(last) = messages.last_mut()
+            && last.get("role").and_then(Value::as_str) == Some(role)
+            && let Some(blocks) = last.get_mut("content").and_then(Value::as_array_mut)
+        {
+            blocks.push(block);
+            return;
+        }
+        messages.push(json!({"role": role, "content": [block]}));
+    };
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                // Developer / system messages are folded into the user turn;
+                // the Messages API only knows `user` and `assistant`.
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for content_item in content {
+                    match content_item {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push_block(role, json!({"type": "text", "text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push_block(role, image_block(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                push_block(
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": name, "input": input}),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let call_id = call_id.as_ref().or(id.as_ref());
+                push_block(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": "local_shell",
+                        "input": action,
+                    }),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                push_block(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": name,
+                        "input": {"input": input},
+                    }),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                push_block(
+                    "user",
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": call_id,
+                        "content": output.content,
+                        "is_error": output.success == Some(false),
+                    }),
+                );
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                push_block(
+                    "user",
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": call_id,
+                        "content": output,
+                    }),
+                );
+            }
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {
+                // Omit these items from the conversation history.
+                continue;
+            }
+        }
+    }
+
+    messages
+}
+
+/// Images are attached as data URLs by the TUI; anything else is passed by
+/// reference.
+fn image_block(image_url: &str) -> Value {
+    if let Some(rest) = image_url.strip_prefix("data:")
+        && let Some((media_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data},
+        });
+    }
+    json!({
+        "type": "image",
+        "source": {"type": "url", "url": image_url},
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum MessagesStreamEvent {
+    MessageStart {
+        message: MessageStart,
+    },
+    ContentBlockStart {
+        index: u32,
+        content_block: ContentBlock,
+    },
+    ContentBlockDelta {
+        index: u32,
+        delta: ContentBlockDelta,
+    },
+    ContentBlockStop {
+        index: u32,
+    },
+    MessageDelta {
+        #[serde(default)]
+        usage: Option<MessagesUsage>,
+    },
+    MessageStop,
+    Ping,
+    Error {
+        error: MessagesError,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageStart {
+    id: String,
+    #[serde(default)]
+    usage: Option<MessagesUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        #[serde(default)]
+        text: String,
+    },
+    Thinking {
+        #[serde(default)]
+        thinking: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlockDelta {
+    TextDelta {
+        text: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MessagesUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesError {
+    #[serde(default)]
+    r#type: Option<String>,
+    message: String,
+}
+
+/// A content block that is still being streamed.
+enum BlockState {
+    Text(String),
+    Thinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        arguments: String,
+    },
+}
+
+impl BlockState {
+    fn into_item(self) -> Option<ResponseItem> {
+        match self {
+            BlockState::Text(text) if !text.is_empty() => Some(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            BlockState::Thinking(text) if !text.is_empty() => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText { text }]),
+                encrypted_content: None,
+            }),
+            BlockState::ToolUse {
+                id,
+                name,
+                arguments,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if arguments.is_empty() {
+                    "{}".to_string()
+                } else {
+                    arguments
+                },
+                call_id: id,
+            }),
+            BlockState::Text(_) | BlockState::Thinking(_) => None,
+        }
+    }
+}
+
+/// SSE processor for the Messages API streaming format.
+async fn process_messages_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut response_id = String::new();
+    let mut usage = MessagesUsage::default();
+    let mut blocks: HashMap<u32, BlockState> = HashMap::new();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        trace!("messages received SSE event: {}", sse.data);
+        let event: MessagesStreamEvent = match serde_json::from_str(&sse.data) {
+            Ok(event) => event,
+            Err(e) => {
+                debug!(
+                    "Failed to parse Messages SSE event: {e}, data: {}",
+                    &sse.data
+                );
+                continue;
+            }
+        };
+
+        let response_event = match event {
+            MessagesStreamEvent::MessageStart { message } => {
+                response_id = message.id;
+                if let Some(start_usage) = message.usage {
+                    usage = start_usage;
+                }
+                Some(ResponseEvent::Created)
+            }
+            MessagesStreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => {
+                // Text and thinking blocks may start with a non-empty prefix.
+                let (state, delta) = match content_block {
+                    ContentBlock::Text { text } => (
+                        BlockState::Text(text.clone()),
+                        (!text.is_empty()).then_some(ResponseEvent::OutputTextDelta(text)),
+                    ),
+                    ContentBlock::Thinking { thinking } => (
+                        BlockState::Thinking(thinking.clone()),
+                        (!thinking.is_empty())
+                            .then_some(ResponseEvent::ReasoningContentDelta(thinking)),
+                    ),
+                    ContentBlock::ToolUse { id, name } => (
+                        BlockState::ToolUse {
+                            id,
+                            name,
+                            arguments: String::new(),
+                        },
+                        None,
+                    ),
+                    ContentBlock::Unsupported => continue,
+                };
+                blocks.insert(index, state);
+                delta
+            }
+            MessagesStreamEvent::ContentBlockDelta { index, delta } => {
+                match (blocks.get_mut(&index), delta) {
+                    (Some(BlockState::Text(text)), ContentBlockDelta::TextDelta { text: d }) => {
+                        text.push_str(&d);
+                        Some(ResponseEvent::OutputTextDelta(d))
+                    }
+                    (
+                        Some(BlockState::Thinking(text)),
+                        ContentBlockDelta::ThinkingDelta { thinking: d },
+                    ) => {
+                        text.push_str(&d);
+                        Some(ResponseEvent::ReasoningContentDelta(d))
+                    }
+                    (
+                        Some(BlockState::ToolUse { arguments, .. }),
+                        ContentBlockDelta::InputJsonDelta { partial_json },
+                    ) => {
+                        arguments.push_str(&partial_json);
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            MessagesStreamEvent::ContentBlockStop { index } => blocks
+                .remove(&index)
+                .and_then(BlockState::into_item)
+                .map(ResponseEvent::OutputItemDone),
+            MessagesStreamEvent::MessageDelta { usage: delta_usage } => {
+                if let Some(delta_usage) = delta_usage {
+                    usage.output_tokens = delta_usage.output_tokens;
+                }
+                None
+            }
+            MessagesStreamEvent::MessageStop => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id: std::mem::take(&mut response_id),
+                        token_usage: Some(std::mem::take(&mut usage).into()),
+                    }))
+                    .await;
+                return;
+            }
+            MessagesStreamEvent::Ping => None,
+            MessagesStreamEvent::Error { error } => {
+                let message = match error.r#type {
+                    Some(kind) => format!("{kind}: {}", error.message),
+                    None => error.message,
+                };
+                let _ = tx_event.send(Err(CodexErr::Stream(message, None))).await;
+                return;
+            }
+        };
+
+        if let Some(response_event) = response_event
+            && tx_event.send(Ok(response_event)).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+impl From<MessagesUsage> for TokenUsage {
+    fn from(val: MessagesUsage) -> Self {
+        // `input_tokens` excludes cache reads and writes in the Messages API,
+        // whereas `TokenUsage::input_tokens` counts every prompt token.
+        let input_tokens =
+            val.input_tokens + val.cache_creation_input_tokens + val.cache_read_input_tokens;
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: val.cache_read_input_tokens,
+            output_tokens: val.output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + val.output_tokens,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icodex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    async fn run_sse(events: Vec<Value>) -> Vec<Result<ResponseEvent>> {
+        let mut body = String::new();
+        for event in events {
+            let kind = event["type"].as_str().unwrap_or_default().to_string();
+            body.push_str(&format!("event: {kind}\ndata: {event}\n\n"));
+        }
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(32);
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        process_messages_sse(stream, tx, Duration::from_secs(1)).await;
+
+        let mut out = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            out.push(ev);
+        }
+        out
+    }
+
+    #[test]
+    fn translates_tool_calls_and_merges_roles() {
+        let input = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "list files".to_string(),
+                }],
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "toolu_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "toolu_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "README.md".to_string(),
+                    success: Some(true),
+                },
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputImage {
+                    image_url: "data:image/png;base64,AAAA".to_string(),
+                }],
+            },
+        ];
+
+        assert_eq!(
+            build_messages(&input),
+            vec![
+                json!({"role": "user", "content": [{"type": "text", "text": "list files"}]}),
+                json!({"role": "assistant", "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "shell",
+                    "input": {"command": ["ls"]},
+                }]}),
+                json!({"role": "user", "content": [
+                    {
+                        "type": "tool_result",
+                        "tool_use_id": "toolu_1",
+                        "content": "README.md",
+                        "is_error": false,
+                    },
+                    {
+                        "type": "image",
+                        "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"},
+                    },
+                ]}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_stream_onto_response_events() {
+        let events = run_sse(vec![
+            json!({"type": "message_start", "message": {
+                "id": "msg_1",
+                "usage": {"input_tokens": 10, "cache_read_input_tokens": 90, "output_tokens": 1},
+            }}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "hmm"}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": "Hi"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "content_block_start", "index": 2, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "shell", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "{\"command\":"}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "[\"ls\"]}"}}),
+            json!({"type": "content_block_stop", "index": 2}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 20}}),
+            json!({"type": "message_stop"}),
+        ])
+        .await;
+
+        assert_eq!(events.len(), 7);
+        assert!(matches!(events[0], Ok(ResponseEvent::Created)));
+        assert!(matches!(&events[1], Ok(ResponseEvent::ReasoningContentDelta(d)) if d == "hmm"));
+        assert!(matches!(
+            &events[2],
+            Ok(ResponseEvent::OutputItemDone(
+                ResponseItem::Reasoning { .. }
+            ))
+        ));
+        assert!(matches!(&events[3], Ok(ResponseEvent::OutputTextDelta(d)) if d == "Hi"));
+        assert!(matches!(
+            &events[4],
+            Ok(ResponseEvent::OutputItemDone(ResponseItem::Message { .. }))
+        ));
+        match &events[5] {
+            Ok(ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            })) => {
+                assert_eq!(name, "shell");
+                assert_eq!(arguments, r#"{"command":["ls"]}"#);
+                assert_eq!(call_id, "toolu_1");
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        match &events[6] {
+            Ok(ResponseEvent::Completed {
+                response_id,
+                token_usage: Some(usage),
+            }) => {
+                assert_eq!(response_id, "msg_1");
+                assert_eq!(usage.input_tokens, 100);
+                assert_eq!(usage.cached_input_tokens, 90);
+                assert_eq!(usage.output_tokens, 20);
+                assert_eq!(usage.total_tokens, 120);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn error_event_surfaces_stream_error() {
+        let events = run_sse(vec![
+            json!({"type": "message_start", "message": {"id": "msg_1"}}),
+            json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}),
+        ])
+        .await;
+
+        match events.last() {
+            Some(Err(CodexErr::Stream(msg, None))) => {
+                assert_eq!(msg, "overloaded_error: Overloaded")
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_ending_without_message_stop_is_an_error() {
+        let events = run_sse(vec![
+            json!({"type": "message_start", "message": {"id": "msg_1"}}),
+        ])
+        .await;
+
+        assert!(matches!(events.last(), Some(Err(CodexErr::Stream(..)))));
+    }
+}
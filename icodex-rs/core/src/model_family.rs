@@ -39,6 +39,10 @@ pub struct ModelFamily {
     /// a tool call instead of just a bash command
     pub apply_patch_tool_type: Option<ApplyPatchToolType>,
 
+    /// True if the model can return several tool calls in one response, in
+    /// which case `parallel_tool_calls` is requested.
+    pub supports_parallel_tool_calls: bool,
+
     // Instructions to use for querying the model
     pub base_instructions: String,
 }
@@ -56,6 +60,7 @@ macro_rules! model_family {
             reasoning_summary_format: ReasoningSummaryFormat::None,
             uses_local_shell_tool: false,
             apply_patch_tool_type: None,
+            supports_parallel_tool_calls: false,
             base_instructions: BASE_INSTRUCTIONS.to_string(),
         };
         // apply overrides
@@ -74,12 +79,14 @@ pub fn find_family_for_model(slug: &str) -> Option<ModelFamily> {
             slug, "o3",
             supports_reasoning_summaries: true,
             needs_special_apply_patch_instructions: true,
+            supports_parallel_tool_calls: true,
         )
     } else if slug.starts_with("o4-mini") {
         model_family!(
             slug, "o4-mini",
             supports_reasoning_summaries: true,
             needs_special_apply_patch_instructions: true,
+            supports_parallel_tool_calls: true,
         )
     } else if slug.starts_with("icodex-mini-latest") {
         model_family!(
@@ -92,11 +99,16 @@ pub fn find_family_for_model(slug: &str) -> Option<ModelFamily> {
         model_family!(
             slug, "gpt-4.1",
             needs_special_apply_patch_instructions: true,
+            supports_parallel_tool_calls: true,
         )
     } else if slug.starts_with("gpt-oss") || slug.starts_with("openai/gpt-oss") {
         model_family!(slug, "gpt-oss", apply_patch_tool_type: Some(ApplyPatchToolType::Function))
     } else if slug.starts_with("gpt-4o") {
-        model_family!(slug, "gpt-4o", needs_special_apply_patch_instructions: true)
+        model_family!(
+            slug, "gpt-4o",
+            needs_special_apply_patch_instructions: true,
+            supports_parallel_tool_calls: true,
+        )
     } else if slug.starts_with("gpt-3.5") {
         model_family!(slug, "gpt-3.5", needs_special_apply_patch_instructions: true)
     } else if slug.starts_with("icodex-") || slug.starts_with("gpt-5-icodex") {
@@ -104,6 +116,7 @@ pub fn find_family_for_model(slug: &str) -> Option<ModelFamily> {
             slug, slug,
             supports_reasoning_summaries: true,
             reasoning_summary_format: ReasoningSummaryFormat::Experimental,
+            supports_parallel_tool_calls: true,
             base_instructions: GPT_5_CODEX_INSTRUCTIONS.to_string(),
         )
     } else if slug.starts_with("gpt-5") {
@@ -111,6 +124,7 @@ pub fn find_family_for_model(slug: &str) -> Option<ModelFamily> {
             slug, "gpt-5",
             supports_reasoning_summaries: true,
             needs_special_apply_patch_instructions: true,
+            supports_parallel_tool_calls: true,
         )
     } else {
         None
@@ -126,6 +140,7 @@ pub fn derive_default_model_family(model: &str) -> ModelFamily {
         reasoning_summary_format: ReasoningSummaryFormat::None,
         uses_local_shell_tool: false,
         apply_patch_tool_type: None,
+        supports_parallel_tool_calls: false,
         base_instructions: BASE_INSTRUCTIONS.to_string(),
     }
 }
@@ -15,6 +15,7 @@ use std::time::Duration;
 
 use crate::error::EnvVarError;
 const DEFAULT_STREAM_IDLE_TIMEOUT_MS: u64 = 300_000;
+const DEFAULT_GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
 const DEFAULT_STREAM_MAX_RETRIES: u64 = 5;
 const DEFAULT_REQUEST_MAX_RETRIES: u64 = 4;
 /// Hard cap for user-configured `stream_max_retries`.
@@ -41,6 +42,12 @@ pub enum WireApi {
     /// Large Models Interface bridge - communicates with Node.js bridge service
     /// to support 51+ model providers through the large-models-interface package.
     LmiBridge,
+
+    /// The Messages API exposed by Claude-style providers at `/v1/messages`.
+    Messages,
+
+    /// The Gemini API's `models/{model}:streamGenerateContent` endpoint.
+    Gemini,
 }
 
 /// Serializable representation of a provider definition.
@@ -96,7 +103,8 @@ impl ModelProviderInfo {
     /// Construct a `POST` RequestBuilder for the given URL using the provided
     /// reqwest Client applying:
     ///   • provider-specific headers (static + env based)
-    ///   • Bearer auth header when an API key is available.
+    ///   • Bearer auth header when an API key is available (`x-api-key` for
+    ///     the Messages API, `x-goog-api-key` for the Gemini API).
     ///   • Auth token for OAuth.
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
@@ -105,6 +113,27 @@ impl ModelProviderInfo {
         &'a self,
         client: &'a reqwest::Client,
         auth: &Option<CodexAuth>,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        self.create_request_builder_with_url(client, auth, |auth| self.get_full_url(auth))
+            .await
+    }
+
+    /// Like [`ModelProviderInfo::create_request_builder`], for the Gemini API
+    /// whose endpoint carries the model name in its path.
+    pub(crate) async fn create_gemini_request_builder(
+        &self,
+        client: &reqwest::Client,
+        model: &str,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        self.create_request_builder_with_url(client, &None, |_| self.get_gemini_stream_url(model))
+            .await
+    }
+
+    async fn create_request_builder_with_url(
+        &self,
+        client: &reqwest::Client,
+        auth: &Option<CodexAuth>,
+        url: impl FnOnce(&Option<CodexAuth>) -> String,
     ) -> crate::error::Result<reqwest::RequestBuilder> {
         let effective_auth = match self.api_key() {
             Ok(Some(key)) => Some(CodexAuth::from_api_key(&key)),
@@ -118,12 +147,15 @@ impl ModelProviderInfo {
             }
         };
 
-        let url = self.get_full_url(&effective_auth);
-
-        let mut builder = client.post(url);
+        let mut builder = client.post(url(&effective_auth));
 
         if let Some(auth) = effective_auth.as_ref() {
-            builder = builder.bearer_auth(auth.get_token().await?);
+            let token = auth.get_token().await?;
+            builder = match self.wire_api {
+                WireApi::Messages => builder.header("x-api-key", token),
+                WireApi::Gemini => builder.header("x-goog-api-key", token),
+                _ => builder.bearer_auth(token),
+            };
         }
 
         Ok(self.apply_http_headers(builder))
@@ -163,6 +195,9 @@ impl ModelProviderInfo {
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::Messages => format!("{base_url}/messages{query_string}"),
+            // The model is part of the path, see `get_gemini_stream_url`.
+            WireApi::Gemini => format!("{base_url}/models{query_string}"),
             WireApi::LmiBridge => {
                 // For LMI bridge, we use a special URL that indicates this should
                 // be handled by the Node.js bridge service
@@ -171,6 +206,23 @@ impl ModelProviderInfo {
         }
     }
 
+    /// URL of the streaming `generateContent` endpoint for `model`. Server-sent
+    /// events are requested with `alt=sse`; configured `query_params` follow.
+    pub(crate) fn get_gemini_stream_url(&self, model: &str) -> String {
+        let base_url = self
+            .base_url
+            .as_deref()
+            .unwrap_or(DEFAULT_GEMINI_BASE_URL)
+            .trim_end_matches('/');
+        let mut query = String::from("alt=sse");
+        if let Some(params) = &self.query_params {
+            for (k, v) in params {
+                query.push_str(&format!("&{k}={v}"));
+            }
+        }
+        format!("{base_url}/models/{model}:streamGenerateContent?{query}")
+    }
+
     pub(crate) fn is_azure_responses_endpoint(&self) -> bool {
         if self.wire_api != WireApi::Responses {
             return false;
@@ -256,6 +308,17 @@ impl ModelProviderInfo {
     }
 }
 
+/// Provider/model pair the client fails over to when the provider in use
+/// keeps returning rate-limit or server errors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallbackProvider {
+    /// Key into the `model_providers` map.
+    pub provider_id: String,
+    pub provider: ModelProviderInfo,
+    /// Model to request from this provider; `None` keeps the session's model.
+    pub model: Option<String>,
+}
+
 const DEFAULT_OLLAMA_PORT: u32 = 11434;
 
 pub const BUILT_IN_OSS_MODEL_PROVIDER_ID: &str = "oss";
@@ -520,4 +583,36 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             );
         }
     }
+
+    #[tokio::test]
+    async fn messages_api_sends_key_in_x_api_key_header() {
+        let provider: ModelProviderInfo = toml::from_str(
+            r#"
+name = "Claude"
+base_url = "https://api.example.com/v1"
+wire_api = "messages"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(provider.wire_api, WireApi::Messages);
+
+        let auth = Some(CodexAuth::from_api_key("sk-test"));
+        let request = provider
+            .create_request_builder(&reqwest::Client::new(), &auth)
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            request.url().as_str(),
+            "https://api.example.com/v1/messages"
+        );
+        assert_eq!(request.headers()["x-api-key"], "sk-test");
+        assert!(
+            !request
+                .headers()
+                .contains_key(reqwest::header::AUTHORIZATION)
+        );
+    }
 }
@@ -0,0 +1,583 @@
+//! Model capabilities that users define or override with `[models."<slug>"]`
+//! tables, layered over the built-in data in [`crate::model_family`] and
+//! `openai_model_info`.
+//!
+//! Tables are read from `CODEX_HOME/models.d/*.toml` (in file name order) and
+//! then from `config.toml`; later definitions win field by field.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::config_types::ModelConfigToml;
+use crate::config_types::ReasoningSummaryFormat;
+use crate::model_family::ModelFamily;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
+use crate::openai_model_info::get_model_info;
+use crate::tool_apply_patch::ApplyPatchToolType;
+
+/// Directory under `CODEX_HOME` with additional `[models."<slug>"]` tables.
+pub const MODELS_DIR: &str = "models.d";
+
+/// Where a resolved model value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelValueSource {
+    /// Generic default for a model Codex has no built-in data for.
+    Default,
+    /// Data shipped with Codex for a known model.
+    BuiltIn,
+    /// A `[models."<slug>"]` table, or a top-level `model_*` key, in this file.
+    File(PathBuf),
+}
+
+impl fmt::Display for ModelValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelValueSource::Default => f.write_str("default"),
+            ModelValueSource::BuiltIn => f.write_str("built-in"),
+            ModelValueSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Capabilities of a model after applying every layer of configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedModel {
+    pub family: ModelFamily,
+    pub context_window: Option<u64>,
+    pub max_output_tokens: Option<u64>,
+    pub auto_compact_token_limit: Option<i64>,
+    /// Source of each value, keyed by its field name in `[models."<slug>"]`.
+    pub sources: BTreeMap<&'static str, ModelValueSource>,
+}
+
+impl ResolvedModel {
+    /// `(field, value, source)` rows describing the model, e.g. for `/status`.
+    pub fn describe(&self) -> Vec<(&'static str, String, ModelValueSource)> {
+        let family = &self.family;
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "unknown".to_string());
+        let rows = [
+            ("family", family.family.clone()),
+            (
+                "context_window",
+                optional(self.context_window.map(|v| v.to_string())),
+            ),
+            (
+                "max_output_tokens",
+                optional(self.max_output_tokens.map(|v| v.to_string())),
+            ),
+            (
+                "auto_compact_token_limit",
+                optional(self.auto_compact_token_limit.map(|v| v.to_string())),
+            ),
+            (
+                "supports_reasoning_summaries",
+                family.supports_reasoning_summaries.to_string(),
+            ),
+            (
+                "reasoning_summary_format",
+                match family.reasoning_summary_format {
+                    ReasoningSummaryFormat::None => "none",
+                    ReasoningSummaryFormat::Experimental => "experimental",
+                }
+                .to_string(),
+            ),
+            (
+                "needs_special_apply_patch_instructions",
+                family.needs_special_apply_patch_instructions.to_string(),
+            ),
+            (
+                "uses_local_shell_tool",
+                family.uses_local_shell_tool.to_string(),
+            ),
+            (
+                "apply_patch_tool_type",
+                match family.apply_patch_tool_type {
+                    Some(ApplyPatchToolType::Freeform) => "freeform",
+                    Some(ApplyPatchToolType::Function) => "function",
+                    None => "none",
+                }
+                .to_string(),
+            ),
+            (
+                "supports_parallel_tool_calls",
+                family.supports_parallel_tool_calls.to_string(),
+            ),
+            (
+                "base_instructions_file",
+                format!("{} bytes", family.base_instructions.len()),
+            ),
+        ];
+        rows.into_iter()
+            .map(|(field, value)| {
+                let source = self
+                    .sources
+                    .get(field)
+                    .cloned()
+                    .unwrap_or(ModelValueSource::Default);
+                (field, value, source)
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelsFile {
+    #[serde(default)]
+    models: HashMap<String, ModelConfigToml>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ModelEntry {
+    config: ModelConfigToml,
+    /// Contents of `config.base_instructions_file`, read at load time.
+    base_instructions: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ModelLayer {
+    source: PathBuf,
+    models: HashMap<String, ModelEntry>,
+}
+
+/// User-defined model capabilities, consulted whenever a model slug is turned
+/// into a [`ModelFamily`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModelRegistry {
+    /// Lowest precedence first.
+    layers: Vec<ModelLayer>,
+}
+
+impl ModelRegistry {
+    /// Loads `models.d/*.toml` under `icodex_home`, then the tables from
+    /// `config.toml`, and validates every model they define.
+    pub(crate) fn load(
+        icodex_home: &Path,
+        config_toml_path: &Path,
+        config_models: HashMap<String, ModelConfigToml>,
+    ) -> io::Result<Self> {
+        let mut layers = Vec::new();
+
+        let models_dir = icodex_home.join(MODELS_DIR);
+        let mut files = match std::fs::read_dir(&models_dir) {
+            Ok(entries) => entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<Vec<_>>>()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        files.retain(|path| path.extension().is_some_and(|ext| ext == "toml"));
+        files.sort();
+        for path in files {
+            let contents = std::fs::read_to_string(&path)?;
+            let file: ModelsFile = toml::from_str(&contents).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("failed to parse {}: {e}", path.display()),
+                )
+            })?;
+            layers.push(ModelLayer::load(path, file.models)?);
+        }
+        layers.push(ModelLayer::load(
+            config_toml_path.to_path_buf(),
+            config_models,
+        )?);
+        layers.retain(|layer| !layer.models.is_empty());
+
+        let registry = Self { layers };
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Adds a highest-precedence layer for `slug` without validating it; used
+    /// for the older top-level `model_*` keys, which predate validation.
+    pub(crate) fn with_overrides(
+        mut self,
+        source: PathBuf,
+        slug: &str,
+        config: ModelConfigToml,
+    ) -> Self {
+        if config != ModelConfigToml::default() {
+            let entry = ModelEntry {
+                config,
+                base_instructions: None,
+            };
+            self.layers.push(ModelLayer {
+                source,
+                models: HashMap::from([(slug.to_string(), entry)]),
+            });
+        }
+        self
+    }
+
+    /// Every slug defined in `models.d` or `[models]`, sorted and deduplicated.
+    pub fn slugs(&self) -> Vec<String> {
+        let mut slugs: Vec<String> = self
+            .layers
+            .iter()
+            .flat_map(|layer| layer.models.keys().cloned())
+            .collect();
+        slugs.sort();
+        slugs.dedup();
+        slugs
+    }
+
+    /// Resolves the capabilities of `slug` from the built-in tables and every
+    /// layer that defines it.
+    pub fn resolve(&self, slug: &str) -> ResolvedModel {
+        let entries: Vec<(&Path, &ModelEntry)> = self
+            .layers
+            .iter()
+            .filter_map(|layer| {
+                layer
+                    .models
+                    .get(slug)
+                    .map(|entry| (layer.source.as_path(), entry))
+            })
+            .collect();
+        let family_override = entries
+            .iter()
+            .rev()
+            .find_map(|(_, entry)| entry.config.family.as_deref());
+
+        // Look up built-in data under the family name when one is given so
+        // that e.g. a proxy slug can inherit everything from "gpt-5".
+        let lookup_slug = family_override.unwrap_or(slug);
+        let builtin = find_family_for_model(lookup_slug);
+        let family_source = if builtin.is_some() {
+            ModelValueSource::BuiltIn
+        } else {
+            ModelValueSource::Default
+        };
+        let lookup_family = builtin.unwrap_or_else(|| derive_default_model_family(lookup_slug));
+        let info = get_model_info(&lookup_family);
+        let info_source = if info.is_some() {
+            ModelValueSource::BuiltIn
+        } else {
+            ModelValueSource::Default
+        };
+
+        let mut family = ModelFamily {
+            slug: slug.to_string(),
+            ..lookup_family
+        };
+        let mut context_window = info.as_ref().map(|info| info.context_window);
+        let mut max_output_tokens = info.as_ref().map(|info| info.max_output_tokens);
+        let mut auto_compact_token_limit =
+            info.as_ref().and_then(|info| info.auto_compact_token_limit);
+
+        let mut sources = BTreeMap::new();
+        for field in [
+            "family",
+            "supports_reasoning_summaries",
+            "reasoning_summary_format",
+            "needs_special_apply_patch_instructions",
+            "uses_local_shell_tool",
+            "apply_patch_tool_type",
+            "supports_parallel_tool_calls",
+            "base_instructions_file",
+        ] {
+            sources.insert(field, family_source.clone());
+        }
+        for field in [
+            "context_window",
+            "max_output_tokens",
+            "auto_compact_token_limit",
+        ] {
+            sources.insert(field, info_source.clone());
+        }
+
+        for (path, entry) in entries {
+            let source = ModelValueSource::File(path.to_path_buf());
+            let config = &entry.config;
+            macro_rules! apply {
+                ($field:ident, $value:ident => $assign:expr) => {
+                    if let Some($value) = config.$field.clone() {
+                        $assign;
+                        sources.insert(stringify!($field), source.clone());
+                    }
+                };
+            }
+            apply!(family, value => family.family = value);
+            apply!(context_window, value => context_window = Some(value));
+            apply!(max_output_tokens, value => max_output_tokens = Some(value));
+            apply!(auto_compact_token_limit, value => auto_compact_token_limit = Some(value));
+            apply!(
+                supports_reasoning_summaries,
+                value => family.supports_reasoning_summaries = value
+            );
+            apply!(reasoning_summary_format, value => family.reasoning_summary_format = value);
+            apply!(
+                needs_special_apply_patch_instructions,
+                value => family.needs_special_apply_patch_instructions = value
+            );
+            apply!(uses_local_shell_tool, value => family.uses_local_shell_tool = value);
+            apply!(apply_patch_tool_type, value => family.apply_patch_tool_type = Some(value));
+            apply!(
+                supports_parallel_tool_calls,
+                value => family.supports_parallel_tool_calls = value
+            );
+            if let Some(base_instructions) = &entry.base_instructions {
+                family.base_instructions = base_instructions.clone();
+                sources.insert("base_instructions_file", source.clone());
+            }
+        }
+
+        ResolvedModel {
+            family,
+            context_window,
+            max_output_tokens,
+            auto_compact_token_limit,
+            sources,
+        }
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        for layer in &self.layers {
+            for (slug, entry) in &layer.models {
+                let invalid = |message: String| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "invalid [models.\"{slug}\"] in {}: {message}",
+                            layer.source.display()
+                        ),
+                    )
+                };
+                if let Some(family) = &entry.config.family
+                    && find_family_for_model(family).is_none()
+                {
+                    return Err(invalid(format!("unknown model family `{family}`")));
+                }
+
+                let model = self.resolve(slug);
+                if model.context_window == Some(0) {
+                    return Err(invalid("context_window must be greater than 0".to_string()));
+                }
+                if model.max_output_tokens == Some(0) {
+                    return Err(invalid(
+                        "max_output_tokens must be greater than 0".to_string(),
+                    ));
+                }
+                if let Some(limit) = model.auto_compact_token_limit
+                    && limit <= 0
+                {
+                    return Err(invalid(
+                        "auto_compact_token_limit must be greater than 0".to_string(),
+                    ));
+                }
+                if let Some(context_window) = model.context_window {
+                    if let Some(max_output_tokens) = model.max_output_tokens
+                        && max_output_tokens > context_window
+                    {
+                        return Err(invalid(format!(
+                            "max_output_tokens ({max_output_tokens}) exceeds context_window ({context_window})"
+                        )));
+                    }
+                    if let Some(limit) = model.auto_compact_token_limit
+                        && limit as u64 > context_window
+                    {
+                        return Err(invalid(format!(
+                            "auto_compact_token_limit ({limit}) exceeds context_window ({context_window})"
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ModelLayer {
+    fn load(source: PathBuf, models: HashMap<String, ModelConfigToml>) -> io::Result<Self> {
+        let base_dir = source.parent().map(Path::to_path_buf).unwrap_or_default();
+        let models = models
+            .into_iter()
+            .map(|(slug, config)| {
+                let base_instructions = match &config.base_instructions_file {
+                    Some(path) => Some(read_base_instructions(&base_dir.join(path), &slug)?),
+                    None => None,
+                };
+                Ok((
+                    slug,
+                    ModelEntry {
+                        config,
+                        base_instructions,
+                    },
+                ))
+            })
+            .collect::<io::Result<HashMap<_, _>>>()?;
+        Ok(Self { source, models })
+    }
+}
+
+fn read_base_instructions(path: &Path, slug: &str) -> io::Result<String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!(
+                "failed to read base_instructions_file for model `{slug}` at {}: {e}",
+                path.display()
+            ),
+        )
+    })?;
+    if contents.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "base_instructions_file for model `{slug}` is empty: {}",
+                path.display()
+            ),
+        ));
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn models(toml: &str) -> HashMap<String, ModelConfigToml> {
+        #[derive(Deserialize)]
+        struct Root {
+            models: HashMap<String, ModelConfigToml>,
+        }
+        toml::from_str::<Root>(toml)
+            .expect("valid models table")
+            .models
+    }
+
+    #[test]
+    fn unknown_models_fall_back_to_defaults() {
+        let model = ModelRegistry::default().resolve("my-local-model");
+        assert_eq!(model.family, derive_default_model_family("my-local-model"));
+        assert_eq!(model.context_window, None);
+        assert_eq!(
+            model.sources.get("context_window"),
+            Some(&ModelValueSource::Default)
+        );
+    }
+
+    #[test]
+    fn config_toml_overrides_models_dir_field_by_field() -> io::Result<()> {
+        let home = TempDir::new()?;
+        let models_dir = home.path().join(MODELS_DIR);
+        std::fs::create_dir(&models_dir)?;
+        std::fs::write(models_dir.join("ignored.txt"), "not toml")?;
+        std::fs::write(
+            models_dir.join("proxy.toml"),
+            r#"
+[models."proxy-gpt5"]
+family = "gpt-5"
+context_window = 200000
+base_instructions_file = "proxy.md"
+"#,
+        )?;
+        std::fs::write(models_dir.join("proxy.md"), "Be brief.")?;
+        let config_toml = home.path().join("config.toml");
+
+        let registry = ModelRegistry::load(
+            home.path(),
+            &config_toml,
+            models(
+                r#"
+[models."proxy-gpt5"]
+max_output_tokens = 64000
+apply_patch_tool_type = "function"
+"#,
+            ),
+        )?;
+        let model = registry.resolve("proxy-gpt5");
+
+        assert_eq!(model.family.slug, "proxy-gpt5");
+        assert_eq!(model.family.family, "gpt-5");
+        assert!(model.family.supports_reasoning_summaries);
+        assert_eq!(model.family.base_instructions, "Be brief.");
+        assert_eq!(
+            model.family.apply_patch_tool_type,
+            Some(ApplyPatchToolType::Function)
+        );
+        assert_eq!(model.context_window, Some(200_000));
+        assert_eq!(model.max_output_tokens, Some(64_000));
+
+        let proxy_file = ModelValueSource::File(models_dir.join("proxy.toml"));
+        let config_file = ModelValueSource::File(config_toml);
+        assert_eq!(model.sources["context_window"], proxy_file);
+        assert_eq!(model.sources["base_instructions_file"], proxy_file);
+        assert_eq!(model.sources["max_output_tokens"], config_file);
+        assert_eq!(model.sources["apply_patch_tool_type"], config_file);
+        assert_eq!(
+            model.sources["supports_reasoning_summaries"],
+            ModelValueSource::BuiltIn
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn load_rejects_inconsistent_limits() -> io::Result<()> {
+        let home = TempDir::new()?;
+        let config_toml = home.path().join("config.toml");
+        let err = ModelRegistry::load(
+            home.path(),
+            &config_toml,
+            models(
+                r#"
+[models."tiny"]
+context_window = 8000
+auto_compact_token_limit = 9000
+"#,
+            ),
+        )
+        .expect_err("limit above the context window should be rejected");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("auto_compact_token_limit"));
+
+        let err = ModelRegistry::load(
+            home.path(),
+            &config_toml,
+            models(
+                r#"
+[models."tiny"]
+family = "not-a-family"
+"#,
+            ),
+        )
+        .expect_err("unknown family should be rejected");
+        assert!(err.to_string().contains("not-a-family"));
+        Ok(())
+    }
+
+    #[test]
+    fn load_rejects_unknown_fields_and_missing_instructions() -> io::Result<()> {
+        let home = TempDir::new()?;
+        let models_dir = home.path().join(MODELS_DIR);
+        std::fs::create_dir(&models_dir)?;
+        std::fs::write(
+            models_dir.join("typo.toml"),
+            "[models.\"m\"]\ncontext_windw = 1000\n",
+        )?;
+        let config_toml = home.path().join("config.toml");
+        let err = ModelRegistry::load(home.path(), &config_toml, HashMap::new())
+            .expect_err("unknown field should be rejected");
+        assert!(err.to_string().contains("typo.toml"));
+
+        std::fs::remove_file(models_dir.join("typo.toml"))?;
+        let err = ModelRegistry::load(
+            home.path(),
+            &config_toml,
+            models("[models.\"m\"]\nbase_instructions_file = \"missing.md\"\n"),
+        )
+        .expect_err("missing instructions file should be rejected");
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+//! HTTP proxy that lets sandboxed commands reach an allowlist of hosts.
+//!
+//! When `sandbox_workspace_write.network_allowlist` is set and the sandbox
+//! policy otherwise blocks the network, the session listens on a Unix socket
+//! for HTTP `CONNECT` tunnels and plain `http://` requests. The Linux sandbox
+//! helper runs each command in a network namespace of its own whose only way
+//! out is that socket: it forwards `127.0.0.1:SANDBOX_PROXY_PORT` inside the
+//! namespace to it, and [`apply_proxy_env`] points `HTTP(S)_PROXY` there.
+//!
+//! Connections to hosts that match no pattern are refused with
+//! `403 Forbidden`, and the host is reported so the user can allow it.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+#[cfg(unix)]
+use std::sync::Arc;
+
+#[cfg(unix)]
+use reqwest::Url;
+use tempfile::TempDir;
+#[cfg(unix)]
+use tokio::io::AsyncReadExt;
+#[cfg(unix)]
+use tokio::io::AsyncWriteExt;
+#[cfg(unix)]
+use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+#[cfg(unix)]
+use tracing::debug;
+#[cfg(unix)]
+use tracing::warn;
+use wildmatch::WildMatchPattern;
+
+/// Carries the proxy socket from [`crate::exec_env::create_env`] to the
+/// Linux sandbox helper, which takes it out of the command's environment.
+pub const CODEX_NETWORK_PROXY_SOCKET_ENV_VAR: &str = "CODEX_NETWORK_PROXY_SOCKET";
+
+/// Port the Linux sandbox helper listens on inside the command's network
+/// namespace. Nothing else runs in that namespace, so it is always free.
+pub const SANDBOX_PROXY_PORT: u16 = 3128;
+
+/// Variables pointing HTTP clients at the proxy.
+const PROXY_ENV_VARS: &[&str] = &["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"];
+
+/// Largest request head (request line plus headers) the proxy reads.
+#[cfg(unix)]
+const MAX_REQUEST_HEAD_BYTES: usize = 16 * 1024;
+
+type HostPattern = WildMatchPattern<'*', '?'>;
+
+/// Hosts the proxy lets through.
+#[derive(Debug, Clone)]
+pub struct NetworkAllowlist {
+    patterns: Vec<HostPattern>,
+}
+
+impl NetworkAllowlist {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns
+                .iter()
+                .map(|p| HostPattern::new(&p.to_ascii_lowercase()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn allows(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.patterns.iter().any(|p| p.matches(&host))
+    }
+}
+
+/// The proxy of one session. Dropping it stops the proxy and removes its
+/// socket.
+pub struct NetworkProxy {
+    /// Private (0700) directory holding the socket, so that other local
+    /// users cannot connect to it. Removed with the proxy.
+    _socket_dir: TempDir,
+    socket_path: PathBuf,
+    task: JoinHandle<()>,
+}
+
+impl NetworkProxy {
+    /// Start listening on a fresh socket in a private directory under the
+    /// temp directory. Hosts the proxy refuses are sent on the returned
+    /// receiver.
+    #[cfg(unix)]
+    pub fn start(
+        allowlist: NetworkAllowlist,
+    ) -> io::Result<(Self, mpsc::UnboundedReceiver<String>)> {
+        let socket_dir = tempfile::Builder::new().prefix("icodex-proxy-").tempdir()?;
+        let socket_path = socket_dir.path().join("proxy.sock");
+        let listener = UnixListener::bind(&socket_path)?;
+        let (denied_tx, denied_rx) = mpsc::unbounded_channel();
+        let allowlist = Arc::new(allowlist);
+        let task = tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("network proxy failed to accept a connection: {e}");
+                        continue;
+                    }
+                };
+                let allowlist = Arc::clone(&allowlist);
+                let denied_tx = denied_tx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &allowlist, &denied_tx).await {
+                        debug!("network proxy connection ended: {e}");
+                    }
+                });
+            }
+        });
+        Ok((
+            Self {
+                _socket_dir: socket_dir,
+                socket_path,
+                task,
+            },
+            denied_rx,
+        ))
+    }
+
+    #[cfg(not(unix))]
+    pub fn start(
+        _allowlist: NetworkAllowlist,
+    ) -> io::Result<(Self, mpsc::UnboundedReceiver<String>)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the network proxy needs Unix domain sockets",
+        ))
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+}
+
+impl Drop for NetworkProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Point HTTP clients in `env` at the proxy listening on `socket_path`.
+pub(crate) fn apply_proxy_env(env: &mut HashMap<String, String>, socket_path: &Path) {
+    let proxy_url = format!("http://127.0.0.1:{SANDBOX_PROXY_PORT}");
+    for var in PROXY_ENV_VARS {
+        env.insert((*var).to_string(), proxy_url.clone());
+    }
+    // A NO_PROXY inherited from the user's shell would send those hosts
+    // around the proxy, where there is no network.
+    env.remove("NO_PROXY");
+    env.remove("no_proxy");
+    env.insert(
+        CODEX_NETWORK_PROXY_SOCKET_ENV_VAR.to_string(),
+        socket_path.to_string_lossy().into_owned(),
+    );
+}
+
+/// Undo [`apply_proxy_env`] for commands that do not run under the Linux
+/// sandbox, where the proxy port does not exist. Returns the proxy socket, if
+/// `env` had one.
+pub(crate) fn take_proxy_env(env: &mut HashMap<String, String>) -> Option<PathBuf> {
+    let socket_path = env.remove(CODEX_NETWORK_PROXY_SOCKET_ENV_VAR)?;
+    for var in PROXY_ENV_VARS {
+        env.remove(*var);
+    }
+    Some(PathBuf::from(socket_path))
+}
+
+/// The parsed first line of a request, and where it has to go.
+#[cfg(unix)]
+#[derive(Debug, PartialEq, Eq)]
+struct ProxyTarget {
+    host: String,
+    port: u16,
+    /// `CONNECT` tunnels answer `200` and then relay raw bytes; other
+    /// requests are forwarded as they are.
+    tunnel: bool,
+}
+
+#[cfg(unix)]
+fn parse_request_line(line: &str) -> Option<ProxyTarget> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = target.rsplit_once(':')?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        return Some(ProxyTarget {
+            host: host.to_string(),
+            port: port.parse().ok()?,
+            tunnel: true,
+        });
+    }
+    let url = Url::parse(target).ok()?;
+    if url.scheme() != "http" {
+        return None;
+    }
+    Some(ProxyTarget {
+        host: url.host_str()?.to_string(),
+        port: url.port_or_known_default()?,
+        tunnel: false,
+    })
+}
+
+#[cfg(unix)]
+async fn handle_connection(
+    mut client: UnixStream,
+    allowlist: &NetworkAllowlist,
+    denied_tx: &mpsc::UnboundedSender<String>,
+) -> io::Result<()> {
+    let head = read_request_head(&mut client).await?;
+    let request_line = head
+        .split(|b| *b == b'\n')
+        .next()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    let Some(target) = parse_request_line(request_line.trim_end()) else {
+        return respond(&mut client, "400 Bad Request").await;
+    };
+
+    if !allowlist.allows(&target.host) {
+        let _ = denied_tx.send(target.host);
+        return respond(&mut client, "403 Forbidden").await;
+    }
+
+    let mut upstream = match TcpStream::connect((target.host.as_str(), target.port)).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            debug!("network proxy could not reach {}: {e}", target.host);
+            return respond(&mut client, "502 Bad Gateway").await;
+        }
+    };
+
+    if target.tunnel {
+        client
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+        // Clients may send the start of the TLS handshake right behind the
+        // CONNECT request.
+        let rest = &head[head_len(&head).unwrap_or(head.len())..];
+        upstream.write_all(rest).await?;
+    } else {
+        upstream.write_all(&head).await?;
+    }
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+/// Read until the blank line ending the request head. The result may extend
+/// past the head.
+#[cfg(unix)]
+async fn read_request_head(client: &mut UnixStream) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 4096];
+    while head_len(&head).is_none() {
+        if head.len() > MAX_REQUEST_HEAD_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        let n = client.read(&mut buf).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    Ok(head)
+}
+
+/// Length of the request head in `buf`, including the blank line, once it
+/// has been read in full.
+#[cfg(unix)]
+fn head_len(buf: &[u8]) -> Option<usize> {
+    buf.windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|pos| pos + 4)
+}
+
+#[cfg(unix)]
+async fn respond(client: &mut UnixStream, status: &str) -> io::Result<()> {
+    let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    client.write_all(response.as_bytes()).await
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn allowlist_matches_wildcards_case_insensitively() {
+        let allowlist =
+            NetworkAllowlist::new(&["crates.io".to_string(), "*.Crates.io".to_string()]);
+        assert!(allowlist.allows("crates.io"));
+        assert!(allowlist.allows("static.crates.io."));
+        assert!(allowlist.allows("INDEX.crates.io"));
+        assert!(!allowlist.allows("crates.io.evil.example"));
+        assert!(!allowlist.allows("github.com"));
+    }
+
+    #[test]
+    fn parses_connect_and_absolute_form_requests() {
+        assert_eq!(
+            parse_request_line("CONNECT index.crates.io:443 HTTP/1.1"),
+            Some(ProxyTarget {
+                host: "index.crates.io".to_string(),
+                port: 443,
+                tunnel: true,
+            })
+        );
+        assert_eq!(
+            parse_request_line("CONNECT [::1]:8443 HTTP/1.1"),
+            Some(ProxyTarget {
+                host: "::1".to_string(),
+                port: 8443,
+                tunnel: true,
+            })
+        );
+        assert_eq!(
+            parse_request_line("GET http://example.com/simple/ HTTP/1.1"),
+            Some(ProxyTarget {
+                host: "example.com".to_string(),
+                port: 80,
+                tunnel: false,
+            })
+        );
+        assert_eq!(parse_request_line("GET /relative HTTP/1.1"), None);
+        assert_eq!(parse_request_line("CONNECT example.com HTTP/1.1"), None);
+    }
+
+    #[test]
+    fn proxy_env_round_trips() {
+        let mut env = HashMap::from([
+            ("PATH".to_string(), "/bin".to_string()),
+            ("NO_PROXY".to_string(), "localhost".to_string()),
+        ]);
+        apply_proxy_env(&mut env, Path::new("/tmp/proxy.sock"));
+        assert_eq!(
+            env.get("HTTPS_PROXY").map(String::as_str),
+            Some("http://127.0.0.1:3128")
+        );
+        assert!(!env.contains_key("NO_PROXY"));
+
+        let socket_path = take_proxy_env(&mut env);
+
+        assert_eq!(socket_path, Some(PathBuf::from("/tmp/proxy.sock")));
+        assert_eq!(
+            env,
+            HashMap::from([("PATH".to_string(), "/bin".to_string())])
+        );
+    }
+
+    #[tokio::test]
+    async fn socket_lives_in_a_private_directory_removed_on_drop() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (proxy, _denied_hosts) = NetworkProxy::start(NetworkAllowlist::new(&[])).unwrap();
+        let socket_dir = proxy.socket_path().parent().unwrap().to_path_buf();
+        let mode = std::fs::metadata(&socket_dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert!(proxy.socket_path().exists());
+
+        drop(proxy);
+
+        assert!(!socket_dir.exists());
+    }
+}
@@ -9,6 +9,7 @@ use crate::model_family::ModelFamily;
 use crate::plan_tool::PLAN_TOOL;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use crate::sub_agent::SPAWN_AGENT_TOOL;
 use crate::tool_apply_patch::ApplyPatchToolType;
 use crate::tool_apply_patch::create_apply_patch_freeform_tool;
 use crate::tool_apply_patch::create_apply_patch_json_tool;
@@ -71,6 +72,7 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub experimental_unified_exec_tool: bool,
+    pub spawn_agent_tool: bool,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -83,6 +85,7 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
+    pub(crate) include_spawn_agent_tool: bool,
 }
 
 impl ToolsConfig {
@@ -97,6 +100,7 @@ impl ToolsConfig {
             use_streamable_shell_tool,
             include_view_image_tool,
             experimental_unified_exec_tool,
+            include_spawn_agent_tool,
         } = params;
         let mut shell_type = if *use_streamable_shell_tool {
             ConfigShellToolType::StreamableShell
@@ -130,6 +134,7 @@ impl ToolsConfig {
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
+            spawn_agent_tool: *include_spawn_agent_tool,
         }
     }
 }
@@ -375,6 +380,31 @@ pub(crate) fn create_tools_json_for_chat_completions_api(
     Ok(tools_json)
 }
 
+/// Returns JSON values that are compatible with tool use in the Messages API
+/// (`/v1/messages`). Only function tools can be expressed there; the built-in
+/// `local_shell`, `web_search` and freeform tools are dropped.
+pub(crate) fn create_tools_json_for_messages_api(
+    tools: &[OpenAiTool],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let mut tools_json = Vec::new();
+    for tool in tools {
+        if let OpenAiTool::Function(ResponsesApiTool {
+            name,
+            description,
+            parameters,
+            ..
+        }) = tool
+        {
+            tools_json.push(json!({
+                "name": name,
+                "description": description,
+                "input_schema": serde_json::to_value(parameters)?,
+            }));
+        }
+    }
+    Ok(tools_json)
+}
+
 pub(crate) fn mcp_tool_to_openai_tool(
     fully_qualified_name: String,
     tool: mcp_types::Tool,
@@ -580,6 +610,10 @@ pub(crate) fn get_openai_tools(
     if config.include_view_image_tool {
         tools.push(create_view_image_tool());
     }
+
+    if config.spawn_agent_tool {
+        tools.push(SPAWN_AGENT_TOOL.clone());
+    }
     if let Some(mcp_tools) = mcp_tools {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
@@ -644,6 +678,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -666,6 +701,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -688,6 +724,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
         let tools = get_openai_tools(
             &config,
@@ -794,6 +831,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
 
         // Intentionally construct a map with keys that would sort alphabetically.
@@ -872,6 +910,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -935,6 +974,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -993,6 +1033,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -1054,6 +1095,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_spawn_agent_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -1148,4 +1190,41 @@ mod tests {
 
         assert_eq!(description, "Runs a shell command and returns its output.");
     }
+
+    #[test]
+    fn messages_api_tools_keep_only_functions() {
+        let tools = vec![
+            OpenAiTool::Function(ResponsesApiTool {
+                name: "view_image".to_string(),
+                description: "Attach an image".to_string(),
+                strict: false,
+                parameters: JsonSchema::Object {
+                    properties: BTreeMap::from([(
+                        "path".to_string(),
+                        JsonSchema::String { description: None },
+                    )]),
+                    required: Some(vec!["path".to_string()]),
+                    additional_properties: Some(false),
+                },
+            }),
+            OpenAiTool::LocalShell {},
+            OpenAiTool::WebSearch {},
+        ];
+
+        let json = create_tools_json_for_messages_api(&tools).expect("tools json");
+
+        assert_eq!(
+            json,
+            vec![json!({
+                "name": "view_image",
+                "description": "Attach an image",
+                "input_schema": {
+                    "type": "object",
+                    "properties": {"path": {"type": "string"}},
+                    "required": ["path"],
+                    "additionalProperties": false,
+                },
+            })]
+        );
+    }
 }
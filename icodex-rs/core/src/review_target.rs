@@ -0,0 +1,334 @@
+//! Structured targets for `Op::Review`. Instead of describing a change in the
+//! prompt, clients name what to review and core collects the diff and the
+//! contents of the affected files from the workspace's git repository.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use icodex_protocol::protocol::ReviewRequest;
+use icodex_protocol::protocol::ReviewTarget;
+
+use crate::git_info::get_default_branch;
+use crate::git_info::run_git_for_stdout;
+use crate::truncate::truncate_middle;
+
+/// Instructions used when the client does not add any of its own.
+const DEFAULT_REVIEW_PROMPT: &str =
+    "Review the code changes below and report the issues you find, if any.";
+
+/// Diffs of large changes can take a while to compute.
+const GIT_REVIEW_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Budget for the diff in the reviewer's prompt. Larger diffs keep their
+/// beginning and end.
+const MAX_DIFF_BYTES: usize = 256 * 1024;
+
+/// Budget for the contents of a single file.
+const MAX_FILE_BYTES: usize = 64 * 1024;
+
+/// Budget for the contents of all files. Files past it are only listed.
+const MAX_FILES_BYTES: usize = 256 * 1024;
+
+/// Git's well-known empty tree, to diff against in a repository without
+/// commits.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Build a request to review `target`. `instructions` replace the default
+/// prompt, e.g. to focus the review on one concern.
+pub fn review_request(target: ReviewTarget, instructions: Option<String>) -> ReviewRequest {
+    ReviewRequest {
+        prompt: instructions.unwrap_or_else(|| DEFAULT_REVIEW_PROMPT.to_string()),
+        user_facing_hint: describe_review_target(&target),
+        target: Some(target),
+    }
+}
+
+/// Short description of `target` for display, e.g. "changes against main".
+pub fn describe_review_target(target: &ReviewTarget) -> String {
+    match target {
+        ReviewTarget::UncommittedChanges => "uncommitted changes".to_string(),
+        ReviewTarget::BaseBranch { base: Some(base) } => format!("changes against {base}"),
+        ReviewTarget::BaseBranch { base: None } => "changes against the default branch".to_string(),
+        ReviewTarget::CommitRange { range } if range.contains("..") => format!("commits {range}"),
+        ReviewTarget::CommitRange { range } => format!("commit {range}"),
+        ReviewTarget::Files { paths } => match paths.as_slice() {
+            [path] => path.display().to_string(),
+            paths => format!("{} files", paths.len()),
+        },
+    }
+}
+
+/// The diff and file contents for `target`, as text to follow the review
+/// prompt.
+pub(crate) async fn collect_review_context(
+    cwd: &Path,
+    target: &ReviewTarget,
+) -> io::Result<String> {
+    let root = PathBuf::from(git(cwd, &["rev-parse", "--show-toplevel"]).await?.trim());
+
+    // Where the affected files are read from: the working tree, or the
+    // revision a commit range ends at.
+    let (diff, paths, revision) = match target {
+        ReviewTarget::UncommittedChanges => {
+            let head = match git(&root, &["rev-parse", "--verify", "--quiet", "HEAD"]).await {
+                Ok(_) => "HEAD",
+                Err(_) => EMPTY_TREE,
+            };
+            let diff = git_diff(&root, head).await?;
+            let mut paths = changed_paths(&root, head).await?;
+            // Untracked files are not part of the diff; their contents are
+            // the whole change.
+            let untracked =
+                git(&root, &["ls-files", "--others", "--exclude-standard", "-z"]).await?;
+            paths.extend(split_nul(&untracked).map(|path| root.join(path)));
+            (Some(diff), paths, None)
+        }
+        ReviewTarget::BaseBranch { base } => {
+            let base = match base {
+                Some(base) => base.clone(),
+                None => get_default_branch(&root)
+                    .await
+                    .ok_or_else(|| io::Error::other("could not determine the default branch"))?,
+            };
+            check_revision(&base)?;
+            let merge_base = git(&root, &["merge-base", "HEAD", &base]).await?;
+            let merge_base = merge_base.trim();
+            let diff = git_diff(&root, merge_base).await?;
+            let paths = changed_paths(&root, merge_base).await?;
+            (Some(diff), paths, None)
+        }
+        ReviewTarget::CommitRange { range } => {
+            for revision in range.split("..") {
+                // The second half of `a...b` is left with a leading dot.
+                check_revision(revision.trim_start_matches('.'))?;
+            }
+            let (spec, end) = match range.rsplit_once("..") {
+                Some((_, "")) => (range.clone(), "HEAD".to_string()),
+                Some((_, end)) => (range.clone(), end.to_string()),
+                None => (format!("{range}^!"), range.clone()),
+            };
+            let diff = git_diff(&root, &spec).await?;
+            let paths = changed_paths(&root, &spec).await?;
+            (Some(diff), paths, Some(end))
+        }
+        ReviewTarget::Files { paths } => {
+            let paths = paths.iter().map(|path| cwd.join(path)).collect();
+            (None, paths, None)
+        }
+    };
+
+    if diff.as_deref().is_some_and(str::is_empty) && paths.is_empty() {
+        return Err(io::Error::other(format!(
+            "there are no {} to review",
+            describe_review_target(target)
+        )));
+    }
+
+    let mut context = format!(
+        "The review covers {} in the git repository at {}.\n",
+        describe_review_target(target),
+        root.display()
+    );
+    if let Some(diff) = diff
+        && !diff.is_empty()
+    {
+        let (diff, _) = truncate_middle(&diff, MAX_DIFF_BYTES);
+        context.push_str(&format!("\n<diff>\n{diff}</diff>\n"));
+    }
+
+    let mut budget = MAX_FILES_BYTES;
+    for path in paths {
+        let contents = match &revision {
+            Some(revision) => {
+                let Ok(relative) = path.strip_prefix(&root) else {
+                    continue;
+                };
+                let object = format!("{revision}:{}", relative.display());
+                match git(&root, &["show", &object]).await {
+                    Ok(contents) => contents,
+                    // Deleted by the range.
+                    Err(_) => continue,
+                }
+            }
+            None => match tokio::fs::read(&path).await {
+                Ok(bytes) if bytes.contains(&0) => "(binary file omitted)\n".to_string(),
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                // Deleted by the change.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            },
+        };
+        let contents = if budget == 0 {
+            "(omitted, too many files)\n".to_string()
+        } else {
+            let (contents, _) = truncate_middle(&contents, MAX_FILE_BYTES.min(budget));
+            budget = budget.saturating_sub(contents.len());
+            contents
+        };
+        context.push_str(&format!(
+            "\n<file path=\"{}\">\n{contents}</file>\n",
+            path.display()
+        ));
+    }
+
+    Ok(context)
+}
+
+async fn git_diff(root: &Path, spec: &str) -> io::Result<String> {
+    git(root, &["diff", "--no-color", "--no-ext-diff", spec]).await
+}
+
+/// Absolute paths of the files `git diff <spec>` touches.
+async fn changed_paths(root: &Path, spec: &str) -> io::Result<Vec<PathBuf>> {
+    let names = git(root, &["diff", "--name-only", "-z", spec]).await?;
+    Ok(split_nul(&names).map(|path| root.join(path)).collect())
+}
+
+/// Refuse revisions git would parse as options, e.g. `--output=<file>`.
+fn check_revision(revision: &str) -> io::Result<()> {
+    if revision.starts_with('-') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid revision '{revision}'"),
+        ));
+    }
+    Ok(())
+}
+
+fn split_nul(output: &str) -> impl Iterator<Item = &str> {
+    output.split('\0').filter(|path| !path.is_empty())
+}
+
+/// Run `git` in `cwd` and return its standard output.
+async fn git(cwd: &Path, args: &[&str]) -> io::Result<String> {
+    run_git_for_stdout(cwd, args, &[], GIT_REVIEW_TIMEOUT).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn init_repo() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        run_git(&root, &["init", "--quiet", "--initial-branch=main"]);
+        fs::write(root.join("lib.rs"), "fn one() {}\n").unwrap();
+        run_git(&root, &["add", "."]);
+        run_git(&root, &["commit", "--quiet", "-m", "initial"]);
+        (dir, root)
+    }
+
+    #[tokio::test]
+    async fn uncommitted_changes_include_untracked_files() {
+        let (_dir, root) = init_repo();
+        fs::write(root.join("lib.rs"), "fn one() {}\nfn two() {}\n").unwrap();
+        fs::write(root.join("new.rs"), "fn three() {}\n").unwrap();
+
+        let context = collect_review_context(&root, &ReviewTarget::UncommittedChanges)
+            .await
+            .unwrap();
+
+        assert!(context.contains("+fn two() {}"), "{context}");
+        assert!(
+            context.contains(&format!(
+                "<file path=\"{}\">\nfn one() {{}}\nfn two() {{}}\n</file>",
+                root.join("lib.rs").display()
+            )),
+            "{context}"
+        );
+        assert!(
+            context.contains(&format!(
+                "<file path=\"{}\">\nfn three() {{}}\n</file>",
+                root.join("new.rs").display()
+            )),
+            "{context}"
+        );
+    }
+
+    #[tokio::test]
+    async fn branch_is_diffed_against_its_merge_base() {
+        let (_dir, root) = init_repo();
+        run_git(&root, &["checkout", "--quiet", "-b", "feature"]);
+        fs::write(root.join("lib.rs"), "fn one() {}\nfn feature() {}\n").unwrap();
+        run_git(&root, &["commit", "--quiet", "-am", "feature"]);
+
+        let context = collect_review_context(&root, &ReviewTarget::BaseBranch { base: None })
+            .await
+            .unwrap();
+
+        assert!(context.contains("+fn feature() {}"), "{context}");
+    }
+
+    #[tokio::test]
+    async fn commit_range_reads_files_at_the_end_of_the_range() {
+        let (_dir, root) = init_repo();
+        fs::write(root.join("lib.rs"), "fn committed() {}\n").unwrap();
+        run_git(&root, &["commit", "--quiet", "-am", "second"]);
+        fs::write(root.join("lib.rs"), "fn uncommitted() {}\n").unwrap();
+
+        let context = collect_review_context(
+            &root,
+            &ReviewTarget::CommitRange {
+                range: "HEAD".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(context.contains("+fn committed() {}"), "{context}");
+        assert!(!context.contains("uncommitted"), "{context}");
+    }
+
+    #[tokio::test]
+    async fn revisions_that_look_like_options_are_rejected() {
+        let (_dir, root) = init_repo();
+        let output = root.join("leaked.diff");
+        let targets = [
+            ReviewTarget::BaseBranch {
+                base: Some(format!("--output={}", output.display())),
+            },
+            ReviewTarget::CommitRange {
+                range: format!("--output={}", output.display()),
+            },
+            ReviewTarget::CommitRange {
+                range: "HEAD..--all".to_string(),
+            },
+        ];
+
+        for target in targets {
+            let err = collect_review_context(&root, &target).await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{err}");
+        }
+        assert!(!output.exists());
+    }
+
+    #[tokio::test]
+    async fn nothing_to_review_is_an_error() {
+        let (_dir, root) = init_repo();
+        let err = collect_review_context(&root, &ReviewTarget::UncommittedChanges)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "there are no uncommitted changes to review"
+        );
+    }
+}
@@ -24,7 +24,9 @@ use super::SESSIONS_SUBDIR;
 use super::list::ConversationsPage;
 use super::list::Cursor;
 use super::list::get_conversations;
+use super::list::parse_timestamp_uuid_from_filename;
 use super::policy::is_persisted_response_item;
+use super::search::SearchIndexWriter;
 use crate::config::Config;
 use crate::default_client::ORIGINATOR;
 use crate::git_info::collect_git_info;
@@ -159,6 +161,21 @@ impl RolloutRecorder {
         // Clone the cwd for the spawned task to collect git info asynchronously
         let cwd = config.cwd.clone();
 
+        // A resumed rollout is indexed under the id in its filename.
+        let index_id = match &meta {
+            Some(meta) => Some(meta.id),
+            None => rollout_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(parse_timestamp_uuid_from_filename)
+                .map(|(_, id)| ConversationId::from(id)),
+        };
+        let index_target = SearchIndexTarget {
+            icodex_home: config.icodex_home.clone(),
+            id: index_id,
+            rollout_path: rollout_path.clone(),
+        };
+
         // A reasonably-sized bounded channel. If the buffer fills up the send
         // future will yield, which is fine – we only need to ensure we do not
         // perform *blocking* I/O on the caller's thread.
@@ -167,7 +184,7 @@ impl RolloutRecorder {
         // Spawn a Tokio task that owns the file handle and performs async
         // writes. Using `tokio::fs::File` keeps everything on the async I/O
         // driver instead of blocking the runtime.
-        tokio::task::spawn(rollout_writer(file, rx, meta, cwd));
+        tokio::task::spawn(rollout_writer(file, rx, meta, cwd, index_target));
 
         Ok(Self { tx, rollout_path })
     }
@@ -345,13 +362,32 @@ fn create_log_file(
     })
 }
 
+/// Where the writer task indexes the session for search.
+struct SearchIndexTarget {
+    icodex_home: PathBuf,
+    /// `None` when the rollout's filename has no id to index it under.
+    id: Option<ConversationId>,
+    rollout_path: PathBuf,
+}
+
 async fn rollout_writer(
     file: tokio::fs::File,
     mut rx: mpsc::Receiver<RolloutCmd>,
     mut meta: Option<SessionMeta>,
     cwd: std::path::PathBuf,
+    index_target: SearchIndexTarget,
 ) -> std::io::Result<()> {
     let mut writer = JsonlWriter { file };
+    let mut index = match index_target.id {
+        Some(id) => match SearchIndexWriter::open(&index_target.icodex_home, id).await {
+            Ok(index) => Some(index),
+            Err(e) => {
+                warn!("failed to open the session search index: {e}");
+                None
+            }
+        },
+        None => None,
+    };
 
     // If we have a meta, collect git info asynchronously and write meta first
     if let Some(session_meta) = meta.take() {
@@ -362,9 +398,9 @@ async fn rollout_writer(
         };
 
         // Write the SessionMeta as the first item in the file, wrapped in a rollout line
-        writer
-            .write_rollout_item(RolloutItem::SessionMeta(session_meta_line))
-            .await?;
+        let item = RolloutItem::SessionMeta(session_meta_line);
+        index_rollout_item(&mut index, &item, &index_target.rollout_path).await;
+        writer.write_rollout_item(item).await?;
     }
 
     // Process rollout commands
@@ -373,6 +409,7 @@ async fn rollout_writer(
             RolloutCmd::AddItems(items) => {
                 for item in items {
                     if is_persisted_response_item(&item) {
+                        index_rollout_item(&mut index, &item, &index_target.rollout_path).await;
                         writer.write_rollout_item(item).await?;
                     }
                 }
@@ -394,6 +431,26 @@ async fn rollout_writer(
     Ok(())
 }
 
+/// The search index is best effort: after a failed write it is no longer
+/// updated for this session, and the rollout itself is unaffected.
+async fn index_rollout_item(
+    index: &mut Option<SearchIndexWriter>,
+    item: &RolloutItem,
+    rollout_path: &Path,
+) {
+    let Some(writer) = index else {
+        return;
+    };
+    let result = match item {
+        RolloutItem::SessionMeta(meta_line) => writer.record_session(meta_line, rollout_path).await,
+        item => writer.record_item(item).await,
+    };
+    if let Err(e) = result {
+        warn!("failed to update the session search index: {e}");
+        *index = None;
+    }
+}
+
 struct JsonlWriter {
     file: tokio::fs::File,
 }
@@ -0,0 +1,920 @@
+//! Full-text search across recorded sessions.
+//!
+//! The index is a JSONL file in the icodex home (`session_index.jsonl`), kept
+//! out of `sessions/` so that nothing scanning for rollouts mistakes it for
+//! one. It holds only the searchable text of each session: user and agent
+//! messages, commands and the paths that patches touched.
+//! [`RolloutRecorder`](super::RolloutRecorder) appends to it while it
+//! records, and sessions recorded before the index existed are added the
+//! first time a search runs. Searches rewrite the index without deleted
+//! rollouts and repeated entries once those make up most of it.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use icodex_apply_patch::Hunk;
+use icodex_apply_patch::parse_patch;
+use icodex_protocol::mcp_protocol::ConversationId;
+use icodex_protocol::models::LocalShellAction;
+use icodex_protocol::models::ResponseItem;
+use icodex_protocol::models::ShellToolCallParams;
+use icodex_protocol::protocol::GitInfo;
+use icodex_protocol::protocol::InputMessageKind;
+use icodex_protocol::protocol::RolloutItem;
+use icodex_protocol::protocol::RolloutLine;
+use icodex_protocol::protocol::SessionMetaLine;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncSeekExt;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+use super::SESSIONS_SUBDIR;
+use super::list::collect_dirs_desc;
+use super::list::collect_files;
+use super::list::parse_timestamp_uuid_from_filename;
+use crate::protocol::EventMsg;
+use crate::truncate::truncate_middle;
+
+pub const SEARCH_INDEX_FILE: &str = "session_index.jsonl";
+
+/// Longer texts (typically pasted logs or heredocs) keep their beginning and
+/// end.
+const MAX_INDEXED_TEXT_BYTES: usize = 2 * 1024;
+
+/// Matching texts reported per session.
+const MAX_MATCHES_PER_SESSION: usize = 3;
+
+/// The kind of text a match was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    UserMessage,
+    AgentMessage,
+    Command,
+    FilePath,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum IndexEntry {
+    Session {
+        id: ConversationId,
+        path: PathBuf,
+        timestamp: String,
+        cwd: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        git: Option<GitInfo>,
+    },
+    Text {
+        id: ConversationId,
+        field: SearchField,
+        text: String,
+    },
+}
+
+/// Which sessions to look for. All filters must match.
+#[derive(Debug, Clone, Default)]
+pub struct SessionSearchQuery {
+    /// Words that must all occur in the session, case-insensitively. Empty
+    /// matches every session.
+    pub text: String,
+    /// Substring of the session's repository URL.
+    pub repo: Option<String>,
+    /// Only sessions started in this directory or below it.
+    pub cwd: Option<PathBuf>,
+    /// Only sessions started on or after this UTC date (`YYYY`, `YYYY-MM` or
+    /// `YYYY-MM-DD`).
+    pub since: Option<String>,
+    /// Only sessions started on or before this UTC date, in the same format
+    /// as `since`.
+    pub until: Option<String>,
+}
+
+/// A session that matched a search.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSearchHit {
+    pub id: ConversationId,
+    /// Absolute path to the rollout file.
+    pub path: PathBuf,
+    /// When the session started, in RFC 3339 (UTC).
+    pub timestamp: String,
+    pub cwd: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
+    /// Texts containing the most query words, best first. Empty for an empty
+    /// query.
+    pub matches: Vec<SearchMatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchMatch {
+    pub field: SearchField,
+    pub text: String,
+}
+
+/// Whether `date` is in one of the formats [`SessionSearchQuery`] accepts.
+pub fn is_valid_search_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    let lengths_ok = match parts.as_slice() {
+        [year] => year.len() == 4,
+        [year, month] => year.len() == 4 && month.len() == 2,
+        [year, month, day] => year.len() == 4 && month.len() == 2 && day.len() == 2,
+        _ => false,
+    };
+    lengths_ok
+        && parts
+            .iter()
+            .all(|part| part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Search the sessions recorded under `icodex_home`, newest first, returning
+/// at most `limit` hits.
+pub async fn search_sessions(
+    icodex_home: &Path,
+    query: &SessionSearchQuery,
+    limit: usize,
+) -> io::Result<Vec<SessionSearchHit>> {
+    let sessions_dir = icodex_home.join(SESSIONS_SUBDIR);
+    if !sessions_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let index_path = icodex_home.join(SEARCH_INDEX_FILE);
+
+    let ReadIndex {
+        mut sessions,
+        lines,
+        len,
+    } = read_index(&index_path).await?;
+    let on_disk = backfill_index(&sessions_dir, &index_path, &mut sessions).await?;
+    // The index can outlive rollouts that were deleted or archived.
+    sessions.retain(|id, session| session.info.is_some() && on_disk.contains(id));
+
+    // Waiting until most lines are unneeded keeps rewrites rare as the index
+    // grows.
+    let needed_lines: usize = sessions
+        .values()
+        .map(|session| 1 + session.texts.len())
+        .sum();
+    if lines > 2 * needed_lines
+        && let Err(e) = compact_index(&index_path, &sessions, len).await
+    {
+        warn!("failed to compact the session search index: {e}");
+    }
+
+    let terms: Vec<String> = query
+        .text
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    let repo = query.repo.as_deref().map(str::to_lowercase);
+
+    let mut hits = Vec::new();
+    for session in sessions.into_values() {
+        let Some(info) = session.info else {
+            continue;
+        };
+        if let Some(repo) = &repo {
+            let url = info
+                .git
+                .as_ref()
+                .and_then(|git| git.repository_url.as_deref())
+                .unwrap_or_default()
+                .to_lowercase();
+            if !url.contains(repo.as_str()) {
+                continue;
+            }
+        }
+        if let Some(cwd) = &query.cwd
+            && !info.cwd.starts_with(cwd)
+        {
+            continue;
+        }
+        if let Some(since) = &query.since
+            && info.timestamp.as_str() < since.as_str()
+        {
+            continue;
+        }
+        if let Some(until) = &query.until
+            && info.timestamp.get(..until.len()).unwrap_or(&info.timestamp) > until.as_str()
+        {
+            continue;
+        }
+
+        let Some(matches) = match_terms(&session.texts, &terms) else {
+            continue;
+        };
+        hits.push(SessionSearchHit {
+            id: info.id,
+            path: info.path,
+            timestamp: info.timestamp,
+            cwd: info.cwd,
+            git: info.git,
+            matches,
+        });
+    }
+
+    hits.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// `None` unless every term occurs in some text of the session.
+fn match_terms(texts: &[SearchMatch], terms: &[String]) -> Option<Vec<SearchMatch>> {
+    if terms.is_empty() {
+        return Some(Vec::new());
+    }
+    let mut found = vec![false; terms.len()];
+    let mut scored: Vec<(usize, &SearchMatch)> = Vec::new();
+    for text in texts {
+        let lowercase = text.text.to_lowercase();
+        let mut score = 0;
+        for (term, found) in terms.iter().zip(found.iter_mut()) {
+            if lowercase.contains(term.as_str()) {
+                *found = true;
+                score += 1;
+            }
+        }
+        if score > 0 {
+            scored.push((score, text));
+        }
+    }
+    if !found.iter().all(|found| *found) {
+        return None;
+    }
+    // Stable, so equally good matches stay in session order.
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+    Some(
+        scored
+            .into_iter()
+            .take(MAX_MATCHES_PER_SESSION)
+            .map(|(_, text)| text.clone())
+            .collect(),
+    )
+}
+
+struct SessionInfo {
+    id: ConversationId,
+    path: PathBuf,
+    timestamp: String,
+    cwd: PathBuf,
+    git: Option<GitInfo>,
+}
+
+#[derive(Default)]
+struct IndexedSession {
+    info: Option<SessionInfo>,
+    texts: Vec<SearchMatch>,
+    /// A resumed session can be indexed both by the recorder and by a
+    /// backfill; keep each text once.
+    seen: HashSet<(SearchField, String)>,
+}
+
+impl IndexedSession {
+    fn add(&mut self, entry: IndexEntry) {
+        match entry {
+            IndexEntry::Session {
+                id,
+                path,
+                timestamp,
+                cwd,
+                git,
+            } => {
+                self.info = Some(SessionInfo {
+                    id,
+                    path,
+                    timestamp,
+                    cwd,
+                    git,
+                });
+            }
+            IndexEntry::Text { field, text, .. } => {
+                if self.seen.insert((field, text.clone())) {
+                    self.texts.push(SearchMatch { field, text });
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ReadIndex {
+    sessions: HashMap<ConversationId, IndexedSession>,
+    /// Lines read, including unparsable and repeated ones.
+    lines: usize,
+    /// Bytes read, up to the end of the last complete line.
+    len: u64,
+}
+
+async fn read_index(index_path: &Path) -> io::Result<ReadIndex> {
+    let mut index = ReadIndex::default();
+    let file = match tokio::fs::File::open(index_path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(index),
+        Err(e) => return Err(e),
+    };
+    let mut reader = tokio::io::BufReader::new(file);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).await?;
+        // A line still being appended is left for the next search.
+        if !line.ends_with('\n') {
+            break;
+        }
+        index.lines += 1;
+        index.len += read as u64;
+        // A line cut short by a crash is skipped rather than failing the
+        // whole search.
+        let Ok(entry) = serde_json::from_str::<IndexEntry>(&line) else {
+            continue;
+        };
+        let id = match &entry {
+            IndexEntry::Session { id, .. } | IndexEntry::Text { id, .. } => *id,
+        };
+        index.sessions.entry(id).or_default().add(entry);
+    }
+    Ok(index)
+}
+
+/// Replace the index with the entries of `sessions`, each written once.
+/// Lines appended after the first `read_len` bytes were read (by the backfill
+/// or by sessions still recording) are carried over unless they repeat an
+/// entry of `sessions`. An entry appended between that copy and the rename
+/// is lost; if it was a session's metadata, the next backfill indexes the
+/// session again.
+async fn compact_index(
+    index_path: &Path,
+    sessions: &HashMap<ConversationId, IndexedSession>,
+    read_len: u64,
+) -> io::Result<()> {
+    let mut contents = Vec::new();
+    for session in sessions.values() {
+        let Some(info) = &session.info else {
+            continue;
+        };
+        let entry = IndexEntry::Session {
+            id: info.id,
+            path: info.path.clone(),
+            timestamp: info.timestamp.clone(),
+            cwd: info.cwd.clone(),
+            git: info.git.clone(),
+        };
+        serde_json::to_writer(&mut contents, &entry)?;
+        contents.push(b'\n');
+        for text in &session.texts {
+            let entry = IndexEntry::Text {
+                id: info.id,
+                field: text.field,
+                text: text.text.clone(),
+            };
+            serde_json::to_writer(&mut contents, &entry)?;
+            contents.push(b'\n');
+        }
+    }
+
+    let mut file = tokio::fs::File::open(index_path).await?;
+    file.seek(SeekFrom::Start(read_len)).await?;
+    let mut appended = tokio::io::BufReader::new(file).lines();
+    while let Some(line) = appended.next_line().await? {
+        let Ok(entry) = serde_json::from_str::<IndexEntry>(&line) else {
+            continue;
+        };
+        let known = match entry {
+            IndexEntry::Session { id, .. } => sessions.contains_key(&id),
+            IndexEntry::Text { id, field, text } => sessions
+                .get(&id)
+                .is_some_and(|session| session.seen.contains(&(field, text))),
+        };
+        if !known {
+            contents.extend_from_slice(line.as_bytes());
+            contents.push(b'\n');
+        }
+    }
+
+    let index_path = index_path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> io::Result<()> {
+        let dir = index_path.parent().unwrap_or_else(|| Path::new("."));
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        tmp.write_all(&contents)?;
+        tmp.persist(&index_path).map_err(|e| e.error)?;
+        Ok(())
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Index the rollouts that are not in the index yet. Returns the ids of all
+/// rollouts found.
+async fn backfill_index(
+    sessions_dir: &Path,
+    index_path: &Path,
+    sessions: &mut HashMap<ConversationId, IndexedSession>,
+) -> io::Result<HashSet<ConversationId>> {
+    let mut on_disk = HashSet::new();
+    let mut index: Option<SearchIndexWriter> = None;
+    for (_, year_path) in collect_dirs_desc(sessions_dir, |s| s.parse::<u16>().ok()).await? {
+        for (_, month_path) in collect_dirs_desc(&year_path, |s| s.parse::<u8>().ok()).await? {
+            for (_, day_path) in collect_dirs_desc(&month_path, |s| s.parse::<u8>().ok()).await? {
+                let rollouts = collect_files(&day_path, |name, path| {
+                    parse_timestamp_uuid_from_filename(name)
+                        .map(|(_, id)| (ConversationId::from(id), path.to_path_buf()))
+                })
+                .await?;
+                for (id, path) in rollouts {
+                    on_disk.insert(id);
+                    if sessions.get(&id).is_some_and(|s| s.info.is_some()) {
+                        continue;
+                    }
+                    let entries = match index_rollout(&path).await {
+                        Ok(entries) => entries,
+                        Err(e) => {
+                            warn!("failed to index {}: {e}", path.display());
+                            continue;
+                        }
+                    };
+                    let writer = match &mut index {
+                        Some(writer) => writer,
+                        None => index.insert(SearchIndexWriter::open_path(index_path, id).await?),
+                    };
+                    for entry in entries {
+                        writer.write_entry(&entry).await?;
+                        sessions.entry(id).or_default().add(entry);
+                    }
+                }
+            }
+        }
+    }
+    Ok(on_disk)
+}
+
+/// Index entries for an existing rollout file.
+async fn index_rollout(path: &Path) -> io::Result<Vec<IndexEntry>> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut lines = tokio::io::BufReader::new(file).lines();
+    let mut entries = Vec::new();
+    let mut id = None;
+    while let Some(line) = lines.next_line().await? {
+        let Ok(RolloutLine { item, .. }) = serde_json::from_str::<RolloutLine>(&line) else {
+            continue;
+        };
+        match (&item, id) {
+            (RolloutItem::SessionMeta(meta_line), None) => {
+                id = Some(meta_line.meta.id);
+                entries.push(session_entry(meta_line, path));
+            }
+            (_, Some(id)) => {
+                entries.extend(
+                    searchable_texts(&item)
+                        .into_iter()
+                        .map(|(field, text)| IndexEntry::Text { id, field, text }),
+                );
+            }
+            _ => {}
+        }
+    }
+    if id.is_none() {
+        return Err(io::Error::other("rollout has no session metadata"));
+    }
+    Ok(entries)
+}
+
+fn session_entry(meta_line: &SessionMetaLine, path: &Path) -> IndexEntry {
+    IndexEntry::Session {
+        id: meta_line.meta.id,
+        path: path.to_path_buf(),
+        timestamp: meta_line.meta.timestamp.clone(),
+        cwd: meta_line.meta.cwd.clone(),
+        git: meta_line.git.clone(),
+    }
+}
+
+/// The searchable texts in a rollout item.
+fn searchable_texts(item: &RolloutItem) -> Vec<(SearchField, String)> {
+    let texts = match item {
+        RolloutItem::EventMsg(EventMsg::UserMessage(ev))
+            if matches!(ev.kind, None | Some(InputMessageKind::Plain)) =>
+        {
+            vec![(SearchField::UserMessage, ev.message.clone())]
+        }
+        RolloutItem::EventMsg(EventMsg::AgentMessage(ev)) => {
+            vec![(SearchField::AgentMessage, ev.message.clone())]
+        }
+        RolloutItem::ResponseItem(ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        }) => vec![(SearchField::Command, command_text(&action.command))],
+        RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+            name, arguments, ..
+        }) => match name.as_str() {
+            "shell" | "container.exec" => {
+                match serde_json::from_str::<ShellToolCallParams>(arguments) {
+                    Ok(params) => vec![(SearchField::Command, command_text(&params.command))],
+                    Err(_) => Vec::new(),
+                }
+            }
+            "apply_patch" => {
+                #[derive(Deserialize)]
+                struct ApplyPatchArguments {
+                    input: String,
+                }
+                match serde_json::from_str::<ApplyPatchArguments>(arguments) {
+                    Ok(args) => patched_paths(&args.input),
+                    Err(_) => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        },
+        RolloutItem::ResponseItem(ResponseItem::CustomToolCall { name, input, .. })
+            if name == "apply_patch" =>
+        {
+            patched_paths(input)
+        }
+        _ => Vec::new(),
+    };
+    texts
+        .into_iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(field, text)| {
+            (
+                field,
+                truncate_middle(text.trim(), MAX_INDEXED_TEXT_BYTES).0,
+            )
+        })
+        .collect()
+}
+
+/// The script of `bash -lc <script>`, or the command as a shell would read
+/// it.
+fn command_text(command: &[String]) -> String {
+    match command {
+        [_, flag, script] if flag == "-lc" || flag == "-c" => script.clone(),
+        _ => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    }
+}
+
+fn patched_paths(patch: &str) -> Vec<(SearchField, String)> {
+    let Ok(args) = parse_patch(patch) else {
+        return Vec::new();
+    };
+    args.hunks
+        .iter()
+        .flat_map(|hunk| match hunk {
+            Hunk::AddFile { path, .. } | Hunk::DeleteFile { path } => vec![path],
+            Hunk::UpdateFile {
+                path, move_path, ..
+            } => std::iter::once(path).chain(move_path).collect(),
+        })
+        .map(|path| (SearchField::FilePath, path.display().to_string()))
+        .collect()
+}
+
+/// Appends one session's entries to the index. Each entry is written with a
+/// single append so that concurrent sessions do not interleave lines, and
+/// the index is reopened for every entry so that entries land in the
+/// compacted index once a search has replaced it.
+pub(crate) struct SearchIndexWriter {
+    index_path: PathBuf,
+    id: ConversationId,
+}
+
+impl SearchIndexWriter {
+    pub(crate) async fn open(icodex_home: &Path, id: ConversationId) -> io::Result<Self> {
+        Self::open_path(&icodex_home.join(SEARCH_INDEX_FILE), id).await
+    }
+
+    async fn open_path(index_path: &Path, id: ConversationId) -> io::Result<Self> {
+        open_for_append(index_path).await?;
+        Ok(Self {
+            index_path: index_path.to_path_buf(),
+            id,
+        })
+    }
+
+    pub(crate) async fn record_session(
+        &mut self,
+        meta_line: &SessionMetaLine,
+        rollout_path: &Path,
+    ) -> io::Result<()> {
+        self.write_entry(&session_entry(meta_line, rollout_path))
+            .await
+    }
+
+    pub(crate) async fn record_item(&mut self, item: &RolloutItem) -> io::Result<()> {
+        for (field, text) in searchable_texts(item) {
+            let entry = IndexEntry::Text {
+                id: self.id,
+                field,
+                text,
+            };
+            self.write_entry(&entry).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_entry(&mut self, entry: &IndexEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = open_for_append(&self.index_path).await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await
+    }
+}
+
+async fn open_for_append(index_path: &Path) -> io::Result<tokio::fs::File> {
+    tokio::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(index_path)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::AgentMessageEvent;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    /// Write a rollout started at `ts_str` (`YYYY-MM-DDThh-mm-ss`) with the
+    /// given lines after its session metadata.
+    fn write_rollout_lines(home: &Path, ts_str: &str, id: Uuid, lines: &[serde_json::Value]) {
+        let dir = home
+            .join(SESSIONS_SUBDIR)
+            .join(&ts_str[0..4])
+            .join(&ts_str[5..7])
+            .join(&ts_str[8..10]);
+        fs::create_dir_all(&dir).unwrap();
+        let meta = json!({
+            "timestamp": ts_str,
+            "type": "session_meta",
+            "payload": {
+                "id": id,
+                "timestamp": ts_str,
+                "instructions": null,
+                "cwd": "/work/app",
+                "originator": "test_originator",
+                "cli_version": "test_version",
+                "git": {"repository_url": "https://github.com/example/app.git"},
+            },
+        });
+        let contents: String = std::iter::once(&meta)
+            .chain(lines)
+            .map(|line| format!("{line}\n"))
+            .collect();
+        fs::write(dir.join(format!("rollout-{ts_str}-{id}.jsonl")), contents).unwrap();
+    }
+
+    fn user_message(text: &str) -> serde_json::Value {
+        json!({
+            "timestamp": "2025-03-01T10:00:00.000Z",
+            "type": "event_msg",
+            "payload": {"type": "user_message", "message": text, "kind": "plain"},
+        })
+    }
+
+    fn shell_call(script: &str) -> serde_json::Value {
+        json!({
+            "timestamp": "2025-03-01T10:00:01.000Z",
+            "type": "response_item",
+            "payload": {
+                "type": "function_call",
+                "name": "shell",
+                "arguments": json!({"command": ["bash", "-lc", script]}).to_string(),
+                "call_id": "call-1",
+            },
+        })
+    }
+
+    fn patch_call(patch: &str) -> serde_json::Value {
+        json!({
+            "timestamp": "2025-03-01T10:00:02.000Z",
+            "type": "response_item",
+            "payload": {
+                "type": "custom_tool_call",
+                "name": "apply_patch",
+                "input": patch,
+                "call_id": "call-2",
+            },
+        })
+    }
+
+    fn query(text: &str) -> SessionSearchQuery {
+        SessionSearchQuery {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_messages_commands_and_patched_paths() {
+        let home = TempDir::new().unwrap();
+        let id = Uuid::new_v4();
+        write_rollout_lines(
+            home.path(),
+            "2025-03-01T10-00-00",
+            id,
+            &[
+                user_message("fix the flaky migration test"),
+                shell_call("cargo test -p migrations"),
+                patch_call(
+                    "*** Begin Patch\n*** Update File: db/migrate.rs\n@@\n-old\n+new\n*** End Patch",
+                ),
+            ],
+        );
+        write_rollout_lines(
+            home.path(),
+            "2025-03-02T10-00-00",
+            Uuid::new_v4(),
+            &[user_message("write the release notes")],
+        );
+
+        let hits = search_sessions(home.path(), &query("Flaky MIGRATION"), 10)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, ConversationId::from(id));
+        // The message has both words, the command only one of them.
+        assert_eq!(
+            hits[0].matches,
+            vec![
+                SearchMatch {
+                    field: SearchField::UserMessage,
+                    text: "fix the flaky migration test".to_string(),
+                },
+                SearchMatch {
+                    field: SearchField::Command,
+                    text: "cargo test -p migrations".to_string(),
+                },
+            ]
+        );
+
+        let hits = search_sessions(home.path(), &query("cargo migrations"), 10)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].matches[0].field, SearchField::Command);
+
+        let hits = search_sessions(home.path(), &query("db/migrate.rs"), 10)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].matches[0].field, SearchField::FilePath);
+
+        // Every word has to occur somewhere in the session.
+        let hits = search_sessions(home.path(), &query("flaky release"), 10)
+            .await
+            .unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn backfill_runs_once_and_filters_apply() {
+        let home = TempDir::new().unwrap();
+        write_rollout_lines(
+            home.path(),
+            "2025-03-01T10-00-00",
+            Uuid::new_v4(),
+            &[user_message("older migration work")],
+        );
+        write_rollout_lines(
+            home.path(),
+            "2025-04-01T10-00-00",
+            Uuid::new_v4(),
+            &[user_message("newer migration work")],
+        );
+
+        let hits = search_sessions(home.path(), &query("migration"), 10)
+            .await
+            .unwrap();
+        let timestamps: Vec<&str> = hits.iter().map(|hit| hit.timestamp.as_str()).collect();
+        assert_eq!(
+            timestamps,
+            vec!["2025-04-01T10-00-00", "2025-03-01T10-00-00"]
+        );
+
+        let index_path = home.path().join(SEARCH_INDEX_FILE);
+        let indexed = std::fs::read_to_string(&index_path).unwrap();
+        search_sessions(home.path(), &query("migration"), 10)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&index_path).unwrap(), indexed);
+
+        let since_april = SessionSearchQuery {
+            since: Some("2025-04".to_string()),
+            ..query("migration")
+        };
+        let hits = search_sessions(home.path(), &since_april, 10)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].timestamp, "2025-04-01T10-00-00");
+
+        let until_march = SessionSearchQuery {
+            until: Some("2025-03-01".to_string()),
+            ..query("migration")
+        };
+        let hits = search_sessions(home.path(), &until_march, 10)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].timestamp, "2025-03-01T10-00-00");
+
+        let same_repo = SessionSearchQuery {
+            repo: Some("Example/App".to_string()),
+            cwd: Some(PathBuf::from("/work")),
+            ..query("migration")
+        };
+        let hits = search_sessions(home.path(), &same_repo, 10).await.unwrap();
+        assert_eq!(hits.len(), 2);
+
+        let other_repo = SessionSearchQuery {
+            repo: Some("github.com/other/repo".to_string()),
+            ..query("migration")
+        };
+        let hits = search_sessions(home.path(), &other_repo, 10).await.unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_compacts_an_index_of_deleted_and_repeated_entries() {
+        let home = TempDir::new().unwrap();
+        let kept = Uuid::new_v4();
+        write_rollout_lines(
+            home.path(),
+            "2025-03-01T10-00-00",
+            kept,
+            &[user_message("keep this migration")],
+        );
+        write_rollout_lines(
+            home.path(),
+            "2025-03-02T10-00-00",
+            Uuid::new_v4(),
+            &[user_message("delete this migration")],
+        );
+        search_sessions(home.path(), &query("migration"), 10)
+            .await
+            .unwrap();
+
+        // A recorder that started before the compaction keeps indexing.
+        let index_path = home.path().join(SEARCH_INDEX_FILE);
+        let mut writer = SearchIndexWriter::open_path(&index_path, ConversationId::from(kept))
+            .await
+            .unwrap();
+
+        // Resuming the kept session indexed it again, and the other rollout
+        // was deleted.
+        let indexed = fs::read_to_string(&index_path).unwrap();
+        let repeated: String = indexed
+            .lines()
+            .filter(|line| line.contains(&kept.to_string()))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        fs::write(&index_path, format!("{indexed}{repeated}{repeated}")).unwrap();
+        let deleted = fs::read_dir(home.path().join(SESSIONS_SUBDIR).join("2025/03/02"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        fs::remove_file(deleted).unwrap();
+
+        let hits = search_sessions(home.path(), &query("migration"), 10)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, ConversationId::from(kept));
+        assert_eq!(fs::read_to_string(&index_path).unwrap().lines().count(), 2);
+
+        writer
+            .record_item(&RolloutItem::EventMsg(EventMsg::AgentMessage(
+                AgentMessageEvent {
+                    message: "ran the migration".to_string(),
+                },
+            )))
+            .await
+            .unwrap();
+        let hits = search_sessions(home.path(), &query("ran"), 10)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn search_dates_accept_year_month_and_day_prefixes() {
+        assert!(is_valid_search_date("2025"));
+        assert!(is_valid_search_date("2025-03"));
+        assert!(is_valid_search_date("2025-03-01"));
+        assert!(!is_valid_search_date("2025-3-1"));
+        assert!(!is_valid_search_date("yesterday"));
+    }
+}
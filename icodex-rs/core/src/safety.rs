@@ -7,6 +7,7 @@ use icodex_apply_patch::ApplyPatchAction;
 use icodex_apply_patch::ApplyPatchFileChange;
 
 use crate::exec::SandboxType;
+use crate::exec_policy::ExecPolicyVerdict;
 use crate::is_safe_command::is_known_safe_command;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
@@ -78,13 +79,34 @@ pub fn assess_patch_safety(
 /// - the user has explicitly approved the command
 /// - the command is on the "known safe" list
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
-pub fn assess_command_safety(
+///
+/// Commands that an `exec.policy` matched without writes are approved
+/// automatically but still run under the platform sandbox, since the policy
+/// vouches for the command's arguments, not for what the program does.
+/// Commands forbidden by an `exec.policy` are always rejected. Commands whose
+/// `exec.policy` match shows they write outside the writable roots are sent to
+/// the user up front (or rejected under `Never`) rather than being left to fail
+/// inside the sandbox.
+///
+/// `sandbox` is the sandbox commands can run under, if any: a configured
+/// container or the platform sandbox.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn assess_command_safety(
     command: &[String],
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
     with_escalated_permissions: bool,
+    exec_policy: &ExecPolicyVerdict,
+    cwd: &Path,
+    sandbox: Option<SandboxType>,
 ) -> SafetyCheck {
+    if let ExecPolicyVerdict::Forbidden { policy, reason } = exec_policy {
+        return SafetyCheck::Reject {
+            reason: format!("{reason} (forbidden by {})", policy.display()),
+        };
+    }
+
     // A command is "trusted" because either:
     // - it belongs to a set of commands we consider "safe" by default, or
     // - the user has explicitly approved the command for this session
@@ -104,13 +126,47 @@ pub fn assess_command_safety(
         };
     }
 
-    assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
+    if matches!(exec_policy, ExecPolicyVerdict::Safe { .. }) {
+        if matches!(sandbox_policy, SandboxPolicy::DangerFullAccess) {
+            return SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None,
+            };
+        }
+        return match sandbox {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            // Without a sandbox the policy alone is not enough.
+            None => SafetyCheck::AskUser,
+        };
+    }
+
+    if let Some(path) = exec_policy.write_outside_writable_roots(sandbox_policy, cwd) {
+        return match approval_policy {
+            AskForApproval::Never => SafetyCheck::Reject {
+                reason: format!(
+                    "command writes to {}, outside the writable roots; rejected by user approval settings",
+                    path.display()
+                ),
+            },
+            AskForApproval::UnlessTrusted
+            | AskForApproval::OnFailure
+            | AskForApproval::OnRequest => SafetyCheck::AskUser,
+        };
+    }
+
+    assess_safety_for_untrusted_command(
+        approval_policy,
+        sandbox_policy,
+        with_escalated_permissions,
+        sandbox,
+    )
 }
 
+/// `sandbox` is the sandbox the action can run under, if any.
 pub(crate) fn assess_safety_for_untrusted_command(
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     with_escalated_permissions: bool,
+    sandbox: Option<SandboxType>,
 ) -> SafetyCheck {
     use AskForApproval::*;
     use SandboxPolicy::*;
@@ -127,11 +183,13 @@ pub(crate) fn assess_safety_for_untrusted_command(
         | (OnRequest, DangerFullAccess) => SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         },
-        (OnRequest, ReadOnly) | (OnRequest, WorkspaceWrite { .. }) => {
+        (OnRequest, ReadOnly)
+        | (OnRequest, WorkspaceRead { .. })
+        | (OnRequest, WorkspaceWrite { .. }) => {
             if with_escalated_permissions {
                 SafetyCheck::AskUser
             } else {
-                match get_platform_sandbox() {
+                match sandbox {
                     Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
                     // Fall back to asking since the command is untrusted and
                     // we do not have a sandbox available
@@ -140,10 +198,12 @@ pub(crate) fn assess_safety_for_untrusted_command(
             }
         }
         (Never, ReadOnly)
+        | (Never, WorkspaceRead { .. })
         | (Never, WorkspaceWrite { .. })
         | (OnFailure, ReadOnly)
+        | (OnFailure, WorkspaceRead { .. })
         | (OnFailure, WorkspaceWrite { .. }) => {
-            match get_platform_sandbox() {
+            match sandbox {
                 Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
                 None => {
                     if matches!(approval_policy, OnFailure) {
@@ -176,6 +236,42 @@ pub fn get_platform_sandbox() -> Option<SandboxType> {
     }
 }
 
+/// Whether `path` (relative paths are resolved against `cwd`) may be written
+/// under `sandbox_policy`.
+pub(crate) fn is_path_writable_under_policy(
+    path: &Path,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> bool {
+    match sandbox_policy {
+        SandboxPolicy::ReadOnly | SandboxPolicy::WorkspaceRead { .. } => false,
+        SandboxPolicy::DangerFullAccess => true,
+        SandboxPolicy::WorkspaceWrite { .. } => {
+            let abs = normalize(&cwd.join(path));
+            sandbox_policy
+                .get_writable_roots_with_cwd(cwd)
+                .iter()
+                .any(|writable_root| writable_root.is_path_writable(&abs))
+        }
+    }
+}
+
+// Normalize a path by removing `.` and resolving `..` without touching the
+// filesystem (works even if the file does not exist).
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => { /* skip */ }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
 fn is_write_patch_constrained_to_writable_paths(
     action: &ApplyPatchAction,
     sandbox_policy: &SandboxPolicy,
@@ -183,7 +279,7 @@ fn is_write_patch_constrained_to_writable_paths(
 ) -> bool {
     // Early‑exit if there are no declared writable roots.
     let writable_roots = match sandbox_policy {
-        SandboxPolicy::ReadOnly => {
+        SandboxPolicy::ReadOnly | SandboxPolicy::WorkspaceRead { .. } => {
             return false;
         }
         SandboxPolicy::DangerFullAccess => {
@@ -192,22 +288,6 @@ fn is_write_patch_constrained_to_writable_paths(
         SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
     };
 
-    // Normalize a path by removing `.` and resolving `..` without touching the
-    // filesystem (works even if the file does not exist).
-    fn normalize(path: &Path) -> Option<PathBuf> {
-        let mut out = PathBuf::new();
-        for comp in path.components() {
-            match comp {
-                Component::ParentDir => {
-                    out.pop();
-                }
-                Component::CurDir => { /* skip */ }
-                other => out.push(other.as_os_str()),
-            }
-        }
-        Some(out)
-    }
-
     // Determine whether `path` is inside **any** writable root. Both `path`
     // and roots are converted to absolute, normalized forms before the
     // prefix check.
@@ -217,10 +297,7 @@ fn is_write_patch_constrained_to_writable_paths(
         } else {
             cwd.join(p)
         };
-        let abs = match normalize(&abs) {
-            Some(v) => v,
-            None => return false,
-        };
+        let abs = normalize(&abs);
 
         writable_roots
             .iter()
@@ -320,6 +397,9 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            &ExecPolicyVerdict::Unmatched,
+            Path::new("/"),
+            get_platform_sandbox(),
         );
 
         assert_eq!(safety_check, SafetyCheck::AskUser);
@@ -339,6 +419,9 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            &ExecPolicyVerdict::Unmatched,
+            Path::new("/"),
+            get_platform_sandbox(),
         );
 
         let expected = match get_platform_sandbox() {
@@ -347,4 +430,117 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn untrusted_commands_run_in_a_configured_container() {
+        let command = vec!["cargo".to_string(), "build".to_string()];
+        let safety_check = |sandbox| {
+            assess_command_safety(
+                &command,
+                AskForApproval::Never,
+                &SandboxPolicy::new_read_only_policy(),
+                &HashSet::new(),
+                false,
+                &ExecPolicyVerdict::Unmatched,
+                Path::new("/"),
+                sandbox,
+            )
+        };
+
+        assert_eq!(
+            safety_check(Some(SandboxType::Container)),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::Container,
+            }
+        );
+        assert_eq!(
+            safety_check(None),
+            SafetyCheck::Reject {
+                reason: "auto-rejected because command is not on trusted list".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn exec_policy_verdict_overrides_trust_lists() {
+        let policy = PathBuf::from("/repo/.icodex/exec.policy");
+        let approved: HashSet<Vec<String>> = HashSet::from([vec!["rm".to_string()]]);
+
+        let forbidden = assess_command_safety(
+            &["rm".to_string()],
+            AskForApproval::Never,
+            &SandboxPolicy::DangerFullAccess,
+            &approved,
+            false,
+            &ExecPolicyVerdict::Forbidden {
+                policy: policy.clone(),
+                reason: "no deleting".to_string(),
+            },
+            Path::new("/repo"),
+            get_platform_sandbox(),
+        );
+        assert_eq!(
+            forbidden,
+            SafetyCheck::Reject {
+                reason: "no deleting (forbidden by /repo/.icodex/exec.policy)".to_string(),
+            }
+        );
+
+        // Policy-safe commands skip the prompt but not the sandbox.
+        let safe = assess_command_safety(
+            &["cargo".to_string(), "check".to_string()],
+            AskForApproval::UnlessTrusted,
+            &SandboxPolicy::ReadOnly,
+            &HashSet::new(),
+            false,
+            &ExecPolicyVerdict::Safe {
+                policy: policy.clone(),
+            },
+            Path::new("/repo"),
+            get_platform_sandbox(),
+        );
+        let expected = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(safe, expected);
+
+        let writes_outside = ExecPolicyVerdict::Writes {
+            policy,
+            paths: vec![PathBuf::from("../elsewhere/out.txt")],
+        };
+        let workspace_write = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let ask = assess_command_safety(
+            &["touch".to_string(), "../elsewhere/out.txt".to_string()],
+            AskForApproval::OnRequest,
+            &workspace_write,
+            &HashSet::new(),
+            false,
+            &writes_outside,
+            Path::new("/repo"),
+            get_platform_sandbox(),
+        );
+        assert_eq!(ask, SafetyCheck::AskUser);
+        let reject = assess_command_safety(
+            &["touch".to_string(), "../elsewhere/out.txt".to_string()],
+            AskForApproval::Never,
+            &workspace_write,
+            &HashSet::new(),
+            false,
+            &writes_outside,
+            Path::new("/repo"),
+            get_platform_sandbox(),
+        );
+        assert_eq!(
+            reject,
+            SafetyCheck::Reject {
+                reason: "command writes to /repo/../elsewhere/out.txt, outside the writable roots; rejected by user approval settings".to_string(),
+            }
+        );
+    }
 }
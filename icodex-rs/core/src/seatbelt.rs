@@ -3,6 +3,7 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+use crate::exec_limits::CommandLimits;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::StdioPolicy;
@@ -22,6 +23,7 @@ pub async fn spawn_command_under_seatbelt(
     cwd: PathBuf,
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
+    limits: Option<&CommandLimits>,
 ) -> std::io::Result<Child> {
     let args = create_seatbelt_command_args(command, sandbox_policy, &cwd);
     let arg0 = None;
@@ -34,6 +36,7 @@ pub async fn spawn_command_under_seatbelt(
         sandbox_policy,
         stdio_policy,
         env,
+        limits,
     )
     .await
 }
@@ -43,7 +46,7 @@ fn create_seatbelt_command_args(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
 ) -> Vec<String> {
-    let (file_write_policy, extra_cli_args) = {
+    let (file_write_policy, mut extra_cli_args) = {
         if sandbox_policy.has_full_disk_write_access() {
             // Allegedly, this is more permissive than `(allow file-write*)`.
             (
@@ -97,9 +100,27 @@ fn create_seatbelt_command_args(
     };
 
     let file_read_policy = if sandbox_policy.has_full_disk_read_access() {
-        "; allow read-only file operations\n(allow file-read*)"
+        "; allow read-only file operations\n(allow file-read*)".to_string()
     } else {
-        ""
+        let mut readable_folder_policies: Vec<String> = Vec::new();
+        for (index, root) in sandbox_policy
+            .get_readable_roots_with_cwd(cwd)
+            .iter()
+            .enumerate()
+        {
+            let canonical_root = root.canonicalize().unwrap_or_else(|_| root.clone());
+            let root_param = format!("READABLE_ROOT_{index}");
+            extra_cli_args.push(format!(
+                "-D{root_param}={}",
+                canonical_root.to_string_lossy()
+            ));
+            readable_folder_policies.push(format!("(subpath (param \"{root_param}\"))"));
+        }
+        // Path traversal needs metadata for the ancestors of readable roots.
+        format!(
+            "(allow file-read-metadata)\n(allow file-read*\n{}\n)",
+            readable_folder_policies.join(" ")
+        )
     };
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
@@ -300,6 +300,7 @@ mod tests {
         for (input, expected_cmd, expected_output) in cases {
             use std::collections::HashMap;
 
+            use crate::config_types::ExecLimits;
             use crate::exec::ExecParams;
             use crate::exec::SandboxType;
             use crate::exec::process_exec_tool_call;
@@ -347,6 +348,8 @@ mod tests {
                 &SandboxPolicy::DangerFullAccess,
                 &None,
                 None,
+                &ExecLimits::default(),
+                None,
             )
             .await
             .unwrap();
@@ -405,6 +408,7 @@ mod macos_tests {
             use std::collections::HashMap;
             use std::path::PathBuf;
 
+            use crate::config_types::ExecLimits;
             use crate::exec::ExecParams;
             use crate::exec::SandboxType;
             use crate::exec::process_exec_tool_call;
@@ -453,6 +457,8 @@ mod macos_tests {
                 &SandboxPolicy::DangerFullAccess,
                 &None,
                 None,
+                &ExecLimits::default(),
+                None,
             )
             .await
             .unwrap();
@@ -5,6 +5,7 @@ use tokio::process::Child;
 use tokio::process::Command;
 use tracing::trace;
 
+use crate::exec_limits::CommandLimits;
 use crate::protocol::SandboxPolicy;
 
 /// Experimental environment variable that will be set to some non-empty value
@@ -43,6 +44,7 @@ pub(crate) async fn spawn_child_async(
     sandbox_policy: &SandboxPolicy,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    #[cfg_attr(not(unix), allow(unused_variables))] limits: Option<&CommandLimits>,
 ) -> std::io::Result<Child> {
     trace!(
         "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?}"
@@ -85,6 +87,15 @@ pub(crate) async fn spawn_child_async(
         });
     }
 
+    // Put the child in its cgroup and under its rlimits before it execs, so
+    // nothing the command starts escapes them.
+    #[cfg(unix)]
+    if let Some(limits) = limits {
+        unsafe {
+            cmd.pre_exec(limits.pre_exec_hook());
+        }
+    }
+
     match stdio_policy {
         StdioPolicy::RedirectForShellTool => {
             // Do not create a file descriptor for stdin because otherwise some
@@ -0,0 +1,322 @@
+//! The `spawn_agent` tool, which delegates a task to a child conversation.
+//!
+//! The child starts with a fresh history, its own prompt and a sandbox no
+//! wider than the parent's. It never asks for approval: commands the sandbox
+//! rejects fail and the child has to work around them. Its events are
+//! forwarded to the parent's clients nested under the `spawn_agent` call, and
+//! its final message becomes the call's output.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::LazyLock;
+
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+use crate::AuthManager;
+use crate::CodexConversation;
+use crate::ConversationManager;
+use crate::NewConversation;
+use crate::config::Config;
+use crate::error::Result as CodexResult;
+use crate::icodex::Session;
+use crate::icodex::TurnContext;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::AskForApproval;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::SubAgentBeginEvent;
+use crate::protocol::SubAgentEvent;
+use icodex_protocol::models::FunctionCallOutputPayload;
+use icodex_protocol::models::ResponseInputItem;
+
+pub(crate) const SPAWN_AGENT_TOOL_NAME: &str = "spawn_agent";
+
+pub(crate) static SPAWN_AGENT_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "prompt".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Complete instructions for the sub-agent. It does not see this conversation."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "sandbox".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "One of: read-only (default), workspace-write. Never wider than your own sandbox."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "model".to_string(),
+        JsonSchema::String {
+            description: Some("Model for the sub-agent. Defaults to your own.".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: SPAWN_AGENT_TOOL_NAME.to_string(),
+        description: r#"Delegates a self-contained task to a sub-agent and returns its final message.
+The sub-agent starts without this conversation's history, works in the same directory and cannot ask the user for approval.
+Read-only sub-agents requested in the same turn run concurrently.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["prompt".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpawnAgentArgs {
+    prompt: String,
+    #[serde(default)]
+    pub(crate) sandbox: AgentSandbox,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// Sandbox requested for a sub-agent, narrowed to the parent's by
+/// [`narrow_sandbox_policy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum AgentSandbox {
+    #[default]
+    ReadOnly,
+    WorkspaceWrite,
+}
+
+pub(crate) fn parse_spawn_agent_arguments(arguments: &str) -> serde_json::Result<SpawnAgentArgs> {
+    serde_json::from_str(arguments)
+}
+
+/// The policy a sub-agent runs under: `requested`, unless `parent` is more
+/// restrictive.
+pub(crate) fn narrow_sandbox_policy(
+    parent: &SandboxPolicy,
+    requested: AgentSandbox,
+) -> SandboxPolicy {
+    match (parent, requested) {
+        (SandboxPolicy::ReadOnly | SandboxPolicy::WorkspaceRead { .. }, _)
+        | (SandboxPolicy::WorkspaceWrite { .. }, AgentSandbox::WorkspaceWrite) => parent.clone(),
+        (_, AgentSandbox::ReadOnly) => SandboxPolicy::new_read_only_policy(),
+        (SandboxPolicy::DangerFullAccess, AgentSandbox::WorkspaceWrite) => {
+            SandboxPolicy::new_workspace_write_policy()
+        }
+    }
+}
+
+pub(crate) async fn handle_spawn_agent(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let output = match run_sub_agent(sess, turn_context, &sub_id, &arguments, &call_id).await {
+        Ok(message) => FunctionCallOutputPayload {
+            content: message,
+            success: Some(true),
+        },
+        Err(message) => FunctionCallOutputPayload {
+            content: message,
+            success: Some(false),
+        },
+    };
+    ResponseInputItem::FunctionCallOutput { call_id, output }
+}
+
+/// Run the sub-agent to completion and return its final message.
+async fn run_sub_agent(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    arguments: &str,
+    call_id: &str,
+) -> Result<String, String> {
+    let args = parse_spawn_agent_arguments(arguments)
+        .map_err(|e| format!("failed to parse function arguments: {e}"))?;
+    let parent_config = turn_context.client.get_config();
+    if !parent_config.can_spawn_agents() {
+        return Err(format!(
+            "sub-agents may not be nested more than {} level(s) deep",
+            parent_config.agents.max_depth
+        ));
+    }
+    let auth_manager = turn_context
+        .client
+        .get_auth_manager()
+        .ok_or_else(|| "sub-agents are not available without authentication".to_string())?;
+
+    let mut config = match &args.model {
+        Some(model) => parent_config.with_model(model),
+        None => (*parent_config).clone(),
+    };
+    config.cwd = turn_context.cwd.clone();
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = narrow_sandbox_policy(&turn_context.sandbox_policy, args.sandbox);
+    config.agent_depth = parent_config.agent_depth + 1;
+    // The parent's snapshot of this turn already covers the child's changes.
+    config.turn_snapshots = false;
+    config.notify = None;
+
+    let _slot = sess
+        .acquire_agent_slot()
+        .await
+        .map_err(|e| format!("failed to start sub-agent: {e}"))?;
+
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::SubAgentBegin(SubAgentBeginEvent {
+            call_id: call_id.to_string(),
+            model: config.model.clone(),
+            prompt: args.prompt.clone(),
+        }),
+    })
+    .await;
+
+    let NewConversation { conversation, .. } = spawn_conversation(auth_manager, config)
+        .await
+        .map_err(|e| format!("failed to start sub-agent: {e}"))?;
+    let child = ChildConversation(conversation);
+    child
+        .0
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: args.prompt }],
+        })
+        .await
+        .map_err(|e| format!("failed to start sub-agent: {e}"))?;
+
+    let mut last_error = None;
+    loop {
+        let event = child
+            .0
+            .next_event()
+            .await
+            .map_err(|e| format!("sub-agent stopped unexpectedly: {e}"))?;
+        let outcome = match &event.msg {
+            EventMsg::Error(e) => {
+                last_error = Some(e.message.clone());
+                None
+            }
+            EventMsg::TaskComplete(complete) => {
+                Some(match (&complete.last_agent_message, last_error.take()) {
+                    (Some(message), _) => Ok(message.clone()),
+                    (None, Some(error)) => Err(format!("sub-agent failed: {error}")),
+                    (None, None) => Ok("sub-agent finished without a final message".to_string()),
+                })
+            }
+            EventMsg::TurnAborted(aborted) => {
+                Some(Err(format!("sub-agent was aborted: {:?}", aborted.reason)))
+            }
+            _ => None,
+        };
+        sess.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::SubAgent(SubAgentEvent {
+                call_id: call_id.to_string(),
+                msg: Box::new(event.msg),
+            }),
+        })
+        .await;
+        if let Some(outcome) = outcome {
+            return outcome;
+        }
+    }
+}
+
+/// Start the child conversation. Boxed because the child's submission loop
+/// can run [`handle_spawn_agent`] itself, which would otherwise make the
+/// future types recursive.
+fn spawn_conversation(
+    auth_manager: Arc<AuthManager>,
+    config: Config,
+) -> BoxFuture<'static, CodexResult<NewConversation>> {
+    async move {
+        ConversationManager::new(auth_manager)
+            .new_conversation(config)
+            .await
+    }
+    .boxed()
+}
+
+/// Ends the child conversation once the call is done, including when the
+/// parent's turn is interrupted while the child is still working.
+struct ChildConversation(Arc<CodexConversation>);
+
+impl Drop for ChildConversation {
+    fn drop(&mut self) {
+        let conversation = self.0.clone();
+        tokio::spawn(async move {
+            let _ = conversation.submit(Op::Interrupt).await;
+            let _ = conversation.submit(Op::Shutdown).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sandbox_is_never_wider_than_the_parent() {
+        let workspace_write = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec!["/extra".into()],
+            network_access: true,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+        };
+        let cases = [
+            (
+                SandboxPolicy::DangerFullAccess,
+                AgentSandbox::WorkspaceWrite,
+                SandboxPolicy::new_workspace_write_policy(),
+            ),
+            (
+                SandboxPolicy::DangerFullAccess,
+                AgentSandbox::ReadOnly,
+                SandboxPolicy::ReadOnly,
+            ),
+            (
+                workspace_write.clone(),
+                AgentSandbox::WorkspaceWrite,
+                workspace_write.clone(),
+            ),
+            (
+                workspace_write,
+                AgentSandbox::ReadOnly,
+                SandboxPolicy::ReadOnly,
+            ),
+            (
+                SandboxPolicy::ReadOnly,
+                AgentSandbox::WorkspaceWrite,
+                SandboxPolicy::ReadOnly,
+            ),
+        ];
+        for (parent, requested, expected) in cases {
+            assert_eq!(narrow_sandbox_policy(&parent, requested), expected);
+        }
+    }
+
+    #[test]
+    fn sandbox_defaults_to_read_only() {
+        let args = parse_spawn_agent_arguments(r#"{"prompt":"look around"}"#).unwrap();
+        assert_eq!(args.sandbox, AgentSandbox::ReadOnly);
+        assert_eq!(args.model, None);
+    }
+}
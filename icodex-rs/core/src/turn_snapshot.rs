@@ -0,0 +1,388 @@
+//! Snapshots of the workspace taken before each turn, so that a turn can be
+//! rolled back and its diff includes changes made by shell commands, which
+//! [`crate::turn_diff_tracker::TurnDiffTracker`] does not see.
+//!
+//! A snapshot is a commit of the working tree (tracked and untracked files,
+//! honoring `.gitignore`) kept under a private ref of the workspace's git
+//! repository, `refs/icodex/snapshots/<conversation id>/<commit>`. Snapshots
+//! are built in a temporary index, so the user's index, branches and stash are
+//! never touched. A session keeps its last [`MAX_TURN_SNAPSHOTS`] snapshots and
+//! deletes the refs of the others, and of all of them when it ends, leaving
+//! their objects to git's garbage collection.
+
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use icodex_protocol::mcp_protocol::ConversationId;
+use tracing::warn;
+
+use crate::git_info::run_git_for_stdout;
+
+const SNAPSHOT_REF_PREFIX: &str = "refs/icodex/snapshots";
+
+/// Snapshots a session keeps; turns older than that cannot be rolled back.
+pub(crate) const MAX_TURN_SNAPSHOTS: usize = 20;
+
+/// Workspaces whose untracked files add up to more than this are not
+/// snapshotted: hashing them would hold up the turn, and they would be copied
+/// into the repository's object store.
+const MAX_UNTRACKED_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Snapshotting hashes every changed file of the workspace, so allow more
+/// time than the quick lookups in `git_info`.
+const GIT_SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `git checkout-index` takes the paths to restore as arguments; pass them in
+/// batches to stay clear of command line length limits.
+const CHECKOUT_BATCH_SIZE: usize = 256;
+
+/// The workspace as it was before the turn `turn_id` ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TurnSnapshot {
+    turn_id: String,
+    repo_root: PathBuf,
+    commit: String,
+    ref_name: String,
+}
+
+impl TurnSnapshot {
+    /// Snapshot the git repository containing `cwd`. Returns `Ok(None)` when
+    /// `cwd` is not inside a git repository, and an error when its untracked
+    /// files are too large to snapshot.
+    pub(crate) async fn take(
+        cwd: &Path,
+        conversation_id: ConversationId,
+        turn_id: &str,
+    ) -> io::Result<Option<Self>> {
+        let Ok(toplevel) = git(cwd, &["rev-parse", "--show-toplevel"], None).await else {
+            return Ok(None);
+        };
+        let repo_root = PathBuf::from(toplevel.trim());
+
+        let untracked_bytes = untracked_size(&repo_root).await?;
+        if untracked_bytes > MAX_UNTRACKED_BYTES {
+            return Err(io::Error::other(format!(
+                "untracked files take up {} MiB, more than the {} MiB limit for snapshots",
+                untracked_bytes / (1024 * 1024),
+                MAX_UNTRACKED_BYTES / (1024 * 1024)
+            )));
+        }
+
+        let tree = worktree_tree(&repo_root).await?;
+        let message = format!("icodex snapshot before turn {turn_id}");
+        let commit = git(&repo_root, &["commit-tree", &tree, "-m", &message], None)
+            .await?
+            .trim()
+            .to_string();
+        // One ref per snapshot, so that each can be discarded on its own.
+        let ref_name = format!("{SNAPSHOT_REF_PREFIX}/{conversation_id}/{commit}");
+        git(&repo_root, &["update-ref", &ref_name, &commit], None).await?;
+
+        Ok(Some(Self {
+            turn_id: turn_id.to_string(),
+            repo_root,
+            commit,
+            ref_name,
+        }))
+    }
+
+    /// Delete the snapshot's ref. Its objects stay until git collects them.
+    pub(crate) async fn discard(&self) -> io::Result<()> {
+        git(&self.repo_root, &["update-ref", "-d", &self.ref_name], None).await?;
+        Ok(())
+    }
+
+    pub(crate) fn turn_id(&self) -> &str {
+        &self.turn_id
+    }
+
+    /// Unified diff of the workspace against the snapshot, or `None` when
+    /// nothing changed since it was taken.
+    pub(crate) async fn diff(&self) -> io::Result<Option<String>> {
+        let tree = worktree_tree(&self.repo_root).await?;
+        let diff = git(
+            &self.repo_root,
+            &[
+                "diff",
+                "--no-color",
+                "--no-ext-diff",
+                "--no-textconv",
+                &self.commit,
+                &tree,
+            ],
+            None,
+        )
+        .await?;
+        Ok((!diff.is_empty()).then_some(diff))
+    }
+
+    /// Put the workspace back the way it was when the snapshot was taken and
+    /// return the paths that changed.
+    pub(crate) async fn restore(&self) -> io::Result<Vec<PathBuf>> {
+        let tree = worktree_tree(&self.repo_root).await?;
+        let added = self.paths_changed_since(&tree, "A").await?;
+        let changed = self.paths_changed_since(&tree, "DMT").await?;
+
+        for path in &added {
+            let path = self.repo_root.join(path);
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => remove_empty_parents(&self.repo_root, &path).await,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !changed.is_empty() {
+            let index_dir = tempfile::tempdir()?;
+            let index = index_dir.path().join("index");
+            git(&self.repo_root, &["read-tree", &self.commit], Some(&index)).await?;
+            for batch in changed.chunks(CHECKOUT_BATCH_SIZE) {
+                let mut args = vec!["checkout-index", "--force", "--"];
+                args.extend(batch.iter().map(String::as_str));
+                git(&self.repo_root, &args, Some(&index)).await?;
+            }
+        }
+
+        Ok(added
+            .iter()
+            .chain(&changed)
+            .map(|path| self.repo_root.join(path))
+            .collect())
+    }
+
+    /// Repository-relative paths whose change from the snapshot to `tree`
+    /// matches `diff_filter`.
+    async fn paths_changed_since(&self, tree: &str, diff_filter: &str) -> io::Result<Vec<String>> {
+        let diff_filter = format!("--diff-filter={diff_filter}");
+        let output = git(
+            &self.repo_root,
+            &[
+                "diff",
+                "--name-only",
+                "-z",
+                "--no-renames",
+                &diff_filter,
+                &self.commit,
+                tree,
+            ],
+            None,
+        )
+        .await?;
+        Ok(output
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+/// Discard `snapshots`, logging the ones that fail.
+pub(crate) async fn discard_snapshots(snapshots: &[TurnSnapshot]) {
+    for snapshot in snapshots {
+        if let Err(e) = snapshot.discard().await {
+            warn!(
+                "failed to discard the snapshot of turn {}: {e}",
+                snapshot.turn_id()
+            );
+        }
+    }
+}
+
+/// Total size of the untracked, not ignored files of `repo_root`.
+async fn untracked_size(repo_root: &Path) -> io::Result<u64> {
+    let output = git(
+        repo_root,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+        None,
+    )
+    .await?;
+    let mut total = 0;
+    for path in output.split('\0').filter(|path| !path.is_empty()) {
+        if let Ok(metadata) = tokio::fs::symlink_metadata(repo_root.join(path)).await {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Write the current working tree of `repo_root` to a tree object.
+async fn worktree_tree(repo_root: &Path) -> io::Result<String> {
+    let index_dir = tempfile::tempdir()?;
+    let index = index_dir.path().join("index");
+
+    // Start from the repository's own index so that files unchanged since it
+    // was last refreshed are not hashed again.
+    if let Ok(path) = git(repo_root, &["rev-parse", "--git-path", "index"], None).await {
+        let _ = tokio::fs::copy(repo_root.join(path.trim()), &index).await;
+    }
+    if git(repo_root, &["add", "--all"], Some(&index))
+        .await
+        .is_err()
+    {
+        // The copied index may use extensions that do not survive being moved
+        // (such as a split index); build one from scratch instead.
+        let _ = tokio::fs::remove_file(&index).await;
+        git(repo_root, &["add", "--all"], Some(&index)).await?;
+    }
+
+    Ok(git(repo_root, &["write-tree"], Some(&index))
+        .await?
+        .trim()
+        .to_string())
+}
+
+/// Remove the directories between `path` and `repo_root` that removing `path`
+/// left empty.
+async fn remove_empty_parents(repo_root: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir
+        && current != repo_root
+        && current.starts_with(repo_root)
+    {
+        // Fails, and stops the walk, once a directory is not empty.
+        if tokio::fs::remove_dir(current).await.is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// Run `git` in `cwd`, with `index` as the index when given, and return its
+/// standard output.
+async fn git(cwd: &Path, args: &[&str], index: Option<&Path>) -> io::Result<String> {
+    // Snapshot commits must not depend on the user having configured an
+    // identity.
+    let mut envs: Vec<(&str, &OsStr)> = vec![
+        ("GIT_AUTHOR_NAME", OsStr::new("icodex")),
+        ("GIT_AUTHOR_EMAIL", OsStr::new("icodex@localhost")),
+        ("GIT_COMMITTER_NAME", OsStr::new("icodex")),
+        ("GIT_COMMITTER_EMAIL", OsStr::new("icodex@localhost")),
+    ];
+    if let Some(index) = index {
+        envs.push(("GIT_INDEX_FILE", index.as_os_str()));
+    }
+    run_git_for_stdout(cwd, args, &envs, GIT_SNAPSHOT_TIMEOUT).await
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn init_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let status = std::process::Command::new("git")
+            .args(["init", "--quiet"])
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        fs::write(dir.path().join("kept.txt"), "kept\n").unwrap();
+        fs::write(dir.path().join("edited.txt"), "before\n").unwrap();
+        fs::write(dir.path().join("removed.txt"), "removed\n").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn restore_undoes_edits_additions_and_deletions() {
+        let repo = init_repo();
+        let root = repo.path().canonicalize().unwrap();
+        let snapshot = TurnSnapshot::take(&root, ConversationId::default(), "1")
+            .await
+            .unwrap()
+            .unwrap();
+
+        // What a shell command run during the turn might do.
+        fs::write(root.join("edited.txt"), "after\n").unwrap();
+        fs::remove_file(root.join("removed.txt")).unwrap();
+        fs::create_dir_all(root.join("new/dir")).unwrap();
+        fs::write(root.join("new/dir/added.txt"), "added\n").unwrap();
+        fs::create_dir(root.join("target")).unwrap();
+        fs::write(root.join("target/ignored.txt"), "ignored\n").unwrap();
+
+        let diff = snapshot.diff().await.unwrap().unwrap();
+        assert!(diff.contains("+after"), "{diff}");
+        assert!(diff.contains("new/dir/added.txt"), "{diff}");
+        assert!(!diff.contains("ignored.txt"), "{diff}");
+
+        let mut restored = snapshot.restore().await.unwrap();
+        restored.sort();
+        assert_eq!(
+            restored,
+            vec![
+                root.join("edited.txt"),
+                root.join("new/dir/added.txt"),
+                root.join("removed.txt"),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("edited.txt")).unwrap(),
+            "before\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("removed.txt")).unwrap(),
+            "removed\n"
+        );
+        assert!(!root.join("new").exists());
+        assert!(root.join("target/ignored.txt").exists());
+        assert_eq!(snapshot.diff().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn discard_deletes_only_its_own_ref() {
+        let repo = init_repo();
+        let root = repo.path().canonicalize().unwrap();
+        let conversation_id = ConversationId::default();
+        let first = TurnSnapshot::take(&root, conversation_id, "1")
+            .await
+            .unwrap()
+            .unwrap();
+        fs::write(root.join("edited.txt"), "after\n").unwrap();
+        let second = TurnSnapshot::take(&root, conversation_id, "2")
+            .await
+            .unwrap()
+            .unwrap();
+
+        first.discard().await.unwrap();
+
+        let refs = git(
+            &root,
+            &["for-each-ref", "--format=%(refname)", SNAPSHOT_REF_PREFIX],
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(refs, format!("{}\n", second.ref_name));
+    }
+
+    #[tokio::test]
+    async fn large_untracked_files_are_not_snapshotted() {
+        let repo = init_repo();
+        let root = repo.path().canonicalize().unwrap();
+        let file = fs::File::create(root.join("big.bin")).unwrap();
+        file.set_len(MAX_UNTRACKED_BYTES + 1).unwrap();
+
+        let err = TurnSnapshot::take(&root, ConversationId::default(), "1")
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("limit for snapshots"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn no_snapshot_outside_a_git_repository() {
+        let dir = TempDir::new().unwrap();
+        let snapshot = TurnSnapshot::take(dir.path(), ConversationId::default(), "1")
+            .await
+            .unwrap();
+        assert_eq!(snapshot, None);
+    }
+}
@@ -171,7 +171,7 @@ async fn compact_resume_and_fork_preserve_model_history_view() {
       ],
       "tools": tool_calls,
       "tool_choice": "auto",
-      "parallel_tool_calls": false,
+      "parallel_tool_calls": true,
       "reasoning": {
         "summary": "auto"
       },
@@ -244,7 +244,7 @@ async fn compact_resume_and_fork_preserve_model_history_view() {
       ],
       "tools": [],
       "tool_choice": "auto",
-      "parallel_tool_calls": false,
+      "parallel_tool_calls": true,
       "reasoning": {
         "summary": "auto"
       },
@@ -309,7 +309,7 @@ SUMMARY_ONLY_CONTEXT"
       ],
       "tools": tool_calls,
       "tool_choice": "auto",
-      "parallel_tool_calls": false,
+      "parallel_tool_calls": true,
       "reasoning": {
         "summary": "auto"
       },
@@ -394,7 +394,7 @@ SUMMARY_ONLY_CONTEXT"
       ],
       "tools": tool_calls,
       "tool_choice": "auto",
-      "parallel_tool_calls": false,
+      "parallel_tool_calls": true,
       "reasoning": {
         "summary": "auto"
       },
@@ -479,7 +479,7 @@ SUMMARY_ONLY_CONTEXT"
       ],
       "tools": tool_calls,
       "tool_choice": "auto",
-      "parallel_tool_calls": false,
+      "parallel_tool_calls": true,
       "reasoning": {
         "summary": "auto"
       },
@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 
+use icodex_core::config_types::ExecLimits;
 use icodex_core::exec::ExecParams;
 use icodex_core::exec::ExecToolCallOutput;
 use icodex_core::exec::SandboxType;
@@ -39,7 +40,16 @@ async fn run_test_cmd(tmp: TempDir, cmd: Vec<&str>) -> Result<ExecToolCallOutput
 
     let policy = SandboxPolicy::new_read_only_policy();
 
-    process_exec_tool_call(params, sandbox_type, &policy, &None, None).await
+    process_exec_tool_call(
+        params,
+        sandbox_type,
+        &policy,
+        &None,
+        None,
+        &ExecLimits::default(),
+        None,
+    )
+    .await
 }
 
 /// Command succeeds with exit code 0 normally
@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use async_channel::Receiver;
+use icodex_core::config_types::ExecLimits;
 use icodex_core::error::CodexErr;
 use icodex_core::error::SandboxErr;
 use icodex_core::exec::ExecParams;
@@ -14,6 +15,7 @@ use icodex_core::exec::process_exec_tool_call;
 use icodex_core::protocol::Event;
 use icodex_core::protocol::EventMsg;
 use icodex_core::protocol::ExecCommandOutputDeltaEvent;
+use icodex_core::protocol::ExecLimitExceeded;
 use icodex_core::protocol::ExecOutputStream;
 use icodex_core::protocol::SandboxPolicy;
 
@@ -65,6 +67,8 @@ async fn test_exec_stdout_stream_events_echo() {
         SandboxType::None,
         &policy,
         &None,
+        None,
+        &ExecLimits::default(),
         Some(stdout_stream),
     )
     .await;
@@ -115,6 +119,8 @@ async fn test_exec_stderr_stream_events_echo() {
         SandboxType::None,
         &policy,
         &None,
+        None,
+        &ExecLimits::default(),
         Some(stdout_stream),
     )
     .await;
@@ -163,9 +169,17 @@ async fn test_aggregated_output_interleaves_in_order() {
 
     let policy = SandboxPolicy::new_read_only_policy();
 
-    let result = process_exec_tool_call(params, SandboxType::None, &policy, &None, None)
-        .await
-        .expect("process_exec_tool_call");
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::None,
+        &policy,
+        &None,
+        None,
+        &ExecLimits::default(),
+        None,
+    )
+    .await
+    .expect("process_exec_tool_call");
 
     assert_eq!(result.exit_code, 0);
     assert_eq!(result.stdout.text, "O1\nO2\n");
@@ -193,7 +207,16 @@ async fn test_exec_timeout_returns_partial_output() {
 
     let policy = SandboxPolicy::new_read_only_policy();
 
-    let result = process_exec_tool_call(params, SandboxType::None, &policy, &None, None).await;
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::None,
+        &policy,
+        &None,
+        None,
+        &ExecLimits::default(),
+        None,
+    )
+    .await;
 
     let Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) = result else {
         panic!("expected timeout error");
@@ -206,3 +229,44 @@ async fn test_exec_timeout_returns_partial_output() {
     assert!(output.duration >= Duration::from_millis(200));
     assert!(output.timed_out);
 }
+
+#[tokio::test]
+async fn test_exec_output_limit_kills_command() {
+    let cmd = vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        "printf 'abcdefghij'; sleep 5; printf 'never'".to_string(),
+    ];
+
+    let params = ExecParams {
+        command: cmd,
+        cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        timeout_ms: Some(10_000),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+
+    let policy = SandboxPolicy::new_read_only_policy();
+    let limits = ExecLimits {
+        max_output_bytes: Some(4),
+        ..Default::default()
+    };
+
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::None,
+        &policy,
+        &None,
+        None,
+        &limits,
+        None,
+    )
+    .await
+    .expect("process_exec_tool_call");
+
+    assert_eq!(result.limit_exceeded, Some(ExecLimitExceeded::OutputLimit));
+    assert_eq!(result.exit_code, 137);
+    assert_eq!(result.aggregated_output.text, "abcd");
+    assert!(result.duration < Duration::from_secs(5));
+}
@@ -0,0 +1,193 @@
+//! Exercises the native Gemini client against a mock `streamGenerateContent`
+//! endpoint.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use core_test_support::load_default_config_for_test;
+use futures::StreamExt;
+use icodex_core::ContentItem;
+use icodex_core::ModelClient;
+use icodex_core::ModelProviderInfo;
+use icodex_core::Prompt;
+use icodex_core::ResponseEvent;
+use icodex_core::ResponseItem;
+use icodex_core::WireApi;
+use icodex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use icodex_protocol::mcp_protocol::ConversationId;
+use icodex_protocol::models::FunctionCallOutputPayload;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::matchers::query_param;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_function_calls_and_retries_server_errors() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    let provider = ModelProviderInfo {
+        name: "gemini".into(),
+        base_url: Some(format!("{}/v1beta", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::Gemini,
+        query_params: Some(HashMap::from([("key".to_string(), "test-key".to_string())])),
+        http_headers: Some(HashMap::from([(
+            "x-goog-user-project".to_string(),
+            "demo".to_string(),
+        )])),
+        env_http_headers: None,
+        request_max_retries: Some(1),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+    };
+
+    let icodex_home = TempDir::new().expect("tempdir");
+    let mut config = load_default_config_for_test(&icodex_home);
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    let endpoint = format!(
+        "/v1beta/models/{}:streamGenerateContent",
+        config.model_family.slug
+    );
+
+    // The first attempt fails with a retryable status.
+    Mock::given(method("POST"))
+        .and(path(endpoint.as_str()))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let body: String = [
+        json!({"candidates": [{"content": {"role": "model", "parts": [{"text": "Checking"}]}}], "responseId": "resp_1"}),
+        json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [
+                    {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}},
+                ]},
+                "finishReason": "STOP",
+            }],
+            "usageMetadata": {"promptTokenCount": 20, "candidatesTokenCount": 4, "totalTokenCount": 24},
+        }),
+    ]
+    .iter()
+    .map(|chunk| format!("data: {chunk}\n\n"))
+    .collect();
+    Mock::given(method("POST"))
+        .and(path(endpoint.as_str()))
+        .and(query_param("alt", "sse"))
+        .and(query_param("key", "test-key"))
+        .and(header("x-goog-user-project", "demo"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(body, "text/event-stream"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let client = ModelClient::new(
+        Arc::new(config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = vec![
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: "what is here?".to_string(),
+            }],
+        },
+        ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: r#"{"command":["pwd"]}"#.to_string(),
+            call_id: "call_1".to_string(),
+        },
+        ResponseItem::FunctionCallOutput {
+            call_id: "call_1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "/repo".to_string(),
+                success: Some(true),
+            },
+        },
+    ];
+
+    let mut stream = client.stream(&prompt).await.expect("stream");
+    let mut events = Vec::new();
+    while let Some(event) = stream.next().await {
+        events.push(event.expect("stream event"));
+    }
+
+    let items: Vec<&ResponseItem> = events
+        .iter()
+        .filter_map(|event| match event {
+            ResponseEvent::OutputItemDone(item) => Some(item),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(items.len(), 2);
+    assert!(matches!(
+        items[0],
+        ResponseItem::Message { content, .. }
+            if content == &vec![ContentItem::OutputText { text: "Checking".to_string() }]
+    ));
+    assert!(matches!(
+        items[1],
+        ResponseItem::FunctionCall { name, arguments, call_id, .. }
+            if name == "shell" && arguments == r#"{"command":["ls"]}"# && !call_id.is_empty()
+    ));
+    match events.last() {
+        Some(ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(usage),
+        }) => {
+            assert_eq!(response_id, "resp_1");
+            assert_eq!(usage.input_tokens, 20);
+            assert_eq!(usage.output_tokens, 4);
+            assert_eq!(usage.total_tokens, 24);
+        }
+        other => panic!("unexpected last event: {other:?}"),
+    }
+
+    let requests = server.received_requests().await.expect("requests");
+    assert_eq!(requests.len(), 2);
+    let request: Value = requests[1].body_json().expect("json body");
+    assert!(request["systemInstruction"]["parts"][0]["text"].is_string());
+    assert_eq!(
+        request["contents"],
+        json!([
+            {"role": "user", "parts": [{"text": "what is here?"}]},
+            {"role": "model", "parts": [
+                {"functionCall": {"name": "shell", "args": {"command": ["pwd"]}}},
+            ]},
+            {"role": "user", "parts": [
+                {"functionResponse": {"name": "shell", "response": {"content": "/repo"}}},
+            ]},
+        ])
+    );
+}
@@ -0,0 +1,184 @@
+//! Drives `ModelClient` against the `fake-lmi-bridge` binary to verify how
+//! the LMI bridge is started, health-checked and restarted.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use core_test_support::load_default_config_for_test;
+use futures::StreamExt;
+use icodex_core::ContentItem;
+use icodex_core::ModelClient;
+use icodex_core::ModelProviderInfo;
+use icodex_core::Prompt;
+use icodex_core::ResponseEvent;
+use icodex_core::ResponseItem;
+use icodex_core::WireApi;
+use icodex_core::error::CodexErr;
+use icodex_core::error::LmiBridgeError;
+use icodex_protocol::mcp_protocol::ConversationId;
+use tempfile::TempDir;
+
+const FAKE_BRIDGE: &str = env!("CARGO_BIN_EXE_fake-lmi-bridge");
+
+fn client_for(icodex_home: &TempDir, command: Vec<String>, idle_timeout_ms: u64) -> ModelClient {
+    let provider = ModelProviderInfo {
+        name: "fake".into(),
+        base_url: None,
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::LmiBridge,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(idle_timeout_ms),
+        requires_openai_auth: false,
+    };
+
+    let mut config = load_default_config_for_test(icodex_home);
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    config.lmi_bridge_command = Some(command);
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+
+    ModelClient::new(
+        Arc::new(config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    )
+}
+
+fn fake_bridge(mode: &str, marker: Option<&Path>) -> Vec<String> {
+    let mut command = vec![FAKE_BRIDGE.to_string(), mode.to_string()];
+    if let Some(marker) = marker {
+        command.push(marker.display().to_string());
+    }
+    command
+}
+
+fn prompt(text: &str) -> Prompt {
+    let mut prompt = Prompt::default();
+    prompt.input = vec![ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: text.to_string(),
+        }],
+    }];
+    prompt
+}
+
+/// Collects every event of one turn, stopping at the first error.
+async fn collect(client: &ModelClient, text: &str) -> Result<Vec<ResponseEvent>, CodexErr> {
+    let mut stream = client.stream(&prompt(text)).await?;
+    let mut events = Vec::new();
+    while let Some(event) = stream.next().await {
+        events.push(event?);
+    }
+    Ok(events)
+}
+
+fn assistant_text(events: &[ResponseEvent]) -> Option<String> {
+    events.iter().find_map(|event| match event {
+        ResponseEvent::OutputItemDone(ResponseItem::Message { content, .. }) => {
+            content.iter().find_map(|part| match part {
+                ContentItem::OutputText { text } => Some(text.clone()),
+                _ => None,
+            })
+        }
+        _ => None,
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_response_after_handshake() {
+    let home = TempDir::new().expect("tempdir");
+    let client = client_for(&home, fake_bridge("echo", None), 5_000);
+
+    let events = collect(&client, "hello bridge").await.expect("stream");
+
+    assert!(matches!(events.first(), Some(ResponseEvent::Created)));
+    assert_eq!(assistant_text(&events).as_deref(), Some("hello bridge"));
+    assert!(matches!(
+        events.last(),
+        Some(ResponseEvent::Completed { response_id, .. }) if response_id == "fake-response"
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn rejects_incompatible_protocol_version() {
+    let home = TempDir::new().expect("tempdir");
+    let client = client_for(&home, fake_bridge("bad-version", None), 5_000);
+
+    let err = collect(&client, "hi")
+        .await
+        .expect_err("handshake should fail");
+
+    assert!(matches!(
+        err,
+        CodexErr::LmiBridge(LmiBridgeError::IncompatibleProtocol {
+            expected: 1,
+            found: 999
+        })
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reports_missing_bridge_executable() {
+    let home = TempDir::new().expect("tempdir");
+    let missing = home.path().join("no-such-bridge").display().to_string();
+    let client = client_for(&home, vec![missing.clone()], 5_000);
+
+    let err = collect(&client, "hi").await.expect_err("spawn should fail");
+
+    match err {
+        CodexErr::LmiBridge(LmiBridgeError::Spawn { command, .. }) => assert_eq!(command, missing),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn restarts_bridge_after_crash() {
+    let home = TempDir::new().expect("tempdir");
+    let marker = home.path().join("crashed-once");
+    let client = client_for(
+        &home,
+        fake_bridge("crash-after-hello", Some(&marker)),
+        5_000,
+    );
+
+    let err = collect(&client, "first").await.expect_err("bridge crashes");
+    assert!(matches!(err, CodexErr::LmiBridge(LmiBridgeError::Exited)));
+    assert!(marker.exists());
+
+    let events = collect(&client, "second").await.expect("restarted bridge");
+    assert_eq!(assistant_text(&events).as_deref(), Some("second"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn silent_bridge_hits_idle_timeout() {
+    let home = TempDir::new().expect("tempdir");
+    let client = client_for(&home, fake_bridge("silent", None), 300);
+
+    let err = collect(&client, "hi").await.expect_err("should time out");
+
+    assert!(matches!(err, CodexErr::Stream(..)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn garbage_output_is_reported_as_invalid() {
+    let home = TempDir::new().expect("tempdir");
+    let client = client_for(&home, fake_bridge("garbage", None), 5_000);
+
+    let err = collect(&client, "hi").await.expect_err("should fail");
+
+    assert!(matches!(
+        err,
+        CodexErr::LmiBridge(LmiBridgeError::InvalidOutput(_))
+    ));
+}
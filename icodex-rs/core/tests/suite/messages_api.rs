@@ -0,0 +1,236 @@
+//! Exercises the native Messages API client against a mock `/v1/messages`
+//! endpoint.
+
+use std::sync::Arc;
+
+use core_test_support::load_default_config_for_test;
+use futures::StreamExt;
+use icodex_core::ContentItem;
+use icodex_core::ModelClient;
+use icodex_core::ModelProviderInfo;
+use icodex_core::Prompt;
+use icodex_core::ResponseEvent;
+use icodex_core::ResponseItem;
+use icodex_core::WireApi;
+use icodex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use icodex_protocol::mcp_protocol::ConversationId;
+use icodex_protocol::models::FunctionCallOutputPayload;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn network_disabled() -> bool {
+    std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok()
+}
+
+fn sse(events: &[Value]) -> String {
+    events
+        .iter()
+        .map(|event| {
+            let kind = event["type"].as_str().unwrap_or_default();
+            format!("event: {kind}\ndata: {event}\n\n")
+        })
+        .collect()
+}
+
+fn provider_for(server: &MockServer) -> ModelProviderInfo {
+    ModelProviderInfo {
+        name: "claude".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::Messages,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_tool_use_and_sends_tool_results() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    let body = sse(&[
+        json!({"type": "message_start", "message": {"id": "msg_1", "usage": {"input_tokens": 12}}}),
+        json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Listing"}}),
+        json!({"type": "content_block_stop", "index": 0}),
+        json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_2", "name": "shell", "input": {}}}),
+        json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"command\":[\"ls\"]}"}}),
+        json!({"type": "content_block_stop", "index": 1}),
+        json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 7}}),
+        json!({"type": "message_stop"}),
+    ]);
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(body, "text/event-stream"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = provider_for(&server);
+    let icodex_home = TempDir::new().expect("tempdir");
+    let mut config = load_default_config_for_test(&icodex_home);
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    config.model_max_output_tokens = Some(1_024);
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let client = ModelClient::new(
+        Arc::new(config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = vec![
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: "what is here?".to_string(),
+            }],
+        },
+        ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: r#"{"command":["pwd"]}"#.to_string(),
+            call_id: "toolu_1".to_string(),
+        },
+        ResponseItem::FunctionCallOutput {
+            call_id: "toolu_1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "/repo".to_string(),
+                success: Some(true),
+            },
+        },
+    ];
+
+    let mut stream = client.stream(&prompt).await.expect("stream");
+    let mut events = Vec::new();
+    while let Some(event) = stream.next().await {
+        events.push(event.expect("stream event"));
+    }
+
+    let items: Vec<&ResponseItem> = events
+        .iter()
+        .filter_map(|event| match event {
+            ResponseEvent::OutputItemDone(item) => Some(item),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(items.len(), 2);
+    assert!(matches!(
+        items[0],
+        ResponseItem::Message { content, .. }
+            if content == &vec![ContentItem::OutputText { text: "Listing".to_string() }]
+    ));
+    assert!(matches!(
+        items[1],
+        ResponseItem::FunctionCall { name, arguments, call_id, .. }
+            if name == "shell" && arguments == r#"{"command":["ls"]}"# && call_id == "toolu_2"
+    ));
+    match events.last() {
+        Some(ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(usage),
+        }) => {
+            assert_eq!(response_id, "msg_1");
+            assert_eq!(usage.input_tokens, 12);
+            assert_eq!(usage.output_tokens, 7);
+        }
+        other => panic!("unexpected last event: {other:?}"),
+    }
+
+    let requests = server.received_requests().await.expect("requests");
+    let request: Value = requests[0].body_json().expect("json body");
+    assert_eq!(request["max_tokens"], 1_024);
+    assert_eq!(request["stream"], true);
+    assert_eq!(request["system"][0]["cache_control"]["type"], "ephemeral");
+    assert_eq!(
+        request["messages"],
+        json!([
+            {"role": "user", "content": [{"type": "text", "text": "what is here?"}]},
+            {"role": "assistant", "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "shell",
+                "input": {"command": ["pwd"]},
+            }]},
+            {"role": "user", "content": [{
+                "type": "tool_result",
+                "tool_use_id": "toolu_1",
+                "content": "/repo",
+                "is_error": false,
+                "cache_control": {"type": "ephemeral"},
+            }]},
+        ])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn surfaces_client_errors_with_body() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(400).set_body_string(
+            r#"{"type":"error","error":{"type":"invalid_request_error","message":"bad"}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = provider_for(&server);
+    let icodex_home = TempDir::new().expect("tempdir");
+    let mut config = load_default_config_for_test(&icodex_home);
+    config.model_provider = provider.clone();
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let client = ModelClient::new(
+        Arc::new(config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let Err(err) = client.stream(&Prompt::default()).await else {
+        panic!("400 should fail");
+    };
+    assert!(
+        err.to_string().contains("invalid_request_error"),
+        "unexpected error: {err}"
+    );
+}
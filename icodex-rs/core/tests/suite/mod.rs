@@ -7,11 +7,18 @@ mod compact_resume_fork;
 mod exec;
 mod exec_stream_events;
 mod fork_conversation;
+mod gemini;
 mod live_cli;
+mod lmi_bridge;
+mod messages_api;
 mod model_overrides;
+mod parallel_tool_calls;
 mod prompt_caching;
+mod provider_fallback;
 mod review;
 mod rollout_list_find;
 mod seatbelt;
+mod spawn_agent;
 mod stream_error_allows_next_turn;
 mod stream_no_completed;
+mod turn_rollback;
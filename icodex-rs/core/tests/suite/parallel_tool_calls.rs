@@ -0,0 +1,195 @@
+#![expect(clippy::unwrap_used)]
+
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+
+use icodex_core::CodexAuth;
+use icodex_core::ConversationManager;
+use icodex_core::ModelProviderInfo;
+use icodex_core::built_in_model_providers;
+use icodex_core::protocol::AskForApproval;
+use icodex_core::protocol::EventMsg;
+use icodex_core::protocol::InputItem;
+use icodex_core::protocol::Op;
+use icodex_core::protocol::SandboxPolicy;
+use icodex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::load_default_config_for_test;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+use super::compact::ev_assistant_message;
+use super::compact::ev_completed;
+use super::compact::sse;
+use super::compact::sse_response;
+
+fn shell_call(call_id: &str, command: &[&str]) -> Value {
+    json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "function_call",
+            "call_id": call_id,
+            "name": "shell",
+            "arguments": json!({ "command": command }).to_string(),
+        }
+    })
+}
+
+/// Read-only calls run concurrently, but a call that writes waits for them,
+/// and the model gets every result in the order of the calls. Each read first
+/// reads a FIFO that is only written once both reads have begun, so the test
+/// cannot finish if the reads run one after another.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn read_only_calls_finish_before_a_write_and_keep_call_order() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let workspace = TempDir::new().unwrap();
+    let cwd = workspace.path().canonicalize().unwrap();
+    fs::write(cwd.join("a.txt"), "alpha\n").unwrap();
+    fs::write(cwd.join("b.txt"), "beta\n").unwrap();
+    for fifo in ["a.fifo", "b.fifo"] {
+        let status = Command::new("mkfifo").arg(cwd.join(fifo)).status().unwrap();
+        assert!(status.success());
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("look and then edit"))
+        .respond_with(sse_response(sse(vec![
+            shell_call("read-a", &["cat", "a.fifo", "a.txt"]),
+            shell_call("read-b", &["cat", "b.fifo", "b.txt"]),
+            shell_call("write-a", &["sh", "-c", "echo changed > a.txt"]),
+            ev_completed("r1"),
+        ])))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "done"),
+            ev_completed("r2"),
+        ])))
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.cwd = cwd.clone();
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let icodex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    icodex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "look and then edit".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    // ("begin" | "end", call_id) in the order the events arrive.
+    let mut exec_events = Vec::new();
+    wait_for_event_with_timeout(
+        &icodex,
+        |ev| match ev {
+            EventMsg::ExecCommandBegin(ev) => {
+                exec_events.push(("begin", ev.call_id.clone()));
+                if exec_events.len() == 2 {
+                    // Opening a FIFO for writing waits for its reader.
+                    let cwd = cwd.clone();
+                    std::thread::spawn(move || {
+                        for fifo in ["a.fifo", "b.fifo"] {
+                            fs::write(cwd.join(fifo), "fifo\n").unwrap();
+                        }
+                    });
+                }
+                false
+            }
+            EventMsg::ExecCommandEnd(ev) => {
+                exec_events.push(("end", ev.call_id.clone()));
+                false
+            }
+            ev => matches!(ev, EventMsg::TaskComplete(_)),
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    // Both reads began before either of them ended.
+    assert!(
+        exec_events[..2].iter().all(|(kind, _)| *kind == "begin"),
+        "{exec_events:?}"
+    );
+
+    // Every call begins once and ends once, after it began.
+    for call_id in ["read-a", "read-b", "write-a"] {
+        let position = |kind: &str| {
+            let matching: Vec<usize> = exec_events
+                .iter()
+                .enumerate()
+                .filter(|(_, (k, id))| *k == kind && id == call_id)
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(matching.len(), 1, "{call_id}: {exec_events:?}");
+            matching[0]
+        };
+        assert!(position("begin") < position("end"), "{exec_events:?}");
+    }
+    // The write starts only after both reads have ended.
+    let write_begin = exec_events
+        .iter()
+        .position(|event| *event == ("begin", "write-a".to_string()))
+        .unwrap();
+    let reads_ended = exec_events[..write_begin]
+        .iter()
+        .filter(|(kind, _)| *kind == "end")
+        .count();
+    assert_eq!(reads_ended, 2, "{exec_events:?}");
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let follow_up: Value = requests[1].body_json().unwrap();
+    let outputs: Vec<(&str, &str)> = follow_up["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["type"] == "function_call_output")
+        .map(|item| {
+            (
+                item["call_id"].as_str().unwrap(),
+                item["output"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    let call_ids: Vec<&str> = outputs.iter().map(|(call_id, _)| *call_id).collect();
+    assert_eq!(call_ids, vec!["read-a", "read-b", "write-a"]);
+    // The read of a.txt saw the file before the write changed it.
+    assert!(outputs[0].1.contains("alpha"), "{}", outputs[0].1);
+    assert!(outputs[1].1.contains("beta"), "{}", outputs[1].1);
+    assert_eq!(fs::read_to_string(cwd.join("a.txt")).unwrap(), "changed\n");
+}
@@ -0,0 +1,154 @@
+use core_test_support::load_default_config_for_test;
+use core_test_support::load_sse_fixture_with_id;
+use core_test_support::wait_for_event;
+use icodex_core::CodexAuth;
+use icodex_core::CodexConversation;
+use icodex_core::ConversationManager;
+use icodex_core::FallbackProvider;
+use icodex_core::ModelProviderInfo;
+use icodex_core::NewConversation;
+use icodex_core::WireApi;
+use icodex_core::protocol::EventMsg;
+use icodex_core::protocol::InputItem;
+use icodex_core::protocol::Op;
+use icodex_core::protocol::RolloutItem;
+use icodex_core::protocol::RolloutLine;
+use icodex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use serde_json::Value;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn sse_completed(id: &str) -> String {
+    load_sse_fixture_with_id("tests/fixtures/completed_template.json", id)
+}
+
+fn provider_for(name: &str, server: &MockServer) -> ModelProviderInfo {
+    ModelProviderInfo {
+        name: name.into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: Some("PATH".into()),
+        env_key_instructions: None,
+        wire_api: WireApi::Responses,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(2_000),
+        requires_openai_auth: false,
+    }
+}
+
+async fn send_turn(icodex: &CodexConversation, text: &str) {
+    icodex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: text.into() }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(icodex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fails_over_to_fallback_provider_and_records_the_switch() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let primary = MockServer::start().await;
+    let backup = MockServer::start().await;
+
+    // The primary is only tried once: after the failover the backup stays
+    // active for the rest of the session.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&primary)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(sse_completed("resp_backup"), "text/event-stream"),
+        )
+        .expect(2)
+        .mount(&backup)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider_id = "primary".to_string();
+    config.model_provider = provider_for("primary", &primary);
+    config.fallback_providers = vec![FallbackProvider {
+        provider_id: "backup".to_string(),
+        provider: provider_for("backup", &backup),
+        model: Some("gpt-5-mini".to_string()),
+    }];
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let NewConversation {
+        conversation: icodex,
+        session_configured,
+        ..
+    } = conversation_manager.new_conversation(config).await.unwrap();
+
+    icodex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "first turn".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let EventMsg::BackgroundEvent(event) =
+        wait_for_event(&icodex, |ev| matches!(ev, EventMsg::BackgroundEvent(_))).await
+    else {
+        unreachable!();
+    };
+    assert!(
+        event.message.contains("`backup`") && event.message.contains("gpt-5-mini"),
+        "unexpected background event: {}",
+        event.message
+    );
+    wait_for_event(&icodex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    send_turn(&icodex, "second turn").await;
+
+    let requests = backup.received_requests().await.unwrap();
+    for request in &requests {
+        let body: Value = request.body_json().unwrap();
+        assert_eq!(body["model"], "gpt-5-mini");
+    }
+
+    icodex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&icodex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+
+    let text = std::fs::read_to_string(&session_configured.rollout_path).unwrap();
+    let turn_contexts: Vec<(String, Option<String>)> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line).ok())
+        .filter_map(|line| match line.item {
+            RolloutItem::TurnContext(ctx) => Some((ctx.model, ctx.model_provider)),
+            _ => None,
+        })
+        .collect();
+    let default_model = session_configured.model;
+    assert_eq!(
+        turn_contexts,
+        vec![
+            (default_model, Some("primary".to_string())),
+            ("gpt-5-mini".to_string(), Some("backup".to_string())),
+            ("gpt-5-mini".to_string(), Some("backup".to_string())),
+        ]
+    );
+}
@@ -0,0 +1,156 @@
+#![expect(clippy::unwrap_used)]
+
+use std::time::Duration;
+
+use icodex_core::CodexAuth;
+use icodex_core::ConversationManager;
+use icodex_core::ModelProviderInfo;
+use icodex_core::built_in_model_providers;
+use icodex_core::protocol::EventMsg;
+use icodex_core::protocol::InputItem;
+use icodex_core::protocol::Op;
+use icodex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::load_default_config_for_test;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+use super::compact::ev_assistant_message;
+use super::compact::ev_completed;
+use super::compact::sse;
+use super::compact::sse_response;
+
+/// A `spawn_agent` call runs a child conversation, streams its events nested
+/// under the call and returns its final message to the parent.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn spawn_agent_returns_the_child_final_message() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    // The parent's follow-up request, which carries the child's answer.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("function_call_output"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m2", "parent done"),
+            ev_completed("r3"),
+        ])))
+        .mount(&server)
+        .await;
+    let spawn_call = json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "function_call",
+            "call_id": "call-1",
+            "name": "spawn_agent",
+            "arguments": json!({"prompt": "child task"}).to_string(),
+        }
+    });
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("delegate this"))
+        .respond_with(sse_response(sse(vec![spawn_call, ev_completed("r1")])))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    // The child's only request.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "child result"),
+            ev_completed("r2"),
+        ])))
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let icodex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    icodex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "delegate this".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::SubAgentBegin(begin) = wait_for_event_with_timeout(
+        &icodex,
+        |ev| matches!(ev, EventMsg::SubAgentBegin(_)),
+        Duration::from_secs(10),
+    )
+    .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(begin.call_id, "call-1");
+    assert_eq!(begin.prompt, "child task");
+
+    let EventMsg::SubAgent(child_complete) = wait_for_event_with_timeout(
+        &icodex,
+        |ev| matches!(ev, EventMsg::SubAgent(ev) if matches!(*ev.msg, EventMsg::TaskComplete(_))),
+        Duration::from_secs(10),
+    )
+    .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(child_complete.call_id, "call-1");
+    let EventMsg::TaskComplete(child_complete) = *child_complete.msg else {
+        unreachable!();
+    };
+    assert_eq!(
+        child_complete.last_agent_message.as_deref(),
+        Some("child result")
+    );
+
+    wait_for_event_with_timeout(
+        &icodex,
+        |ev| matches!(ev, EventMsg::TaskComplete(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let parent_request = String::from_utf8(requests[0].body.clone()).unwrap();
+    assert!(parent_request.contains("\"spawn_agent\""));
+    // The child starts from a fresh history and, at the default depth limit
+    // of 1, cannot start agents of its own.
+    let child_request = String::from_utf8(requests[1].body.clone()).unwrap();
+    assert!(child_request.contains("child task"));
+    assert!(!child_request.contains("delegate this"));
+    assert!(!child_request.contains("spawn_agent"));
+
+    let follow_up: serde_json::Value = requests[2].body_json().unwrap();
+    let output = follow_up["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["type"] == "function_call_output")
+        .unwrap();
+    assert_eq!(output["call_id"], "call-1");
+    assert_eq!(output["output"], "child result");
+}
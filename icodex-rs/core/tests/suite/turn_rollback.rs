@@ -0,0 +1,144 @@
+#![expect(clippy::unwrap_used)]
+
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+
+use icodex_core::CodexAuth;
+use icodex_core::ConversationManager;
+use icodex_core::ModelProviderInfo;
+use icodex_core::built_in_model_providers;
+use icodex_core::protocol::AskForApproval;
+use icodex_core::protocol::EventMsg;
+use icodex_core::protocol::InputItem;
+use icodex_core::protocol::Op;
+use icodex_core::protocol::SandboxPolicy;
+use icodex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::load_default_config_for_test;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+use super::compact::ev_assistant_message;
+use super::compact::ev_completed;
+use super::compact::sse;
+use super::compact::sse_response;
+
+/// A shell command run during a turn (which `apply_patch` tracking would not
+/// see) is undone by `Op::RollbackTurn`.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn rollback_restores_files_changed_by_shell_commands() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let workspace = TempDir::new().unwrap();
+    let cwd = workspace.path().canonicalize().unwrap();
+    let status = Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(&cwd)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    fs::write(cwd.join("tracked.txt"), "original\n").unwrap();
+
+    let server = MockServer::start().await;
+    let shell_call = json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "function_call",
+            "call_id": "call-1",
+            "name": "shell",
+            "arguments": json!({
+                "command": ["sh", "-c", "echo new > created.txt && echo changed > tracked.txt"],
+            })
+            .to_string(),
+        }
+    });
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("change some files"))
+        .respond_with(sse_response(sse(vec![shell_call, ev_completed("r1")])))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "done"),
+            ev_completed("r2"),
+        ])))
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.cwd = cwd.clone();
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let icodex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    icodex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "change some files".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event_with_timeout(
+        &icodex,
+        |ev| matches!(ev, EventMsg::TaskComplete(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+    assert_eq!(
+        fs::read_to_string(cwd.join("tracked.txt")).unwrap(),
+        "changed\n"
+    );
+    assert!(cwd.join("created.txt").exists());
+
+    icodex
+        .submit(Op::RollbackTurn { turn_id: None })
+        .await
+        .unwrap();
+    let event = wait_for_event_with_timeout(
+        &icodex,
+        |ev| matches!(ev, EventMsg::TurnRolledBack(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+    let EventMsg::TurnRolledBack(event) = event else {
+        unreachable!();
+    };
+
+    let mut restored_paths = event.restored_paths;
+    restored_paths.sort();
+    assert_eq!(
+        restored_paths,
+        vec![cwd.join("created.txt"), cwd.join("tracked.txt")]
+    );
+    assert_eq!(
+        fs::read_to_string(cwd.join("tracked.txt")).unwrap(),
+        "original\n"
+    );
+    assert!(!cwd.join("created.txt").exists());
+}
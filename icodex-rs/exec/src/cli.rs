@@ -1,3 +1,4 @@
+use clap::Args;
 use clap::Parser;
 use clap::ValueEnum;
 use icodex_common::CliConfigOverrides;
@@ -77,6 +78,10 @@ pub struct Cli {
 pub enum Command {
     /// Resume a previous session by id or pick the most recent with --last.
     Resume(ResumeArgs),
+
+    /// Review code changes: uncommitted changes by default, a branch, a
+    /// commit range or a list of files.
+    Review(ReviewArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -95,6 +100,55 @@ pub struct ResumeArgs {
     pub prompt: Option<String>,
 }
 
+#[derive(Parser, Debug)]
+pub struct ReviewArgs {
+    /// Review staged, unstaged and untracked changes. This is the default.
+    #[arg(long = "uncommitted", default_value_t = false, conflicts_with_all = ["base", "commit", "files"])]
+    pub uncommitted: bool,
+
+    /// Review the current branch against BRANCH, or against the repository's
+    /// default branch when BRANCH is omitted.
+    #[arg(long = "base", value_name = "BRANCH", num_args = 0..=1, conflicts_with_all = ["commit", "files"])]
+    pub base: Option<Option<String>>,
+
+    /// Review a single commit (e.g. `HEAD`) or a range (e.g. `main..HEAD`).
+    #[arg(long = "commit", value_name = "RANGE", conflicts_with = "files")]
+    pub commit: Option<String>,
+
+    /// Review these files as they are in the working tree.
+    #[arg(long = "file", value_name = "PATH", value_delimiter = ',', num_args = 1..)]
+    pub files: Vec<PathBuf>,
+
+    /// Instructions for the reviewer, replacing the default ones.
+    #[arg(value_name = "PROMPT")]
+    pub prompt: Option<String>,
+
+    #[clap(flatten)]
+    pub export: ReviewExportArgs,
+}
+
+/// Where to report review findings besides the transcript, for CI.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ReviewExportArgs {
+    /// Write the findings to FILE as SARIF 2.1.0.
+    #[arg(long = "sarif", value_name = "FILE")]
+    pub sarif_file: Option<PathBuf>,
+
+    /// Write the findings to FILE as JUnit XML, one failed test case per finding.
+    #[arg(long = "junit", value_name = "FILE")]
+    pub junit_file: Option<PathBuf>,
+
+    /// Print the findings as GitHub Actions workflow commands
+    /// (`::warning file=...`), which annotate the pull request.
+    #[arg(long = "github-annotations", default_value_t = false)]
+    pub github_annotations: bool,
+
+    /// Exit with status 1 when a finding has priority P or a more urgent one
+    /// (P0 is the most urgent), or when the review did not complete.
+    #[arg(long = "fail-on-priority", value_name = "P")]
+    pub fail_on_priority: Option<i32>,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 #[value(rename_all = "kebab-case")]
 pub enum Color {
@@ -13,6 +13,7 @@ use icodex_core::protocol::Event;
 use icodex_core::protocol::EventMsg;
 use icodex_core::protocol::ExecCommandBeginEvent;
 use icodex_core::protocol::ExecCommandEndEvent;
+use icodex_core::protocol::ExitedReviewModeEvent;
 use icodex_core::protocol::FileChange;
 use icodex_core::protocol::McpInvocation;
 use icodex_core::protocol::McpToolCallBeginEvent;
@@ -21,6 +22,8 @@ use icodex_core::protocol::PatchApplyBeginEvent;
 use icodex_core::protocol::PatchApplyEndEvent;
 use icodex_core::protocol::SessionConfiguredEvent;
 use icodex_core::protocol::StreamErrorEvent;
+use icodex_core::protocol::SubAgentBeginEvent;
+use icodex_core::protocol::SubAgentEvent;
 use icodex_core::protocol::TaskCompleteEvent;
 use icodex_core::protocol::TurnAbortReason;
 use icodex_core::protocol::TurnDiffEvent;
@@ -299,6 +302,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 aggregated_output,
                 duration,
                 exit_code,
+                limit_exceeded,
                 ..
             }) => {
                 let exec_command = self.call_id_to_command.remove(&call_id);
@@ -317,8 +321,12 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     .take(MAX_OUTPUT_LINES_FOR_EXEC_TOOL_CALL)
                     .collect::<Vec<_>>()
                     .join("\n");
-                match exit_code {
-                    0 => {
+                match (exit_code, limit_exceeded) {
+                    (_, Some(limit)) => {
+                        let title = format!("{call} {limit}{duration}:");
+                        ts_println!(self, "{}", title.style(self.red));
+                    }
+                    (0, None) => {
                         let title = format!("{call} succeeded{duration}:");
                         ts_println!(self, "{}", title.style(self.green));
                     }
@@ -495,12 +503,50 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 ts_println!(self, "{}", "turn diff:".style(self.magenta));
                 println!("{unified_diff}");
             }
+            EventMsg::TurnRolledBack(ev) => {
+                ts_println!(
+                    self,
+                    "rolled back turn {}: {} file(s) restored",
+                    ev.turn_id,
+                    ev.restored_paths.len()
+                );
+            }
+            EventMsg::SubAgentBegin(SubAgentBeginEvent {
+                call_id: _,
+                model,
+                prompt,
+            }) => {
+                ts_println!(
+                    self,
+                    "{} ({model}) {}",
+                    "agent".style(self.magenta),
+                    prompt.style(self.bold),
+                );
+            }
+            EventMsg::SubAgent(SubAgentEvent { call_id: _, msg }) => match *msg {
+                EventMsg::Error(ErrorEvent { message }) => {
+                    ts_println!(self, "{} {message}", "agent error:".style(self.red));
+                }
+                EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                    ts_println!(self, "{}", "agent finished:".style(self.green));
+                    for line in last_agent_message.unwrap_or_default().lines() {
+                        println!("{}", line.style(self.dimmed));
+                    }
+                }
+                _ => {}
+            },
             EventMsg::ExecApprovalRequest(_) => {
                 // Should we exit?
             }
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::McpToolCallApprovalRequest(_) => {
+                // Should we exit?
+            }
+            EventMsg::McpSamplingApprovalRequest(_) => {
+                // Should we exit?
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
                     if !self.reasoning_started {
@@ -551,6 +597,9 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ListCustomPromptsResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::McpListResourcesResponse(_) | EventMsg::McpListPromptsResponse(_) => {
+                // Currently ignored in exec output.
+            }
             EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
                 TurnAbortReason::Interrupted => {
                     ts_println!(self, "task interrupted");
@@ -562,8 +611,47 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationPath(_) => {}
             EventMsg::UserMessage(_) => {}
-            EventMsg::EnteredReviewMode(_) => {}
-            EventMsg::ExitedReviewMode(_) => {}
+            EventMsg::EnteredReviewMode(review_request) => {
+                ts_println!(
+                    self,
+                    "{} {}",
+                    "reviewing".style(self.magenta),
+                    review_request.user_facing_hint
+                );
+            }
+            EventMsg::ExitedReviewMode(ExitedReviewModeEvent { review_output }) => {
+                let Some(review_output) = review_output else {
+                    return CodexStatus::Running;
+                };
+                ts_println!(self, "{}", "review".style(self.magenta).style(self.italic));
+                if !review_output.overall_explanation.trim().is_empty() {
+                    println!("{}", review_output.overall_explanation.trim());
+                }
+                if review_output.findings.is_empty() {
+                    println!("no issues found");
+                }
+                for finding in review_output.findings {
+                    let location = finding.code_location;
+                    println!(
+                        "\n{} {}",
+                        format!("[P{}]", finding.priority).style(self.red),
+                        finding.title.trim().style(self.bold)
+                    );
+                    println!(
+                        "{}",
+                        format!(
+                            "{}:{}-{}",
+                            location.absolute_file_path.display(),
+                            location.line_range.start,
+                            location.line_range.end
+                        )
+                        .style(self.cyan)
+                    );
+                    for line in finding.body.trim().lines() {
+                        println!("  {line}");
+                    }
+                }
+            }
         }
         CodexStatus::Running
     }
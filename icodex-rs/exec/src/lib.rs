@@ -2,6 +2,7 @@ mod cli;
 mod event_processor;
 mod event_processor_with_human_output;
 mod event_processor_with_json_output;
+mod review_export;
 
 use std::io::IsTerminal;
 use std::io::Read;
@@ -18,9 +19,12 @@ use icodex_core::git_info::get_git_repo_root;
 use icodex_core::protocol::AskForApproval;
 use icodex_core::protocol::Event;
 use icodex_core::protocol::EventMsg;
+use icodex_core::protocol::ExitedReviewModeEvent;
 use icodex_core::protocol::InputItem;
 use icodex_core::protocol::Op;
+use icodex_core::protocol::ReviewTarget;
 use icodex_core::protocol::TaskCompleteEvent;
+use icodex_core::review_target::review_request;
 use icodex_ollama::DEFAULT_OSS_MODEL;
 use icodex_protocol::config_types::SandboxMode;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
@@ -31,8 +35,11 @@ use tracing::info;
 use tracing_subscriber::EnvFilter;
 
 use crate::cli::Command as ExecCommand;
+use crate::cli::ReviewArgs;
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
+use crate::review_export::has_finding_at_or_above;
+use crate::review_export::write_review_exports;
 use icodex_core::find_conversation_path_by_id_str;
 
 pub async fn run_main(cli: Cli, icodex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
@@ -54,18 +61,29 @@ pub async fn run_main(cli: Cli, icodex_linux_sandbox_exe: Option<PathBuf>) -> an
         config_overrides,
     } = cli;
 
+    // A review brings its own instructions and never reads them from stdin.
+    let (review_request, review_export) = match &command {
+        Some(ExecCommand::Review(args)) => (
+            Some(review_request(review_target(args), args.prompt.clone())),
+            Some(args.export.clone()),
+        ),
+        _ => (None, None),
+    };
+
     // Determine the prompt source (parent or subcommand) and read from stdin if needed.
     let prompt_arg = match &command {
         // Allow prompt before the subcommand by falling back to the parent-level prompt
         // when the Resume subcommand did not provide its own prompt.
         Some(ExecCommand::Resume(args)) => args.prompt.clone().or(prompt),
+        Some(ExecCommand::Review(_)) => None,
         None => prompt,
     };
 
-    let prompt = match prompt_arg {
-        Some(p) if p != "-" => p,
+    let prompt = match (prompt_arg, &review_request) {
+        (_, Some(review_request)) => review_request.prompt.clone(),
+        (Some(p), None) if p != "-" => p,
         // Either `-` was passed or no positional arg.
-        maybe_dash => {
+        (maybe_dash, None) => {
             // When no arg (None) **and** stdin is a TTY, bail out early – unless the
             // user explicitly forced reading via `-`.
             let force_stdin = matches!(maybe_dash.as_deref(), Some("-"));
@@ -198,6 +216,10 @@ pub async fn run_main(cli: Cli, icodex_linux_sandbox_exe: Option<PathBuf>) -> an
         std::process::exit(1);
     }
 
+    // Exported findings are relative to the repository, like code scanning
+    // and annotations expect.
+    let export_root = get_git_repo_root(&config.cwd).unwrap_or_else(|| config.cwd.clone());
+
     let conversation_manager =
         ConversationManager::new(AuthManager::shared(config.icodex_home.clone()));
 
@@ -286,13 +308,25 @@ pub async fn run_main(cli: Cli, icodex_linux_sandbox_exe: Option<PathBuf>) -> an
         }
     }
 
-    // Send the prompt.
-    let items: Vec<InputItem> = vec![InputItem::Text { text: prompt }];
-    let initial_prompt_task_id = conversation.submit(Op::UserInput { items }).await?;
+    // Send the prompt, or start the review.
+    let initial_prompt_task_id = match review_request {
+        Some(review_request) => conversation.submit(Op::Review { review_request }).await?,
+        None => {
+            let items: Vec<InputItem> = vec![InputItem::Text { text: prompt }];
+            conversation.submit(Op::UserInput { items }).await?
+        }
+    };
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
 
     // Run the loop until the task is complete.
+    let mut review_output = None;
     while let Some(event) = rx.recv().await {
+        if let EventMsg::ExitedReviewMode(ExitedReviewModeEvent {
+            review_output: Some(output),
+        }) = &event.msg
+        {
+            review_output = Some(output.clone());
+        }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
@@ -305,9 +339,45 @@ pub async fn run_main(cli: Cli, icodex_linux_sandbox_exe: Option<PathBuf>) -> an
         }
     }
 
+    if let Some(review_export) = review_export {
+        let Some(review_output) = review_output else {
+            if review_export.fail_on_priority.is_some() {
+                eprintln!("The review did not complete.");
+                std::process::exit(1);
+            }
+            return Ok(());
+        };
+        if let Err(e) = write_review_exports(&review_export, &review_output, &export_root) {
+            eprintln!("Failed to export review findings: {e}");
+            std::process::exit(1);
+        }
+        if let Some(threshold) = review_export.fail_on_priority
+            && has_finding_at_or_above(&review_output, threshold)
+        {
+            eprintln!("The review found issues of priority P{threshold} or higher.");
+            std::process::exit(1);
+        }
+    }
+
     Ok(())
 }
 
+fn review_target(args: &ReviewArgs) -> ReviewTarget {
+    if let Some(base) = &args.base {
+        ReviewTarget::BaseBranch { base: base.clone() }
+    } else if let Some(range) = &args.commit {
+        ReviewTarget::CommitRange {
+            range: range.clone(),
+        }
+    } else if !args.files.is_empty() {
+        ReviewTarget::Files {
+            paths: args.files.clone(),
+        }
+    } else {
+        ReviewTarget::UncommittedChanges
+    }
+}
+
 async fn resolve_resume_path(
     config: &Config,
     args: &crate::cli::ResumeArgs,
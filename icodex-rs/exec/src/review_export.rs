@@ -0,0 +1,233 @@
+//! Exports of review findings for CI: SARIF 2.1.0 for code scanning, JUnit
+//! XML for test reporters and GitHub Actions workflow commands, which show up
+//! as annotations on the pull request.
+//!
+//! Paths are written relative to `root` (the git repository, or the working
+//! directory outside one) since that is what these consumers resolve them
+//! against.
+
+use std::io;
+use std::path::Path;
+
+use icodex_core::protocol::ReviewFinding;
+use icodex_core::protocol::ReviewOutputEvent;
+use serde_json::Value;
+use serde_json::json;
+
+use crate::cli::ReviewExportArgs;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// All findings share one rule; their priority is carried by the level and
+/// the result properties.
+const SARIF_RULE_ID: &str = "icodex-review";
+
+/// Severity derived from a finding's priority, where P0 is the most urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn of(finding: &ReviewFinding) -> Self {
+        match finding.priority {
+            i32::MIN..=1 => Severity::Error,
+            2 => Severity::Warning,
+            _ => Severity::Note,
+        }
+    }
+
+    fn sarif_level(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    fn workflow_command(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "notice",
+        }
+    }
+}
+
+/// Write the exports requested in `args`.
+pub(crate) fn write_review_exports(
+    args: &ReviewExportArgs,
+    output: &ReviewOutputEvent,
+    root: &Path,
+) -> io::Result<()> {
+    if let Some(path) = &args.sarif_file {
+        let sarif = serde_json::to_string_pretty(&to_sarif(output, root))?;
+        std::fs::write(path, sarif)?;
+    }
+    if let Some(path) = &args.junit_file {
+        std::fs::write(path, to_junit(output, root))?;
+    }
+    if args.github_annotations {
+        print!("{}", to_github_annotations(output, root));
+    }
+    Ok(())
+}
+
+/// Whether any finding is at least as urgent as `threshold`, i.e. has a
+/// priority number no greater than it.
+pub(crate) fn has_finding_at_or_above(output: &ReviewOutputEvent, threshold: i32) -> bool {
+    output
+        .findings
+        .iter()
+        .any(|finding| finding.priority <= threshold)
+}
+
+fn to_sarif(output: &ReviewOutputEvent, root: &Path) -> Value {
+    let results: Vec<Value> = output
+        .findings
+        .iter()
+        .map(|finding| {
+            let range = &finding.code_location.line_range;
+            let start_line = range.start.max(1);
+            json!({
+                "ruleId": SARIF_RULE_ID,
+                "level": Severity::of(finding).sarif_level(),
+                "message": { "text": message_text(finding) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": display_path(finding, root),
+                        },
+                        "region": {
+                            "startLine": start_line,
+                            "endLine": range.end.max(start_line),
+                        },
+                    },
+                }],
+                "properties": {
+                    "priority": finding.priority,
+                    "confidence": finding.confidence_score,
+                },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "icodex",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": SARIF_RULE_ID,
+                        "shortDescription": { "text": "Code review finding" },
+                    }],
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// One test case per finding, each failed. A review without findings is a
+/// single passing test case, so the report is never empty.
+fn to_junit(output: &ReviewOutputEvent, root: &Path) -> String {
+    let mut cases = String::new();
+    for finding in &output.findings {
+        let range = &finding.code_location.line_range;
+        let path = display_path(finding, root);
+        let details = format!(
+            "{path}:{}-{}\n\n{}",
+            range.start,
+            range.end,
+            finding.body.trim()
+        );
+        cases.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\">\n      <failure type=\"P{}\" message=\"{}\">{}</failure>\n    </testcase>\n",
+            escape_xml(&path),
+            escape_xml(finding.title.trim()),
+            finding.priority,
+            escape_xml(finding.title.trim()),
+            escape_xml(&details),
+        ));
+    }
+    if output.findings.is_empty() {
+        cases.push_str("    <testcase classname=\"icodex\" name=\"review\"/>\n");
+    }
+
+    let tests = output.findings.len().max(1);
+    let failures = output.findings.len();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites tests=\"{tests}\" failures=\"{failures}\">\n  <testsuite name=\"icodex review\" tests=\"{tests}\" failures=\"{failures}\">\n{cases}  </testsuite>\n</testsuites>\n"
+    )
+}
+
+/// `::warning file=...,line=...::message` lines, one per finding.
+fn to_github_annotations(output: &ReviewOutputEvent, root: &Path) -> String {
+    let mut annotations = String::new();
+    for finding in &output.findings {
+        let range = &finding.code_location.line_range;
+        annotations.push_str(&format!(
+            "::{} file={},line={},endLine={},title={}::{}\n",
+            Severity::of(finding).workflow_command(),
+            escape_workflow_property(&display_path(finding, root)),
+            range.start.max(1),
+            range.end.max(range.start.max(1)),
+            escape_workflow_property(&format!("[P{}] {}", finding.priority, finding.title.trim())),
+            escape_workflow_data(finding.body.trim()),
+        ));
+    }
+    annotations
+}
+
+fn message_text(finding: &ReviewFinding) -> String {
+    let title = finding.title.trim();
+    let body = finding.body.trim();
+    if body.is_empty() {
+        title.to_string()
+    } else {
+        format!("{title}\n\n{body}")
+    }
+}
+
+/// The finding's path relative to `root` with forward slashes, or as
+/// reported when it lies outside `root`.
+fn display_path(finding: &ReviewFinding, root: &Path) -> String {
+    let path = &finding.code_location.absolute_file_path;
+    let path = path.strip_prefix(root).unwrap_or(path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escaping for the message of a workflow command, which must stay on one
+/// line.
+fn escape_workflow_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escaping for a `key=value` property of a workflow command.
+fn escape_workflow_property(text: &str) -> String {
+    escape_workflow_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
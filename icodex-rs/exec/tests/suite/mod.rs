@@ -2,4 +2,5 @@
 mod apply_patch;
 mod common;
 mod resume;
+mod review;
 mod sandbox;
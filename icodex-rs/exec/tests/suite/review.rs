@@ -0,0 +1,133 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+use anyhow::Context;
+use assert_cmd::prelude::*;
+use predicates::str::contains;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn exec_review_reviews_uncommitted_changes_without_a_prompt() -> anyhow::Result<()> {
+    let home = TempDir::new()?;
+    let repo = TempDir::new()?;
+    git(repo.path(), &["init", "--quiet"]);
+    std::fs::write(repo.path().join("lib.rs"), "fn one() {}\n")?;
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/cli_responses_fixture.sse");
+
+    Command::cargo_bin("icodex-exec")
+        .context("should find binary for icodex-exec")?
+        .env("CODEX_HOME", home.path())
+        .env("OPENAI_API_KEY", "dummy")
+        .env("CODEX_RS_SSE_FIXTURE", &fixture)
+        .env("OPENAI_BASE_URL", "http://unused.local")
+        .arg("-C")
+        .arg(repo.path())
+        .arg("review")
+        .arg("--uncommitted")
+        .assert()
+        .success()
+        .stdout(contains("reviewing uncommitted changes"));
+    Ok(())
+}
+
+#[test]
+fn exec_review_exports_findings_and_fails_on_priority() -> anyhow::Result<()> {
+    let home = TempDir::new()?;
+    let repo = TempDir::new()?;
+    let root = repo.path().canonicalize()?;
+    git(&root, &["init", "--quiet"]);
+    std::fs::write(root.join("lib.rs"), "fn one() {}\n")?;
+
+    let review_output = serde_json::json!({
+        "findings": [{
+            "title": "Missing error handling",
+            "body": "The result is ignored,\nso failures go unnoticed.",
+            "confidence_score": 0.8,
+            "priority": 1,
+            "code_location": {
+                "absolute_file_path": root.join("lib.rs"),
+                "line_range": {"start": 1, "end": 1},
+            },
+        }],
+        "overall_correctness": "patch is incorrect",
+        "overall_explanation": "One issue.",
+        "overall_confidence_score": 0.8,
+    });
+    let message = serde_json::json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "message",
+            "role": "assistant",
+            "content": [{"type": "output_text", "text": review_output.to_string()}],
+        },
+    });
+    let completed = serde_json::json!({
+        "type": "response.completed",
+        "response": {"id": "resp1", "output": []},
+    });
+    let fixture = home.path().join("review.sse");
+    std::fs::write(&fixture, format!("data: {message}\ndata: {completed}\n"))?;
+
+    let sarif_path = home.path().join("review.sarif");
+    let junit_path = home.path().join("review.xml");
+    let review = |threshold: &str| -> anyhow::Result<assert_cmd::assert::Assert> {
+        Ok(Command::cargo_bin("icodex-exec")
+            .context("should find binary for icodex-exec")?
+            .env("CODEX_HOME", home.path())
+            .env("OPENAI_API_KEY", "dummy")
+            .env("CODEX_RS_SSE_FIXTURE", &fixture)
+            .env("OPENAI_BASE_URL", "http://unused.local")
+            .arg("-C")
+            .arg(&root)
+            .arg("review")
+            .arg("--sarif")
+            .arg(&sarif_path)
+            .arg("--junit")
+            .arg(&junit_path)
+            .arg("--github-annotations")
+            .arg("--fail-on-priority")
+            .arg(threshold)
+            .assert())
+    };
+
+    // The body keeps its comma but its newline is escaped, since each
+    // annotation is a single line.
+    review("1")?.code(1).stdout(contains(
+        "::error file=lib.rs,line=1,endLine=1,title=[P1] Missing error handling::The result is ignored,%0Aso failures go unnoticed.",
+    ));
+    review("0")?.success();
+
+    let sarif: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&sarif_path)?)?;
+    assert_eq!(sarif["version"], "2.1.0");
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["level"], "error");
+    assert_eq!(
+        result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "lib.rs"
+    );
+    assert_eq!(
+        result["locations"][0]["physicalLocation"]["region"]["startLine"],
+        1
+    );
+
+    let junit = std::fs::read_to_string(&junit_path)?;
+    assert!(junit.contains("failures=\"1\""), "{junit}");
+    assert!(
+        junit.contains("<testcase classname=\"lib.rs\" name=\"Missing error handling\">"),
+        "{junit}"
+    );
+    Ok(())
+}
@@ -3,6 +3,8 @@ use std::ffi::CString;
 use std::path::PathBuf;
 
 use crate::landlock::apply_sandbox_policy_to_current_thread;
+use crate::proxy_bridge::enter_proxy_network_namespace;
+use crate::proxy_bridge::exec_with_proxy_bridge;
 
 #[derive(Debug, Parser)]
 pub struct LandlockCommand {
@@ -12,6 +14,13 @@ pub struct LandlockCommand {
 
     pub sandbox_policy: icodex_core::protocol::SandboxPolicy,
 
+    /// Unix socket of the network proxy. Unless the policy grants full network
+    /// access, the command then runs in a network namespace of its own whose
+    /// only way out is this proxy, instead of having network sockets blocked.
+    /// If the namespace cannot be created, network sockets stay blocked.
+    #[arg(long)]
+    pub network_proxy_socket: Option<PathBuf>,
+
     /// Full command args to run under landlock.
     #[arg(trailing_var_arg = true)]
     pub command: Vec<String>,
@@ -21,10 +30,35 @@ pub fn run_main() -> ! {
     let LandlockCommand {
         sandbox_policy_cwd,
         sandbox_policy,
+        network_proxy_socket,
         command,
     } = LandlockCommand::parse();
 
-    if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &sandbox_policy_cwd) {
+    let network_proxy_socket =
+        network_proxy_socket.filter(|_| !sandbox_policy.has_full_network_access());
+    // Entering the namespace has to happen while this process is still
+    // single-threaded.
+    let proxy_listener = match &network_proxy_socket {
+        Some(_) => match enter_proxy_network_namespace() {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                // E.g. unprivileged user namespaces are disabled. Without the
+                // proxy the command gets no network at all, as it would with
+                // no allowlist.
+                eprintln!(
+                    "warning: network proxy unavailable, running without network access: {e}"
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    if let Err(e) = apply_sandbox_policy_to_current_thread(
+        &sandbox_policy,
+        &sandbox_policy_cwd,
+        proxy_listener.is_some(),
+    ) {
         panic!("error running landlock: {e:?}");
     }
 
@@ -44,6 +78,10 @@ pub fn run_main() -> ! {
     let mut c_args_ptrs: Vec<*const libc::c_char> = c_args.iter().map(|arg| arg.as_ptr()).collect();
     c_args_ptrs.push(std::ptr::null());
 
+    if let (Some(listener), Some(socket_path)) = (proxy_listener, network_proxy_socket) {
+        exec_with_proxy_bridge(listener, socket_path, &c_command, &c_args_ptrs);
+    }
+
     unsafe {
         libc::execvp(c_command.as_ptr(), c_args_ptrs.as_ptr());
     }
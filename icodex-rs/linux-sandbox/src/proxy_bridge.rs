@@ -0,0 +1,141 @@
+use std::ffi::CString;
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::Shutdown;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+
+use icodex_core::network_proxy::SANDBOX_PROXY_PORT;
+
+/// Move this process into new user and network namespaces, whose only
+/// interface is loopback, and listen on the proxy port there.
+///
+/// Must run before any other thread is started: the kernel refuses to
+/// create a user namespace for a multithreaded process.
+pub(crate) fn enter_proxy_network_namespace() -> io::Result<TcpListener> {
+    let uid = unsafe { libc::getuid() };
+    let gid = unsafe { libc::getgid() };
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Keep the same ids inside the namespace so files created by the command
+    // are owned by the user as usual.
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))?;
+
+    bring_up_loopback()?;
+    TcpListener::bind((Ipv4Addr::LOCALHOST, SANDBOX_PROXY_PORT))
+}
+
+/// A new network namespace starts with `lo` down.
+fn bring_up_loopback() -> io::Result<()> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut ifr: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in ifr.ifr_name.iter_mut().zip(b"lo\0") {
+        *dst = *src as libc::c_char;
+    }
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::SIOCGIFFLAGS as _, &mut ifr) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe {
+        ifr.ifr_ifru.ifru_flags |= (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+    }
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::SIOCSIFFLAGS as _, &ifr) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Run the command in a child process while this one relays connections to
+/// `listener` to the proxy socket, then exit the way the child did.
+pub(crate) fn exec_with_proxy_bridge(
+    listener: TcpListener,
+    socket_path: PathBuf,
+    c_command: &CString,
+    c_args_ptrs: &[*const libc::c_char],
+) -> ! {
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        let err = io::Error::last_os_error();
+        panic!("failed to fork the network proxy bridge: {err}");
+    }
+    if pid == 0 {
+        // The child must not outlive the bridge, which is what gets killed
+        // when the command times out.
+        unsafe {
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+            libc::close(listener.as_raw_fd());
+            libc::execvp(c_command.as_ptr(), c_args_ptrs.as_ptr());
+            libc::_exit(127);
+        }
+    }
+
+    std::thread::spawn(move || serve_bridge(listener, socket_path));
+    exit_like_child(pid)
+}
+
+fn serve_bridge(listener: TcpListener, socket_path: PathBuf) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let socket_path = socket_path.clone();
+        std::thread::spawn(move || {
+            let _ = relay(stream, &socket_path);
+        });
+    }
+}
+
+/// Copy bytes both ways between `tcp` and a new connection to the proxy.
+fn relay(tcp: TcpStream, socket_path: &Path) -> io::Result<()> {
+    let proxy = UnixStream::connect(socket_path)?;
+    let mut tcp_read = tcp.try_clone()?;
+    let mut proxy_write = proxy.try_clone()?;
+    let upload = std::thread::spawn(move || {
+        let _ = io::copy(&mut tcp_read, &mut proxy_write);
+        let _ = proxy_write.shutdown(Shutdown::Write);
+    });
+
+    let (mut proxy_read, mut tcp_write) = (proxy, tcp);
+    let _ = io::copy(&mut proxy_read, &mut tcp_write);
+    let _ = tcp_write.shutdown(Shutdown::Write);
+    let _ = upload.join();
+    Ok(())
+}
+
+/// Wait for `pid` and exit with its status, re-raising the signal that
+/// killed it so callers can tell timeouts and crashes apart.
+fn exit_like_child(pid: libc::pid_t) -> ! {
+    let mut status: libc::c_int = 0;
+    loop {
+        if unsafe { libc::waitpid(pid, &mut status, 0) } >= 0 {
+            break;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            panic!("failed to wait for the sandboxed command: {err}");
+        }
+    }
+
+    if libc::WIFSIGNALED(status) {
+        let signal = libc::WTERMSIG(status);
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::kill(libc::getpid(), signal);
+        }
+        std::process::exit(128 + signal);
+    }
+    std::process::exit(libc::WEXITSTATUS(status));
+}
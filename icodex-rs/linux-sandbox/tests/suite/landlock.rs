@@ -1,4 +1,5 @@
 #![cfg(target_os = "linux")]
+use icodex_core::config_types::ExecLimits;
 use icodex_core::config_types::ShellEnvironmentPolicy;
 use icodex_core::error::CodexErr;
 use icodex_core::error::SandboxErr;
@@ -8,6 +9,7 @@ use icodex_core::exec::process_exec_tool_call;
 use icodex_core::exec_env::create_env;
 use icodex_core::protocol::SandboxPolicy;
 use std::collections::HashMap;
+use std::path::Path;
 use std::path::PathBuf;
 use tempfile::NamedTempFile;
 
@@ -30,7 +32,7 @@ const NETWORK_TIMEOUT_MS: u64 = 10_000;
 
 fn create_env_from_core_vars() -> HashMap<String, String> {
     let policy = ShellEnvironmentPolicy::default();
-    create_env(&policy)
+    create_env(&policy, None)
 }
 
 #[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
@@ -61,6 +63,8 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
         &sandbox_policy,
         &icodex_linux_sandbox_exe,
         None,
+        &ExecLimits::default(),
+        None,
     )
     .await
     .unwrap();
@@ -120,6 +124,105 @@ async fn test_writable_root() {
     .await;
 }
 
+/// Runs `cmd` under a `workspace-read` policy that only adds `readable_roots`
+/// to the defaults and returns the exit code. `home` replaces `$HOME`, which
+/// the per-user default roots are relative to.
+#[expect(clippy::expect_used)]
+async fn run_cmd_with_readable_roots(
+    cmd: &[&str],
+    readable_roots: &[PathBuf],
+    home: Option<&Path>,
+) -> i32 {
+    let mut env = create_env_from_core_vars();
+    if let Some(home) = home {
+        env.insert("HOME".to_string(), home.to_string_lossy().into_owned());
+    }
+    let params = ExecParams {
+        command: cmd.iter().map(|elm| elm.to_string()).collect(),
+        cwd: std::env::current_dir().expect("cwd should exist"),
+        timeout_ms: Some(LONG_TIMEOUT_MS),
+        env,
+        with_escalated_permissions: None,
+        justification: None,
+    };
+
+    let sandbox_policy = SandboxPolicy::WorkspaceRead {
+        readable_roots: readable_roots.to_vec(),
+        exclude_default_readable_roots: false,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_icodex-linux-sandbox");
+    let icodex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        &icodex_linux_sandbox_exe,
+        None,
+        &ExecLimits::default(),
+        None,
+    )
+    .await;
+
+    match result {
+        Ok(output) => output.exit_code,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => output.exit_code,
+        _ => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_workspace_read_allows_readable_root() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let file_path = tmpdir.path().join("allowed.txt");
+    std::fs::write(&file_path, "ok").unwrap();
+
+    let exit_code = run_cmd_with_readable_roots(
+        &["cat", &file_path.to_string_lossy()],
+        &[tmpdir.path().to_path_buf()],
+        None,
+    )
+    .await;
+    assert_eq!(exit_code, 0);
+}
+
+#[tokio::test]
+async fn test_workspace_read_blocks_other_paths() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let file_path = tmpdir.path().join("secret.txt");
+    std::fs::write(&file_path, "secret").unwrap();
+
+    // `/tmp` is not among the default readable roots.
+    let exit_code =
+        run_cmd_with_readable_roots(&["cat", &file_path.to_string_lossy()], &[], None).await;
+    assert_ne!(exit_code, 0);
+}
+
+#[tokio::test]
+async fn test_workspace_read_hides_cargo_credentials() {
+    let home = tempfile::tempdir().unwrap();
+    let cargo_bin = home.path().join(".cargo").join("bin");
+    std::fs::create_dir_all(&cargo_bin).unwrap();
+    std::fs::write(cargo_bin.join("tool"), "tool").unwrap();
+    let credentials = home.path().join(".cargo").join("credentials.toml");
+    std::fs::write(&credentials, "[registry]\ntoken = \"secret\"\n").unwrap();
+
+    let exit_code = run_cmd_with_readable_roots(
+        &["cat", &cargo_bin.join("tool").to_string_lossy()],
+        &[],
+        Some(home.path()),
+    )
+    .await;
+    assert_eq!(exit_code, 0);
+
+    let exit_code = run_cmd_with_readable_roots(
+        &["cat", &credentials.to_string_lossy()],
+        &[],
+        Some(home.path()),
+    )
+    .await;
+    assert_ne!(exit_code, 0);
+}
+
 #[tokio::test]
 #[should_panic(expected = "Sandbox(Timeout")]
 async fn test_timeout() {
@@ -153,6 +256,8 @@ async fn assert_network_blocked(cmd: &[&str]) {
         &sandbox_policy,
         &icodex_linux_sandbox_exe,
         None,
+        &ExecLimits::default(),
+        None,
     )
     .await;
 
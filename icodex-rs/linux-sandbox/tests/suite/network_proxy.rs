@@ -0,0 +1,155 @@
+#![cfg(target_os = "linux")]
+use icodex_core::network_proxy::NetworkAllowlist;
+use icodex_core::network_proxy::NetworkProxy;
+use icodex_core::network_proxy::SANDBOX_PROXY_PORT;
+use seccompiler::BpfProgram;
+use seccompiler::SeccompAction;
+use seccompiler::SeccompFilter;
+use seccompiler::TargetArch;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Output;
+use tokio::process::Command;
+
+/// Printed by the helper when it cannot create the proxy's network namespace.
+const PROXY_UNAVAILABLE_WARNING: &str = "network proxy unavailable";
+
+/// Serve `200 OK` to every connection on a free local port.
+#[expect(clippy::unwrap_used)]
+fn start_http_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(b"HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\nok");
+        }
+    });
+    port
+}
+
+/// A filter making `unshare(2)` fail, as it does where unprivileged user
+/// namespaces are disabled.
+#[expect(clippy::unwrap_used)]
+fn deny_unshare_filter() -> BpfProgram {
+    let mut rules = BTreeMap::new();
+    rules.insert(libc::SYS_unshare, vec![]);
+    let arch = if cfg!(target_arch = "x86_64") {
+        TargetArch::x86_64
+    } else {
+        TargetArch::aarch64
+    };
+    SeccompFilter::new(
+        rules,
+        SeccompAction::Allow,
+        SeccompAction::Errno(libc::EPERM as u32),
+        arch,
+    )
+    .unwrap()
+    .try_into()
+    .unwrap()
+}
+
+/// Run `script` with bash under the helper in read-only mode, handing it the
+/// proxy listening on `socket_path`.
+#[expect(clippy::expect_used, clippy::unwrap_used)]
+async fn run_with_proxy(socket_path: &Path, script: &str, deny_unshare: bool) -> Output {
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let mut command = Command::new(env!("CARGO_BIN_EXE_icodex-linux-sandbox"));
+    command
+        .arg("--network-proxy-socket")
+        .arg(socket_path)
+        .arg(&cwd)
+        .arg(r#"{"mode":"read-only"}"#)
+        .arg("--")
+        .args(["bash", "-c", script]);
+    if deny_unshare {
+        let filter = deny_unshare_filter();
+        // SAFETY: only installs the prebuilt filter between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                seccompiler::apply_filter(&filter).map_err(|e| std::io::Error::other(e.to_string()))
+            });
+        }
+    }
+    command.output().await.unwrap()
+}
+
+/// Send a plain `http://` request for `url` to the bridge inside the
+/// namespace and print the status line of the response.
+fn fetch_through_bridge(url: &str) -> String {
+    format!(
+        "exec 3<>/dev/tcp/127.0.0.1/{SANDBOX_PROXY_PORT} && \
+         printf 'GET {url} HTTP/1.0\\r\\n\\r\\n' >&3 && head -n 1 <&3"
+    )
+}
+
+#[tokio::test]
+async fn bridge_forwards_allowlisted_hosts() {
+    let port = start_http_server();
+    let (proxy, _denied_hosts) =
+        NetworkProxy::start(NetworkAllowlist::new(&["127.0.0.1".to_string()])).unwrap();
+
+    let output = run_with_proxy(
+        proxy.socket_path(),
+        &fetch_through_bridge(&format!("http://127.0.0.1:{port}/")),
+        false,
+    )
+    .await;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains(PROXY_UNAVAILABLE_WARNING) {
+        println!("Skipping test because user namespaces are unavailable.");
+        return;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stdout.starts_with("HTTP/1.0 200"), "stdout: {stdout}");
+}
+
+#[tokio::test]
+async fn bridge_refuses_and_reports_other_hosts() {
+    let (proxy, mut denied_hosts) =
+        NetworkProxy::start(NetworkAllowlist::new(&["127.0.0.1".to_string()])).unwrap();
+
+    let output = run_with_proxy(
+        proxy.socket_path(),
+        &fetch_through_bridge("http://blocked.example.com/"),
+        false,
+    )
+    .await;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains(PROXY_UNAVAILABLE_WARNING) {
+        println!("Skipping test because user namespaces are unavailable.");
+        return;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stdout.starts_with("HTTP/1.1 403"), "stdout: {stdout}");
+    assert_eq!(denied_hosts.recv().await.unwrap(), "blocked.example.com");
+}
+
+#[tokio::test]
+async fn sockets_stay_blocked_when_the_namespace_cannot_be_created() {
+    // Without a namespace of its own, the command shares this network, so
+    // only the seccomp filter keeps it from reaching the server.
+    let port = start_http_server();
+    let (proxy, _denied_hosts) =
+        NetworkProxy::start(NetworkAllowlist::new(&["127.0.0.1".to_string()])).unwrap();
+
+    let output = run_with_proxy(
+        proxy.socket_path(),
+        &format!("exec 3<>/dev/tcp/127.0.0.1/{port}"),
+        true,
+    )
+    .await;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(PROXY_UNAVAILABLE_WARNING),
+        "stderr: {stderr}"
+    );
+    assert!(!output.status.success(), "stderr: {stderr}");
+}
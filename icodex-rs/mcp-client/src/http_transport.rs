@@ -0,0 +1,626 @@
+//! HTTP transports for remote MCP servers.
+//!
+//! Messages are sent with the streamable HTTP transport (protocol revision
+//! 2025-03-26 and later): every JSON-RPC message is POSTed to the server URL
+//! and the reply comes back either as a JSON body or as an SSE stream. When
+//! the server rejects the very first POST, the client falls back to the older
+//! HTTP+SSE transport (revision 2024-11-05), where replies arrive on a
+//! long-lived `GET` event stream and messages are POSTed to the endpoint the
+//! server announces on that stream.
+//!
+//! Both transports reconnect on their own: transient failures are retried
+//! with backoff, and when the server forgets the session (or the event stream
+//! drops) the `initialize` handshake is replayed before carrying on.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use mcp_types::InitializeRequest;
+use mcp_types::InitializedNotification;
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use mcp_types::JSONRPCRequest;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::ModelContextProtocolRequest;
+use mcp_types::RequestId;
+use reqwest::StatusCode;
+use reqwest::Url;
+use reqwest::header::ACCEPT;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+use crate::mcp_client::PendingMap;
+use crate::mcp_client::ServerRequestRouter;
+use crate::mcp_client::dispatch_incoming;
+
+/// Header carrying the session id assigned by a streamable HTTP server.
+const MCP_SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// Number of times a request is retried after a transient failure.
+const MAX_RETRIES: u32 = 3;
+
+/// Delay before the first retry; doubled on every further attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Upper bound for the delay between reconnection attempts of the legacy
+/// event stream.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How long to wait for a legacy server to announce its POST endpoint.
+const LEGACY_ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON-RPC error code used to fail a request when the transport itself
+/// could not deliver it. Falls in the range reserved for implementation
+/// defined server errors.
+const TRANSPORT_ERROR_CODE: i64 = -32000;
+
+/// Convert the configured header map into a [`HeaderMap`], rejecting names or
+/// values that are not valid HTTP.
+pub(crate) fn build_header_map(headers: HashMap<String, String>) -> Result<HeaderMap> {
+    let mut map = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("invalid HTTP header name `{name}`"))?;
+        let header_value = HeaderValue::from_str(&value)
+            .with_context(|| format!("invalid value for HTTP header `{name}`"))?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+/// Spawn the task that delivers messages from `outgoing_rx` to the server at
+/// `url` and routes the replies to `pending` and the requests the server
+/// sends to `server_requests`.
+pub(crate) fn spawn_http_transport(
+    url: Url,
+    headers: HeaderMap,
+    outgoing_rx: mpsc::Receiver<JSONRPCMessage>,
+    pending: PendingMap,
+    server_requests: ServerRequestRouter,
+) -> JoinHandle<()> {
+    let transport = Arc::new(HttpTransport {
+        client: reqwest::Client::new(),
+        url,
+        headers,
+        pending,
+        server_requests,
+        session_id: Mutex::new(None),
+        handshake: Mutex::new(Handshake::default()),
+    });
+    tokio::spawn(transport.run(outgoing_rx))
+}
+
+/// Why a message could not be delivered.
+#[derive(Debug)]
+enum PostError {
+    /// The server answered 404 to a request that carried a session id: the
+    /// session has expired and must be re-initialized.
+    SessionExpired,
+    Status(StatusCode, String),
+    Transport(String),
+}
+
+impl fmt::Display for PostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostError::SessionExpired => write!(f, "MCP session expired"),
+            PostError::Status(status, body) if body.is_empty() => {
+                write!(f, "MCP server returned {status}")
+            }
+            PostError::Status(status, body) => write!(f, "MCP server returned {status}: {body}"),
+            PostError::Transport(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// The messages that opened the session, kept so the handshake can be
+/// replayed after a reconnect.
+#[derive(Default)]
+struct Handshake {
+    initialize: Option<JSONRPCMessage>,
+    initialized: Option<JSONRPCMessage>,
+}
+
+/// Which transport the server turned out to speak.
+enum Mode {
+    /// Nothing has been sent yet.
+    Unknown,
+    Streamable,
+    Legacy(LegacyStream),
+}
+
+struct HttpTransport {
+    client: reqwest::Client,
+    url: Url,
+    headers: HeaderMap,
+    pending: PendingMap,
+    server_requests: ServerRequestRouter,
+    session_id: Mutex<Option<String>>,
+    handshake: Mutex<Handshake>,
+}
+
+impl HttpTransport {
+    async fn run(self: Arc<Self>, mut outgoing_rx: mpsc::Receiver<JSONRPCMessage>) {
+        let mut mode = Mode::Unknown;
+        while let Some(message) = outgoing_rx.recv().await {
+            debug!("MCP message to server: {message:?}");
+            self.remember_handshake(&message).await;
+            if let Mode::Unknown = mode {
+                mode = self.send_first(message).await;
+                continue;
+            }
+
+            // Requests are sent concurrently so a slow tool call does not
+            // hold up the rest; notifications stay in order.
+            let is_request = matches!(message, JSONRPCMessage::Request(_));
+            let send: futures::future::BoxFuture<'static, ()> = match &mode {
+                Mode::Legacy(legacy) => Box::pin(
+                    self.clone()
+                        .send_legacy(legacy.endpoint_rx.clone(), message),
+                ),
+                Mode::Unknown | Mode::Streamable => Box::pin(self.clone().send_streamable(message)),
+            };
+            if is_request {
+                tokio::spawn(send);
+            } else {
+                send.await;
+            }
+        }
+    }
+
+    /// Send the first message (the `initialize` request). The server's answer
+    /// tells us which transport it speaks.
+    async fn send_first(self: &Arc<Self>, message: JSONRPCMessage) -> Mode {
+        match self.post_streamable(&message, true).await {
+            Err(PostError::Status(status, _)) if is_legacy_rejection(status) => {
+                info!("MCP server rejected POST with {status}; falling back to HTTP+SSE");
+                match LegacyStream::connect(self.clone()).await {
+                    Ok(legacy) => {
+                        self.clone()
+                            .send_legacy(legacy.endpoint_rx.clone(), message)
+                            .await;
+                        Mode::Legacy(legacy)
+                    }
+                    Err(err) => {
+                        self.fail(&message, PostError::Transport(format!("{err:#}")))
+                            .await;
+                        Mode::Unknown
+                    }
+                }
+            }
+            result => {
+                self.finish(&message, result).await;
+                Mode::Streamable
+            }
+        }
+    }
+
+    async fn send_streamable(self: Arc<Self>, message: JSONRPCMessage) {
+        let result = match self.post_streamable(&message, true).await {
+            Err(PostError::SessionExpired) => {
+                // The server dropped our session (e.g. it restarted): open a
+                // new one and try once more.
+                match self.replay_handshake().await {
+                    Ok(()) => self.post_streamable(&message, true).await,
+                    Err(err) => Err(err),
+                }
+            }
+            other => other,
+        };
+        self.finish(&message, result).await;
+    }
+
+    async fn send_legacy(
+        self: Arc<Self>,
+        mut endpoint_rx: watch::Receiver<Option<Url>>,
+        message: JSONRPCMessage,
+    ) {
+        // While the event stream reconnects there is no endpoint; wait for
+        // the next one rather than failing the message.
+        let endpoint = match endpoint_rx.wait_for(Option::is_some).await {
+            Ok(endpoint) => endpoint.clone(),
+            Err(_) => None,
+        };
+        let result = match endpoint {
+            Some(endpoint) => self.post_legacy(&endpoint, &message).await,
+            None => Err(PostError::Transport("MCP event stream closed".to_string())),
+        };
+        if let Err(err) = result {
+            self.fail(&message, err).await;
+        }
+    }
+
+    /// POST `message` to the streamable HTTP endpoint and route whatever the
+    /// server sends back. Replies are dropped when `dispatch` is false, which
+    /// is used while replaying the handshake.
+    async fn post_streamable(
+        &self,
+        message: &JSONRPCMessage,
+        dispatch: bool,
+    ) -> Result<(), PostError> {
+        let mut attempt = 0;
+        let response = loop {
+            let session_id = self.session_id.lock().await.clone();
+            let mut request = self
+                .client
+                .post(self.url.clone())
+                .headers(self.headers.clone())
+                .header(ACCEPT, "application/json, text/event-stream")
+                .json(message);
+            if let Some(session_id) = &session_id {
+                request = request.header(MCP_SESSION_ID_HEADER, session_id);
+            }
+
+            match request.send().await {
+                Ok(response) if is_retryable_status(response.status()) && attempt < MAX_RETRIES => {
+                    warn!("MCP server returned {}; retrying", response.status());
+                }
+                Ok(response)
+                    if response.status() == StatusCode::NOT_FOUND && session_id.is_some() =>
+                {
+                    *self.session_id.lock().await = None;
+                    return Err(PostError::SessionExpired);
+                }
+                Ok(response) if !response.status().is_success() => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(PostError::Status(status, body));
+                }
+                Ok(response) => break response,
+                Err(err) if err.is_connect() && attempt < MAX_RETRIES => {
+                    warn!("failed to connect to MCP server: {err}; retrying");
+                }
+                Err(err) => return Err(PostError::Transport(err.to_string())),
+            }
+            tokio::time::sleep(backoff(attempt)).await;
+            attempt += 1;
+        };
+
+        if let Some(session_id) = response
+            .headers()
+            .get(MCP_SESSION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            *self.session_id.lock().await = Some(session_id.to_string());
+        }
+
+        if response.status() == StatusCode::ACCEPTED {
+            return Ok(());
+        }
+
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if is_event_stream {
+            let mut events = response.bytes_stream().eventsource();
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(event) if event.data.is_empty() => {}
+                    Ok(event) => self.handle_payload(&event.data, dispatch).await,
+                    Err(err) => {
+                        return Err(PostError::Transport(format!(
+                            "MCP event stream interrupted: {err}"
+                        )));
+                    }
+                }
+            }
+        } else {
+            let body = response
+                .text()
+                .await
+                .map_err(|err| PostError::Transport(err.to_string()))?;
+            if !body.trim().is_empty() {
+                self.handle_payload(&body, dispatch).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// POST `message` to the endpoint announced by a legacy HTTP+SSE server.
+    /// The reply arrives on the event stream.
+    async fn post_legacy(&self, endpoint: &Url, message: &JSONRPCMessage) -> Result<(), PostError> {
+        let mut attempt = 0;
+        loop {
+            let request = self
+                .client
+                .post(endpoint.clone())
+                .headers(self.headers.clone())
+                .json(message);
+            match request.send().await {
+                Ok(response) if is_retryable_status(response.status()) && attempt < MAX_RETRIES => {
+                    warn!("MCP server returned {}; retrying", response.status());
+                }
+                Ok(response) if !response.status().is_success() => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(PostError::Status(status, body));
+                }
+                Ok(_) => return Ok(()),
+                Err(err) if err.is_connect() && attempt < MAX_RETRIES => {
+                    warn!("failed to connect to MCP server: {err}; retrying");
+                }
+                Err(err) => return Err(PostError::Transport(err.to_string())),
+            }
+            tokio::time::sleep(backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn remember_handshake(&self, message: &JSONRPCMessage) {
+        match message {
+            JSONRPCMessage::Request(request) if request.method == InitializeRequest::METHOD => {
+                self.handshake.lock().await.initialize = Some(message.clone());
+            }
+            JSONRPCMessage::Notification(notification)
+                if notification.method == InitializedNotification::METHOD =>
+            {
+                self.handshake.lock().await.initialized = Some(message.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// Open a fresh streamable HTTP session by resending the original
+    /// `initialize` request and `initialized` notification.
+    async fn replay_handshake(&self) -> Result<(), PostError> {
+        let (initialize, initialized) = {
+            let handshake = self.handshake.lock().await;
+            (handshake.initialize.clone(), handshake.initialized.clone())
+        };
+        let Some(initialize) = initialize else {
+            return Err(PostError::SessionExpired);
+        };
+        info!("MCP session expired; re-initializing");
+        self.post_streamable(&initialize, false).await?;
+        if let Some(initialized) = initialized {
+            self.post_streamable(&initialized, false).await?;
+        }
+        Ok(())
+    }
+
+    /// Parse a message (or batch of messages) received from the server and
+    /// route it to the caller awaiting it.
+    async fn handle_payload(&self, payload: &str, dispatch: bool) {
+        debug!("MCP message from server: {payload}");
+        let messages = match serde_json::from_str::<JSONRPCMessage>(payload) {
+            Ok(message) => vec![message],
+            Err(_) => match serde_json::from_str::<Vec<JSONRPCMessage>>(payload) {
+                Ok(messages) => messages,
+                Err(err) => {
+                    error!("failed to deserialize JSONRPCMessage: {err}; payload = {payload}");
+                    return;
+                }
+            },
+        };
+        if !dispatch {
+            return;
+        }
+        for message in messages {
+            dispatch_incoming(message, &self.pending, &self.server_requests).await;
+        }
+    }
+
+    /// Report the outcome of a streamable HTTP send. A request that got no
+    /// reply (an error, or a stream that ended early) is failed so its caller
+    /// does not wait for the full timeout.
+    async fn finish(&self, message: &JSONRPCMessage, result: Result<(), PostError>) {
+        match result {
+            Err(err) => self.fail(message, err).await,
+            Ok(()) => {
+                let unanswered = match message {
+                    JSONRPCMessage::Request(JSONRPCRequest {
+                        id: RequestId::Integer(id),
+                        ..
+                    }) => self.pending.lock().await.contains_key(id),
+                    _ => false,
+                };
+                if unanswered {
+                    let err = PostError::Transport(
+                        "MCP server closed the response without replying".to_string(),
+                    );
+                    self.fail(message, err).await;
+                }
+            }
+        }
+    }
+
+    async fn fail(&self, message: &JSONRPCMessage, err: PostError) {
+        match message {
+            JSONRPCMessage::Request(request) => {
+                warn!("failed to send MCP request `{}`: {err}", request.method);
+                let error = JSONRPCError {
+                    error: JSONRPCErrorError {
+                        code: TRANSPORT_ERROR_CODE,
+                        data: None,
+                        message: err.to_string(),
+                    },
+                    id: request.id.clone(),
+                    jsonrpc: JSONRPC_VERSION.to_string(),
+                };
+                dispatch_incoming(
+                    JSONRPCMessage::Error(error),
+                    &self.pending,
+                    &self.server_requests,
+                )
+                .await;
+            }
+            JSONRPCMessage::Notification(notification) => {
+                warn!(
+                    "failed to send MCP notification `{}`: {err}",
+                    notification.method
+                );
+            }
+            other => warn!("failed to send MCP message {other:?}: {err}"),
+        }
+    }
+}
+
+/// The long-lived event stream of a legacy HTTP+SSE server. Dropping it stops
+/// the background task that keeps the stream connected.
+struct LegacyStream {
+    endpoint_rx: watch::Receiver<Option<Url>>,
+    task: JoinHandle<()>,
+}
+
+impl LegacyStream {
+    /// Open the event stream and wait until the server announces the endpoint
+    /// that messages should be POSTed to.
+    async fn connect(transport: Arc<HttpTransport>) -> Result<Self> {
+        let (endpoint_tx, mut endpoint_rx) = watch::channel(None);
+        let task = tokio::spawn(run_legacy_stream(transport, endpoint_tx));
+        let stream = Self {
+            endpoint_rx: endpoint_rx.clone(),
+            task,
+        };
+        tokio::time::timeout(
+            LEGACY_ENDPOINT_TIMEOUT,
+            endpoint_rx.wait_for(Option::is_some),
+        )
+        .await
+        .context("timed out waiting for the MCP server to announce its endpoint")?
+        .context("MCP event stream closed before announcing an endpoint")?;
+        Ok(stream)
+    }
+}
+
+impl Drop for LegacyStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Keep the legacy event stream connected, routing the messages it carries.
+/// After a reconnect the handshake is replayed on the new endpoint before it
+/// is handed to waiting senders.
+async fn run_legacy_stream(transport: Arc<HttpTransport>, endpoint_tx: watch::Sender<Option<Url>>) {
+    let mut attempt = 0;
+    let mut reconnecting = false;
+    loop {
+        let request = transport
+            .client
+            .get(transport.url.clone())
+            .headers(transport.headers.clone())
+            .header(ACCEPT, "text/event-stream");
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                attempt = 0;
+                let mut events = response.bytes_stream().eventsource();
+                while let Some(Ok(event)) = events.next().await {
+                    if event.event == "endpoint" {
+                        let endpoint = match transport.url.join(event.data.trim()) {
+                            Ok(endpoint) => endpoint,
+                            Err(err) => {
+                                warn!("invalid MCP endpoint `{}`: {err}", event.data);
+                                continue;
+                            }
+                        };
+                        if reconnecting {
+                            replay_legacy_handshake(&transport, &endpoint).await;
+                        }
+                        endpoint_tx.send_replace(Some(endpoint));
+                    } else if !event.data.is_empty() {
+                        transport.handle_payload(&event.data, true).await;
+                    }
+                }
+                warn!("MCP event stream closed; reconnecting");
+                endpoint_tx.send_replace(None);
+            }
+            Ok(response) => warn!("MCP event stream returned {}", response.status()),
+            Err(err) => warn!("failed to connect to MCP event stream: {err}"),
+        }
+        reconnecting = true;
+        tokio::time::sleep(backoff(attempt).min(MAX_BACKOFF)).await;
+        attempt = attempt.saturating_add(1);
+    }
+}
+
+async fn replay_legacy_handshake(transport: &HttpTransport, endpoint: &Url) {
+    let (initialize, initialized) = {
+        let handshake = transport.handshake.lock().await;
+        (handshake.initialize.clone(), handshake.initialized.clone())
+    };
+    info!("MCP event stream reconnected; re-initializing");
+    for message in initialize.iter().chain(initialized.iter()) {
+        if let Err(err) = transport.post_legacy(endpoint, message).await {
+            warn!("failed to replay MCP handshake: {err}");
+            return;
+        }
+    }
+}
+
+/// Statuses worth retrying: the server (or a proxy in front of it) is
+/// temporarily unavailable and did not process the message.
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Servers that only implement HTTP+SSE answer the initial POST with a client
+/// error. Authentication failures are reported as-is instead.
+fn is_legacy_rejection(status: StatusCode) -> bool {
+    status.is_client_error()
+        && status != StatusCode::UNAUTHORIZED
+        && status != StatusCode::FORBIDDEN
+}
+
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF.saturating_mul(2u32.saturating_pow(attempt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn builds_header_map() {
+        let headers = build_header_map(HashMap::from([(
+            "Authorization".to_string(),
+            "Bearer secret".to_string(),
+        )]))
+        .expect("valid headers");
+        assert_eq!(
+            headers.get("authorization").and_then(|v| v.to_str().ok()),
+            Some("Bearer secret")
+        );
+
+        let err = build_header_map(HashMap::from([(
+            "bad header".to_string(),
+            "value".to_string(),
+        )]))
+        .expect_err("space is not allowed in a header name");
+        assert!(err.to_string().contains("bad header"));
+    }
+
+    #[test]
+    fn falls_back_only_on_non_auth_client_errors() {
+        assert!(is_legacy_rejection(StatusCode::METHOD_NOT_ALLOWED));
+        assert!(is_legacy_rejection(StatusCode::NOT_FOUND));
+        assert!(is_legacy_rejection(StatusCode::BAD_REQUEST));
+        assert!(!is_legacy_rejection(StatusCode::UNAUTHORIZED));
+        assert!(!is_legacy_rejection(StatusCode::FORBIDDEN));
+        assert!(!is_legacy_rejection(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+}
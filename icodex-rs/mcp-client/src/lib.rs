@@ -1,3 +1,7 @@
+mod http_transport;
 mod mcp_client;
 
 pub use mcp_client::McpClient;
+pub use mcp_client::NotificationHandler;
+pub use mcp_client::ServerRequestFuture;
+pub use mcp_client::ServerRequestHandler;
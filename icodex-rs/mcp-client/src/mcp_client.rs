@@ -2,10 +2,16 @@
 //!
 //! The client is intentionally lightweight – it is only capable of:
 //!   1. Spawning a subprocess that launches a conforming MCP server that
-//!      communicates over stdio.
+//!      communicates over stdio, or connecting to a remote server over
+//!      streamable HTTP (with a fallback to the older HTTP+SSE transport).
 //!   2. Sending MCP requests and pairing them with their corresponding
 //!      responses.
-//!   3. Offering a convenience helper for the common `tools/list` request.
+//!   3. Offering convenience helpers for the common `tools/*`, `resources/*`
+//!      and `prompts/*` requests.
+//!   4. Answering requests the server sends back to the client (for example
+//!      `roots/list` or `sampling/createMessage`) through a
+//!      [`ServerRequestHandler`], and passing the notifications it sends to a
+//!      [`NotificationHandler`].
 //!
 //! The crate hides all JSON‐RPC framing details behind a typed API. Users
 //! interact with the [`ModelContextProtocolRequest`] trait from `mcp-types` to
@@ -14,6 +20,7 @@
 use std::collections::HashMap;
 use std::ffi::OsString;
 use std::sync::Arc;
+use std::sync::OnceLock;
 use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
@@ -21,22 +28,38 @@ use std::time::Duration;
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
+use futures::future::BoxFuture;
 use mcp_types::CallToolRequest;
 use mcp_types::CallToolRequestParams;
+use mcp_types::GetPromptRequest;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::InitializeRequest;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializedNotification;
 use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
 use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListPromptsRequest;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesRequest;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ListResourcesResult;
 use mcp_types::ListToolsRequest;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ReadResourceRequest;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
 use mcp_types::RequestId;
+use reqwest::Url;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use tokio::io::AsyncBufReadExt;
@@ -46,12 +69,16 @@ use tokio::process::Command;
 use tokio::sync::Mutex;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio::time;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
 
+use crate::http_transport::build_header_map;
+use crate::http_transport::spawn_http_transport;
+
 /// Capacity of the bounded channels used for transporting messages between the
 /// client API and the IO tasks.
 const CHANNEL_CAPACITY: usize = 128;
@@ -59,23 +86,118 @@ const CHANNEL_CAPACITY: usize = 128;
 /// Internal representation of a pending request sender.
 type PendingSender = oneshot::Sender<JSONRPCMessage>;
 
+/// Map of `request.id -> oneshot::Sender` shared with the transport tasks.
+pub(crate) type PendingMap = Arc<Mutex<HashMap<i64, PendingSender>>>;
+
+/// JSON-RPC error code returned for server requests nobody handles.
+const METHOD_NOT_FOUND_ERROR_CODE: i64 = -32601;
+
+/// Future resolving to the `result` (or the error) sent back for a request
+/// initiated by the server.
+pub type ServerRequestFuture = BoxFuture<'static, Result<serde_json::Value, JSONRPCErrorError>>;
+
+/// Answers requests that the server sends to the client, such as
+/// `roots/list` or `sampling/createMessage`.
+pub type ServerRequestHandler = Arc<dyn Fn(JSONRPCRequest) -> ServerRequestFuture + Send + Sync>;
+
+/// Receives the notifications the server sends, such as
+/// `notifications/tools/list_changed`.
+pub type NotificationHandler = Arc<dyn Fn(JSONRPCNotification) + Send + Sync>;
+
+/// Hands requests initiated by the server to the registered
+/// [`ServerRequestHandler`] and sends its reply back to the server. Requests
+/// are answered with "method not found" while no handler is registered.
+/// Notifications go to the [`NotificationHandler`], if any.
+#[derive(Clone)]
+pub(crate) struct ServerRequestRouter {
+    handler: Arc<OnceLock<ServerRequestHandler>>,
+    notification_handler: Arc<OnceLock<NotificationHandler>>,
+    outgoing_tx: mpsc::Sender<JSONRPCMessage>,
+}
+
+impl ServerRequestRouter {
+    fn new(outgoing_tx: mpsc::Sender<JSONRPCMessage>) -> Self {
+        Self {
+            handler: Arc::new(OnceLock::new()),
+            notification_handler: Arc::new(OnceLock::new()),
+            outgoing_tx,
+        }
+    }
+
+    fn notify(&self, notification: JSONRPCNotification) {
+        info!("<- notification: {}", notification.method);
+        if let Some(handler) = self.notification_handler.get() {
+            handler(notification);
+        }
+    }
+
+    /// Answer `request` in the background so a slow handler (e.g. one waiting
+    /// for user approval) does not hold up the messages that follow it.
+    fn route(&self, request: JSONRPCRequest) {
+        let handler = self.handler.get().cloned();
+        let outgoing_tx = self.outgoing_tx.clone();
+        tokio::spawn(async move {
+            let id = request.id.clone();
+            let method = request.method.clone();
+            let result = match handler {
+                Some(handler) => handler(request).await,
+                None => Err(JSONRPCErrorError {
+                    code: METHOD_NOT_FOUND_ERROR_CODE,
+                    data: None,
+                    message: format!("client does not handle `{method}`"),
+                }),
+            };
+            let message = match result {
+                Ok(result) => JSONRPCMessage::Response(JSONRPCResponse {
+                    id,
+                    jsonrpc: JSONRPC_VERSION.to_string(),
+                    result,
+                }),
+                Err(error) => JSONRPCMessage::Error(JSONRPCError {
+                    error,
+                    id,
+                    jsonrpc: JSONRPC_VERSION.to_string(),
+                }),
+            };
+            if outgoing_tx.send(message).await.is_err() {
+                warn!("failed to answer server request `{method}`: writer task closed");
+            }
+        });
+    }
+}
+
+/// Resources that keep the connection to the server alive.
+enum Transport {
+    /// Retain this child process until the client is dropped. The Tokio
+    /// runtime will make a "best effort" to reap the process after it exits,
+    /// but it is not a guarantee. See the `kill_on_drop` documentation for
+    /// details. The reader task finishes when the process closes its stdout.
+    Stdio {
+        child: tokio::process::Child,
+        reader: JoinHandle<()>,
+    },
+
+    /// Task delivering messages over HTTP. It is aborted when the client is
+    /// dropped.
+    Http(JoinHandle<()>),
+}
+
 /// A running MCP client instance.
 pub struct McpClient {
-    /// Retain this child process until the client is dropped. The Tokio runtime
-    /// will make a "best effort" to reap the process after it exits, but it is
-    /// not a guarantee. See the `kill_on_drop` documentation for details.
-    #[allow(dead_code)]
-    child: tokio::process::Child,
+    transport: Transport,
 
     /// Channel for sending JSON-RPC messages *to* the background writer task.
     outgoing_tx: mpsc::Sender<JSONRPCMessage>,
 
     /// Map of `request.id -> oneshot::Sender` used to dispatch responses back
     /// to the originating caller.
-    pending: Arc<Mutex<HashMap<i64, PendingSender>>>,
+    pending: PendingMap,
 
     /// Monotonically increasing counter used to generate request IDs.
     id_counter: AtomicI64,
+
+    /// Routes requests initiated by the server to the registered handler.
+    server_requests: ServerRequestRouter,
 }
 
 impl McpClient {
@@ -110,7 +232,8 @@ impl McpClient {
             .ok_or_else(|| std::io::Error::other("failed to capture child stdout"))?;
 
         let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
-        let pending: Arc<Mutex<HashMap<i64, PendingSender>>> = Arc::new(Mutex::new(HashMap::new()));
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let server_requests = ServerRequestRouter::new(outgoing_tx.clone());
 
         // Spawn writer task. It listens on the `outgoing_rx` channel and
         // writes messages to the child's STDIN.
@@ -138,9 +261,11 @@ impl McpClient {
         };
 
         // Spawn reader task. It reads line-delimited JSON from the child's
-        // STDOUT and dispatches responses to the pending map.
+        // STDOUT, dispatches responses to the pending map and hands requests
+        // from the server to the request router.
         let reader_handle = {
             let pending = pending.clone();
+            let server_requests = server_requests.clone();
             let mut lines = BufReader::new(stdout).lines();
 
             tokio::spawn(async move {
@@ -153,14 +278,11 @@ impl McpClient {
                         Ok(JSONRPCMessage::Error(err)) => {
                             Self::dispatch_error(err, &pending).await;
                         }
-                        Ok(JSONRPCMessage::Notification(JSONRPCNotification { .. })) => {
-                            // For now we only log server-initiated notifications.
-                            info!("<- notification: {}", line);
+                        Ok(JSONRPCMessage::Request(request)) => {
+                            server_requests.route(request);
                         }
-                        Ok(other) => {
-                            // Batch responses and requests are currently not
-                            // expected from the server – log and ignore.
-                            info!("<- unhandled message: {:?}", other);
+                        Ok(JSONRPCMessage::Notification(notification)) => {
+                            server_requests.notify(notification);
                         }
                         Err(e) => {
                             error!("failed to deserialize JSONRPCMessage: {e}; line = {}", line)
@@ -170,20 +292,90 @@ impl McpClient {
             })
         };
 
-        // We intentionally *detach* the tasks. They will keep running in the
-        // background as long as their respective resources (channels/stdin/
-        // stdout) are alive. Dropping `McpClient` cancels the tasks due to
-        // dropped resources.
-        let _ = (writer_handle, reader_handle);
+        // We intentionally *detach* the writer task. It keeps running in the
+        // background as long as its channel and stdin are alive. Dropping
+        // `McpClient` cancels both tasks due to dropped resources.
+        let _ = writer_handle;
 
         Ok(Self {
-            child,
+            transport: Transport::Stdio {
+                child,
+                reader: reader_handle,
+            },
             outgoing_tx,
             pending,
             id_counter: AtomicI64::new(1),
+            server_requests,
         })
     }
 
+    /// Connect to the MCP server at `url`. The streamable HTTP transport is
+    /// tried first; servers that only implement the older HTTP+SSE transport
+    /// are detected when they reject the first message. `headers` are sent
+    /// with every request, e.g. `Authorization`.
+    ///
+    /// As with [`new_stdio_client`](Self::new_stdio_client), the caller is
+    /// responsible for calling [`initialize`](Self::initialize).
+    pub async fn new_streamable_http_client(
+        url: &str,
+        headers: HashMap<String, String>,
+    ) -> Result<Self> {
+        let url = Url::parse(url).with_context(|| format!("invalid MCP server URL `{url}`"))?;
+        let headers = build_header_map(headers)?;
+
+        let (outgoing_tx, outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let server_requests = ServerRequestRouter::new(outgoing_tx.clone());
+        let handle = spawn_http_transport(
+            url,
+            headers,
+            outgoing_rx,
+            pending.clone(),
+            server_requests.clone(),
+        );
+
+        Ok(Self {
+            transport: Transport::Http(handle),
+            outgoing_tx,
+            pending,
+            id_counter: AtomicI64::new(1),
+            server_requests,
+        })
+    }
+
+    /// Register the handler that answers requests initiated by the server.
+    /// Register it before calling [`initialize`](Self::initialize): servers
+    /// may send requests as soon as the session is open. Only the first
+    /// handler registered is used.
+    pub fn set_server_request_handler(&self, handler: ServerRequestHandler) {
+        if self.server_requests.handler.set(handler).is_err() {
+            warn!("MCP server request handler is already registered");
+        }
+    }
+
+    /// Register the handler that receives the notifications sent by the
+    /// server. Only the first handler registered is used.
+    pub fn set_notification_handler(&self, handler: NotificationHandler) {
+        if self
+            .server_requests
+            .notification_handler
+            .set(handler)
+            .is_err()
+        {
+            warn!("MCP notification handler is already registered");
+        }
+    }
+
+    /// Whether the connection to the server is gone for good: the server
+    /// process exited, or the HTTP transport gave up. Requests sent to a
+    /// closed client fail.
+    pub fn is_closed(&self) -> bool {
+        match &self.transport {
+            Transport::Stdio { reader, .. } => reader.is_finished(),
+            Transport::Http(handle) => handle.is_finished(),
+        }
+    }
+
     /// Send an arbitrary MCP request and await the typed result.
     ///
     /// If `timeout` is `None` the call waits indefinitely. If `Some(duration)`
@@ -347,11 +539,50 @@ impl McpClient {
         self.send_request::<CallToolRequest>(params, timeout).await
     }
 
+    /// Convenience wrapper around `resources/list`.
+    pub async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourcesResult> {
+        self.send_request::<ListResourcesRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `resources/read`.
+    pub async fn read_resource(
+        &self,
+        uri: String,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        let params = ReadResourceRequestParams { uri };
+        self.send_request::<ReadResourceRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/list`.
+    pub async fn list_prompts(
+        &self,
+        params: Option<ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListPromptsResult> {
+        self.send_request::<ListPromptsRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/get`.
+    pub async fn get_prompt(
+        &self,
+        name: String,
+        arguments: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        let params = GetPromptRequestParams { arguments, name };
+        self.send_request::<GetPromptRequest>(params, timeout).await
+    }
+
     /// Internal helper: route a JSON-RPC *response* object to the pending map.
-    async fn dispatch_response(
-        resp: JSONRPCResponse,
-        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
-    ) {
+    async fn dispatch_response(resp: JSONRPCResponse, pending: &PendingMap) {
         let id = match resp.id {
             RequestId::Integer(i) => i,
             RequestId::String(_) => {
@@ -375,10 +606,7 @@ impl McpClient {
     }
 
     /// Internal helper: route a JSON-RPC *error* object to the pending map.
-    async fn dispatch_error(
-        err: mcp_types::JSONRPCError,
-        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
-    ) {
+    async fn dispatch_error(err: mcp_types::JSONRPCError, pending: &PendingMap) {
         let id = match err.id {
             RequestId::Integer(i) => i,
             RequestId::String(_) => return, // see comment above
@@ -394,13 +622,34 @@ impl McpClient {
     }
 }
 
+/// Route a message received from an HTTP transport to the caller awaiting
+/// it, or to the request router when the server initiated it.
+pub(crate) async fn dispatch_incoming(
+    message: JSONRPCMessage,
+    pending: &PendingMap,
+    server_requests: &ServerRequestRouter,
+) {
+    match message {
+        JSONRPCMessage::Response(resp) => McpClient::dispatch_response(resp, pending).await,
+        JSONRPCMessage::Error(err) => McpClient::dispatch_error(err, pending).await,
+        JSONRPCMessage::Request(request) => server_requests.route(request),
+        JSONRPCMessage::Notification(notification) => server_requests.notify(notification),
+    }
+}
+
 impl Drop for McpClient {
     fn drop(&mut self) {
-        // Even though we have already tagged this process with
-        // `kill_on_drop(true)` above, this extra check has the benefit of
-        // forcing the process to be reaped immediately if it has already exited
-        // instead of waiting for the Tokio runtime to reap it later.
-        let _ = self.child.try_wait();
+        match &mut self.transport {
+            Transport::Stdio { child, .. } => {
+                // Even though we have already tagged this process with
+                // `kill_on_drop(true)` above, this extra check has the benefit
+                // of forcing the process to be reaped immediately if it has
+                // already exited instead of waiting for the Tokio runtime to
+                // reap it later.
+                let _ = child.try_wait();
+            }
+            Transport::Http(handle) => handle.abort(),
+        }
     }
 }
 
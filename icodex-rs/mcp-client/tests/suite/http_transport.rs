@@ -0,0 +1,349 @@
+//! Exercises the streamable HTTP and HTTP+SSE transports against a local
+//! hyper server.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use http_body_util::BodyExt;
+use http_body_util::Empty;
+use http_body_util::Full;
+use http_body_util::StreamBody;
+use http_body_util::combinators::UnsyncBoxBody;
+use hyper::Method;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use hyper::body::Bytes;
+use hyper::body::Frame;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use icodex_mcp_client::McpClient;
+use mcp_types::ClientCapabilities;
+use mcp_types::Implementation;
+use mcp_types::InitializeRequestParams;
+use mcp_types::MCP_SCHEMA_VERSION;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+/// Serve `handler` on an ephemeral local port and return the base URL.
+async fn serve<F, Fut>(handler: F) -> String
+where
+    F: Fn(Request<Incoming>) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Response<Body>> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    let response = handler(request);
+                    async move { Ok::<_, Infallible>(response.await) }
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+    format!("http://{addr}")
+}
+
+fn initialize_params() -> InitializeRequestParams {
+    InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            elicitation: None,
+            experimental: None,
+            roots: None,
+            sampling: None,
+        },
+        client_info: Implementation {
+            name: "test-client".to_string(),
+            title: None,
+            version: "0.0.0".to_string(),
+            user_agent: None,
+        },
+        protocol_version: MCP_SCHEMA_VERSION.to_string(),
+    }
+}
+
+/// The reply a minimal MCP server sends to `message`, if any.
+fn reply_for(message: &Value) -> Option<Value> {
+    let result = match message["method"].as_str()? {
+        "initialize" => json!({
+            "capabilities": {"tools": {}},
+            "protocolVersion": MCP_SCHEMA_VERSION,
+            "serverInfo": {"name": "test-server", "version": "1.0.0"},
+        }),
+        "tools/list" => json!({
+            "tools": [{"name": "echo", "inputSchema": {"type": "object"}}],
+        }),
+        _ => return None,
+    };
+    Some(json!({"jsonrpc": "2.0", "id": message.get("id")?, "result": result}))
+}
+
+fn header(request: &Request<Incoming>, name: &str) -> Option<String> {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Empty::new().boxed_unsync())
+        .unwrap()
+}
+
+fn sse_event(data: &Value) -> String {
+    format!("event: message\ndata: {data}\n\n")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RecordedRequest {
+    method: String,
+    session_id: Option<String>,
+    authorization: Option<String>,
+}
+
+/// A streamable HTTP server that answers `initialize` with JSON and
+/// everything else with an SSE stream. Every `initialize` opens a new session
+/// and only the most recent one is accepted.
+#[derive(Default)]
+struct StreamableServer {
+    requests: Mutex<Vec<RecordedRequest>>,
+    sessions: Mutex<u32>,
+}
+
+impl StreamableServer {
+    async fn handle(self: Arc<Self>, request: Request<Incoming>) -> Response<Body> {
+        let session_id = header(&request, "mcp-session-id");
+        let authorization = header(&request, "authorization");
+        let body = request.into_body().collect().await.unwrap().to_bytes();
+        let message: Value = serde_json::from_slice(&body).unwrap();
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        self.requests.lock().unwrap().push(RecordedRequest {
+            method: method.clone(),
+            session_id: session_id.clone(),
+            authorization,
+        });
+
+        if method == "initialize" {
+            let session = {
+                let mut sessions = self.sessions.lock().unwrap();
+                *sessions += 1;
+                format!("session-{sessions}")
+            };
+            return Response::builder()
+                .header("content-type", "application/json")
+                .header("mcp-session-id", session)
+                .body(Full::from(reply_for(&message).unwrap().to_string()).boxed_unsync())
+                .unwrap();
+        }
+
+        let current = format!("session-{}", self.sessions.lock().unwrap());
+        if session_id.as_deref() != Some(current.as_str()) {
+            return status(StatusCode::NOT_FOUND);
+        }
+        match reply_for(&message) {
+            Some(reply) => Response::builder()
+                .header("content-type", "text/event-stream")
+                .body(Full::from(sse_event(&reply)).boxed_unsync())
+                .unwrap(),
+            None => status(StatusCode::ACCEPTED),
+        }
+    }
+
+    fn expire_session(&self) {
+        *self.sessions.lock().unwrap() += 1;
+    }
+
+    fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn start_streamable_server() -> (Arc<StreamableServer>, String) {
+    let server = Arc::new(StreamableServer::default());
+    let handler_server = server.clone();
+    let url = serve(move |request| handler_server.clone().handle(request)).await;
+    (server, format!("{url}/mcp"))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streamable_http_tracks_session_and_sends_headers() {
+    let (server, url) = start_streamable_server().await;
+    let client = McpClient::new_streamable_http_client(
+        &url,
+        HashMap::from([(
+            "Authorization".to_string(),
+            "Bearer secret-token".to_string(),
+        )]),
+    )
+    .await
+    .unwrap();
+
+    let init = client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(init.server_info.name, "test-server");
+
+    let tools = client.list_tools(None, TIMEOUT).await.unwrap();
+    assert_eq!(tools.tools.len(), 1);
+    assert_eq!(tools.tools[0].name, "echo");
+
+    let record = |method: &str, session_id: Option<&str>| RecordedRequest {
+        method: method.to_string(),
+        session_id: session_id.map(str::to_string),
+        authorization: Some("Bearer secret-token".to_string()),
+    };
+    assert_eq!(
+        server.requests(),
+        vec![
+            record("initialize", None),
+            record("notifications/initialized", Some("session-1")),
+            record("tools/list", Some("session-1")),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streamable_http_reinitializes_expired_session() {
+    let (server, url) = start_streamable_server().await;
+    let client = McpClient::new_streamable_http_client(&url, HashMap::new())
+        .await
+        .unwrap();
+    client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+
+    server.expire_session();
+    let tools = client.list_tools(None, TIMEOUT).await.unwrap();
+    assert_eq!(tools.tools[0].name, "echo");
+
+    let calls: Vec<(String, Option<String>)> = server
+        .requests()
+        .into_iter()
+        .map(|request| (request.method, request.session_id))
+        .collect();
+    let call = |method: &str, session_id: Option<&str>| {
+        (method.to_string(), session_id.map(str::to_string))
+    };
+    assert_eq!(
+        calls,
+        vec![
+            call("initialize", None),
+            call("notifications/initialized", Some("session-1")),
+            call("tools/list", Some("session-1")),
+            call("initialize", None),
+            call("notifications/initialized", Some("session-3")),
+            call("tools/list", Some("session-3")),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streamable_http_fails_requests_the_server_rejects() {
+    let url = serve(|_request| async { status(StatusCode::UNAUTHORIZED) }).await;
+    let client = McpClient::new_streamable_http_client(&format!("{url}/mcp"), HashMap::new())
+        .await
+        .unwrap();
+
+    let err = client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("401"), "unexpected error: {err:#}");
+}
+
+/// A server that only speaks the HTTP+SSE transport: replies go out on the
+/// `GET /mcp` event stream and messages are POSTed to `/messages`.
+#[derive(Default)]
+struct LegacyServer {
+    events: Mutex<Option<mpsc::UnboundedSender<String>>>,
+    methods: Mutex<Vec<String>>,
+}
+
+impl LegacyServer {
+    async fn handle(self: Arc<Self>, request: Request<Incoming>) -> Response<Body> {
+        match (request.method().clone(), request.uri().path()) {
+            (Method::GET, "/mcp") => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                tx.send("event: endpoint\ndata: /messages?session_id=abc\n\n".to_string())
+                    .unwrap();
+                *self.events.lock().unwrap() = Some(tx);
+                let stream = futures::stream::unfold(rx, |mut rx| async move {
+                    let event = rx.recv().await?;
+                    Some((Ok::<_, Infallible>(Frame::data(Bytes::from(event))), rx))
+                });
+                Response::builder()
+                    .header("content-type", "text/event-stream")
+                    .body(StreamBody::new(stream).boxed_unsync())
+                    .unwrap()
+            }
+            (Method::POST, "/messages") => {
+                assert_eq!(request.uri().query(), Some("session_id=abc"));
+                let body = request.into_body().collect().await.unwrap().to_bytes();
+                let message: Value = serde_json::from_slice(&body).unwrap();
+                self.methods
+                    .lock()
+                    .unwrap()
+                    .push(message["method"].as_str().unwrap_or_default().to_string());
+                if let Some(reply) = reply_for(&message)
+                    && let Some(events) = self.events.lock().unwrap().as_ref()
+                {
+                    events.send(sse_event(&reply)).unwrap();
+                }
+                status(StatusCode::ACCEPTED)
+            }
+            _ => status(StatusCode::METHOD_NOT_ALLOWED),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn falls_back_to_http_sse_transport() {
+    let server = Arc::new(LegacyServer::default());
+    let handler_server = server.clone();
+    let url = serve(move |request| handler_server.clone().handle(request)).await;
+
+    let client = McpClient::new_streamable_http_client(&format!("{url}/mcp"), HashMap::new())
+        .await
+        .unwrap();
+    let init = client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(init.server_info.name, "test-server");
+
+    let tools = client.list_tools(None, TIMEOUT).await.unwrap();
+    assert_eq!(tools.tools[0].name, "echo");
+
+    assert_eq!(
+        *server.methods.lock().unwrap(),
+        vec![
+            "initialize".to_string(),
+            "notifications/initialized".to_string(),
+            "tools/list".to_string(),
+        ]
+    );
+}
@@ -31,6 +31,7 @@ use icodex_core::protocol::Event;
 use icodex_core::protocol::EventMsg;
 use icodex_core::protocol::ExecApprovalRequestEvent;
 use icodex_core::protocol::InputItem as CoreInputItem;
+use icodex_core::protocol::McpToolCallApprovalRequestEvent;
 use icodex_core::protocol::Op;
 use icodex_core::protocol::ReviewDecision;
 use icodex_login::ServerOptions as LoginServerOptions;
@@ -64,6 +65,9 @@ use icodex_protocol::mcp_protocol::LoginApiKeyParams;
 use icodex_protocol::mcp_protocol::LoginApiKeyResponse;
 use icodex_protocol::mcp_protocol::LoginChatGptCompleteNotification;
 use icodex_protocol::mcp_protocol::LoginChatGptResponse;
+use icodex_protocol::mcp_protocol::MCP_TOOL_CALL_APPROVAL_METHOD;
+use icodex_protocol::mcp_protocol::McpToolCallApprovalParams;
+use icodex_protocol::mcp_protocol::McpToolCallApprovalResponse;
 use icodex_protocol::mcp_protocol::NewConversationParams;
 use icodex_protocol::mcp_protocol::NewConversationResponse;
 use icodex_protocol::mcp_protocol::RemoveConversationListenerParams;
@@ -564,7 +568,7 @@ impl CodexMessageProcessor {
         }
 
         let cwd = params.cwd.unwrap_or_else(|| self.config.cwd.clone());
-        let env = create_env(&self.config.shell_environment_policy);
+        let env = create_env(&self.config.shell_environment_policy, None);
         let timeout_ms = params.timeout_ms;
         let exec_params = ExecParams {
             command: params.command,
@@ -583,10 +587,15 @@ impl CodexMessageProcessor {
             icodex_core::protocol::SandboxPolicy::DangerFullAccess => {
                 icodex_core::exec::SandboxType::None
             }
+            _ if self.config.container_sandbox.is_some() => {
+                icodex_core::exec::SandboxType::Container
+            }
             _ => get_platform_sandbox().unwrap_or(icodex_core::exec::SandboxType::None),
         };
         tracing::debug!("Sandbox type: {sandbox_type:?}");
         let icodex_linux_sandbox_exe = self.config.icodex_linux_sandbox_exe.clone();
+        let container_sandbox = self.config.container_sandbox.clone();
+        let exec_limits = self.config.exec_limits;
         let outgoing = self.outgoing.clone();
         let req_id = request_id;
 
@@ -596,6 +605,8 @@ impl CodexMessageProcessor {
                 sandbox_type,
                 &effective_policy,
                 &icodex_linux_sandbox_exe,
+                container_sandbox.as_ref(),
+                &exec_limits,
                 None,
             )
             .await
@@ -1220,6 +1231,26 @@ async fn apply_bespoke_event_handling(
                 on_exec_approval_response(event_id, rx, conversation).await;
             });
         }
+        EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+            call_id,
+            invocation,
+        }) => {
+            let params = McpToolCallApprovalParams {
+                conversation_id,
+                call_id,
+                server: invocation.server,
+                tool: invocation.tool,
+                arguments: invocation.arguments,
+            };
+            let value = serde_json::to_value(&params).unwrap_or_default();
+            let rx = outgoing
+                .send_request(MCP_TOOL_CALL_APPROVAL_METHOD, Some(value))
+                .await;
+
+            tokio::spawn(async move {
+                on_mcp_tool_call_approval_response(event_id, rx, conversation).await;
+            });
+        }
         // If this is a TurnAborted, reply to any pending interrupt requests.
         EventMsg::TurnAborted(turn_aborted_event) => {
             let pending = {
@@ -1359,7 +1390,37 @@ async fn on_exec_approval_response(
     }
 }
 
-fn extract_conversation_summary(
+async fn on_mcp_tool_call_approval_response(
+    event_id: String,
+    receiver: oneshot::Receiver<mcp_types::Result>,
+    conversation: Arc<CodexConversation>,
+) {
+    // A failed request denies the call, so the turn does not wait forever.
+    let decision = match receiver.await {
+        Ok(value) => serde_json::from_value::<McpToolCallApprovalResponse>(value)
+            .map(|response| response.decision)
+            .unwrap_or_else(|err| {
+                error!("failed to deserialize McpToolCallApprovalResponse: {err}");
+                ReviewDecision::Denied
+            }),
+        Err(err) => {
+            error!("request failed: {err:?}");
+            ReviewDecision::Denied
+        }
+    };
+
+    if let Err(err) = conversation
+        .submit(Op::McpToolCallApproval {
+            id: event_id,
+            decision,
+        })
+        .await
+    {
+        error!("failed to submit McpToolCallApproval: {err}");
+    }
+}
+
+pub(crate) fn extract_conversation_summary(
     path: PathBuf,
     head: &[serde_json::Value],
 ) -> Option<ConversationSummary> {
@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::exec_approval::handle_exec_approval_request;
+use crate::mcp_tool_approval::handle_mcp_tool_approval_request;
 use crate::outgoing_message::OutgoingMessageSender;
 use crate::outgoing_message::OutgoingNotificationMeta;
 use crate::patch_approval::handle_patch_approval_request;
@@ -19,6 +20,7 @@ use icodex_core::protocol::Event;
 use icodex_core::protocol::EventMsg;
 use icodex_core::protocol::ExecApprovalRequestEvent;
 use icodex_core::protocol::InputItem;
+use icodex_core::protocol::McpToolCallApprovalRequestEvent;
 use icodex_core::protocol::Op;
 use icodex_core::protocol::Submission;
 use icodex_core::protocol::TaskCompleteEvent;
@@ -220,6 +222,22 @@ async fn run_icodex_tool_session_inner(
                         .await;
                         continue;
                     }
+                    EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+                        call_id,
+                        invocation,
+                    }) => {
+                        handle_mcp_tool_approval_request(
+                            call_id,
+                            invocation,
+                            outgoing.clone(),
+                            icodex.clone(),
+                            request_id.clone(),
+                            request_id_str.clone(),
+                            event.id.clone(),
+                        )
+                        .await;
+                        continue;
+                    }
                     EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
                         let text = match last_agent_message {
                             Some(msg) => msg,
@@ -264,6 +282,9 @@ async fn run_icodex_tool_session_inner(
                     | EventMsg::McpToolCallEnd(_)
                     | EventMsg::McpListToolsResponse(_)
                     | EventMsg::ListCustomPromptsResponse(_)
+                    | EventMsg::McpListResourcesResponse(_)
+                    | EventMsg::McpListPromptsResponse(_)
+                    | EventMsg::McpSamplingApprovalRequest(_)
                     | EventMsg::ExecCommandBegin(_)
                     | EventMsg::ExecCommandOutputDelta(_)
                     | EventMsg::ExecCommandEnd(_)
@@ -272,6 +293,9 @@ async fn run_icodex_tool_session_inner(
                     | EventMsg::PatchApplyBegin(_)
                     | EventMsg::PatchApplyEnd(_)
                     | EventMsg::TurnDiff(_)
+                    | EventMsg::TurnRolledBack(_)
+                    | EventMsg::SubAgentBegin(_)
+                    | EventMsg::SubAgent(_)
                     | EventMsg::WebSearchBegin(_)
                     | EventMsg::WebSearchEnd(_)
                     | EventMsg::GetHistoryEntryResponse(_)
@@ -0,0 +1,104 @@
+//! Argument completion (`completion/complete`) for the `profile` and `model`
+//! arguments accepted by the `icodex` tool.
+
+use std::collections::BTreeSet;
+
+use icodex_common::model_presets::builtin_model_presets;
+use icodex_core::config::Config;
+use icodex_core::config::load_config_as_toml_with_cli_overrides;
+use mcp_types::CompleteResultCompletion;
+
+/// The MCP spec caps a completion response at 100 values.
+const MAX_COMPLETION_VALUES: usize = 100;
+
+/// Complete `value` for the argument called `name`. Arguments other than
+/// `profile` and `model` have no completions.
+pub(crate) fn complete_argument(
+    config: &Config,
+    name: &str,
+    value: &str,
+) -> CompleteResultCompletion {
+    let candidates = match name {
+        "profile" => profile_names(config),
+        "model" => model_slugs(config),
+        _ => BTreeSet::new(),
+    };
+    completion_from(candidates, value)
+}
+
+fn completion_from(candidates: BTreeSet<String>, value: &str) -> CompleteResultCompletion {
+    let matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(value))
+        .collect();
+    let total = matches.len();
+    CompleteResultCompletion {
+        has_more: Some(total > MAX_COMPLETION_VALUES),
+        total: Some(total as i64),
+        values: matches.into_iter().take(MAX_COMPLETION_VALUES).collect(),
+    }
+}
+
+fn profile_names(config: &Config) -> BTreeSet<String> {
+    match load_config_as_toml_with_cli_overrides(&config.icodex_home, Vec::new()) {
+        Ok(config_toml) => config_toml.profiles.into_keys().collect(),
+        Err(e) => {
+            tracing::warn!("failed to load config.toml for profile completion: {e}");
+            BTreeSet::new()
+        }
+    }
+}
+
+/// Built-in presets, models defined in `models.d`/`[models]`, and every model
+/// named in `config.toml` or one of its profiles.
+fn model_slugs(config: &Config) -> BTreeSet<String> {
+    let mut slugs: BTreeSet<String> = builtin_model_presets(None)
+        .into_iter()
+        .map(|preset| preset.model.to_string())
+        .collect();
+    slugs.insert(config.model.clone());
+    slugs.extend(config.models.slugs());
+    if let Ok(config_toml) = load_config_as_toml_with_cli_overrides(&config.icodex_home, Vec::new())
+    {
+        slugs.extend(config_toml.model);
+        slugs.extend(
+            config_toml
+                .profiles
+                .into_values()
+                .filter_map(|profile| profile.model),
+        );
+    }
+    slugs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn completion_filters_by_prefix() {
+        let candidates = BTreeSet::from([
+            "gpt-5".to_string(),
+            "gpt-5-icodex".to_string(),
+            "o3".to_string(),
+        ]);
+        assert_eq!(
+            completion_from(candidates, "gpt"),
+            CompleteResultCompletion {
+                has_more: Some(false),
+                total: Some(2),
+                values: vec!["gpt-5".to_string(), "gpt-5-icodex".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn completion_caps_the_number_of_values() {
+        let candidates = (0..150).map(|i| format!("model-{i:03}")).collect();
+        let completion = completion_from(candidates, "model-");
+        assert_eq!(completion.values.len(), MAX_COMPLETION_VALUES);
+        assert_eq!(completion.total, Some(150));
+        assert_eq!(completion.has_more, Some(true));
+    }
+}
@@ -23,12 +23,16 @@ use tracing_subscriber::EnvFilter;
 mod icodex_message_processor;
 mod icodex_tool_config;
 mod icodex_tool_runner;
+mod completion;
 mod error_code;
 mod exec_approval;
 mod json_to_toml;
+mod mcp_tool_approval;
 pub(crate) mod message_processor;
 mod outgoing_message;
 mod patch_approval;
+mod prompts;
+mod session_resources;
 
 use crate::message_processor::MessageProcessor;
 use crate::outgoing_message::OutgoingMessage;
@@ -38,6 +42,8 @@ pub use crate::icodex_tool_config::CodexToolCallParam;
 pub use crate::icodex_tool_config::CodexToolCallReplyParam;
 pub use crate::exec_approval::ExecApprovalElicitRequestParams;
 pub use crate::exec_approval::ExecApprovalResponse;
+pub use crate::mcp_tool_approval::McpToolApprovalElicitRequestParams;
+pub use crate::mcp_tool_approval::McpToolApprovalResponse;
 pub use crate::patch_approval::PatchApprovalElicitRequestParams;
 pub use crate::patch_approval::PatchApprovalResponse;
 
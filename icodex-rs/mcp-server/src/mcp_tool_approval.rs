@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use icodex_core::CodexConversation;
+use icodex_core::protocol::McpInvocation;
+use icodex_core::protocol::Op;
+use icodex_core::protocol::ReviewDecision;
+use mcp_types::ElicitRequest;
+use mcp_types::ElicitRequestParamsRequestedSchema;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::ModelContextProtocolRequest;
+use mcp_types::RequestId;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use tracing::error;
+
+use crate::icodex_tool_runner::INVALID_PARAMS_ERROR_CODE;
+use crate::outgoing_message::OutgoingMessageSender;
+
+#[derive(Debug, Serialize)]
+pub struct McpToolApprovalElicitRequestParams {
+    pub message: String,
+    #[serde(rename = "requestedSchema")]
+    pub requested_schema: ElicitRequestParamsRequestedSchema,
+    pub icodex_elicitation: String,
+    pub icodex_mcp_tool_call_id: String,
+    pub icodex_event_id: String,
+    pub icodex_call_id: String,
+    pub icodex_server: String,
+    pub icodex_tool: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icodex_arguments: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct McpToolApprovalResponse {
+    pub decision: ReviewDecision,
+}
+
+pub(crate) async fn handle_mcp_tool_approval_request(
+    call_id: String,
+    invocation: McpInvocation,
+    outgoing: Arc<OutgoingMessageSender>,
+    icodex: Arc<CodexConversation>,
+    request_id: RequestId,
+    tool_call_id: String,
+    event_id: String,
+) {
+    let McpInvocation {
+        server,
+        tool,
+        arguments,
+    } = invocation;
+    let params = McpToolApprovalElicitRequestParams {
+        message: format!("Allow Codex to call the `{tool}` tool of MCP server `{server}`?"),
+        requested_schema: ElicitRequestParamsRequestedSchema {
+            r#type: "object".to_string(),
+            properties: json!({}),
+            required: None,
+        },
+        icodex_elicitation: "mcp-tool-approval".to_string(),
+        icodex_mcp_tool_call_id: tool_call_id.clone(),
+        icodex_event_id: event_id.clone(),
+        icodex_call_id: call_id,
+        icodex_server: server,
+        icodex_tool: tool,
+        icodex_arguments: arguments,
+    };
+    let params_json = match serde_json::to_value(&params) {
+        Ok(value) => value,
+        Err(err) => {
+            let message = format!("Failed to serialize McpToolApprovalElicitRequestParams: {err}");
+            error!("{message}");
+
+            outgoing
+                .send_error(
+                    request_id.clone(),
+                    JSONRPCErrorError {
+                        code: INVALID_PARAMS_ERROR_CODE,
+                        message,
+                        data: None,
+                    },
+                )
+                .await;
+
+            return;
+        }
+    };
+
+    let on_response = outgoing
+        .send_request(ElicitRequest::METHOD, Some(params_json))
+        .await;
+
+    // Listen for the response on a separate task so we don't block the main agent loop.
+    tokio::spawn(async move {
+        on_mcp_tool_approval_response(event_id, on_response, icodex).await;
+    });
+}
+
+async fn on_mcp_tool_approval_response(
+    event_id: String,
+    receiver: tokio::sync::oneshot::Receiver<mcp_types::Result>,
+    icodex: Arc<CodexConversation>,
+) {
+    let response = receiver.await;
+    let value = match response {
+        Ok(value) => value,
+        Err(err) => {
+            error!("request failed: {err:?}");
+            if let Err(submit_err) = icodex
+                .submit(Op::McpToolCallApproval {
+                    id: event_id.clone(),
+                    decision: ReviewDecision::Denied,
+                })
+                .await
+            {
+                error!(
+                    "failed to submit denied McpToolCallApproval after request failure: {submit_err}"
+                );
+            }
+            return;
+        }
+    };
+
+    let response = serde_json::from_value::<McpToolApprovalResponse>(value).unwrap_or_else(|err| {
+        error!("failed to deserialize McpToolApprovalResponse: {err}");
+        McpToolApprovalResponse {
+            decision: ReviewDecision::Denied,
+        }
+    });
+
+    if let Err(err) = icodex
+        .submit(Op::McpToolCallApproval {
+            id: event_id,
+            decision: response.decision,
+        })
+        .await
+    {
+        error!("failed to submit McpToolCallApproval: {err}");
+    }
+}
@@ -1,13 +1,21 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::completion::complete_argument;
+use crate::error_code::INTERNAL_ERROR_CODE;
+use crate::error_code::INVALID_PARAMS_ERROR_CODE;
+use crate::error_code::INVALID_REQUEST_ERROR_CODE;
 use crate::icodex_message_processor::CodexMessageProcessor;
 use crate::icodex_tool_config::CodexToolCallParam;
 use crate::icodex_tool_config::CodexToolCallReplyParam;
 use crate::icodex_tool_config::create_tool_for_icodex_tool_call_param;
 use crate::icodex_tool_config::create_tool_for_icodex_tool_call_reply_param;
-use crate::error_code::INVALID_REQUEST_ERROR_CODE;
 use crate::outgoing_message::OutgoingMessageSender;
+use crate::prompts;
+use crate::session_resources::SessionSubscriptions;
+use crate::session_resources::list_session_resources;
+use crate::session_resources::read_session_resource;
+use crate::session_resources::resolve_session_uri;
 use icodex_protocol::mcp_protocol::ClientRequest;
 use icodex_protocol::mcp_protocol::ConversationId;
 
@@ -20,15 +28,20 @@ use icodex_core::protocol::Submission;
 use mcp_types::CallToolRequestParams;
 use mcp_types::CallToolResult;
 use mcp_types::ClientRequest as McpClientRequest;
+use mcp_types::CompleteResult;
 use mcp_types::ContentBlock;
 use mcp_types::JSONRPCError;
 use mcp_types::JSONRPCErrorError;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourceTemplatesResult;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolRequest;
 use mcp_types::RequestId;
+use mcp_types::ServerCapabilitiesPrompts;
+use mcp_types::ServerCapabilitiesResources;
 use mcp_types::ServerCapabilitiesTools;
 use mcp_types::ServerNotification;
 use mcp_types::TextContent;
@@ -45,6 +58,8 @@ pub(crate) struct MessageProcessor {
     icodex_linux_sandbox_exe: Option<PathBuf>,
     conversation_manager: Arc<ConversationManager>,
     running_requests_id_to_icodex_uuid: Arc<Mutex<HashMap<RequestId, ConversationId>>>,
+    config: Arc<Config>,
+    session_subscriptions: SessionSubscriptions,
 }
 
 impl MessageProcessor {
@@ -63,8 +78,9 @@ impl MessageProcessor {
             conversation_manager.clone(),
             outgoing.clone(),
             icodex_linux_sandbox_exe.clone(),
-            config,
+            config.clone(),
         );
+        let session_subscriptions = SessionSubscriptions::new(outgoing.clone());
         Self {
             icodex_message_processor,
             outgoing,
@@ -72,6 +88,8 @@ impl MessageProcessor {
             icodex_linux_sandbox_exe,
             conversation_manager,
             running_requests_id_to_icodex_uuid: Arc::new(Mutex::new(HashMap::new())),
+            config,
+            session_subscriptions,
         }
     }
 
@@ -107,25 +125,26 @@ impl MessageProcessor {
                 self.handle_ping(request_id, params).await;
             }
             McpClientRequest::ListResourcesRequest(params) => {
-                self.handle_list_resources(params);
+                self.handle_list_resources(request_id, params).await;
             }
             McpClientRequest::ListResourceTemplatesRequest(params) => {
-                self.handle_list_resource_templates(params);
+                self.handle_list_resource_templates(request_id, params)
+                    .await;
             }
             McpClientRequest::ReadResourceRequest(params) => {
-                self.handle_read_resource(params);
+                self.handle_read_resource(request_id, params).await;
             }
             McpClientRequest::SubscribeRequest(params) => {
-                self.handle_subscribe(params);
+                self.handle_subscribe(request_id, params).await;
             }
             McpClientRequest::UnsubscribeRequest(params) => {
-                self.handle_unsubscribe(params);
+                self.handle_unsubscribe(request_id, params).await;
             }
             McpClientRequest::ListPromptsRequest(params) => {
-                self.handle_list_prompts(params);
+                self.handle_list_prompts(request_id, params).await;
             }
             McpClientRequest::GetPromptRequest(params) => {
-                self.handle_get_prompt(params);
+                self.handle_get_prompt(request_id, params).await;
             }
             McpClientRequest::ListToolsRequest(params) => {
                 self.handle_list_tools(request_id, params).await;
@@ -137,7 +156,7 @@ impl MessageProcessor {
                 self.handle_set_level(params);
             }
             McpClientRequest::CompleteRequest(params) => {
-                self.handle_complete(params);
+                self.handle_complete(request_id, params).await;
             }
         }
     }
@@ -222,11 +241,16 @@ impl MessageProcessor {
         // Build a minimal InitializeResult. Fill with placeholders.
         let result = mcp_types::InitializeResult {
             capabilities: mcp_types::ServerCapabilities {
-                completions: None,
+                completions: Some(json!({})),
                 experimental: None,
                 logging: None,
-                prompts: None,
-                resources: None,
+                prompts: Some(ServerCapabilitiesPrompts {
+                    list_changed: Some(false),
+                }),
+                resources: Some(ServerCapabilitiesResources {
+                    list_changed: Some(false),
+                    subscribe: Some(true),
+                }),
                 tools: Some(ServerCapabilitiesTools {
                     list_changed: Some(true),
                 }),
@@ -263,54 +287,159 @@ impl MessageProcessor {
             .await;
     }
 
-    fn handle_list_resources(
+    async fn handle_list_resources(
         &self,
+        id: RequestId,
         params: <mcp_types::ListResourcesRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/list -> params: {:?}", params);
+        let cursor = params.and_then(|params| params.cursor);
+        match list_session_resources(&self.config.icodex_home, cursor).await {
+            Ok(result) => {
+                self.send_response::<mcp_types::ListResourcesRequest>(id, result)
+                    .await;
+            }
+            Err(e) => {
+                self.send_error(
+                    id,
+                    INTERNAL_ERROR_CODE,
+                    format!("failed to list sessions: {e}"),
+                )
+                .await;
+            }
+        }
     }
 
-    fn handle_list_resource_templates(
+    async fn handle_list_resource_templates(
         &self,
+        id: RequestId,
         params:
             <mcp_types::ListResourceTemplatesRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/templates/list -> params: {:?}", params);
+        let result = ListResourceTemplatesResult {
+            next_cursor: None,
+            resource_templates: Vec::new(),
+        };
+        self.send_response::<mcp_types::ListResourceTemplatesRequest>(id, result)
+            .await;
     }
 
-    fn handle_read_resource(
+    async fn handle_read_resource(
         &self,
+        id: RequestId,
         params: <mcp_types::ReadResourceRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/read -> params: {:?}", params);
+        match read_session_resource(&self.config.icodex_home, &params.uri).await {
+            Ok(Some(result)) => {
+                self.send_response::<mcp_types::ReadResourceRequest>(id, result)
+                    .await;
+            }
+            Ok(None) => {
+                self.send_error(
+                    id,
+                    INVALID_PARAMS_ERROR_CODE,
+                    format!("resource not found: {}", params.uri),
+                )
+                .await;
+            }
+            Err(e) => {
+                self.send_error(
+                    id,
+                    INTERNAL_ERROR_CODE,
+                    format!("failed to read {}: {e}", params.uri),
+                )
+                .await;
+            }
+        }
     }
 
-    fn handle_subscribe(
-        &self,
+    async fn handle_subscribe(
+        &mut self,
+        id: RequestId,
         params: <mcp_types::SubscribeRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/subscribe -> params: {:?}", params);
+        match resolve_session_uri(&self.config.icodex_home, &params.uri).await {
+            Ok(Some(path)) => {
+                self.session_subscriptions.subscribe(params.uri, path);
+                self.send_response::<mcp_types::SubscribeRequest>(id, json!({}))
+                    .await;
+            }
+            Ok(None) => {
+                self.send_error(
+                    id,
+                    INVALID_PARAMS_ERROR_CODE,
+                    format!("resource not found: {}", params.uri),
+                )
+                .await;
+            }
+            Err(e) => {
+                self.send_error(
+                    id,
+                    INTERNAL_ERROR_CODE,
+                    format!("failed to subscribe to {}: {e}", params.uri),
+                )
+                .await;
+            }
+        }
     }
 
-    fn handle_unsubscribe(
-        &self,
+    async fn handle_unsubscribe(
+        &mut self,
+        id: RequestId,
         params: <mcp_types::UnsubscribeRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/unsubscribe -> params: {:?}", params);
+        self.session_subscriptions.unsubscribe(&params.uri);
+        self.send_response::<mcp_types::UnsubscribeRequest>(id, json!({}))
+            .await;
     }
 
-    fn handle_list_prompts(
+    async fn handle_list_prompts(
         &self,
+        id: RequestId,
         params: <mcp_types::ListPromptsRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("prompts/list -> params: {:?}", params);
+        let result = ListPromptsResult {
+            next_cursor: None,
+            prompts: prompts::list_prompts(&self.config).await,
+        };
+        self.send_response::<mcp_types::ListPromptsRequest>(id, result)
+            .await;
     }
 
-    fn handle_get_prompt(
+    async fn handle_get_prompt(
         &self,
+        id: RequestId,
         params: <mcp_types::GetPromptRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("prompts/get -> params: {:?}", params);
+        match prompts::get_prompt(&self.config, &params.name).await {
+            Some(result) => {
+                self.send_response::<mcp_types::GetPromptRequest>(id, result)
+                    .await;
+            }
+            None => {
+                self.send_error(
+                    id,
+                    INVALID_PARAMS_ERROR_CODE,
+                    format!("prompt not found: {}", params.name),
+                )
+                .await;
+            }
+        }
+    }
+
+    async fn send_error(&self, id: RequestId, code: i64, message: String) {
+        let error = JSONRPCErrorError {
+            code,
+            message,
+            data: None,
+        };
+        self.outgoing.send_error(id, error).await;
     }
 
     async fn handle_list_tools(
@@ -556,11 +685,18 @@ impl MessageProcessor {
         tracing::info!("logging/setLevel -> params: {:?}", params);
     }
 
-    fn handle_complete(
+    async fn handle_complete(
         &self,
+        id: RequestId,
         params: <mcp_types::CompleteRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("completion/complete -> params: {:?}", params);
+        let argument = params.argument;
+        let result = CompleteResult {
+            completion: complete_argument(&self.config, &argument.name, &argument.value),
+        };
+        self.send_response::<mcp_types::CompleteRequest>(id, result)
+            .await;
     }
 
     // ---------------------------------------------------------------------
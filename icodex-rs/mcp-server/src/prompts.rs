@@ -0,0 +1,91 @@
+//! Exposes the project's AGENTS.md and the user's custom prompts
+//! (`$CODEX_HOME/prompts/*.md`) as MCP prompts.
+
+use std::collections::HashSet;
+
+use icodex_core::config::Config;
+use icodex_core::custom_prompts::discover_prompts_in_excluding;
+use icodex_core::project_doc::read_project_docs;
+use icodex_protocol::custom_prompts::CustomPrompt;
+use mcp_types::ContentBlock;
+use mcp_types::GetPromptResult;
+use mcp_types::Prompt;
+use mcp_types::PromptMessage;
+use mcp_types::Role;
+use mcp_types::TextContent;
+
+/// Name of the prompt that carries the project's AGENTS.md.
+pub(crate) const AGENTS_MD_PROMPT_NAME: &str = "agents-md";
+
+const AGENTS_MD_DESCRIPTION: &str = "Project instructions from AGENTS.md";
+
+/// List the available prompts: AGENTS.md first (when the project has one),
+/// then custom prompts sorted by name.
+pub(crate) async fn list_prompts(config: &Config) -> Vec<Prompt> {
+    let mut prompts = Vec::new();
+    if agents_md(config).await.is_some() {
+        prompts.push(Prompt {
+            arguments: None,
+            description: Some(AGENTS_MD_DESCRIPTION.to_string()),
+            name: AGENTS_MD_PROMPT_NAME.to_string(),
+            title: Some("AGENTS.md".to_string()),
+        });
+    }
+    prompts.extend(
+        custom_prompts(config)
+            .await
+            .into_iter()
+            .map(|prompt| Prompt {
+                arguments: None,
+                description: Some(format!("Custom prompt from {}", prompt.path.display())),
+                name: prompt.name,
+                title: None,
+            }),
+    );
+    prompts
+}
+
+/// Fetch the prompt called `name`, or `None` if there is no such prompt.
+pub(crate) async fn get_prompt(config: &Config, name: &str) -> Option<GetPromptResult> {
+    if name == AGENTS_MD_PROMPT_NAME {
+        let contents = agents_md(config).await?;
+        return Some(user_prompt(
+            Some(AGENTS_MD_DESCRIPTION.to_string()),
+            contents,
+        ));
+    }
+    let prompt = custom_prompts(config)
+        .await
+        .into_iter()
+        .find(|prompt| prompt.name == name)?;
+    Some(user_prompt(None, prompt.content))
+}
+
+async fn agents_md(config: &Config) -> Option<String> {
+    match read_project_docs(config).await {
+        Ok(contents) => contents,
+        Err(e) => {
+            tracing::warn!("failed to read AGENTS.md: {e}");
+            None
+        }
+    }
+}
+
+async fn custom_prompts(config: &Config) -> Vec<CustomPrompt> {
+    let exclude = HashSet::from([AGENTS_MD_PROMPT_NAME.to_string()]);
+    discover_prompts_in_excluding(&config.icodex_home.join("prompts"), &exclude).await
+}
+
+fn user_prompt(description: Option<String>, text: String) -> GetPromptResult {
+    GetPromptResult {
+        description,
+        messages: vec![PromptMessage {
+            content: ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text,
+                r#type: "text".to_string(),
+            }),
+            role: Role::User,
+        }],
+    }
+}
@@ -0,0 +1,210 @@
+//! Exposes recorded sessions (rollout files) as MCP resources named
+//! `icodex://sessions/<conversation id>`, and notifies subscribers when a
+//! session gets new items.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use icodex_core::Cursor as RolloutCursor;
+use icodex_core::RolloutRecorder;
+use icodex_core::find_conversation_path_by_id_str;
+use mcp_types::ListResourcesResult;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::Resource;
+use mcp_types::ResourceUpdatedNotification;
+use mcp_types::ResourceUpdatedNotificationParams;
+use mcp_types::TextResourceContents;
+use tokio::task::JoinHandle;
+
+use crate::icodex_message_processor::extract_conversation_summary;
+use crate::outgoing_message::OutgoingMessageSender;
+use crate::outgoing_message::OutgoingNotification;
+
+pub(crate) const SESSION_URI_PREFIX: &str = "icodex://sessions/";
+
+/// Rollouts are JSON Lines files.
+const SESSION_MIME_TYPE: &str = "application/x-ndjson";
+
+const SESSIONS_PAGE_SIZE: usize = 50;
+
+/// Longest session preview used as a resource title.
+const MAX_TITLE_CHARS: usize = 80;
+
+/// How often subscribed rollout files are checked for new items.
+const SUBSCRIPTION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// List one page of recorded sessions, newest first.
+pub(crate) async fn list_session_resources(
+    icodex_home: &Path,
+    cursor: Option<String>,
+) -> std::io::Result<ListResourcesResult> {
+    // Cursors are handed out as the rollout cursor's string form.
+    let cursor = cursor.and_then(|cursor| {
+        serde_json::from_value::<RolloutCursor>(serde_json::Value::String(cursor)).ok()
+    });
+    let page =
+        RolloutRecorder::list_conversations(icodex_home, SESSIONS_PAGE_SIZE, cursor.as_ref())
+            .await?;
+
+    let resources = page
+        .items
+        .into_iter()
+        .filter_map(|item| extract_conversation_summary(item.path, &item.head))
+        .map(|summary| {
+            let id = summary.conversation_id.to_string();
+            Resource {
+                annotations: None,
+                description: summary.timestamp.map(|ts| format!("Session started {ts}")),
+                mime_type: Some(SESSION_MIME_TYPE.to_string()),
+                name: id.clone(),
+                size: None,
+                title: Some(session_title(&summary.preview)),
+                uri: format!("{SESSION_URI_PREFIX}{id}"),
+            }
+        })
+        .collect();
+    let next_cursor = page
+        .next_cursor
+        .and_then(|cursor| match serde_json::to_value(&cursor) {
+            Ok(serde_json::Value::String(cursor)) => Some(cursor),
+            _ => None,
+        });
+
+    Ok(ListResourcesResult {
+        next_cursor,
+        resources,
+    })
+}
+
+/// Read the rollout behind a session URI. Returns `Ok(None)` when the URI
+/// does not name a recorded session.
+pub(crate) async fn read_session_resource(
+    icodex_home: &Path,
+    uri: &str,
+) -> std::io::Result<Option<ReadResourceResult>> {
+    let Some(path) = resolve_session_uri(icodex_home, uri).await? else {
+        return Ok(None);
+    };
+    let text = tokio::fs::read_to_string(&path).await?;
+    Ok(Some(ReadResourceResult {
+        contents: vec![ReadResourceResultContents::TextResourceContents(
+            TextResourceContents {
+                mime_type: Some(SESSION_MIME_TYPE.to_string()),
+                text,
+                uri: uri.to_string(),
+            },
+        )],
+    }))
+}
+
+/// Locate the rollout file for `uri`, if it names a recorded session.
+pub(crate) async fn resolve_session_uri(
+    icodex_home: &Path,
+    uri: &str,
+) -> std::io::Result<Option<PathBuf>> {
+    match uri.strip_prefix(SESSION_URI_PREFIX) {
+        Some(id) => find_conversation_path_by_id_str(icodex_home, id).await,
+        None => Ok(None),
+    }
+}
+
+fn session_title(preview: &str) -> String {
+    let preview = preview.lines().next().unwrap_or_default().trim();
+    if preview.chars().count() <= MAX_TITLE_CHARS {
+        return preview.to_string();
+    }
+    let truncated: String = preview.chars().take(MAX_TITLE_CHARS - 1).collect();
+    format!("{truncated}…")
+}
+
+/// Active `resources/subscribe` subscriptions. Each subscription polls its
+/// rollout file and sends `notifications/resources/updated` when it grows.
+pub(crate) struct SessionSubscriptions {
+    outgoing: Arc<OutgoingMessageSender>,
+    watchers: HashMap<String, JoinHandle<()>>,
+}
+
+impl SessionSubscriptions {
+    pub(crate) fn new(outgoing: Arc<OutgoingMessageSender>) -> Self {
+        Self {
+            outgoing,
+            watchers: HashMap::new(),
+        }
+    }
+
+    /// Start watching `path` on behalf of `uri`. Subscribing twice to the
+    /// same URI keeps the existing watcher.
+    pub(crate) fn subscribe(&mut self, uri: String, path: PathBuf) {
+        if self.watchers.contains_key(&uri) {
+            return;
+        }
+        let outgoing = self.outgoing.clone();
+        let watcher = tokio::spawn(watch_rollout(uri.clone(), path, outgoing));
+        self.watchers.insert(uri, watcher);
+    }
+
+    pub(crate) fn unsubscribe(&mut self, uri: &str) {
+        if let Some(watcher) = self.watchers.remove(uri) {
+            watcher.abort();
+        }
+    }
+}
+
+impl Drop for SessionSubscriptions {
+    fn drop(&mut self) {
+        for watcher in self.watchers.values() {
+            watcher.abort();
+        }
+    }
+}
+
+async fn watch_rollout(uri: String, path: PathBuf, outgoing: Arc<OutgoingMessageSender>) {
+    let mut last_len = rollout_len(&path).await;
+    loop {
+        tokio::time::sleep(SUBSCRIPTION_POLL_INTERVAL).await;
+        let len = rollout_len(&path).await;
+        if len == last_len {
+            continue;
+        }
+        last_len = len;
+
+        let params = ResourceUpdatedNotificationParams { uri: uri.clone() };
+        outgoing
+            .send_notification(OutgoingNotification {
+                method: ResourceUpdatedNotification::METHOD.to_string(),
+                params: serde_json::to_value(params).ok(),
+            })
+            .await;
+    }
+}
+
+async fn rollout_len(path: &Path) -> Option<u64> {
+    tokio::fs::metadata(path)
+        .await
+        .ok()
+        .map(|metadata| metadata.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn session_title_uses_first_line_and_truncates() {
+        assert_eq!(
+            session_title("fix the build\nthen run tests"),
+            "fix the build"
+        );
+
+        let long = "x".repeat(100);
+        let title = session_title(&long);
+        assert_eq!(title.chars().count(), MAX_TITLE_CHARS);
+        assert!(title.ends_with('…'));
+    }
+}
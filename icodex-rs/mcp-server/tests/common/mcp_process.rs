@@ -28,6 +28,8 @@ use icodex_protocol::mcp_protocol::SetDefaultModelParams;
 
 use mcp_types::CallToolRequestParams;
 use mcp_types::ClientCapabilities;
+use mcp_types::CompleteRequestParams;
+use mcp_types::GetPromptRequestParams;
 use mcp_types::Implementation;
 use mcp_types::InitializeRequestParams;
 use mcp_types::JSONRPC_VERSION;
@@ -35,9 +37,12 @@ use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListResourcesRequestParams;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ReadResourceRequestParams;
 use mcp_types::RequestId;
+use mcp_types::SubscribeRequestParams;
 use pretty_assertions::assert_eq;
 use serde_json::json;
 use std::process::Command as StdCommand;
@@ -169,6 +174,14 @@ impl McpProcess {
                 id: RequestId::Integer(request_id),
                 result: json!({
                     "capabilities": {
+                        "completions": {},
+                        "prompts": {
+                            "listChanged": false
+                        },
+                        "resources": {
+                            "listChanged": false,
+                            "subscribe": true
+                        },
                         "tools": {
                             "listChanged": true
                         },
@@ -357,6 +370,62 @@ impl McpProcess {
         self.send_request("logoutChatGpt", None).await
     }
 
+    /// Send a `resources/list` JSON-RPC request.
+    pub async fn send_list_resources_request(
+        &mut self,
+        params: Option<ListResourcesRequestParams>,
+    ) -> anyhow::Result<i64> {
+        let params = params.map(serde_json::to_value).transpose()?;
+        self.send_request(mcp_types::ListResourcesRequest::METHOD, params)
+            .await
+    }
+
+    /// Send a `resources/read` JSON-RPC request.
+    pub async fn send_read_resource_request(
+        &mut self,
+        params: ReadResourceRequestParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request(mcp_types::ReadResourceRequest::METHOD, params)
+            .await
+    }
+
+    /// Send a `resources/subscribe` JSON-RPC request.
+    pub async fn send_subscribe_request(
+        &mut self,
+        params: SubscribeRequestParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request(mcp_types::SubscribeRequest::METHOD, params)
+            .await
+    }
+
+    /// Send a `prompts/list` JSON-RPC request.
+    pub async fn send_list_prompts_request(&mut self) -> anyhow::Result<i64> {
+        self.send_request(mcp_types::ListPromptsRequest::METHOD, None)
+            .await
+    }
+
+    /// Send a `prompts/get` JSON-RPC request.
+    pub async fn send_get_prompt_request(
+        &mut self,
+        params: GetPromptRequestParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request(mcp_types::GetPromptRequest::METHOD, params)
+            .await
+    }
+
+    /// Send a `completion/complete` JSON-RPC request.
+    pub async fn send_complete_request(
+        &mut self,
+        params: CompleteRequestParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request(mcp_types::CompleteRequest::METHOD, params)
+            .await
+    }
+
     async fn send_request(
         &mut self,
         method: &str,
@@ -8,6 +8,7 @@ mod create_conversation;
 mod interrupt;
 mod list_resume;
 mod login;
+mod resources_prompts;
 mod send_message;
 mod set_default_model;
 mod user_agent;
@@ -0,0 +1,259 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use mcp_test_support::McpProcess;
+use mcp_test_support::to_response;
+use mcp_types::CompleteRequestParams;
+use mcp_types::CompleteRequestParamsArgument;
+use mcp_types::CompleteRequestParamsRef;
+use mcp_types::CompleteResult;
+use mcp_types::ContentBlock;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
+use mcp_types::JSONRPCResponse;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesResult;
+use mcp_types::PromptReference;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::RequestId;
+use mcp_types::SubscribeRequestParams;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sessions_are_listed_read_and_watched() {
+    let icodex_home = TempDir::new().expect("create temp dir");
+    let (uuid, rollout_path) = create_fake_rollout(icodex_home.path(), "Fix the flaky test");
+    let uri = format!("icodex://sessions/{uuid}");
+
+    let mut mcp = McpProcess::new(icodex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    let list_id = mcp
+        .send_list_resources_request(None)
+        .await
+        .expect("send resources/list");
+    let resp = read_response(&mut mcp, list_id).await;
+    let ListResourcesResult {
+        resources,
+        next_cursor,
+    } = to_response(resp).expect("deserialize resources/list");
+    assert_eq!(next_cursor, None);
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].uri, uri);
+    assert_eq!(resources[0].title.as_deref(), Some("Fix the flaky test"));
+
+    let read_id = mcp
+        .send_read_resource_request(ReadResourceRequestParams { uri: uri.clone() })
+        .await
+        .expect("send resources/read");
+    let resp = read_response(&mut mcp, read_id).await;
+    let ReadResourceResult { contents } = to_response(resp).expect("deserialize resources/read");
+    let [ReadResourceResultContents::TextResourceContents(contents)] = contents.as_slice() else {
+        panic!("expected a single text resource, got {contents:?}");
+    };
+    assert_eq!(contents.uri, uri);
+    assert!(contents.text.contains("Fix the flaky test"));
+
+    let subscribe_id = mcp
+        .send_subscribe_request(SubscribeRequestParams { uri: uri.clone() })
+        .await
+        .expect("send resources/subscribe");
+    read_response(&mut mcp, subscribe_id).await;
+
+    // Appending to the rollout should trigger an update notification.
+    let mut rollout = fs::OpenOptions::new()
+        .append(true)
+        .open(&rollout_path)
+        .expect("open rollout");
+    writeln!(
+        rollout,
+        "{}",
+        json!({
+            "timestamp": "2025-01-02T12:00:01Z",
+            "type": "event_msg",
+            "payload": {"type": "agent_message", "message": "Done"}
+        })
+    )
+    .expect("append to rollout");
+
+    let notification = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_notification_message("notifications/resources/updated"),
+    )
+    .await
+    .expect("resources/updated timeout")
+    .expect("resources/updated notification");
+    assert_eq!(notification.params, Some(json!({ "uri": uri })));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_unknown_session_is_an_error() {
+    let icodex_home = TempDir::new().expect("create temp dir");
+    let mut mcp = McpProcess::new(icodex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    let read_id = mcp
+        .send_read_resource_request(ReadResourceRequestParams {
+            uri: format!("icodex://sessions/{}", Uuid::new_v4()),
+        })
+        .await
+        .expect("send resources/read");
+    let error = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(read_id)),
+    )
+    .await
+    .expect("resources/read timeout")
+    .expect("resources/read error");
+    assert_eq!(error.error.code, -32602);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_custom_prompts_and_profile_completion() {
+    let icodex_home = TempDir::new().expect("create temp dir");
+    let prompts_dir = icodex_home.path().join("prompts");
+    fs::create_dir_all(&prompts_dir).expect("create prompts dir");
+    fs::write(prompts_dir.join("triage.md"), "Triage the open issues.").expect("write prompt");
+    fs::write(
+        icodex_home.path().join("config.toml"),
+        r#"
+[profiles.fast]
+model = "o4-mini"
+
+[profiles.thorough]
+model = "gpt-5"
+"#,
+    )
+    .expect("write config.toml");
+
+    let mut mcp = McpProcess::new(icodex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    let list_id = mcp
+        .send_list_prompts_request()
+        .await
+        .expect("send prompts/list");
+    let resp = read_response(&mut mcp, list_id).await;
+    let ListPromptsResult { prompts, .. } = to_response(resp).expect("deserialize prompts/list");
+    assert!(prompts.iter().any(|prompt| prompt.name == "triage"));
+
+    let get_id = mcp
+        .send_get_prompt_request(GetPromptRequestParams {
+            arguments: None,
+            name: "triage".to_string(),
+        })
+        .await
+        .expect("send prompts/get");
+    let resp = read_response(&mut mcp, get_id).await;
+    let GetPromptResult { messages, .. } = to_response(resp).expect("deserialize prompts/get");
+    let [message] = messages.as_slice() else {
+        panic!("expected a single prompt message, got {messages:?}");
+    };
+    let ContentBlock::TextContent(text) = &message.content else {
+        panic!("expected text content, got {:?}", message.content);
+    };
+    assert_eq!(text.text, "Triage the open issues.");
+
+    let complete_id = mcp
+        .send_complete_request(CompleteRequestParams {
+            argument: CompleteRequestParamsArgument {
+                name: "profile".to_string(),
+                value: "f".to_string(),
+            },
+            context: None,
+            r#ref: CompleteRequestParamsRef::PromptReference(PromptReference {
+                name: "icodex".to_string(),
+                title: None,
+                r#type: "ref/prompt".to_string(),
+            }),
+        })
+        .await
+        .expect("send completion/complete");
+    let resp = read_response(&mut mcp, complete_id).await;
+    let CompleteResult { completion } = to_response(resp).expect("deserialize completion");
+    assert_eq!(completion.values, vec!["fast".to_string()]);
+    assert_eq!(completion.total, Some(1));
+}
+
+async fn read_response(mcp: &mut McpProcess, request_id: i64) -> JSONRPCResponse {
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("response timeout")
+    .expect("response")
+}
+
+fn create_fake_rollout(icodex_home: &Path, preview: &str) -> (Uuid, PathBuf) {
+    let uuid = Uuid::new_v4();
+    let timestamp = "2025-01-02T12:00:00Z";
+    let dir = icodex_home
+        .join("sessions")
+        .join("2025")
+        .join("01")
+        .join("02");
+    fs::create_dir_all(&dir).unwrap_or_else(|e| panic!("create sessions dir: {e}"));
+
+    let file_path = dir.join(format!("rollout-2025-01-02T12-00-00-{uuid}.jsonl"));
+    let lines = [
+        json!({
+            "timestamp": timestamp,
+            "type": "session_meta",
+            "payload": {
+                "id": uuid,
+                "timestamp": timestamp,
+                "cwd": "/",
+                "originator": "icodex",
+                "cli_version": "0.0.0",
+                "instructions": null
+            }
+        }),
+        json!({
+            "timestamp": timestamp,
+            "type": "response_item",
+            "payload": {
+                "type": "message",
+                "role": "user",
+                "content": [{"type": "input_text", "text": preview}]
+            }
+        }),
+        json!({
+            "timestamp": timestamp,
+            "type": "event_msg",
+            "payload": {
+                "type": "user_message",
+                "message": preview,
+                "kind": "plain"
+            }
+        }),
+    ];
+    let contents: String = lines.iter().map(|line| format!("{line}\n")).collect();
+    fs::write(&file_path, contents).unwrap_or_else(|e| panic!("write rollout file: {e}"));
+    (uuid, file_path)
+}
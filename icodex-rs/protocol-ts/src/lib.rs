@@ -39,6 +39,7 @@ pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     icodex_protocol::mcp_protocol::GetAuthStatusResponse::export_all_to(out_dir)?;
     icodex_protocol::mcp_protocol::ApplyPatchApprovalResponse::export_all_to(out_dir)?;
     icodex_protocol::mcp_protocol::ExecCommandApprovalResponse::export_all_to(out_dir)?;
+    icodex_protocol::mcp_protocol::McpToolCallApprovalResponse::export_all_to(out_dir)?;
     icodex_protocol::mcp_protocol::GetUserSavedConfigResponse::export_all_to(out_dir)?;
     icodex_protocol::mcp_protocol::SetDefaultModelResponse::export_all_to(out_dir)?;
     icodex_protocol::mcp_protocol::GetUserAgentResponse::export_all_to(out_dir)?;
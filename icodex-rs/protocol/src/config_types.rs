@@ -53,6 +53,9 @@ pub enum SandboxMode {
     #[default]
     ReadOnly,
 
+    #[serde(rename = "workspace-read")]
+    WorkspaceRead,
+
     #[serde(rename = "workspace-write")]
     WorkspaceWrite,
 
@@ -21,6 +21,9 @@ use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
 use crate::plan_tool::UpdatePlanArgs;
 use mcp_types::CallToolResult;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::Prompt as McpPrompt;
+use mcp_types::Resource as McpResource;
 use mcp_types::Tool as McpTool;
 use serde::Deserialize;
 use serde::Serialize;
@@ -34,6 +37,8 @@ pub const USER_INSTRUCTIONS_OPEN_TAG: &str = "<user_instructions>";
 pub const USER_INSTRUCTIONS_CLOSE_TAG: &str = "</user_instructions>";
 pub const ENVIRONMENT_CONTEXT_OPEN_TAG: &str = "<environment_context>";
 pub const ENVIRONMENT_CONTEXT_CLOSE_TAG: &str = "</environment_context>";
+pub const MCP_RESOURCES_OPEN_TAG: &str = "<mcp_resources>";
+pub const MCP_RESOURCES_CLOSE_TAG: &str = "</mcp_resources>";
 pub const USER_MESSAGE_BEGIN: &str = "## My request for Codex:";
 
 /// Submission Queue Entry - requests from user
@@ -140,6 +145,22 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Approve a call to an MCP tool that requires approval.
+    McpToolCallApproval {
+        /// The id of the submission we are approving
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
+    /// Approve a `sampling/createMessage` request from an MCP server.
+    McpSamplingApproval {
+        /// The id of the `McpSamplingApprovalRequest` event we are answering.
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -160,9 +181,38 @@ pub enum Op {
     /// Reply is delivered via `EventMsg::McpListToolsResponse`.
     ListMcpTools,
 
+    /// Stop the MCP server `server` if it is running and start it again, e.g.
+    /// after it crashed or failed to start. Also re-enables a disabled server.
+    /// Reply is delivered via `EventMsg::McpListToolsResponse`.
+    RestartMcpServer { server: String },
+
+    /// Start (`enabled: true`) or stop the MCP server `server` for the rest of
+    /// the session. Reply is delivered via `EventMsg::McpListToolsResponse`.
+    SetMcpServerEnabled { server: String, enabled: bool },
+
+    /// Ask `server`, or every running server when `None`, for its tools again.
+    /// Reply is delivered via `EventMsg::McpListToolsResponse`.
+    RefreshMcpTools { server: Option<String> },
+
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
+    /// Request the resources listed by the configured MCP servers.
+    /// Reply is delivered via `EventMsg::McpListResourcesResponse`.
+    ListMcpResources,
+
+    /// Request the prompts listed by the configured MCP servers.
+    /// Reply is delivered via `EventMsg::McpListPromptsResponse`.
+    ListMcpPrompts,
+
+    /// Fetch the prompt `name` from the MCP server `server` and submit its
+    /// messages as user input.
+    RunMcpPrompt {
+        server: String,
+        name: String,
+        arguments: std::collections::HashMap<String, String>,
+    },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -171,6 +221,12 @@ pub enum Op {
     /// Request a code review from the agent.
     Review { review_request: ReviewRequest },
 
+    /// Restore the workspace files to their state before the turn `turn_id`
+    /// (the id of the submission that started it), or before the most recent
+    /// turn when `None`. Turns after it are rolled back as well.
+    /// Reply is delivered via `EventMsg::TurnRolledBack`.
+    RollbackTurn { turn_id: Option<String> },
+
     /// Request to shut down icodex instance.
     Shutdown,
 }
@@ -216,6 +272,23 @@ pub enum SandboxPolicy {
     #[serde(rename = "read-only")]
     ReadOnly,
 
+    /// Read-only access limited to the current working directory, the
+    /// configured `readable_roots`, and a default set of system directories
+    /// and toolchains needed to run common programs.
+    #[serde(rename = "workspace-read")]
+    WorkspaceRead {
+        /// Additional folders (beyond cwd and the defaults) that should be
+        /// readable from within the sandbox.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        readable_roots: Vec<PathBuf>,
+
+        /// When set to `true`, will NOT include the default system and
+        /// toolchain directories among the readable roots. Defaults to
+        /// `false`.
+        #[serde(default)]
+        exclude_default_readable_roots: bool,
+    },
+
     /// Same as `ReadOnly` but additionally grants write access to the current
     /// working directory ("workspace").
     #[serde(rename = "workspace-write")]
@@ -243,6 +316,67 @@ pub enum SandboxPolicy {
     },
 }
 
+/// System locations that remain readable under
+/// [`SandboxPolicy::WorkspaceRead`] so that shells, compilers, and other common
+/// tools keep working. Paths that do not exist on the host are skipped.
+/// `/proc` is left out: it exposes the environment of every process of the
+/// same user, including the secrets in it.
+const DEFAULT_READABLE_ROOTS: &[&str] = &[
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/libx32",
+    "/opt",
+    "/nix/store",
+    "/dev",
+    "/etc/alternatives",
+    "/etc/ca-certificates",
+    "/etc/ssl",
+    "/etc/pki",
+    "/etc/ld.so.cache",
+    "/etc/ld.so.conf",
+    "/etc/ld.so.conf.d",
+    "/etc/localtime",
+    "/etc/passwd",
+    "/etc/group",
+    "/etc/nsswitch.conf",
+    "/etc/hosts",
+    "/etc/resolv.conf",
+    "/etc/profile",
+    "/etc/profile.d",
+    "/etc/bash.bashrc",
+    "/etc/inputrc",
+    "/etc/gitconfig",
+    "/System",
+    "/Library",
+    "/private/etc",
+    "/private/var/db/timezone",
+];
+
+/// Per-user toolchains and shell startup files (relative to `$HOME`) that
+/// remain readable under [`SandboxPolicy::WorkspaceRead`]. Only the parts of
+/// `~/.cargo` without credentials are listed, and git's configuration, which
+/// may hold credential helpers and tokens, is not.
+const DEFAULT_READABLE_HOME_ROOTS: &[&str] = &[
+    ".cargo/bin",
+    ".cargo/registry",
+    ".cargo/git",
+    ".rustup",
+    ".nvm",
+    ".pyenv",
+    ".rbenv",
+    ".local/bin",
+    ".local/lib",
+    ".local/share/mise",
+    ".profile",
+    ".bash_profile",
+    ".bashrc",
+    ".zshenv",
+];
+
 /// A writable root path accompanied by a list of subpaths that should remain
 /// read‑only even when the root is writable. This is primarily used to ensure
 /// top‑level VCS metadata directories (e.g. `.git`) under a writable root are
@@ -300,15 +434,20 @@ impl SandboxPolicy {
         }
     }
 
-    /// Always returns `true`; restricting read access is not supported.
     pub fn has_full_disk_read_access(&self) -> bool {
-        true
+        match self {
+            SandboxPolicy::DangerFullAccess => true,
+            SandboxPolicy::ReadOnly => true,
+            SandboxPolicy::WorkspaceRead { .. } => false,
+            SandboxPolicy::WorkspaceWrite { .. } => true,
+        }
     }
 
     pub fn has_full_disk_write_access(&self) -> bool {
         match self {
             SandboxPolicy::DangerFullAccess => true,
             SandboxPolicy::ReadOnly => false,
+            SandboxPolicy::WorkspaceRead { .. } => false,
             SandboxPolicy::WorkspaceWrite { .. } => false,
         }
     }
@@ -317,10 +456,39 @@ impl SandboxPolicy {
         match self {
             SandboxPolicy::DangerFullAccess => true,
             SandboxPolicy::ReadOnly => false,
+            SandboxPolicy::WorkspaceRead { .. } => false,
             SandboxPolicy::WorkspaceWrite { network_access, .. } => *network_access,
         }
     }
 
+    /// Returns the list of readable roots (tailored to the current working
+    /// directory) for policies that restrict reads. Empty when the policy has
+    /// full disk read access. Only paths that exist are returned.
+    pub fn get_readable_roots_with_cwd(&self, cwd: &Path) -> Vec<PathBuf> {
+        let SandboxPolicy::WorkspaceRead {
+            readable_roots,
+            exclude_default_readable_roots,
+        } = self
+        else {
+            return Vec::new();
+        };
+
+        let mut roots: Vec<PathBuf> = vec![cwd.to_path_buf()];
+        roots.extend(readable_roots.iter().cloned());
+        if !exclude_default_readable_roots {
+            roots.extend(DEFAULT_READABLE_ROOTS.iter().map(PathBuf::from));
+            if let Some(home) = std::env::var_os("HOME")
+                && !home.is_empty()
+            {
+                let home = PathBuf::from(home);
+                roots.extend(DEFAULT_READABLE_HOME_ROOTS.iter().map(|p| home.join(p)));
+            }
+        }
+        roots.retain(|root| root.exists());
+        roots.dedup();
+        roots
+    }
+
     /// Returns the list of writable roots (tailored to the current working
     /// directory) together with subpaths that should remain read‑only under
     /// each writable root.
@@ -328,6 +496,7 @@ impl SandboxPolicy {
         match self {
             SandboxPolicy::DangerFullAccess => Vec::new(),
             SandboxPolicy::ReadOnly => Vec::new(),
+            SandboxPolicy::WorkspaceRead { .. } => Vec::new(),
             SandboxPolicy::WorkspaceWrite {
                 writable_roots,
                 exclude_tmpdir_env_var,
@@ -478,6 +647,14 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// The model wants to call an MCP tool that requires approval; answer
+    /// with `Op::McpToolCallApproval`.
+    McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent),
+
+    /// An MCP server asked to sample the model; answer with
+    /// `Op::McpSamplingApproval`.
+    McpSamplingApprovalRequest(McpSamplingApprovalRequestEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
     /// Notification that a model stream experienced an error or disconnect
@@ -493,6 +670,16 @@ pub enum EventMsg {
 
     TurnDiff(TurnDiffEvent),
 
+    /// Reply to `Op::RollbackTurn`.
+    TurnRolledBack(TurnRolledBackEvent),
+
+    /// A `spawn_agent` call started a sub-agent.
+    SubAgentBegin(SubAgentBeginEvent),
+
+    /// An event of a sub-agent, nested under the `spawn_agent` call that
+    /// started it.
+    SubAgent(SubAgentEvent),
+
     /// Response to GetHistoryEntryRequest.
     GetHistoryEntryResponse(GetHistoryEntryResponseEvent),
 
@@ -502,6 +689,12 @@ pub enum EventMsg {
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// List of resources exposed by the MCP servers.
+    McpListResourcesResponse(McpListResourcesResponseEvent),
+
+    /// List of prompts exposed by the MCP servers.
+    McpListPromptsResponse(McpListPromptsResponseEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
     TurnAborted(TurnAbortedEvent),
@@ -709,6 +902,8 @@ pub enum InputMessageKind {
     UserInstructions,
     /// XML-wrapped environment context (<environment_context>...)
     EnvironmentContext,
+    /// XML-wrapped contents of mentioned MCP resources (<mcp_resources>...)
+    McpResources,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -737,6 +932,10 @@ where
             && ends_with_ignore_ascii_case(trimmed, USER_INSTRUCTIONS_CLOSE_TAG)
         {
             InputMessageKind::UserInstructions
+        } else if starts_with_ignore_ascii_case(trimmed, MCP_RESOURCES_OPEN_TAG)
+            && ends_with_ignore_ascii_case(trimmed, MCP_RESOURCES_CLOSE_TAG)
+        {
+            InputMessageKind::McpResources
         } else {
             InputMessageKind::Plain
         }
@@ -947,6 +1146,10 @@ pub struct TurnContextItem {
     pub approval_policy: AskForApproval,
     pub sandbox_policy: SandboxPolicy,
     pub model: String,
+    /// Key of the provider that served the turn. Differs from the configured
+    /// provider after a failover to one of `fallback_providers`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_provider: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<ReasoningEffortConfig>,
     pub summary: ReasoningSummaryConfig,
@@ -977,6 +1180,27 @@ pub struct GitInfo {
 pub struct ReviewRequest {
     pub prompt: String,
     pub user_facing_hint: String,
+    /// What to review. When set, the diff and the contents of the affected
+    /// files are collected and appended to `prompt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<ReviewTarget>,
+}
+
+/// Change to review, resolved against the git repository of the session's
+/// working directory.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReviewTarget {
+    /// Staged, unstaged and untracked changes.
+    UncommittedChanges,
+    /// The working tree against the point where the current branch forked
+    /// from `base`, or from the repository's default branch when unset.
+    BaseBranch { base: Option<String> },
+    /// A single commit (`abc123`) or a range of commits (`main..HEAD`).
+    CommitRange { range: String },
+    /// Files reviewed as a whole. Relative paths are resolved against the
+    /// working directory.
+    Files { paths: Vec<PathBuf> },
 }
 
 /// Structured review result produced by a child review session.
@@ -1052,6 +1276,35 @@ pub struct ExecCommandEndEvent {
     pub duration: Duration,
     /// Formatted output from the command, as seen by the model.
     pub formatted_output: String,
+    /// Set when the command was stopped for exceeding one of the configured
+    /// `exec_limits`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_exceeded: Option<ExecLimitExceeded>,
+}
+
+/// The resource limit a command ran into.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecLimitExceeded {
+    /// Killed by the kernel for using more than `memory_mb`.
+    OomKilled,
+    /// Tried to start more than `max_processes` processes.
+    ProcessLimit,
+    /// A process used more than `cpu_seconds` of CPU time.
+    CpuTimeLimit,
+    /// Killed after writing more than `max_output_bytes`.
+    OutputLimit,
+}
+
+impl fmt::Display for ExecLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExecLimitExceeded::OomKilled => "killed for exceeding the memory limit",
+            ExecLimitExceeded::ProcessLimit => "ran into the process limit",
+            ExecLimitExceeded::CpuTimeLimit => "exceeded the CPU time limit",
+            ExecLimitExceeded::OutputLimit => "killed for exceeding the output limit",
+        })
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
@@ -1100,6 +1353,22 @@ pub struct ApplyPatchApprovalRequestEvent {
     pub grant_root: Option<PathBuf>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpToolCallApprovalRequestEvent {
+    /// Identifier for the corresponding McpToolCallBegin that will follow if
+    /// the call is approved.
+    pub call_id: String,
+    pub invocation: McpInvocation,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpSamplingApprovalRequestEvent {
+    /// Name of the MCP server making the request.
+    pub server: String,
+    /// The messages, system prompt and limits the server wants sampled.
+    pub request: CreateMessageRequestParams,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct BackgroundEventEvent {
     pub message: String,
@@ -1137,6 +1406,33 @@ pub struct TurnDiffEvent {
     pub unified_diff: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct TurnRolledBackEvent {
+    /// The turn the workspace was restored to the start of.
+    pub turn_id: String,
+    /// Files that were rewritten or removed.
+    pub restored_paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubAgentBeginEvent {
+    /// Identifier of the `spawn_agent` call.
+    pub call_id: String,
+    /// Model the sub-agent uses.
+    pub model: String,
+    /// Instructions the sub-agent was started with.
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubAgentEvent {
+    /// Identifier of the `spawn_agent` call that started the sub-agent.
+    pub call_id: String,
+    /// The sub-agent's event. Events of agents the sub-agent started are
+    /// nested once more.
+    pub msg: Box<EventMsg>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct GetHistoryEntryResponseEvent {
     pub offset: usize,
@@ -1151,6 +1447,43 @@ pub struct GetHistoryEntryResponseEvent {
 pub struct McpListToolsResponseEvent {
     /// Fully qualified tool name -> tool definition.
     pub tools: std::collections::HashMap<String, McpTool>,
+    /// Status of every configured server, sorted by name.
+    #[serde(default)]
+    pub servers: Vec<McpServerStatus>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, TS)]
+pub struct McpServerStatus {
+    /// Name of the MCP server as defined in the config.
+    pub name: String,
+    pub state: McpServerState,
+    /// Why the server is not running, when it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServerState {
+    Running,
+    /// The server failed to start, or exited since.
+    Failed,
+    /// The user stopped the server for the rest of the session.
+    Disabled,
+}
+
+/// Response payload for `Op::ListMcpResources`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpListResourcesResponseEvent {
+    /// Server name -> resources listed by that server.
+    pub resources: std::collections::HashMap<String, Vec<McpResource>>,
+}
+
+/// Response payload for `Op::ListMcpPrompts`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpListPromptsResponseEvent {
+    /// Server name -> prompts listed by that server.
+    pub prompts: std::collections::HashMap<String, Vec<McpPrompt>>,
 }
 
 /// Response payload for `Op::ListCustomPrompts`.
@@ -1298,4 +1631,28 @@ mod tests {
         let deserialized: ExecCommandOutputDeltaEvent = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized, event);
     }
+
+    #[test]
+    fn workspace_read_restricts_reads_to_existing_roots() {
+        let cwd = tempfile::tempdir().unwrap();
+        let extra = tempfile::tempdir().unwrap();
+        let policy = SandboxPolicy::WorkspaceRead {
+            readable_roots: vec![extra.path().to_path_buf(), PathBuf::from("/does/not/exist")],
+            exclude_default_readable_roots: true,
+        };
+
+        assert!(!policy.has_full_disk_read_access());
+        assert!(!policy.has_full_disk_write_access());
+        assert_eq!(
+            policy.get_readable_roots_with_cwd(cwd.path()),
+            vec![cwd.path().to_path_buf(), extra.path().to_path_buf()]
+        );
+        assert_eq!(
+            r#"{"mode":"workspace-read"}"#.parse::<SandboxPolicy>().unwrap(),
+            SandboxPolicy::WorkspaceRead {
+                readable_roots: vec![],
+                exclude_default_readable_roots: false,
+            }
+        );
+    }
 }
@@ -12,7 +12,6 @@ use icodex_core::AuthManager;
 use icodex_core::ConversationManager;
 use icodex_core::config::Config;
 use icodex_core::config::persist_model_selection;
-use icodex_core::model_family::find_family_for_model;
 use icodex_core::protocol::TokenUsage;
 use icodex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use color_eyre::eyre::Result;
@@ -304,10 +303,7 @@ impl App {
             }
             AppEvent::UpdateModel(model) => {
                 self.chat_widget.set_model(&model);
-                self.config.model = model.clone();
-                if let Some(family) = find_family_for_model(&model) {
-                    self.config.model_family = family;
-                }
+                self.config = self.config.with_model(&model);
             }
             AppEvent::PersistModelSelection { model, effort } => {
                 let profile = self.active_profile.as_deref();
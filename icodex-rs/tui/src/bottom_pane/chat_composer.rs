@@ -24,6 +24,7 @@ use ratatui::widgets::WidgetRef;
 use super::chat_composer_history::ChatComposerHistory;
 use super::command_popup::CommandItem;
 use super::command_popup::CommandPopup;
+use super::command_popup::McpPromptCommand;
 use super::file_search_popup::FileSearchPopup;
 use super::paste_burst::CharDecision;
 use super::paste_burst::PasteBurst;
@@ -37,9 +38,12 @@ use crate::bottom_pane::textarea::TextArea;
 use crate::bottom_pane::textarea::TextAreaState;
 use crate::clipboard_paste::normalize_pasted_path;
 use crate::clipboard_paste::pasted_image_format;
+use crate::history_cell;
 use crate::key_hint;
 use crate::ui_consts::LIVE_PREFIX_COLS;
+use icodex_common::fuzzy_match::fuzzy_match;
 use icodex_file_search::FileMatch;
+use mcp_types::Resource;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::Path;
@@ -56,6 +60,14 @@ const LARGE_PASTE_CHAR_THRESHOLD: usize = 1000;
 pub enum InputResult {
     Submitted(String),
     Command(SlashCommand),
+    /// An MCP prompt picked from the slash popup. `text` is what the user
+    /// typed, for display in the transcript.
+    McpPrompt {
+        text: String,
+        server: String,
+        name: String,
+        arguments: HashMap<String, String>,
+    },
     None,
 }
 
@@ -87,6 +99,9 @@ pub(crate) struct ChatComposer {
     // When true, disables paste-burst logic and inserts characters immediately.
     disable_paste_burst: bool,
     custom_prompts: Vec<CustomPrompt>,
+    mcp_prompts: Vec<McpPromptCommand>,
+    // `server:uri` for every resource listed by an MCP server, sorted.
+    mcp_resource_mentions: Vec<String>,
 }
 
 /// Popup state – at most one can be visible at any time.
@@ -130,6 +145,8 @@ impl ChatComposer {
             paste_burst: PasteBurst::default(),
             disable_paste_burst: false,
             custom_prompts: Vec::new(),
+            mcp_prompts: Vec::new(),
+            mcp_resource_mentions: Vec::new(),
         };
         // Apply configuration via the setter to keep side-effects centralized.
         this.set_disable_paste_burst(disable_paste_burst);
@@ -401,6 +418,16 @@ impl ChatComposer {
                                 }
                             }
                         }
+                        CommandItem::McpPrompt(idx) => {
+                            if let Some(prompt) = popup.mcp_prompt(idx) {
+                                let command = &prompt.command;
+                                let starts_with_cmd =
+                                    first_line.trim_start().starts_with(&format!("/{command}"));
+                                if !starts_with_cmd {
+                                    self.textarea.set_text(&format!("/{command} "));
+                                }
+                            }
+                        }
                     }
                     // After completing the command, move cursor to the end.
                     if !self.textarea.text().is_empty() {
@@ -415,6 +442,11 @@ impl ChatComposer {
                 modifiers: KeyModifiers::NONE,
                 ..
             } => {
+                if let Some(CommandItem::McpPrompt(idx)) = popup.selected_item()
+                    && let Some(prompt) = popup.mcp_prompt(idx).cloned()
+                {
+                    return self.submit_mcp_prompt(prompt);
+                }
                 if let Some(sel) = popup.selected_item() {
                     // Clear textarea so no residual text remains.
                     self.textarea.set_text("");
@@ -438,6 +470,7 @@ impl ChatComposer {
                             }
                             return (InputResult::None, true);
                         }
+                        CommandItem::McpPrompt(_) => return (InputResult::None, true),
                     }
                 }
                 // Fallback to default newline handling if no command selected.
@@ -446,6 +479,40 @@ impl ChatComposer {
             input => self.handle_input_basic(input),
         }
     }
+
+    /// Run `prompt` with the arguments typed after the command. When the
+    /// arguments do not fit the prompt, the text is kept so it can be fixed.
+    fn submit_mcp_prompt(&mut self, prompt: McpPromptCommand) -> (InputResult, bool) {
+        let text = self.textarea.text().trim().to_string();
+        let args = text
+            .split_once(char::is_whitespace)
+            .map(|(_, args)| args.trim())
+            .unwrap_or("");
+        match prompt.parse_arguments(args) {
+            Ok(arguments) => {
+                let text = if args.is_empty() {
+                    format!("/{}", prompt.command)
+                } else {
+                    format!("/{} {args}", prompt.command)
+                };
+                self.textarea.set_text("");
+                self.active_popup = ActivePopup::None;
+                let result = InputResult::McpPrompt {
+                    text,
+                    server: prompt.server,
+                    name: prompt.prompt.name,
+                    arguments,
+                };
+                (result, true)
+            }
+            Err(err) => {
+                self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                    history_cell::new_error_event(format!("/{}: {err}", prompt.command)),
+                )));
+                (InputResult::None, true)
+            }
+        }
+    }
     #[inline]
     fn clamp_to_char_boundary(text: &str, pos: usize) -> usize {
         let mut p = pos.min(text.len());
@@ -516,6 +583,11 @@ impl ChatComposer {
                 };
 
                 let sel_path = sel.to_string();
+                if self.mcp_resource_mentions.contains(&sel_path) {
+                    self.insert_selected_path(&format!("@{sel_path}"));
+                    self.active_popup = ActivePopup::None;
+                    return (InputResult::None, true);
+                }
                 // If selected path looks like an image (png/jpeg), attach as image instead of inserting text.
                 let is_image = Self::is_image_path(&sel_path);
                 if is_image {
@@ -1157,7 +1229,8 @@ impl ChatComposer {
             }
             _ => {
                 if input_starts_with_slash {
-                    let mut command_popup = CommandPopup::new(self.custom_prompts.clone());
+                    let mut command_popup =
+                        CommandPopup::new(self.custom_prompts.clone(), self.mcp_prompts.clone());
                     command_popup.on_composer_text_change(first_line.to_string());
                     self.active_popup = ActivePopup::Command(command_popup);
                 }
@@ -1172,6 +1245,62 @@ impl ChatComposer {
         }
     }
 
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptCommand>) {
+        self.mcp_prompts = prompts.clone();
+        if let ActivePopup::Command(popup) = &mut self.active_popup {
+            popup.set_mcp_prompts(prompts);
+        }
+    }
+
+    pub(crate) fn set_mcp_resources(&mut self, resources: HashMap<String, Vec<Resource>>) {
+        let mut mentions: Vec<String> = resources
+            .into_iter()
+            .flat_map(|(server, resources)| {
+                resources
+                    .into_iter()
+                    .map(move |resource| format!("{server}:{}", resource.uri))
+            })
+            .collect();
+        mentions.sort();
+        self.mcp_resource_mentions = mentions;
+    }
+
+    /// Resources matching an `@server:...` query, if `server` is an MCP
+    /// server with resources. Such queries are completed from the listed
+    /// resources instead of the file search.
+    fn mcp_resource_matches(&self, query: &str) -> Option<Vec<FileMatch>> {
+        let (server, _) = query.split_once(':')?;
+        let server_prefix = format!("{server}:");
+        if !self
+            .mcp_resource_mentions
+            .iter()
+            .any(|mention| mention.starts_with(&server_prefix))
+        {
+            return None;
+        }
+
+        let mut matches: Vec<(i32, FileMatch)> = self
+            .mcp_resource_mentions
+            .iter()
+            .filter_map(|mention| {
+                let (indices, score) = fuzzy_match(mention, query)?;
+                let file_match = FileMatch {
+                    score: 0,
+                    path: mention.clone(),
+                    indices: Some(indices.into_iter().map(|i| i as u32).collect()),
+                };
+                Some((score, file_match))
+            })
+            .collect();
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.path.cmp(&b.1.path)));
+        Some(
+            matches
+                .into_iter()
+                .map(|(_, file_match)| file_match)
+                .collect(),
+        )
+    }
+
     /// Synchronize `self.file_search_popup` with the current text in the textarea.
     /// Note this is only called when self.active_popup is NOT Command.
     fn sync_file_search_popup(&mut self) {
@@ -1190,7 +1319,8 @@ impl ChatComposer {
             return;
         }
 
-        if !query.is_empty() {
+        let resource_matches = self.mcp_resource_matches(&query);
+        if !query.is_empty() && resource_matches.is_none() {
             self.app_event_tx
                 .send(AppEvent::StartFileSearch(query.clone()));
         }
@@ -1213,6 +1343,11 @@ impl ChatComposer {
                 self.active_popup = ActivePopup::File(popup);
             }
         }
+        if let Some(matches) = resource_matches
+            && let ActivePopup::File(popup) = &mut self.active_popup
+        {
+            popup.set_matches(&query, matches);
+        }
 
         self.current_file_query = Some(query);
         self.dismissed_file_popup_token = None;
@@ -1379,6 +1514,7 @@ mod tests {
     use crate::bottom_pane::InputResult;
     use crate::bottom_pane::chat_composer::AttachedImage;
     use crate::bottom_pane::chat_composer::LARGE_PASTE_CHAR_THRESHOLD;
+    use crate::bottom_pane::command_popup::McpPromptCommand;
     use crate::bottom_pane::textarea::TextArea;
     use tokio::sync::mpsc::unbounded_channel;
 
@@ -1863,7 +1999,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
-            InputResult::None => panic!("expected Command result for '/init'"),
+            InputResult::McpPrompt { .. } | InputResult::None => {
+                panic!("expected Command result for '/init'")
+            }
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
     }
@@ -1921,7 +2059,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
-            InputResult::None => panic!("expected Command result for '/mention'"),
+            InputResult::McpPrompt { .. } | InputResult::None => {
+                panic!("expected Command result for '/mention'")
+            }
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
         composer.insert_str("@");
@@ -2346,6 +2486,122 @@ mod tests {
         assert_eq!(InputResult::Submitted(prompt_text.to_string()), result);
     }
 
+    #[test]
+    fn selecting_mcp_prompt_submits_parsed_arguments() {
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+        composer.set_mcp_prompts(vec![McpPromptCommand::new(
+            "docs".to_string(),
+            mcp_types::Prompt {
+                arguments: Some(vec![mcp_types::PromptArgument {
+                    description: None,
+                    name: "topic".to_string(),
+                    required: Some(true),
+                    title: None,
+                }]),
+                description: None,
+                name: "explain".to_string(),
+                title: None,
+            },
+        )]);
+
+        type_chars_humanlike(&mut composer, &['/', 'd', 'o', 'c', 's', ':', 'e', 'x']);
+        composer.handle_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(composer.textarea.text(), "/docs:explain ");
+        type_chars_humanlike(&mut composer, &['l', 'i', 'f', 'e']);
+
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(
+            result,
+            InputResult::McpPrompt {
+                text: "/docs:explain life".to_string(),
+                server: "docs".to_string(),
+                name: "explain".to_string(),
+                arguments: HashMap::from([("topic".to_string(), "life".to_string())]),
+            }
+        );
+        assert!(composer.textarea.is_empty());
+    }
+
+    #[test]
+    fn mcp_prompt_with_missing_arguments_keeps_text() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+        composer.set_mcp_prompts(vec![McpPromptCommand::new(
+            "docs".to_string(),
+            mcp_types::Prompt {
+                arguments: Some(vec![mcp_types::PromptArgument {
+                    description: None,
+                    name: "topic".to_string(),
+                    required: Some(true),
+                    title: None,
+                }]),
+                description: None,
+                name: "explain".to_string(),
+                title: None,
+            },
+        )]);
+
+        composer.set_text_content("/docs:explain".to_string());
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(result, InputResult::None);
+        assert_eq!(composer.textarea.text(), "/docs:explain");
+        assert!(matches!(rx.try_recv(), Ok(AppEvent::InsertHistoryCell(_))));
+    }
+
+    #[test]
+    fn at_server_query_completes_mcp_resources() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+        let resource = |uri: &str| Resource {
+            annotations: None,
+            description: None,
+            mime_type: None,
+            name: uri.to_string(),
+            size: None,
+            title: None,
+            uri: uri.to_string(),
+        };
+        composer.set_mcp_resources(HashMap::from([(
+            "docs".to_string(),
+            vec![resource("docs://guide"), resource("docs://faq")],
+        )]));
+
+        composer.set_text_content("@docs:g".to_string());
+        while let Ok(event) = rx.try_recv() {
+            assert!(
+                !matches!(event, AppEvent::StartFileSearch(_)),
+                "resource queries should not start a file search"
+            );
+        }
+
+        composer.handle_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(composer.textarea.text(), "@docs:docs://guide ");
+    }
+
     #[test]
     fn burst_paste_fast_small_buffers_and_flushes_on_stop() {
         use crossterm::event::KeyCode;
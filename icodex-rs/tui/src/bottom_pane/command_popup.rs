@@ -10,34 +10,135 @@ use crate::slash_command::SlashCommand;
 use crate::slash_command::built_in_slash_commands;
 use icodex_common::fuzzy_match::fuzzy_match;
 use icodex_protocol::custom_prompts::CustomPrompt;
+use mcp_types::Prompt;
+use std::collections::HashMap;
 use std::collections::HashSet;
 
-/// A selectable item in the popup: either a built-in command or a user prompt.
+/// A selectable item in the popup: a built-in command, a user prompt or a
+/// prompt exposed by an MCP server.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum CommandItem {
     Builtin(SlashCommand),
     // Index into `prompts`
     UserPrompt(usize),
+    // Index into `mcp_prompts`
+    McpPrompt(usize),
+}
+
+/// A prompt exposed by an MCP server, invoked as `/server:prompt`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct McpPromptCommand {
+    /// Command name without the leading slash, e.g. `docs:summarize`.
+    pub(crate) command: String,
+    pub(crate) server: String,
+    pub(crate) prompt: Prompt,
+}
+
+impl McpPromptCommand {
+    pub(crate) fn new(server: String, prompt: Prompt) -> Self {
+        Self {
+            command: format!("{server}:{}", prompt.name),
+            server,
+            prompt,
+        }
+    }
+
+    /// Parse the text typed after the command into prompt arguments.
+    ///
+    /// Arguments are whitespace separated and may be quoted. `name=value`
+    /// sets the named argument; bare values fill the remaining arguments in
+    /// the order the server declared them.
+    pub(crate) fn parse_arguments(&self, text: &str) -> Result<HashMap<String, String>, String> {
+        let declared = self.prompt.arguments.as_deref().unwrap_or_default();
+        let tokens = shlex::split(text).ok_or_else(|| "unbalanced quotes".to_string())?;
+
+        let mut arguments = HashMap::new();
+        let mut positional = Vec::new();
+        for token in tokens {
+            match token.split_once('=') {
+                Some((name, value)) if declared.iter().any(|arg| arg.name == name) => {
+                    arguments.insert(name.to_string(), value.to_string());
+                }
+                _ => positional.push(token),
+            }
+        }
+
+        let mut unfilled = declared
+            .iter()
+            .filter(|arg| !arguments.contains_key(&arg.name));
+        for value in positional {
+            let Some(arg) = unfilled.next() else {
+                return Err(format!("unexpected argument '{value}'"));
+            };
+            arguments.insert(arg.name.clone(), value);
+        }
+
+        let missing: Vec<&str> = declared
+            .iter()
+            .filter(|arg| arg.required == Some(true) && !arguments.contains_key(&arg.name))
+            .map(|arg| arg.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "missing required argument(s): {}",
+                missing.join(", ")
+            ));
+        }
+        Ok(arguments)
+    }
+
+    fn description(&self) -> String {
+        let description = self
+            .prompt
+            .description
+            .clone()
+            .or_else(|| self.prompt.title.clone())
+            .unwrap_or_else(|| "MCP prompt".to_string());
+        let arguments: Vec<String> = self
+            .prompt
+            .arguments
+            .iter()
+            .flatten()
+            .map(|arg| {
+                if arg.required == Some(true) {
+                    format!("<{}>", arg.name)
+                } else {
+                    format!("[{}]", arg.name)
+                }
+            })
+            .collect();
+        if arguments.is_empty() {
+            description
+        } else {
+            format!("{} {description}", arguments.join(" "))
+        }
+    }
 }
 
 pub(crate) struct CommandPopup {
     command_filter: String,
     builtins: Vec<(&'static str, SlashCommand)>,
     prompts: Vec<CustomPrompt>,
+    mcp_prompts: Vec<McpPromptCommand>,
     state: ScrollState,
 }
 
 impl CommandPopup {
-    pub(crate) fn new(mut prompts: Vec<CustomPrompt>) -> Self {
+    pub(crate) fn new(
+        mut prompts: Vec<CustomPrompt>,
+        mut mcp_prompts: Vec<McpPromptCommand>,
+    ) -> Self {
         let builtins = built_in_slash_commands();
         // Exclude prompts that collide with builtin command names and sort by name.
         let exclude: HashSet<String> = builtins.iter().map(|(n, _)| (*n).to_string()).collect();
         prompts.retain(|p| !exclude.contains(&p.name));
         prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        mcp_prompts.sort_by(|a, b| a.command.cmp(&b.command));
         Self {
             command_filter: String::new(),
             builtins,
             prompts,
+            mcp_prompts,
             state: ScrollState::new(),
         }
     }
@@ -53,6 +154,15 @@ impl CommandPopup {
         self.prompts = prompts;
     }
 
+    pub(crate) fn set_mcp_prompts(&mut self, mut mcp_prompts: Vec<McpPromptCommand>) {
+        mcp_prompts.sort_by(|a, b| a.command.cmp(&b.command));
+        self.mcp_prompts = mcp_prompts;
+    }
+
+    pub(crate) fn mcp_prompt(&self, idx: usize) -> Option<&McpPromptCommand> {
+        self.mcp_prompts.get(idx)
+    }
+
     pub(crate) fn prompt_name(&self, idx: usize) -> Option<&str> {
         self.prompts.get(idx).map(|p| p.name.as_str())
     }
@@ -116,6 +226,12 @@ impl CommandPopup {
                         is_current: false,
                         description: Some("send saved prompt".to_string()),
                     },
+                    CommandItem::McpPrompt(i) => GenericDisplayRow {
+                        name: format!("/{}", self.mcp_prompts[i].command),
+                        match_indices: indices.map(|v| v.into_iter().map(|i| i + 1).collect()),
+                        is_current: false,
+                        description: Some(self.mcp_prompts[i].description()),
+                    },
                 })
                 .collect()
         };
@@ -138,6 +254,10 @@ impl CommandPopup {
             for idx in 0..self.prompts.len() {
                 out.push((CommandItem::UserPrompt(idx), None, 0));
             }
+            // Then MCP prompts, sorted by `server:prompt`.
+            for idx in 0..self.mcp_prompts.len() {
+                out.push((CommandItem::McpPrompt(idx), None, 0));
+            }
             return out;
         }
 
@@ -151,16 +271,23 @@ impl CommandPopup {
                 out.push((CommandItem::UserPrompt(idx), Some(indices), score));
             }
         }
+        for (idx, p) in self.mcp_prompts.iter().enumerate() {
+            if let Some((indices, score)) = fuzzy_match(&p.command, filter) {
+                out.push((CommandItem::McpPrompt(idx), Some(indices), score));
+            }
+        }
         // When filtering, sort by ascending score and then by name for stability.
         out.sort_by(|a, b| {
             a.2.cmp(&b.2).then_with(|| {
                 let an = match a.0 {
                     CommandItem::Builtin(c) => c.command(),
                     CommandItem::UserPrompt(i) => &self.prompts[i].name,
+                    CommandItem::McpPrompt(i) => &self.mcp_prompts[i].command,
                 };
                 let bn = match b.0 {
                     CommandItem::Builtin(c) => c.command(),
                     CommandItem::UserPrompt(i) => &self.prompts[i].name,
+                    CommandItem::McpPrompt(i) => &self.mcp_prompts[i].command,
                 };
                 an.cmp(bn)
             })
@@ -217,6 +344,12 @@ impl WidgetRef for CommandPopup {
                         is_current: false,
                         description: Some("send saved prompt".to_string()),
                     },
+                    CommandItem::McpPrompt(i) => GenericDisplayRow {
+                        name: format!("/{}", self.mcp_prompts[i].command),
+                        match_indices: indices.map(|v| v.into_iter().map(|i| i + 1).collect()),
+                        is_current: false,
+                        description: Some(self.mcp_prompts[i].description()),
+                    },
                 })
                 .collect()
         };
@@ -235,10 +368,11 @@ impl WidgetRef for CommandPopup {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use mcp_types::PromptArgument;
 
     #[test]
     fn filter_includes_init_when_typing_prefix() {
-        let mut popup = CommandPopup::new(Vec::new());
+        let mut popup = CommandPopup::new(Vec::new(), Vec::new());
         // Simulate the composer line starting with '/in' so the popup filters
         // matching commands by prefix.
         popup.on_composer_text_change("/in".to_string());
@@ -248,7 +382,7 @@ mod tests {
         let matches = popup.filtered_items();
         let has_init = matches.iter().any(|item| match item {
             CommandItem::Builtin(cmd) => cmd.command() == "init",
-            CommandItem::UserPrompt(_) => false,
+            CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_) => false,
         });
         assert!(
             has_init,
@@ -258,7 +392,7 @@ mod tests {
 
     #[test]
     fn selecting_init_by_exact_match() {
-        let mut popup = CommandPopup::new(Vec::new());
+        let mut popup = CommandPopup::new(Vec::new(), Vec::new());
         popup.on_composer_text_change("/init".to_string());
 
         // When an exact match exists, the selected command should be that
@@ -266,19 +400,21 @@ mod tests {
         let selected = popup.selected_item();
         match selected {
             Some(CommandItem::Builtin(cmd)) => assert_eq!(cmd.command(), "init"),
-            Some(CommandItem::UserPrompt(_)) => panic!("unexpected prompt selected for '/init'"),
+            Some(CommandItem::UserPrompt(_)) | Some(CommandItem::McpPrompt(_)) => {
+                panic!("unexpected prompt selected for '/init'")
+            }
             None => panic!("expected a selected command for exact match"),
         }
     }
 
     #[test]
     fn model_is_first_suggestion_for_mo() {
-        let mut popup = CommandPopup::new(Vec::new());
+        let mut popup = CommandPopup::new(Vec::new(), Vec::new());
         popup.on_composer_text_change("/mo".to_string());
         let matches = popup.filtered_items();
         match matches.first() {
             Some(CommandItem::Builtin(cmd)) => assert_eq!(cmd.command(), "model"),
-            Some(CommandItem::UserPrompt(_)) => {
+            Some(CommandItem::UserPrompt(_)) | Some(CommandItem::McpPrompt(_)) => {
                 panic!("unexpected prompt ranked before '/model' for '/mo'")
             }
             None => panic!("expected at least one match for '/mo'"),
@@ -299,7 +435,7 @@ mod tests {
                 content: "hello from bar".to_string(),
             },
         ];
-        let popup = CommandPopup::new(prompts);
+        let popup = CommandPopup::new(prompts, Vec::new());
         let items = popup.filtered_items();
         let mut prompt_names: Vec<String> = items
             .into_iter()
@@ -315,11 +451,14 @@ mod tests {
     #[test]
     fn prompt_name_collision_with_builtin_is_ignored() {
         // Create a prompt named like a builtin (e.g. "init").
-        let popup = CommandPopup::new(vec![CustomPrompt {
-            name: "init".to_string(),
-            path: "/tmp/init.md".to_string().into(),
-            content: "should be ignored".to_string(),
-        }]);
+        let popup = CommandPopup::new(
+            vec![CustomPrompt {
+                name: "init".to_string(),
+                path: "/tmp/init.md".to_string().into(),
+                content: "should be ignored".to_string(),
+            }],
+            Vec::new(),
+        );
         let items = popup.filtered_items();
         let has_collision_prompt = items.into_iter().any(|it| match it {
             CommandItem::UserPrompt(i) => popup.prompt_name(i) == Some("init"),
@@ -330,4 +469,75 @@ mod tests {
             "prompt with builtin name should be ignored"
         );
     }
+
+    fn review_prompt() -> McpPromptCommand {
+        McpPromptCommand::new(
+            "docs".to_string(),
+            Prompt {
+                arguments: Some(vec![
+                    PromptArgument {
+                        description: None,
+                        name: "file".to_string(),
+                        required: Some(true),
+                        title: None,
+                    },
+                    PromptArgument {
+                        description: None,
+                        name: "focus".to_string(),
+                        required: None,
+                        title: None,
+                    },
+                ]),
+                description: Some("Review a file".to_string()),
+                name: "review".to_string(),
+                title: None,
+            },
+        )
+    }
+
+    #[test]
+    fn mcp_prompts_are_listed_as_server_commands() {
+        let mut popup = CommandPopup::new(Vec::new(), vec![review_prompt()]);
+        popup.on_composer_text_change("/docs:rev".to_string());
+        match popup.selected_item() {
+            Some(CommandItem::McpPrompt(i)) => {
+                assert_eq!(
+                    popup.mcp_prompt(i).map(|p| p.command.as_str()),
+                    Some("docs:review")
+                );
+            }
+            other => panic!("expected the MCP prompt to be selected, got {other:?}"),
+        }
+        assert_eq!(
+            review_prompt().description(),
+            "<file> [focus] Review a file"
+        );
+    }
+
+    #[test]
+    fn mcp_prompt_arguments_accept_names_and_positions() {
+        let prompt = review_prompt();
+        assert_eq!(
+            prompt.parse_arguments("src/main.rs \"error handling\""),
+            Ok(HashMap::from([
+                ("file".to_string(), "src/main.rs".to_string()),
+                ("focus".to_string(), "error handling".to_string()),
+            ]))
+        );
+        assert_eq!(
+            prompt.parse_arguments("focus=tests lib.rs"),
+            Ok(HashMap::from([
+                ("file".to_string(), "lib.rs".to_string()),
+                ("focus".to_string(), "tests".to_string()),
+            ]))
+        );
+        assert_eq!(
+            prompt.parse_arguments("focus=tests"),
+            Err("missing required argument(s): file".to_string())
+        );
+        assert_eq!(
+            prompt.parse_arguments("a b c"),
+            Err("unexpected argument 'c'".to_string())
+        );
+    }
 }
@@ -1,4 +1,5 @@
 //! Bottom pane: shows the ChatComposer or a BottomPaneView, if one is active.
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::app_event_sender::AppEventSender;
@@ -8,6 +9,7 @@ use bottom_pane_view::BottomPaneView;
 use icodex_core::protocol::TokenUsageInfo;
 use icodex_file_search::FileMatch;
 use crossterm::event::KeyEvent;
+use mcp_types::Resource;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
@@ -36,6 +38,7 @@ pub(crate) enum CancellationEvent {
 
 pub(crate) use chat_composer::ChatComposer;
 pub(crate) use chat_composer::InputResult;
+pub(crate) use command_popup::McpPromptCommand;
 use icodex_protocol::custom_prompts::CustomPrompt;
 
 use crate::status_indicator_widget::StatusIndicatorWidget;
@@ -351,6 +354,17 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Update MCP prompts available for the slash popup.
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptCommand>) {
+        self.composer.set_mcp_prompts(prompts);
+        self.request_redraw();
+    }
+
+    /// Update MCP resources offered when completing `@server:` mentions.
+    pub(crate) fn set_mcp_resources(&mut self, resources: HashMap<String, Vec<Resource>>) {
+        self.composer.set_mcp_resources(resources);
+    }
+
     pub(crate) fn composer_is_empty(&self) -> bool {
         self.composer.is_empty()
     }
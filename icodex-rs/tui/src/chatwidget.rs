@@ -19,28 +19,43 @@ use icodex_core::protocol::EventMsg;
 use icodex_core::protocol::ExecApprovalRequestEvent;
 use icodex_core::protocol::ExecCommandBeginEvent;
 use icodex_core::protocol::ExecCommandEndEvent;
+use icodex_core::protocol::ExitedReviewModeEvent;
 use icodex_core::protocol::InputItem;
 use icodex_core::protocol::InputMessageKind;
 use icodex_core::protocol::ListCustomPromptsResponseEvent;
+use icodex_core::protocol::McpListPromptsResponseEvent;
 use icodex_core::protocol::McpListToolsResponseEvent;
+use icodex_core::protocol::McpSamplingApprovalRequestEvent;
+use icodex_core::protocol::McpServerState;
+use icodex_core::protocol::McpServerStatus;
+use icodex_core::protocol::McpToolCallApprovalRequestEvent;
 use icodex_core::protocol::McpToolCallBeginEvent;
 use icodex_core::protocol::McpToolCallEndEvent;
 use icodex_core::protocol::Op;
 use icodex_core::protocol::PatchApplyBeginEvent;
+use icodex_core::protocol::ReviewRequest;
+use icodex_core::protocol::ReviewTarget;
 use icodex_core::protocol::StreamErrorEvent;
+use icodex_core::protocol::SubAgentBeginEvent;
+use icodex_core::protocol::SubAgentEvent;
 use icodex_core::protocol::TaskCompleteEvent;
 use icodex_core::protocol::TokenUsage;
 use icodex_core::protocol::TokenUsageInfo;
 use icodex_core::protocol::TurnAbortReason;
 use icodex_core::protocol::TurnDiffEvent;
+use icodex_core::protocol::TurnRolledBackEvent;
 use icodex_core::protocol::UserMessageEvent;
 use icodex_core::protocol::WebSearchBeginEvent;
 use icodex_core::protocol::WebSearchEndEvent;
+use icodex_core::review_target::describe_review_target;
+use icodex_core::review_target::review_request;
 use icodex_protocol::parse_command::ParsedCommand;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
 use crossterm::event::KeyModifiers;
+use mcp_types::Role;
+use mcp_types::SamplingMessageContent;
 use rand::Rng;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
@@ -57,6 +72,7 @@ use crate::bottom_pane::BottomPane;
 use crate::bottom_pane::BottomPaneParams;
 use crate::bottom_pane::CancellationEvent;
 use crate::bottom_pane::InputResult;
+use crate::bottom_pane::McpPromptCommand;
 use crate::bottom_pane::SelectionAction;
 use crate::bottom_pane::SelectionItem;
 use crate::clipboard_paste::paste_image_to_temp_png;
@@ -141,6 +157,9 @@ pub(crate) struct ChatWidget {
     queued_user_messages: VecDeque<UserMessage>,
     // Pending notification to show when unfocused on next Draw
     pending_notification: Option<Notification>,
+    // Whether the next MCP tool list was requested by `/mcp` and should open
+    // the server management popup
+    show_mcp_popup: bool,
 }
 
 struct UserMessage {
@@ -186,8 +205,11 @@ impl ChatWidget {
         if let Some(messages) = initial_messages {
             self.replay_initial_messages(messages);
         }
-        // Ask icodex-core to enumerate custom prompts for this session.
+        // Ask icodex-core to enumerate custom prompts and MCP prompts and
+        // resources for this session.
         self.submit_op(Op::ListCustomPrompts);
+        self.submit_op(Op::ListMcpPrompts);
+        self.submit_op(Op::ListMcpResources);
         if let Some(user_message) = self.initial_user_message.take() {
             self.submit_user_message(user_message);
         }
@@ -347,6 +369,32 @@ impl ChatWidget {
         );
     }
 
+    fn on_mcp_tool_call_approval_request(
+        &mut self,
+        id: String,
+        ev: McpToolCallApprovalRequestEvent,
+    ) {
+        let id2 = id.clone();
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_mcp_tool_call_approval(id, ev),
+            |s| s.handle_mcp_tool_call_approval_now(id2, ev2),
+        );
+    }
+
+    fn on_mcp_sampling_approval_request(
+        &mut self,
+        id: String,
+        ev: McpSamplingApprovalRequestEvent,
+    ) {
+        let id2 = id.clone();
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_mcp_sampling_approval(id, ev),
+            |s| s.handle_mcp_sampling_approval_now(id2, ev2),
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -426,6 +474,55 @@ impl ChatWidget {
         debug!("TurnDiffEvent: {unified_diff}");
     }
 
+    fn on_turn_rolled_back(&mut self, ev: TurnRolledBackEvent) {
+        let message = match ev.restored_paths.len() {
+            0 => "Undid the last turn; no files had changed".to_string(),
+            1 => "Undid the last turn; restored 1 file".to_string(),
+            n => format!("Undid the last turn; restored {n} files"),
+        };
+        self.add_to_history(history_cell::new_info_event(message, None));
+        self.request_redraw();
+    }
+
+    fn on_sub_agent_begin(&mut self, ev: SubAgentBeginEvent) {
+        let prompt = ev.prompt.lines().next().unwrap_or_default();
+        self.add_to_history(history_cell::new_info_event(
+            format!("Started a sub-agent: {prompt}"),
+            Some(ev.model),
+        ));
+        self.request_redraw();
+    }
+
+    fn on_sub_agent_event(&mut self, ev: SubAgentEvent) {
+        let cell = match *ev.msg {
+            EventMsg::Error(ErrorEvent { message }) => {
+                history_cell::new_error_event(format!("Sub-agent: {message}"))
+            }
+            EventMsg::TaskComplete(_) => {
+                history_cell::new_info_event("Sub-agent finished".to_string(), None)
+            }
+            _ => return,
+        };
+        self.add_to_history(cell);
+        self.request_redraw();
+    }
+
+    fn on_entered_review_mode(&mut self, request: ReviewRequest) {
+        self.add_to_history(history_cell::new_info_event(
+            format!("Reviewing {}", request.user_facing_hint),
+            None,
+        ));
+        self.request_redraw();
+    }
+
+    fn on_exited_review_mode(&mut self, ev: ExitedReviewModeEvent) {
+        let Some(output) = ev.review_output else {
+            return;
+        };
+        self.add_to_history(history_cell::new_review_output(&output, &self.config));
+        self.request_redraw();
+    }
+
     fn on_background_event(&mut self, message: String) {
         debug!("BackgroundEvent: {message}");
     }
@@ -506,13 +603,19 @@ impl ChatWidget {
                 parsed,
             ));
         }
+        // Failed commands show their stderr, so say there which limit stopped
+        // the command.
+        let stderr = match ev.limit_exceeded {
+            Some(limit) => format!("command {limit}\n{}", ev.stderr),
+            None => ev.stderr.clone(),
+        };
         if let Some(cell) = self.active_exec_cell.as_mut() {
             cell.complete_call(
                 &ev.call_id,
                 CommandOutput {
                     exit_code: ev.exit_code,
                     stdout: ev.stdout.clone(),
-                    stderr: ev.stderr.clone(),
+                    stderr,
                     formatted_output: ev.formatted_output.clone(),
                 },
                 ev.duration,
@@ -576,6 +679,57 @@ impl ChatWidget {
         });
     }
 
+    pub(crate) fn handle_mcp_tool_call_approval_now(
+        &mut self,
+        id: String,
+        ev: McpToolCallApprovalRequestEvent,
+    ) {
+        self.flush_answer_stream_with_separator();
+        let invocation = ev.invocation;
+        let request = ApprovalRequest::McpToolCall {
+            id,
+            server: invocation.server,
+            tool: invocation.tool,
+            arguments: invocation.arguments.map(|arguments| arguments.to_string()),
+        };
+        self.bottom_pane.push_approval_request(request);
+        self.request_redraw();
+    }
+
+    pub(crate) fn handle_mcp_sampling_approval_now(
+        &mut self,
+        id: String,
+        ev: McpSamplingApprovalRequestEvent,
+    ) {
+        self.flush_answer_stream_with_separator();
+        let messages = ev
+            .request
+            .messages
+            .iter()
+            .map(|message| {
+                let role = match message.role {
+                    Role::User => "user",
+                    Role::Assistant => "assistant",
+                };
+                let content = match &message.content {
+                    SamplingMessageContent::TextContent(text) => text.text.as_str(),
+                    SamplingMessageContent::ImageContent(_) => "[image]",
+                    SamplingMessageContent::AudioContent(_) => "[audio]",
+                };
+                format!("{role}: {content}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let request = ApprovalRequest::McpSampling {
+            id,
+            server: ev.server,
+            messages,
+        };
+        self.bottom_pane.push_approval_request(request);
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_exec_begin_now(&mut self, ev: ExecCommandBeginEvent) {
         // Ensure the status indicator is visible while the command runs.
         self.running_commands.insert(
@@ -701,6 +855,7 @@ impl ChatWidget {
             show_welcome_banner: true,
             suppress_session_configured_redraw: false,
             pending_notification: None,
+            show_mcp_popup: false,
         }
     }
 
@@ -757,6 +912,7 @@ impl ChatWidget {
             show_welcome_banner: true,
             suppress_session_configured_redraw: true,
             pending_notification: None,
+            show_mcp_popup: false,
         }
     }
 
@@ -828,6 +984,19 @@ impl ChatWidget {
                     InputResult::Command(cmd) => {
                         self.dispatch_command(cmd);
                     }
+                    InputResult::McpPrompt {
+                        text,
+                        server,
+                        name,
+                        arguments,
+                    } => {
+                        self.submit_op(Op::RunMcpPrompt {
+                            server,
+                            name,
+                            arguments,
+                        });
+                        self.add_to_history(history_cell::new_user_prompt(text));
+                    }
                     InputResult::None => {}
                 }
             }
@@ -903,6 +1072,13 @@ impl ChatWidget {
                     tx.send(AppEvent::DiffResult(text));
                 });
             }
+            SlashCommand::Review => {
+                self.open_review_popup();
+            }
+            SlashCommand::Undo => {
+                self.app_event_tx
+                    .send(AppEvent::CodexOp(Op::RollbackTurn { turn_id: None }));
+            }
             SlashCommand::Mention => {
                 self.insert_str("@");
             }
@@ -1102,6 +1278,12 @@ impl ChatWidget {
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::McpToolCallApprovalRequest(ev) => {
+                self.on_mcp_tool_call_approval_request(id.unwrap_or_default(), ev)
+            }
+            EventMsg::McpSamplingApprovalRequest(ev) => {
+                self.on_mcp_sampling_approval_request(id.unwrap_or_default(), ev)
+            }
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
             EventMsg::PatchApplyBegin(ev) => self.on_patch_apply_begin(ev),
@@ -1114,8 +1296,15 @@ impl ChatWidget {
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
+            EventMsg::McpListPromptsResponse(ev) => self.on_list_mcp_prompts(ev),
+            EventMsg::McpListResourcesResponse(ev) => {
+                self.bottom_pane.set_mcp_resources(ev.resources);
+            }
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
+            EventMsg::TurnRolledBack(ev) => self.on_turn_rolled_back(ev),
+            EventMsg::SubAgentBegin(ev) => self.on_sub_agent_begin(ev),
+            EventMsg::SubAgent(ev) => self.on_sub_agent_event(ev),
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
                 self.on_background_event(message)
             }
@@ -1129,15 +1318,16 @@ impl ChatWidget {
                 self.app_event_tx
                     .send(crate::app_event::AppEvent::ConversationHistory(ev));
             }
-            EventMsg::EnteredReviewMode(_) => {}
-            EventMsg::ExitedReviewMode(_) => {}
+            EventMsg::EnteredReviewMode(ev) => self.on_entered_review_mode(ev),
+            EventMsg::ExitedReviewMode(ev) => self.on_exited_review_mode(ev),
         }
     }
 
     fn on_user_message_event(&mut self, event: UserMessageEvent) {
         match event.kind {
             Some(InputMessageKind::EnvironmentContext)
-            | Some(InputMessageKind::UserInstructions) => {
+            | Some(InputMessageKind::UserInstructions)
+            | Some(InputMessageKind::McpResources) => {
                 // Skip XML‑wrapped context blocks in the transcript.
             }
             Some(InputMessageKind::Plain) | None => {
@@ -1320,6 +1510,47 @@ impl ChatWidget {
         );
     }
 
+    /// Open a popup to choose what `/review` should look at.
+    pub(crate) fn open_review_popup(&mut self) {
+        let targets = [
+            ("Uncommitted changes", ReviewTarget::UncommittedChanges),
+            (
+                "Against the default branch",
+                ReviewTarget::BaseBranch { base: None },
+            ),
+            (
+                "Last commit",
+                ReviewTarget::CommitRange {
+                    range: "HEAD".to_string(),
+                },
+            ),
+        ];
+        let items = targets
+            .into_iter()
+            .map(|(name, target)| {
+                let description = Some(describe_review_target(&target));
+                let actions: Vec<SelectionAction> = vec![Box::new(move |tx| {
+                    tx.send(AppEvent::CodexOp(Op::Review {
+                        review_request: review_request(target.clone(), None),
+                    }));
+                })];
+                SelectionItem {
+                    name: name.to_string(),
+                    description,
+                    is_current: false,
+                    actions,
+                }
+            })
+            .collect();
+
+        self.bottom_pane.show_selection_view(
+            "Select what to review".to_string(),
+            None,
+            Some("Press Enter to confirm or Esc to go back".to_string()),
+            items,
+        );
+    }
+
     /// Set the approval policy in the widget's config copy.
     pub(crate) fn set_approval_policy(&mut self, policy: AskForApproval) {
         self.config.approval_policy = policy;
@@ -1338,7 +1569,7 @@ impl ChatWidget {
     /// Set the model in the widget's config copy.
     pub(crate) fn set_model(&mut self, model: &str) {
         self.session_header.set_model(model);
-        self.config.model = model.to_string();
+        self.config = self.config.with_model(model);
     }
 
     pub(crate) fn add_info_message(&mut self, message: String, hint: Option<String>) {
@@ -1355,6 +1586,7 @@ impl ChatWidget {
         if self.config.mcp_servers.is_empty() {
             self.add_to_history(history_cell::empty_mcp_output());
         } else {
+            self.show_mcp_popup = true;
             self.submit_op(Op::ListMcpTools);
         }
     }
@@ -1416,7 +1648,68 @@ impl ChatWidget {
     }
 
     fn on_list_mcp_tools(&mut self, ev: McpListToolsResponseEvent) {
-        self.add_to_history(history_cell::new_mcp_tools_output(&self.config, ev.tools));
+        self.add_to_history(history_cell::new_mcp_tools_output(
+            &self.config,
+            ev.tools,
+            &ev.servers,
+        ));
+        if std::mem::take(&mut self.show_mcp_popup) && !ev.servers.is_empty() {
+            self.open_mcp_popup(&ev.servers);
+        }
+    }
+
+    /// Offer to restart, enable or disable each MCP server, and to list
+    /// every server's tools again.
+    fn open_mcp_popup(&mut self, servers: &[McpServerStatus]) {
+        let mut items: Vec<SelectionItem> = Vec::new();
+        for status in servers {
+            let server = status.name.clone();
+            if status.state != McpServerState::Disabled {
+                let restart = server.clone();
+                items.push(SelectionItem {
+                    name: format!("Restart {server}"),
+                    description: status.error.clone(),
+                    is_current: false,
+                    actions: vec![Box::new(move |tx| {
+                        tx.send(AppEvent::CodexOp(Op::RestartMcpServer {
+                            server: restart.clone(),
+                        }));
+                    })],
+                });
+            }
+            let enabled = status.state == McpServerState::Disabled;
+            let name = if enabled {
+                format!("Enable {server}")
+            } else {
+                format!("Disable {server}")
+            };
+            items.push(SelectionItem {
+                name,
+                description: None,
+                is_current: false,
+                actions: vec![Box::new(move |tx| {
+                    tx.send(AppEvent::CodexOp(Op::SetMcpServerEnabled {
+                        server: server.clone(),
+                        enabled,
+                    }));
+                })],
+            });
+        }
+        items.push(SelectionItem {
+            name: "Refresh tools".to_string(),
+            description: Some("Ask every running server for its tools again".to_string()),
+            is_current: false,
+            actions: vec![Box::new(|tx| {
+                tx.send(AppEvent::CodexOp(Op::RefreshMcpTools { server: None }));
+            })],
+        });
+
+        self.bottom_pane.show_selection_view(
+            "Manage MCP servers".to_string(),
+            None,
+            Some("Press Enter to confirm or Esc to go back".to_string()),
+            items,
+        );
     }
 
     fn on_list_custom_prompts(&mut self, ev: ListCustomPromptsResponseEvent) {
@@ -1426,6 +1719,19 @@ impl ChatWidget {
         self.bottom_pane.set_custom_prompts(ev.custom_prompts);
     }
 
+    fn on_list_mcp_prompts(&mut self, ev: McpListPromptsResponseEvent) {
+        let prompts = ev
+            .prompts
+            .into_iter()
+            .flat_map(|(server, prompts)| {
+                prompts
+                    .into_iter()
+                    .map(move |prompt| McpPromptCommand::new(server.clone(), prompt))
+            })
+            .collect();
+        self.bottom_pane.set_mcp_prompts(prompts);
+    }
+
     /// Programmatically submit a user text message as if typed in the
     /// composer. The text will be added to conversation history and sent to
     /// the agent.
@@ -4,6 +4,8 @@ use icodex_core::protocol::ApplyPatchApprovalRequestEvent;
 use icodex_core::protocol::ExecApprovalRequestEvent;
 use icodex_core::protocol::ExecCommandBeginEvent;
 use icodex_core::protocol::ExecCommandEndEvent;
+use icodex_core::protocol::McpSamplingApprovalRequestEvent;
+use icodex_core::protocol::McpToolCallApprovalRequestEvent;
 use icodex_core::protocol::McpToolCallBeginEvent;
 use icodex_core::protocol::McpToolCallEndEvent;
 use icodex_core::protocol::PatchApplyEndEvent;
@@ -14,6 +16,8 @@ use super::ChatWidget;
 pub(crate) enum QueuedInterrupt {
     ExecApproval(String, ExecApprovalRequestEvent),
     ApplyPatchApproval(String, ApplyPatchApprovalRequestEvent),
+    McpToolCallApproval(String, McpToolCallApprovalRequestEvent),
+    McpSamplingApproval(String, McpSamplingApprovalRequestEvent),
     ExecBegin(ExecCommandBeginEvent),
     ExecEnd(ExecCommandEndEvent),
     McpBegin(McpToolCallBeginEvent),
@@ -51,6 +55,24 @@ impl InterruptManager {
             .push_back(QueuedInterrupt::ApplyPatchApproval(id, ev));
     }
 
+    pub(crate) fn push_mcp_tool_call_approval(
+        &mut self,
+        id: String,
+        ev: McpToolCallApprovalRequestEvent,
+    ) {
+        self.queue
+            .push_back(QueuedInterrupt::McpToolCallApproval(id, ev));
+    }
+
+    pub(crate) fn push_mcp_sampling_approval(
+        &mut self,
+        id: String,
+        ev: McpSamplingApprovalRequestEvent,
+    ) {
+        self.queue
+            .push_back(QueuedInterrupt::McpSamplingApproval(id, ev));
+    }
+
     pub(crate) fn push_exec_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.queue.push_back(QueuedInterrupt::ExecBegin(ev));
     }
@@ -78,6 +100,12 @@ impl InterruptManager {
                 QueuedInterrupt::ApplyPatchApproval(id, ev) => {
                     chat.handle_apply_patch_approval_now(id, ev)
                 }
+                QueuedInterrupt::McpToolCallApproval(id, ev) => {
+                    chat.handle_mcp_tool_call_approval_now(id, ev)
+                }
+                QueuedInterrupt::McpSamplingApproval(id, ev) => {
+                    chat.handle_mcp_sampling_approval_now(id, ev)
+                }
                 QueuedInterrupt::ExecBegin(ev) => chat.handle_exec_begin_now(ev),
                 QueuedInterrupt::ExecEnd(ev) => chat.handle_exec_end_now(ev),
                 QueuedInterrupt::McpBegin(ev) => chat.handle_mcp_begin_now(ev),
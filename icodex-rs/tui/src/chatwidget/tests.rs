@@ -252,6 +252,7 @@ fn make_chatwidget_manual() -> (
         queued_user_messages: VecDeque::new(),
         suppress_session_configured_redraw: false,
         pending_notification: None,
+        show_mcp_popup: false,
     };
     (widget, rx, op_rx)
 }
@@ -428,6 +429,7 @@ fn end_exec(chat: &mut ChatWidget, call_id: &str, stdout: &str, stderr: &str, ex
             exit_code,
             duration: std::time::Duration::from_millis(5),
             formatted_output: aggregated,
+            limit_exceeded: None,
         }),
     });
 }
@@ -1695,6 +1697,7 @@ fn chatwidget_exec_and_status_layout_vt100_snapshot() {
             exit_code: 0,
             duration: std::time::Duration::from_millis(16000),
             formatted_output: String::new(),
+            limit_exceeded: None,
         }),
     });
     chat.handle_icodex_event(Event {
@@ -1897,3 +1900,24 @@ printf 'fenced within fenced\n'
     let visual = vt_lines.join("\n");
     assert_snapshot!(visual);
 }
+
+#[test]
+fn review_popup_submits_structured_review() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+
+    chat.dispatch_command(SlashCommand::Review);
+    chat.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+    let mut review_request = None;
+    while let Ok(app_ev) = rx.try_recv() {
+        if let AppEvent::CodexOp(Op::Review { review_request: r }) = app_ev {
+            review_request = Some(r);
+        }
+    }
+    let review_request = review_request.expect("expected Op::Review after Enter");
+    assert_eq!(
+        review_request.target,
+        Some(ReviewTarget::UncommittedChanges)
+    );
+    assert_eq!(review_request.user_facing_hint, "uncommitted changes");
+}
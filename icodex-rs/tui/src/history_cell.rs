@@ -17,13 +17,18 @@ use icodex_common::elapsed::format_duration;
 use icodex_core::auth::get_auth_file;
 use icodex_core::auth::try_read_auth_json;
 use icodex_core::config::Config;
+use icodex_core::config_types::McpServerTransportConfig;
 use icodex_core::config_types::ReasoningSummaryFormat;
+use icodex_core::model_registry::ModelValueSource;
 use icodex_core::plan_tool::PlanItemArg;
 use icodex_core::plan_tool::StepStatus;
 use icodex_core::plan_tool::UpdatePlanArgs;
 use icodex_core::project_doc::discover_project_doc_paths;
 use icodex_core::protocol::FileChange;
 use icodex_core::protocol::McpInvocation;
+use icodex_core::protocol::McpServerState;
+use icodex_core::protocol::McpServerStatus;
+use icodex_core::protocol::ReviewOutputEvent;
 use icodex_core::protocol::SandboxPolicy;
 use icodex_core::protocol::SessionConfiguredEvent;
 use icodex_core::protocol::TokenUsage;
@@ -1057,6 +1062,7 @@ pub(crate) fn new_status_output(
     let sandbox_name = match &config.sandbox_policy {
         SandboxPolicy::DangerFullAccess => "danger-full-access",
         SandboxPolicy::ReadOnly => "read-only",
+        SandboxPolicy::WorkspaceRead { .. } => "workspace-read",
         SandboxPolicy::WorkspaceWrite { .. } => "workspace-write",
     };
     lines.push(vec!["  • Sandbox: ".into(), sandbox_name.into()].into());
@@ -1151,6 +1157,17 @@ pub(crate) fn new_status_output(
     if !rsum.is_empty() {
         lines.push(vec!["  • Reasoning Summaries: ".into(), title_case(&rsum).into()].into());
     }
+    lines.push("  • Capabilities:".into());
+    for (field, value, source) in config.models.resolve(&config.model).describe() {
+        lines.push(
+            vec![
+                format!("      {field}: ").into(),
+                value.into(),
+                format!(" ({})", display_model_value_source(&source, config)).dim(),
+            ]
+            .into(),
+        );
+    }
 
     lines.push("".into());
 
@@ -1189,6 +1206,18 @@ pub(crate) fn new_status_output(
     PlainHistoryCell { lines }
 }
 
+/// Shows files under `CODEX_HOME` relative to it, e.g. `models.d/local.toml`.
+fn display_model_value_source(source: &ModelValueSource, config: &Config) -> String {
+    match source {
+        ModelValueSource::File(path) => path
+            .strip_prefix(&config.icodex_home)
+            .unwrap_or(path)
+            .display()
+            .to_string(),
+        other => other.to_string(),
+    }
+}
+
 /// Render a summary of configured MCP servers from the current `Config`.
 pub(crate) fn empty_mcp_output() -> PlainHistoryCell {
     let lines: Vec<Line<'static>> = vec![
@@ -1212,6 +1241,7 @@ pub(crate) fn empty_mcp_output() -> PlainHistoryCell {
 pub(crate) fn new_mcp_tools_output(
     config: &Config,
     tools: std::collections::HashMap<String, mcp_types::Tool>,
+    servers: &[McpServerStatus],
 ) -> PlainHistoryCell {
     let mut lines: Vec<Line<'static>> = vec![
         "/mcp".magenta().into(),
@@ -1220,7 +1250,7 @@ pub(crate) fn new_mcp_tools_output(
         "".into(),
     ];
 
-    if tools.is_empty() {
+    if tools.is_empty() && servers.is_empty() {
         lines.push("  • No MCP tools available.".italic().into());
         lines.push("".into());
         return PlainHistoryCell { lines };
@@ -1237,10 +1267,29 @@ pub(crate) fn new_mcp_tools_output(
 
         lines.push(vec!["  • Server: ".into(), server.clone().into()].into());
 
-        if !cfg.command.is_empty() {
-            let cmd_display = format!("{} {}", cfg.command, cfg.args.join(" "));
+        if let Some(status) = servers.iter().find(|status| &status.name == server) {
+            let state: Span<'static> = match status.state {
+                McpServerState::Running => "running".green(),
+                McpServerState::Failed => "failed".red(),
+                McpServerState::Disabled => "disabled".dim(),
+            };
+            let mut line = vec!["    • Status: ".into(), state];
+            if let Some(error) = &status.error {
+                line.push(format!(" ({error})").red());
+            }
+            lines.push(line.into());
+        }
+
+        match &cfg.transport {
+            McpServerTransportConfig::Stdio { command, args, .. } if !command.is_empty() => {
+                let cmd_display = format!("{} {}", command, args.join(" "));
 
-            lines.push(vec!["    • Command: ".into(), cmd_display.into()].into());
+                lines.push(vec!["    • Command: ".into(), cmd_display.into()].into());
+            }
+            McpServerTransportConfig::Stdio { .. } => {}
+            McpServerTransportConfig::StreamableHttp { url, .. } => {
+                lines.push(vec!["    • URL: ".into(), url.clone().into()].into());
+            }
         }
 
         if names.is_empty() {
@@ -1383,6 +1432,45 @@ pub(crate) fn new_patch_apply_failure(stderr: String) -> PlainHistoryCell {
     PlainHistoryCell { lines }
 }
 
+/// Render the reviewer's findings. Each finding links to its location when a
+/// file opener is configured.
+pub(crate) fn new_review_output(output: &ReviewOutputEvent, config: &Config) -> PlainHistoryCell {
+    let mut markdown = String::new();
+    if !output.overall_explanation.trim().is_empty() {
+        markdown.push_str(output.overall_explanation.trim());
+        markdown.push_str("\n\n");
+    }
+    if output.findings.is_empty() {
+        markdown.push_str("No issues found.\n");
+    }
+    for finding in &output.findings {
+        let location = &finding.code_location;
+        let path = location
+            .absolute_file_path
+            .strip_prefix(&config.cwd)
+            .unwrap_or(&location.absolute_file_path)
+            .display();
+        let range = &location.line_range;
+        let location = match config.file_opener.get_scheme() {
+            Some(_) => format!("【F:{path}†L{}-L{}】", range.start, range.end),
+            None => format!("`{path}:{}`", range.start),
+        };
+        markdown.push_str(&format!(
+            "- **[P{}] {}** {location}\n",
+            finding.priority,
+            finding.title.trim()
+        ));
+        for line in finding.body.trim().lines() {
+            markdown.push_str(&format!("  {line}\n"));
+        }
+    }
+
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    lines.push(Line::from("review".magenta().bold()));
+    append_markdown(&markdown, &mut lines, config);
+    PlainHistoryCell { lines }
+}
+
 /// Create a new history cell for a proposed command approval.
 /// Renders a header and the command preview similar to how proposed patches
 /// show a header and summary.
@@ -1557,6 +1645,10 @@ mod tests {
     use icodex_core::config::Config;
     use icodex_core::config::ConfigOverrides;
     use icodex_core::config::ConfigToml;
+    use icodex_core::config_types::UriBasedFileOpener;
+    use icodex_core::protocol::ReviewCodeLocation;
+    use icodex_core::protocol::ReviewFinding;
+    use icodex_core::protocol::ReviewLineRange;
     use dirs::home_dir;
 
     fn test_config() -> Config {
@@ -1627,6 +1719,43 @@ mod tests {
         assert_eq!(formatted, expected);
     }
 
+    #[test]
+    fn review_findings_show_paths_relative_to_cwd() {
+        let mut config = test_config();
+        config.cwd = PathBuf::from("/repo");
+        config.file_opener = UriBasedFileOpener::None;
+        let output = ReviewOutputEvent {
+            findings: vec![ReviewFinding {
+                title: "Off-by-one in loop".to_string(),
+                body: "The last element is skipped.".to_string(),
+                confidence_score: 0.9,
+                priority: 1,
+                code_location: ReviewCodeLocation {
+                    absolute_file_path: PathBuf::from("/repo/src/lib.rs"),
+                    line_range: ReviewLineRange { start: 3, end: 5 },
+                },
+            }],
+            overall_explanation: "One bug.".to_string(),
+            ..Default::default()
+        };
+
+        let lines = render_lines(&new_review_output(&output, &config).display_lines(80));
+
+        assert_eq!(lines[0], "review");
+        assert!(lines.iter().any(|line| line.contains("One bug.")));
+        let finding = lines
+            .iter()
+            .find(|line| line.contains("[P1] Off-by-one in loop"))
+            .expect("finding line");
+        assert!(finding.contains("src/lib.rs:3"), "{finding}");
+        assert!(!finding.contains("/repo/src"), "{finding}");
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.contains("The last element is skipped."))
+        );
+    }
+
     #[test]
     fn coalesces_sequential_reads_within_one_call() {
         // Build one exec cell with a Search followed by two Reads
@@ -7,6 +7,10 @@ use icodex_core::ConversationItem;
 use icodex_core::ConversationsPage;
 use icodex_core::Cursor;
 use icodex_core::RolloutRecorder;
+use icodex_core::SearchField;
+use icodex_core::SessionSearchHit;
+use icodex_core::SessionSearchQuery;
+use icodex_core::search_sessions;
 use color_eyre::eyre::Result;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -29,6 +33,9 @@ use icodex_protocol::protocol::USER_MESSAGE_BEGIN;
 
 const PAGE_SIZE: usize = 25;
 
+/// Sessions shown when searching all of them.
+const SEARCH_LIMIT: usize = 100;
+
 #[derive(Debug, Clone)]
 pub enum ResumeSelection {
     StartFresh,
@@ -38,7 +45,9 @@ pub enum ResumeSelection {
 
 /// Interactive session picker that lists recorded rollout files with simple
 /// search and pagination. Shows the first user input as the preview, relative
-/// time (e.g., "5 seconds ago"), and the absolute path.
+/// time (e.g., "5 seconds ago"), and the absolute path. Tab switches the
+/// search from the previews on the current page to the full text of all
+/// sessions.
 pub async fn run_resume_picker(tui: &mut Tui, icodex_home: &Path) -> Result<ResumeSelection> {
     let alt = AltScreenGuard::enter(tui);
     let mut state = PickerState::new(icodex_home.to_path_buf(), alt.tui.frame_requester());
@@ -97,6 +106,9 @@ struct PickerState {
     selected: usize,
     // search
     query: String,
+    /// Search the full text of all sessions instead of the previews on the
+    /// current page.
+    search_all: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -129,6 +141,7 @@ impl PickerState {
             filtered_rows: Vec::new(),
             selected: 0,
             query: String::new(),
+            search_all: false,
         }
     }
 
@@ -163,15 +176,22 @@ impl PickerState {
                 }
                 self.request_frame();
             }
-            KeyCode::Left | KeyCode::Char('a') => {
+            KeyCode::Tab => {
+                self.search_all = !self.search_all;
+                self.selected = 0;
+                self.update_results().await?;
+            }
+            // Search results are not paged, so `a` and `d` are part of the
+            // query there.
+            KeyCode::Left | KeyCode::Char('a') if !self.search_all => {
                 self.prev_page().await?;
             }
-            KeyCode::Right | KeyCode::Char('d') => {
+            KeyCode::Right | KeyCode::Char('d') if !self.search_all => {
                 self.next_page().await?;
             }
             KeyCode::Backspace => {
                 self.query.pop();
-                self.apply_filter();
+                self.update_results().await?;
             }
             KeyCode::Char(c) => {
                 // basic text input for search
@@ -181,7 +201,7 @@ impl PickerState {
                     && !key.modifiers.contains(crossterm::event::KeyModifiers::ALT)
                 {
                     self.query.push(c);
-                    self.apply_filter();
+                    self.update_results().await?;
                 }
             }
             _ => {}
@@ -234,6 +254,25 @@ impl PickerState {
         Ok(())
     }
 
+    async fn update_results(&mut self) -> Result<()> {
+        // Without a query, both modes list the current page.
+        if !self.search_all || self.query.trim().is_empty() {
+            self.apply_filter();
+            return Ok(());
+        }
+        let query = SessionSearchQuery {
+            text: self.query.clone(),
+            ..Default::default()
+        };
+        let hits = search_sessions(&self.icodex_home, &query, SEARCH_LIMIT).await?;
+        self.filtered_rows = hits.iter().map(hit_to_row).collect();
+        if self.selected >= self.filtered_rows.len() {
+            self.selected = self.filtered_rows.len().saturating_sub(1);
+        }
+        self.request_frame();
+        Ok(())
+    }
+
     fn apply_filter(&mut self) {
         if self.query.is_empty() {
             self.filtered_rows = self.all_rows.clone();
@@ -278,6 +317,31 @@ fn head_to_row(item: &ConversationItem) -> Row {
     }
 }
 
+/// The preview of a search hit is its best match, so the row shows why the
+/// session matched.
+fn hit_to_row(hit: &SessionSearchHit) -> Row {
+    let ts = chrono::DateTime::parse_from_rfc3339(&hit.timestamp)
+        .ok()
+        .map(|parsed| parsed.with_timezone(&Utc));
+    let preview = match hit.matches.first() {
+        Some(best) => {
+            let text = best.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            match best.field {
+                SearchField::UserMessage => text,
+                SearchField::AgentMessage => format!("agent: {text}"),
+                SearchField::Command => format!("$ {text}"),
+                SearchField::FilePath => format!("edited {text}"),
+            }
+        }
+        None => String::from("(no message yet)"),
+    };
+    Row {
+        path: hit.path.clone(),
+        preview,
+        ts,
+    }
+}
+
 fn preview_from_head(head: &[serde_json::Value]) -> Option<String> {
     head.iter()
         .filter_map(|value| serde_json::from_value::<ResponseItem>(value.clone()).ok())
@@ -336,10 +400,11 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
         );
 
         // Search line
-        let q = if state.query.is_empty() {
-            "Type to search".dim().to_string()
-        } else {
-            format!("Search: {}", state.query)
+        let q = match (state.search_all, state.query.is_empty()) {
+            (false, true) => "Type to search".dim().to_string(),
+            (true, true) => "Type to search all sessions".dim().to_string(),
+            (false, false) => format!("Search: {}", state.query),
+            (true, false) => format!("Search all sessions: {}", state.query),
         };
         frame.render_widget_ref(Line::from(q), search);
 
@@ -347,19 +412,27 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
         render_list(frame, list, state);
 
         // Hint line
-        let hint_line: Line = vec![
+        let mut hint_spans = vec![
             "Enter".bold(),
             " to resume  ".into(),
             "Esc".bold(),
             " to start new  ".into(),
             "Ctrl+C".into(),
             " to quit  ".dim(),
-            "←/a".into(),
-            " prev  ".dim(),
-            "→/d".into(),
-            " next".dim(),
-        ]
-        .into();
+            "Tab".into(),
+        ];
+        if state.search_all {
+            hint_spans.push(" search this page".dim());
+        } else {
+            hint_spans.extend([
+                " search all sessions  ".dim(),
+                "←/a".into(),
+                " prev  ".dim(),
+                "→/d".into(),
+                " next".dim(),
+            ]);
+        }
+        let hint_line: Line = hint_spans.into();
         frame.render_widget_ref(hint_line, hint);
     })
 }
@@ -494,4 +567,52 @@ mod tests {
         assert!(rows[0].preview.contains('A'));
         assert!(rows[1].preview.contains('B'));
     }
+
+    #[tokio::test]
+    async fn tab_searches_the_full_text_of_all_sessions() {
+        let home = tempfile::tempdir().unwrap();
+        let day_dir = home.path().join("sessions/2025/01/02");
+        std::fs::create_dir_all(&day_dir).unwrap();
+        let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let rollout_path = day_dir.join(format!("rollout-2025-01-02T00-00-00-{id}.jsonl"));
+        let arguments = json!({"command": ["bash", "-lc", "cargo deny check"]}).to_string();
+        let lines = [
+            json!({
+                "timestamp": "2025-01-02T00:00:00.000Z",
+                "type": "session_meta",
+                "payload": {
+                    "id": id,
+                    "timestamp": "2025-01-02T00:00:00.000Z",
+                    "instructions": null,
+                    "cwd": "/work",
+                    "originator": "icodex_cli_rs",
+                    "cli_version": "0.0.0",
+                },
+            }),
+            json!({
+                "timestamp": "2025-01-02T00:00:01.000Z",
+                "type": "response_item",
+                "payload": {
+                    "type": "function_call",
+                    "name": "shell",
+                    "arguments": arguments,
+                    "call_id": "call-1",
+                },
+            }),
+        ];
+        let contents: String = lines.iter().map(|line| format!("{line}\n")).collect();
+        std::fs::write(&rollout_path, contents).unwrap();
+
+        let mut state = PickerState::new(home.path().to_path_buf(), FrameRequester::test_dummy());
+        let key = |code| KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+        state.handle_key(key(KeyCode::Tab)).await.unwrap();
+        for c in "deny".chars() {
+            state.handle_key(key(KeyCode::Char(c))).await.unwrap();
+        }
+
+        assert_eq!(state.query, "deny");
+        assert_eq!(state.filtered_rows.len(), 1);
+        assert_eq!(state.filtered_rows[0].path, rollout_path);
+        assert_eq!(state.filtered_rows[0].preview, "$ cargo deny check");
+    }
 }
@@ -44,8 +44,27 @@ pub(crate) enum ApprovalRequest {
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     },
+    McpToolCall {
+        id: String,
+        server: String,
+        tool: String,
+        /// The call's arguments as compact JSON, if any.
+        arguments: Option<String>,
+    },
+    McpSampling {
+        id: String,
+        server: String,
+        /// The messages the server wants sampled, one `role: text` per line.
+        messages: String,
+    },
 }
 
+/// Number of lines of a sampling request shown in the modal.
+const MAX_SAMPLING_PREVIEW_LINES: usize = 8;
+
+/// Number of graphemes of an MCP tool call's arguments shown in the modal.
+const MAX_TOOL_ARGUMENTS_PREVIEW_LEN: usize = 200;
+
 /// Options displayed in the *select* mode.
 ///
 /// The `key` is matched case-insensitively.
@@ -96,6 +115,52 @@ static PATCH_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     ]
 });
 
+static MCP_TOOL_CALL_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
+    vec![
+        SelectOption {
+            label: Line::from(vec!["Y".underlined(), "es".into()]),
+            description: "Approve and call the tool",
+            key: KeyCode::Char('y'),
+            decision: ReviewDecision::Approved,
+        },
+        SelectOption {
+            label: Line::from(vec!["A".underlined(), "lways".into()]),
+            description: "Approve this tool for the remainder of this session",
+            key: KeyCode::Char('a'),
+            decision: ReviewDecision::ApprovedForSession,
+        },
+        SelectOption {
+            label: Line::from(vec!["N".underlined(), "o, provide feedback".into()]),
+            description: "Do not call the tool; provide feedback",
+            key: KeyCode::Char('n'),
+            decision: ReviewDecision::Abort,
+        },
+    ]
+});
+
+static SAMPLING_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
+    vec![
+        SelectOption {
+            label: Line::from(vec!["Y".underlined(), "es".into()]),
+            description: "Send the messages to the model and return its reply to the server",
+            key: KeyCode::Char('y'),
+            decision: ReviewDecision::Approved,
+        },
+        SelectOption {
+            label: Line::from(vec!["A".underlined(), "lways".into()]),
+            description: "Let this server use the model for the remainder of this session",
+            key: KeyCode::Char('a'),
+            decision: ReviewDecision::ApprovedForSession,
+        },
+        SelectOption {
+            label: Line::from(vec!["N".underlined(), "o".into()]),
+            description: "Decline the request",
+            key: KeyCode::Char('n'),
+            decision: ReviewDecision::Denied,
+        },
+    ]
+});
+
 /// A modal prompting the user to approve or deny the pending request.
 pub(crate) struct UserApprovalWidget {
     approval_request: ApprovalRequest,
@@ -142,12 +207,52 @@ impl UserApprovalWidget {
 
                 Paragraph::new(contents).wrap(Wrap { trim: false })
             }
+            ApprovalRequest::McpToolCall {
+                server,
+                tool,
+                arguments,
+                ..
+            } => {
+                let mut contents: Vec<Line> = vec![Line::from(vec![
+                    "Call ".into(),
+                    tool.clone().bold(),
+                    " on MCP server ".into(),
+                    server.clone().bold(),
+                ])];
+                if let Some(arguments) = arguments {
+                    contents.push(Line::from(
+                        truncate_text(arguments, MAX_TOOL_ARGUMENTS_PREVIEW_LEN).dim(),
+                    ));
+                }
+                contents.push(Line::from(""));
+                Paragraph::new(contents).wrap(Wrap { trim: false })
+            }
+            ApprovalRequest::McpSampling {
+                server, messages, ..
+            } => {
+                let mut contents: Vec<Line> = vec![Line::from(vec![
+                    "MCP server ".into(),
+                    server.clone().bold(),
+                    " wants to send these messages to the model:".into(),
+                ])];
+                let mut lines = messages.lines();
+                for line in lines.by_ref().take(MAX_SAMPLING_PREVIEW_LINES) {
+                    contents.push(Line::from(line.to_string().dim()));
+                }
+                if lines.next().is_some() {
+                    contents.push(Line::from("…".dim()));
+                }
+                contents.push(Line::from(""));
+                Paragraph::new(contents).wrap(Wrap { trim: false })
+            }
         };
 
         Self {
             select_options: match &approval_request {
                 ApprovalRequest::Exec { .. } => &COMMAND_SELECT_OPTIONS,
                 ApprovalRequest::ApplyPatch { .. } => &PATCH_SELECT_OPTIONS,
+                ApprovalRequest::McpToolCall { .. } => &MCP_TOOL_CALL_SELECT_OPTIONS,
+                ApprovalRequest::McpSampling { .. } => &SAMPLING_SELECT_OPTIONS,
             },
             approval_request,
             app_event_tx,
@@ -291,8 +396,11 @@ impl UserApprovalWidget {
                     history_cell::new_user_approval_decision(lines),
                 )));
             }
-            ApprovalRequest::ApplyPatch { .. } => {
-                // No history line for patch approval decisions.
+            ApprovalRequest::ApplyPatch { .. }
+            | ApprovalRequest::McpToolCall { .. }
+            | ApprovalRequest::McpSampling { .. } => {
+                // No history line for patch, MCP tool call or sampling approval
+                // decisions.
             }
         }
 
@@ -305,6 +413,14 @@ impl UserApprovalWidget {
                 id: id.clone(),
                 decision,
             },
+            ApprovalRequest::McpToolCall { id, .. } => Op::McpToolCallApproval {
+                id: id.clone(),
+                decision,
+            },
+            ApprovalRequest::McpSampling { id, .. } => Op::McpSamplingApproval {
+                id: id.clone(),
+                decision,
+            },
         };
 
         self.app_event_tx.send(AppEvent::CodexOp(op));
@@ -357,6 +473,8 @@ impl WidgetRef for &UserApprovalWidget {
         let title = match &self.approval_request {
             ApprovalRequest::Exec { .. } => "Allow command?",
             ApprovalRequest::ApplyPatch { .. } => "Apply changes?",
+            ApprovalRequest::McpToolCall { .. } => "Allow tool call?",
+            ApprovalRequest::McpSampling { .. } => "Allow sampling?",
         };
         Line::from(title).render(title_area, buf);
 
@@ -445,4 +563,55 @@ mod tests {
             })
         )));
     }
+
+    #[test]
+    fn declining_mcp_tool_call_aborts() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let req = ApprovalRequest::McpToolCall {
+            id: "3".to_string(),
+            server: "github".to_string(),
+            tool: "delete_branch".to_string(),
+            arguments: Some(r#"{"branch":"main"}"#.to_string()),
+        };
+        let mut widget = UserApprovalWidget::new(req, tx);
+        widget.handle_key_event(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE));
+        assert!(widget.is_complete());
+        let mut events: Vec<AppEvent> = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            events.push(ev);
+        }
+        assert!(events.iter().any(|e| matches!(
+            e,
+            AppEvent::CodexOp(Op::McpToolCallApproval {
+                decision: ReviewDecision::Abort,
+                ..
+            })
+        )));
+    }
+
+    #[test]
+    fn always_shortcut_approves_sampling_for_session() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let req = ApprovalRequest::McpSampling {
+            id: "mcp-sampling-1".to_string(),
+            server: "planner".to_string(),
+            messages: "user: summarize the diff".to_string(),
+        };
+        let mut widget = UserApprovalWidget::new(req, tx);
+        widget.handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
+        assert!(widget.is_complete());
+        let mut events: Vec<AppEvent> = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            events.push(ev);
+        }
+        assert!(events.iter().any(|e| matches!(
+            e,
+            AppEvent::CodexOp(Op::McpSamplingApproval {
+                decision: ReviewDecision::ApprovedForSession,
+                ..
+            })
+        )));
+    }
 }